@@ -0,0 +1,16 @@
+// Ejemplo de shader propio: un planeta oceánico con continentes y nubes que giran.
+// Se usa desde la escena con `fragmento = "shaders/oceano.wgsl"` y se recarga al guardar.
+//
+// Debe definir superficie_propia con esta firma; puede usar las funciones del shader
+// principal (fbm_ruido, ruido_suave, patron_voronoi...), los datos del cuerpo con `cuerpo()`
+// y los `uniformes` globales del cuadro (tiempo, cámara, luz)
+fn superficie_propia(direccion: vec3<f32>, uv: vec2<f32>, t: f32) -> vec3<f32> {
+    let altura = fbm_ruido(direccion * 3.0, 5);
+    let oceano = mix(vec3<f32>(0.02, 0.10, 0.35), vec3<f32>(0.05, 0.35, 0.55), altura);
+    let tierra = mix(vec3<f32>(0.15, 0.40, 0.12), vec3<f32>(0.55, 0.45, 0.30), smoothstep(0.6, 0.8, altura));
+    var color = select(oceano, tierra, altura > 0.55);
+
+    let nubes = fbm_ruido(direccion * 5.0 + vec3<f32>(t * 0.05, 0.0, 0.0), 4);
+    color = mix(color, vec3<f32>(1.0), smoothstep(0.55, 0.75, nubes) * cuerpo().actividad);
+    return color;
+}
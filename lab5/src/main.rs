@@ -5,6 +5,7 @@
 // Descripción: Todos los módulos concentrados en un solo archivo
 // =============================================================================
 
+use bytemuck::Zeroable;
 use wgpu::util::DeviceExt;
 use winit::{
     event::*,
@@ -13,7 +14,7 @@ use winit::{
     window::Window,
 };
 use std::sync::Arc;
-use nalgebra_glm::{Vec3, Mat4};
+use nalgebra_glm::{Vec3, DVec3, Mat4};
 use std::fmt;
 
 // =============================================================================
@@ -29,16 +30,14 @@ pub struct ColorRGB {
 }
 
 impl ColorRGB {
-    pub fn nuevo(r: u8, g: u8, b: u8) -> Self {
+    pub const fn nuevo(r: u8, g: u8, b: u8) -> Self {
         ColorRGB { rojo: r, verde: g, azul: b }
     }
 
+    /// Canales en [0, 1] (se recortan) redondeados al entero de 8 bits más cercano
     pub fn desde_flotante(r: f32, g: f32, b: f32) -> Self {
-        ColorRGB {
-            rojo: (r.clamp(0.0, 1.0) * 255.0) as u8,
-            verde: (g.clamp(0.0, 1.0) * 255.0) as u8,
-            azul: (b.clamp(0.0, 1.0) * 255.0) as u8,
-        }
+        let canal = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        ColorRGB { rojo: canal(r), verde: canal(g), azul: canal(b) }
     }
 
     pub fn a_hexadecimal(&self) -> u32 {
@@ -71,12 +70,387 @@ impl ColorRGB {
     }
 }
 
+impl ColorRGB {
+    pub fn a_flotante(&self) -> [f32; 3] {
+        [self.rojo as f32 / 255.0, self.verde as f32 / 255.0, self.azul as f32 / 255.0]
+    }
+
+    /// Los mismos canales como luz lineal; `interpolar`, `multiplicar` y `sumar` operan
+    /// sobre los valores sRGB, que es como mezclar pinturas y no luz
+    pub fn a_lineal(&self) -> ColorLineal {
+        let [r, g, b] = self.a_flotante().map(srgb_a_lineal);
+        ColorLineal::nuevo(r, g, b)
+    }
+
+    pub fn desde_hexadecimal(hexadecimal: u32) -> Self {
+        ColorRGB::nuevo((hexadecimal >> 16) as u8, (hexadecimal >> 8) as u8, hexadecimal as u8)
+    }
+
+    /// Desde tono (`h` en grados, cualquier valor), saturación y valor (0-1)
+    pub fn desde_hsv(h: f32, s: f32, v: f32) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let croma = v * s;
+        let [r, g, b] = Self::desde_tono(h, croma);
+        let minimo = v - croma;
+        ColorRGB::desde_flotante(r + minimo, g + minimo, b + minimo)
+    }
+
+    /// `(h, s, v)`: tono en grados [0, 360), saturación y valor en [0, 1]
+    pub fn a_hsv(&self) -> (f32, f32, f32) {
+        let (tono, maximo, minimo) = self.tono();
+        let saturacion = if maximo > 0.0 { (maximo - minimo) / maximo } else { 0.0 };
+        (tono, saturacion, maximo)
+    }
+
+    /// Desde tono (`h` en grados), saturación y luminosidad (0-1)
+    pub fn desde_hsl(h: f32, s: f32, l: f32) -> Self {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let croma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let [r, g, b] = Self::desde_tono(h, croma);
+        let minimo = l - croma / 2.0;
+        ColorRGB::desde_flotante(r + minimo, g + minimo, b + minimo)
+    }
+
+    /// `(h, s, l)`: tono en grados [0, 360), saturación y luminosidad en [0, 1]
+    pub fn a_hsl(&self) -> (f32, f32, f32) {
+        let (tono, maximo, minimo) = self.tono();
+        let luminosidad = (maximo + minimo) / 2.0;
+        let croma = maximo - minimo;
+        let saturacion = if croma > 0.0 { croma / (1.0 - (2.0 * luminosidad - 1.0).abs()) } else { 0.0 };
+        (tono, saturacion.min(1.0), luminosidad)
+    }
+
+    /// Canales con croma `croma` y mínimo 0 para el tono `h`
+    fn desde_tono(h: f32, croma: f32) -> [f32; 3] {
+        let sector = h.rem_euclid(360.0) / 60.0;
+        let x = croma * (1.0 - (sector % 2.0 - 1.0).abs());
+        match sector as u32 {
+            0 => [croma, x, 0.0],
+            1 => [x, croma, 0.0],
+            2 => [0.0, croma, x],
+            3 => [0.0, x, croma],
+            4 => [x, 0.0, croma],
+            _ => [croma, 0.0, x],
+        }
+    }
+
+    /// Tono en grados y los canales mayor y menor (0-1)
+    fn tono(&self) -> (f32, f32, f32) {
+        let [r, g, b] = self.a_flotante();
+        let (maximo, minimo) = (r.max(g).max(b), r.min(g).min(b));
+        let croma = maximo - minimo;
+        let tono = if croma <= 0.0 {
+            0.0
+        } else if maximo == r {
+            60.0 * ((g - b) / croma).rem_euclid(6.0)
+        } else if maximo == g {
+            60.0 * ((b - r) / croma + 2.0)
+        } else {
+            60.0 * ((r - g) / croma + 4.0)
+        };
+        (tono, maximo, minimo)
+    }
+
+    /// El mismo color con opacidad `alfa` (0-1)
+    pub fn con_alfa(&self, alfa: f32) -> ColorRGBA {
+        ColorRGBA { color: *self, alfa: (alfa.clamp(0.0, 1.0) * 255.0) as u8 }
+    }
+}
+
+/// Componente sRGB (0-1) a intensidad lineal
+pub fn srgb_a_lineal(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Intensidad lineal (0-1) a componente sRGB
+pub fn lineal_a_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Color como luz lineal, el espacio en que sombrean los shaders: mezclar, escalar y sumar
+/// aquí y convertir a sRGB al final da lo mismo que la GPU al escribir en una superficie sRGB
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ColorLineal {
+    pub rojo: f32,
+    pub verde: f32,
+    pub azul: f32,
+}
+
+impl ColorLineal {
+    pub fn nuevo(r: f32, g: f32, b: f32) -> Self {
+        ColorLineal { rojo: r, verde: g, azul: b }
+    }
+
+    /// Codifica en sRGB de 8 bits, recortando a [0, 1] y redondeando cada canal
+    pub fn a_srgb(&self) -> ColorRGB {
+        let canal = |c: f32| lineal_a_srgb(c.clamp(0.0, 1.0));
+        ColorRGB::desde_flotante(canal(self.rojo), canal(self.verde), canal(self.azul))
+    }
+
+    pub fn interpolar(&self, otro_color: &ColorLineal, factor: f32) -> ColorLineal {
+        let t = factor.clamp(0.0, 1.0);
+        self.multiplicar(1.0 - t).sumar(&otro_color.multiplicar(t))
+    }
+
+    pub fn multiplicar(&self, escalar: f32) -> ColorLineal {
+        ColorLineal::nuevo(self.rojo * escalar, self.verde * escalar, self.azul * escalar)
+    }
+
+    pub fn sumar(&self, otro_color: &ColorLineal) -> ColorLineal {
+        ColorLineal::nuevo(self.rojo + otro_color.rojo, self.verde + otro_color.verde, self.azul + otro_color.azul)
+    }
+
+    /// Luminancia relativa (Rec. 709)
+    pub fn luminancia(&self) -> f32 {
+        0.2126 * self.rojo + 0.7152 * self.verde + 0.0722 * self.azul
+    }
+}
+
+impl From<Vec3> for ColorLineal {
+    fn from(color: Vec3) -> Self {
+        ColorLineal::nuevo(color.x, color.y, color.z)
+    }
+}
+
+impl From<ColorLineal> for Vec3 {
+    fn from(color: ColorLineal) -> Self {
+        Vec3::new(color.rojo, color.verde, color.azul)
+    }
+}
+
+/// Color con transparencia (alfa 0-255, sin premultiplicar) para lo que el render por CPU
+/// pinta encima de lo ya dibujado: anillos, atmósferas, superposiciones
+#[derive(Debug, Clone, Copy)]
+pub struct ColorRGBA {
+    pub color: ColorRGB,
+    pub alfa: u8,
+}
+
+impl ColorRGBA {
+    pub fn nuevo(r: u8, g: u8, b: u8, a: u8) -> Self {
+        ColorRGBA { color: ColorRGB::nuevo(r, g, b), alfa: a }
+    }
+
+    pub fn desde_flotante(r: f32, g: f32, b: f32, a: f32) -> Self {
+        ColorRGB::desde_flotante(r, g, b).con_alfa(a)
+    }
+
+    /// `0xAARRGGBB`
+    pub fn desde_hexadecimal_argb(hexadecimal: u32) -> Self {
+        ColorRGB::desde_hexadecimal(hexadecimal).con_alfa((hexadecimal >> 24) as f32 / 255.0)
+    }
+
+    /// `0xAARRGGBB`
+    pub fn a_hexadecimal_argb(&self) -> u32 {
+        ((self.alfa as u32) << 24) | self.color.a_hexadecimal()
+    }
+
+    pub fn a_flotante(&self) -> [f32; 4] {
+        let [r, g, b] = self.color.a_flotante();
+        [r, g, b, self.alfa as f32 / 255.0]
+    }
+
+    /// Este color sobre `fondo` (operador *over* de Porter-Duff), mezclado como luz lineal
+    pub fn sobre(&self, fondo: &ColorRGBA) -> ColorRGBA {
+        let (a1, a2) = (self.alfa as f32 / 255.0, fondo.alfa as f32 / 255.0);
+        let alfa = a1 + a2 * (1.0 - a1);
+        if alfa <= f32::EPSILON {
+            return ColorRGBA::nuevo(0, 0, 0, 0);
+        }
+        let frente = self.color.a_lineal().multiplicar(a1);
+        let detras = fondo.color.a_lineal().multiplicar(a2 * (1.0 - a1));
+        frente.sumar(&detras).multiplicar(1.0 / alfa).a_srgb().con_alfa(alfa)
+    }
+
+    /// Este color debajo de `frente`: lo que queda al pintar `frente` encima
+    pub fn bajo(&self, frente: &ColorRGBA) -> ColorRGBA {
+        frente.sobre(self)
+    }
+
+    /// Este color sobre un fondo opaco
+    pub fn sobre_opaco(&self, fondo: &ColorRGB) -> ColorRGB {
+        fondo.a_lineal().interpolar(&self.color.a_lineal(), self.alfa as f32 / 255.0).a_srgb()
+    }
+}
+
+impl From<ColorRGB> for ColorRGBA {
+    fn from(color: ColorRGB) -> Self {
+        ColorRGBA { color, alfa: 255 }
+    }
+}
+
+/// Degradado lineal entre paradas de color, ordenadas por posición en [0, 1], que se
+/// lee con `muestrear(t)` (mezcla en sRGB), `muestrear_lineal(t)` (como luz) o `escalon(t)`.
+/// La usan los shaders por CPU y, como tabla de consulta (`textura_1d`), los de la GPU; las
+/// predefinidas se arman en HSV o HSL sobre `ColorRGB`, cerca de los colores del WGSL
+#[derive(Debug, Clone)]
+pub struct Paleta {
+    paradas: Vec<(f32, ColorRGB)>,
+}
+
+impl Paleta {
+    pub fn nuevo(paradas: Vec<(f32, ColorRGB)>) -> Self {
+        Paleta { paradas }
+    }
+
+    /// Lava de la más fría a la más caliente: un barrido de tono del rojo anaranjado al
+    /// amarillo que pierde saturación hasta casi blanco
+    pub fn lava() -> Self {
+        Paleta::nuevo(vec![
+            (0.0, ColorRGB::desde_hsv(20.0, 0.74, 0.95)),
+            (0.35, ColorRGB::desde_hsv(36.0, 0.65, 1.0)),
+            (0.55, ColorRGB::desde_hsv(53.0, 0.42, 1.0)),
+            (0.75, ColorRGB::desde_hsv(60.0, 0.09, 1.0)),
+        ])
+    }
+
+    /// Hielo de los mares a las tierras altas: celeste grisáceo a blanco azulado, cada vez
+    /// más luminoso
+    pub fn hielo() -> Self {
+        Paleta::nuevo(vec![
+            (0.0, ColorRGB::desde_hsl(198.0, 0.47, 0.82)),
+            (0.5, ColorRGB::desde_hsl(206.0, 0.54, 0.925)),
+            (1.0, ColorRGB::desde_hsl(210.0, 1.0, 0.975)),
+        ])
+    }
+
+    /// Bandas de gigante gaseoso: crema, óxido, ocre y marfil, y de vuelta a crema
+    pub fn bandas_gas() -> Self {
+        let crema = ColorRGB::desde_hsv(41.0, 0.15, 0.98);
+        Paleta::nuevo(vec![
+            (0.0, crema),
+            (0.25, ColorRGB::desde_hsv(27.0, 0.37, 0.93)),
+            (0.5, ColorRGB::desde_hsv(33.0, 0.23, 0.95)),
+            (0.75, ColorRGB::desde_hsv(40.0, 0.15, 1.0)),
+            (1.0, crema),
+        ])
+    }
+
+    /// De lento a rápido: azul, cian, amarillo y rojo
+    pub fn velocidad() -> Self {
+        Paleta::nuevo(vec![
+            (0.0, ColorRGB::nuevo(40, 80, 255)),
+            (0.35, ColorRGB::nuevo(0, 220, 255)),
+            (0.65, ColorRGB::nuevo(255, 230, 0)),
+            (1.0, ColorRGB::nuevo(255, 40, 20)),
+        ])
+    }
+
+    /// De débil a intenso: violeta, azul, verde y amarillo claro
+    pub fn campo_magnetico() -> Self {
+        Paleta::nuevo(vec![
+            (0.0, ColorRGB::nuevo(90, 30, 140)),
+            (0.35, ColorRGB::nuevo(40, 110, 255)),
+            (0.7, ColorRGB::nuevo(40, 230, 140)),
+            (1.0, ColorRGB::nuevo(255, 250, 170)),
+        ])
+    }
+
+    /// Barrido de tono de `desde` a `hasta` grados (p. ej. 20 a 50 para bandas de gigante
+    /// gaseoso, 0 a 60 para lava) en `paradas` tramos de saturación y valor fijos
+    pub fn barrido_tono(desde: f32, hasta: f32, saturacion: f32, valor: f32, paradas: usize) -> Self {
+        let tramos = paradas.max(1);
+        Paleta::nuevo(
+            (0..=tramos)
+                .map(|i| {
+                    let t = i as f32 / tramos as f32;
+                    (t, ColorRGB::desde_hsv(desde + (hasta - desde) * t, saturacion, valor))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn muestrear(&self, t: f32) -> ColorRGB {
+        let Some((primera, _)) = self.paradas.first() else { return ColorRGB::nuevo(0, 0, 0) };
+        if t <= *primera {
+            return self.paradas[0].1;
+        }
+        for par in self.paradas.windows(2) {
+            let ((inicio, desde), (fin, hasta)) = (par[0], par[1]);
+            if t <= fin {
+                return desde.interpolar(&hasta, (t - inicio) / (fin - inicio).max(f32::EPSILON));
+            }
+        }
+        self.paradas[self.paradas.len() - 1].1
+    }
+
+    /// Como `muestrear`, pero mezclando las paradas como luz lineal, igual que la GPU al
+    /// filtrar una textura sRGB; es lo que usan los shaders
+    pub fn muestrear_lineal(&self, t: f32) -> ColorLineal {
+        let Some((primera, color)) = self.paradas.first() else { return ColorLineal::default() };
+        if t <= *primera {
+            return color.a_lineal();
+        }
+        for par in self.paradas.windows(2) {
+            let ((inicio, desde), (fin, hasta)) = (par[0], par[1]);
+            if t <= fin {
+                return desde.a_lineal().interpolar(&hasta.a_lineal(), (t - inicio) / (fin - inicio).max(f32::EPSILON));
+            }
+        }
+        self.paradas[self.paradas.len() - 1].1.a_lineal()
+    }
+
+    /// Color de la última parada que no pasa de `t`, sin mezclar: una paleta por tramos
+    pub fn escalon(&self, t: f32) -> ColorLineal {
+        let parada = self.paradas.iter().rev().find(|(posicion, _)| *posicion <= t).or(self.paradas.first());
+        parada.map_or(ColorLineal::default(), |(_, color)| color.a_lineal())
+    }
+
+    /// `ancho` muestras RGBA8 sRGB de 0 a 1, mezcladas como `muestrear_lineal`
+    pub fn texeles(&self, ancho: u32) -> Vec<u8> {
+        let ultimo = ancho.saturating_sub(1).max(1) as f32;
+        (0..ancho)
+            .flat_map(|i| {
+                let color = self.muestrear_lineal(i as f32 / ultimo).a_srgb();
+                [color.rojo, color.verde, color.azul, 255]
+            })
+            .collect()
+    }
+
+    /// Tabla de consulta de `ancho` texeles en una textura 2D de una fila, porque GLES no
+    /// tiene texturas 1D (`paleta_bandas_gas` en el WGSL); `muestrear_paleta` interpola
+    /// entre ellos como luz
+    pub fn textura_1d(&self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, etiqueta: &str, ancho: u32) -> wgpu::Texture {
+        let ancho = ancho.clamp(1, dispositivo.limits().max_texture_dimension_2d);
+        dispositivo.create_texture_with_data(
+            cola,
+            &wgpu::TextureDescriptor {
+                label: Some(etiqueta),
+                size: wgpu::Extent3d { width: ancho, height: 1, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &self.texeles(ancho),
+        )
+    }
+}
+
 impl fmt::Display for ColorRGB {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "ColorRGB(R: {}, G: {}, B: {})", self.rojo, self.verde, self.azul)
     }
 }
 
+impl fmt::Display for ColorRGBA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ColorRGB { rojo, verde, azul } = self.color;
+        write!(f, "ColorRGBA(R: {}, G: {}, B: {}, A: {})", rojo, verde, azul, self.alfa)
+    }
+}
+
 // =============================================================================
 // MÓDULO: VERTEX
 // =============================================================================
@@ -88,6 +462,10 @@ pub struct Vertice {
     pub coordenadas_textura: Vec3,
     pub posicion_transformada: Vec3,
     pub normal_transformada: Vec3,
+    /// Posición de mundo, para la luz del fragmento
+    pub posicion_mundo: Vec3,
+    /// w de recorte: con 1 los atributos se interpolan linealmente en pantalla
+    pub w: f32,
 }
 
 impl Vertice {
@@ -98,6 +476,8 @@ impl Vertice {
             coordenadas_textura: tex,
             posicion_transformada: pos,
             normal_transformada: norm,
+            posicion_mundo: pos,
+            w: 1.0,
         }
     }
 }
@@ -110,16 +490,185 @@ pub struct CamaraVirtual {
     pub ojo: Vec3,
     pub objetivo: Vec3,
     pub vector_arriba: Vec3,
+    /// Ángulos de la órbita alrededor del objetivo, en radianes
+    pub guinada: f32,
+    pub cabeceo: f32,
+    /// Distancia del ojo al objetivo y sus límites para el zoom
+    pub radio: f32,
+    pub radio_minimo: f32,
+    pub radio_maximo: f32,
+    /// Radio del cuerpo enfocado (0 sin foco): el zoom acerca a su superficie en vez de a
+    /// `radio_minimo` y avanza en proporción a la altura sobre ella
+    pub superficie: f32,
+    /// Campo de visión vertical (radianes) y planos de recorte de la proyección
+    pub campo_vision: f32,
+    pub plano_cercano: f32,
+    pub plano_lejano: f32,
+    /// Objetivo anterior menos el del foco nuevo y fracción de ese tramo que falta recorrer:
+    /// al cambiar de foco el objetivo se desliza en vez de saltar
+    pub desfase: Vec3,
+    pub transicion: f32,
 }
 
 impl CamaraVirtual {
+    /// Límite del cabeceo para no alinear la vista con el vector arriba
+    const CABECEO_MAXIMO: f32 = 1.5;
+    /// Constante de tiempo del deslizamiento al cambiar de foco, en segundos
+    const CONSTANTE_TRANSICION: f32 = 0.25;
+    /// Fracción pendiente por debajo de la cual el deslizamiento termina
+    const FIN_TRANSICION: f32 = 1e-4;
+
+    /// Vista con que arranca la aplicación: de frente a la escena desde `DISTANCIA_OBSERVADOR`
+    pub fn inicial() -> Self {
+        CamaraVirtual::nueva(Vec3::new(0.0, 0.0, DISTANCIA_OBSERVADOR), Vec3::zeros(), Vec3::y())
+    }
+
     pub fn nueva(posicion_ojo: Vec3, punto_objetivo: Vec3, dir_arriba: Vec3) -> Self {
+        let desplazamiento = posicion_ojo - punto_objetivo;
+        let radio = desplazamiento.norm();
         CamaraVirtual { 
             ojo: posicion_ojo, 
             objetivo: punto_objetivo, 
-            vector_arriba: dir_arriba 
+            vector_arriba: dir_arriba,
+            guinada: desplazamiento.x.atan2(desplazamiento.z),
+            cabeceo: (desplazamiento.y / radio.max(f32::EPSILON)).clamp(-1.0, 1.0).asin(),
+            radio,
+            radio_minimo: radio * 0.25,
+            radio_maximo: radio * 4.0,
+            superficie: 0.0,
+            campo_vision: std::f32::consts::FRAC_PI_3,
+            plano_cercano: 0.01,
+            plano_lejano: 100.0,
+            desfase: Vec3::zeros(),
+            transicion: 0.0,
+        }
+    }
+
+    /// Gira la cámara alrededor del objetivo
+    pub fn orbitar(&mut self, delta_guinada: f32, delta_cabeceo: f32) {
+        self.guinada += delta_guinada;
+        self.cabeceo = (self.cabeceo + delta_cabeceo)
+            .clamp(-Self::CABECEO_MAXIMO, Self::CABECEO_MAXIMO);
+        self.actualizar_ojo();
+    }
+
+    /// Acerca (pasos positivos) o aleja la cámara, respetando los límites del radio
+    pub fn acercar(&mut self, pasos: f32) {
+        let altura = (self.radio - self.superficie) * 0.9_f32.powf(pasos);
+        self.radio = (self.superficie + altura).clamp(self.radio_cercano(), self.radio_maximo);
+        self.actualizar_ojo();
+    }
+
+    /// Radio más corto del zoom: apenas sobre la superficie enfocada, o `radio_minimo`
+    fn radio_cercano(&self) -> f32 {
+        if self.superficie > 0.0 {
+            self.superficie * 1.0005
+        } else {
+            self.radio_minimo
+        }
+    }
+
+    fn actualizar_ojo(&mut self) {
+        self.ojo = self.objetivo + Vec3::new(
+            self.cabeceo.cos() * self.guinada.sin(),
+            self.cabeceo.sin(),
+            self.cabeceo.cos() * self.guinada.cos(),
+        ) * self.radio;
+    }
+
+    /// Coloca la cámara en una órbita dada alrededor del objetivo actual
+    pub fn orientar(&mut self, guinada: f32, cabeceo: f32, radio: f32) {
+        self.guinada = guinada;
+        self.cabeceo = cabeceo.clamp(-Self::CABECEO_MAXIMO, Self::CABECEO_MAXIMO);
+        self.radio = radio.clamp(self.radio_cercano(), self.radio_maximo);
+        self.actualizar_ojo();
+    }
+
+    /// Mueve el objetivo conservando la orientación y el radio de la órbita
+    pub fn enfocar(&mut self, objetivo: Vec3) {
+        self.objetivo = objetivo;
+        self.actualizar_ojo();
+    }
+
+    /// Empieza a deslizar el objetivo desde el actual hasta `destino`
+    pub fn transicionar(&mut self, destino: Vec3) {
+        self.desfase = self.objetivo - destino;
+        self.transicion = 1.0;
+    }
+
+    /// Avanza el deslizamiento `paso` segundos; la caída exponencial no depende de cómo se
+    /// reparta el tiempo entre cuadros
+    pub fn avanzar_transicion(&mut self, paso: f32) {
+        self.transicion *= (-paso / Self::CONSTANTE_TRANSICION).exp();
+        if self.transicion < Self::FIN_TRANSICION {
+            self.transicion = 0.0;
         }
     }
+
+    /// Sigue a `destino`, corrido por lo que falta del deslizamiento
+    pub fn seguir(&mut self, destino: Vec3) {
+        self.enfocar(destino + self.desfase * self.transicion);
+    }
+
+    /// Salta al final del deslizamiento en curso
+    pub fn terminar_transicion(&mut self) {
+        self.enfocar(self.objetivo - self.desfase * self.transicion);
+        self.transicion = 0.0;
+    }
+
+    pub fn matriz_vista(&self) -> Mat4 {
+        nalgebra_glm::look_at(&self.ojo, &self.objetivo, &self.vector_arriba)
+    }
+
+    /// Perspectiva con profundidad en [0, 1], como espera wgpu. Rozando una superficie el
+    /// plano cercano baja para no recortarla
+    pub fn matriz_proyeccion(&self, aspecto: f32) -> Mat4 {
+        let cercano = if self.superficie > 0.0 {
+            self.plano_cercano.min((self.radio - self.superficie) * 0.5).max(1e-6)
+        } else {
+            self.plano_cercano
+        };
+        nalgebra_glm::perspective_rh_zo(aspecto, self.campo_vision, cercano, self.plano_lejano)
+    }
+}
+
+/// Los seis planos del volumen de visión de una matriz vista·proyección (profundidad en
+/// [0, 1]), con la normal hacia adentro y normalizados: `n·x + d` es la distancia con signo
+pub struct Frustum {
+    planos: [nalgebra_glm::Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracción de Gribb–Hartmann desde las filas de la matriz
+    pub fn desde_matriz(vista_proyeccion: &Mat4) -> Self {
+        let fila = |i: usize| vista_proyeccion.row(i).transpose();
+        let (x, y, z, w) = (fila(0), fila(1), fila(2), fila(3));
+        let planos = [w + x, w - x, w + y, w - y, z, w - z].map(|plano| plano / plano.xyz().norm().max(f32::EPSILON));
+        Frustum { planos }
+    }
+
+    pub fn de_camara(vista: &Mat4, proyeccion: &Mat4) -> Self {
+        Self::desde_matriz(&(proyeccion * vista))
+    }
+
+    /// Falso solo si la esfera queda entera fuera de algún plano; el resultado es conservador
+    pub fn contiene_esfera(&self, centro: &Vec3, radio: f32) -> bool {
+        self.planos.iter().all(|plano| plano.xyz().dot(centro) + plano.w >= -radio)
+    }
+}
+
+/// Distancia a lo largo de un rayo (dirección unitaria) hasta la primera intersección con
+/// una esfera; si el origen está dentro, la salida. `None` si no la toca por delante
+pub fn interseccion_rayo_esfera(origen: &Vec3, direccion: &Vec3, centro: &Vec3, radio: f32) -> Option<f32> {
+    let hacia_origen = origen - centro;
+    let b = hacia_origen.dot(direccion);
+    let c = hacia_origen.norm_squared() - radio * radio;
+    let discriminante = b * b - c;
+    if discriminante < 0.0 {
+        return None;
+    }
+    let raiz = discriminante.sqrt();
+    [-b - raiz, -b + raiz].into_iter().find(|&t| t >= 0.0)
 }
 
 // =============================================================================
@@ -132,6 +681,10 @@ pub struct Fragmento {
     pub profundidad: f32,
     pub posicion_vertice: Vec3,
     pub intensidad: f32,
+    /// Posición en el marco del cuerpo (antes de la matriz de modelo), para los patrones
+    pub posicion_cuerpo: Vec3,
+    /// UV interpoladas de los vértices; cero si quien lo crea no las tiene
+    pub coordenadas_textura: nalgebra_glm::Vec2,
 }
 
 impl Fragmento {
@@ -140,7 +693,8 @@ impl Fragmento {
         norm: Vec3, 
         prof: f32, 
         pos_vert: Vec3, 
-        intens: f32
+        intens: f32,
+        pos_cuerpo: Vec3,
     ) -> Self {
         Fragmento {
             posicion: pos,
@@ -148,6 +702,8 @@ impl Fragmento {
             profundidad: prof,
             posicion_vertice: pos_vert,
             intensidad: intens,
+            posicion_cuerpo: pos_cuerpo,
+            coordenadas_textura: nalgebra_glm::Vec2::zeros(),
         }
     }
 }
@@ -186,6 +742,11 @@ impl BufferDePantalla {
         }
     }
 
+    /// Prueba de profundidad sin escribir: si un fragmento en `(x, y)` taparía lo que ya hay
+    pub fn pasa_profundidad(&self, x: usize, y: usize, prof: f32) -> bool {
+        x < self.ancho && y < self.alto && prof < self.buffer_profundidad[y * self.ancho + x]
+    }
+
     pub fn dibujar_punto(&mut self, x: usize, y: usize, prof: f32) {
         if x < self.ancho && y < self.alto {
             let indice = y * self.ancho + x;
@@ -196,6 +757,79 @@ impl BufferDePantalla {
         }
     }
 
+    /// Segmento de Bresenham entre dos píxeles; `prof` son las profundidades de cada extremo y
+    /// se interpolan a lo largo del eje mayor. Los extremos pueden quedar fuera del buffer
+    pub fn dibujar_linea(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, prof: (f32, f32)) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (paso_x, paso_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let pasos = dx.max(-dy).max(1) as f32;
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+        let mut recorridos = 0;
+        loop {
+            if x >= 0 && y >= 0 {
+                let t = recorridos as f32 / pasos;
+                self.dibujar_punto(x as usize, y as usize, prof.0 + (prof.1 - prof.0) * t);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doble = 2 * error;
+            if doble >= dy {
+                error += dy;
+                x += paso_x;
+            }
+            if doble <= dx {
+                error += dx;
+                y += paso_y;
+            }
+            recorridos += 1;
+        }
+    }
+
+    /// Fragmentos de un triángulo cuyos vértices tienen `posicion_transformada` ya en píxeles
+    /// (z = profundidad). La profundidad se interpola con las coordenadas baricéntricas de
+    /// cada píxel cubierto; la normal, las UV y las posiciones de mundo y del cuerpo
+    /// (`posicion`), divididas por `w`, con corrección de perspectiva. No hay luz ni prueba
+    /// de profundidad: la intensidad es 1
+    pub fn dibujar_triangulo(&self, vertices: &[Vertice; 3]) -> Vec<Fragmento> {
+        let [a, b, c] = vertices;
+        let esquinas = [a.posicion_transformada, b.posicion_transformada, c.posicion_transformada];
+        let inversas_w = [1.0 / a.w, 1.0 / b.w, 1.0 / c.w];
+        let mut fragmentos = Vec::new();
+        recorrer_triangulo(self.ancho, self.alto, esquinas, |x, y, pesos| {
+            let profundidad = pesos[0] * esquinas[0].z + pesos[1] * esquinas[1].z + pesos[2] * esquinas[2].z;
+            let pesos_w = [pesos[0] * inversas_w[0], pesos[1] * inversas_w[1], pesos[2] * inversas_w[2]];
+            let total_w = pesos_w[0] + pesos_w[1] + pesos_w[2];
+            let mezclar = |p: &Vec3, q: &Vec3, r: &Vec3| (p * pesos_w[0] + q * pesos_w[1] + r * pesos_w[2]) / total_w;
+            let normal = mezclar(&a.normal_transformada, &b.normal_transformada, &c.normal_transformada);
+            let normal = normal.try_normalize(f32::EPSILON).unwrap_or(normal);
+            let mundo = mezclar(&a.posicion_mundo, &b.posicion_mundo, &c.posicion_mundo);
+            let cuerpo = mezclar(&a.posicion, &b.posicion, &c.posicion);
+            let uv = mezclar(&a.coordenadas_textura, &b.coordenadas_textura, &c.coordenadas_textura);
+            let mut fragmento = Fragmento::nuevo(
+                Vec3::new(x as f32 + 0.5, y as f32 + 0.5, profundidad),
+                normal,
+                profundidad,
+                mundo,
+                1.0,
+                cuerpo,
+            );
+            fragmento.coordenadas_textura = uv.xy();
+            fragmentos.push(fragmento);
+        });
+        fragmentos
+    }
+
+    /// Mezcla `color` sobre el píxel si pasa la prueba de profundidad, sin escribirla: lo
+    /// transparente no tapa lo que se dibuje detrás después
+    pub fn mezclar_punto(&mut self, x: usize, y: usize, prof: f32, color: ColorRGBA) {
+        if self.pasa_profundidad(x, y, prof) {
+            let pixel = &mut self.buffer_colores[y * self.ancho + x];
+            *pixel = color.sobre_opaco(&ColorRGB::desde_hexadecimal(*pixel)).a_hexadecimal();
+        }
+    }
+
     pub fn establecer_color_fondo(&mut self, color: u32) {
         self.color_fondo = color;
     }
@@ -203,33 +837,190 @@ impl BufferDePantalla {
     pub fn establecer_color_actual(&mut self, color: u32) {
         self.color_actual = color;
     }
+
+    pub fn a_imagen(&self) -> image::RgbImage {
+        image::RgbImage::from_fn(self.ancho as u32, self.alto as u32, |x, y| {
+            let color = self.buffer_colores[y as usize * self.ancho + x as usize];
+            image::Rgb([(color >> 16) as u8, (color >> 8) as u8, color as u8])
+        })
+    }
+}
+
+/// Recorre la caja de un triángulo en píxeles (recortada a `ancho` × `alto`) y llama a
+/// `visitar` con cada píxel cuyo centro cubre y sus coordenadas baricéntricas, que valen
+/// para cualquier sentido de giro. Un centro justo sobre un lado cuenta solo si es un lado
+/// superior o izquierdo, así los triángulos que lo comparten no pintan dos veces el mismo
+/// píxel. Un triángulo degenerado no cubre nada
+fn recorrer_triangulo(ancho: usize, alto: usize, [a, b, c]: [Vec3; 3], mut visitar: impl FnMut(usize, usize, [f32; 3])) {
+    let borde = |p: &Vec3, q: &Vec3, x: f32, y: f32| (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x);
+    let area = borde(&a, &b, c.x, c.y);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+    // Lados orientados como si el área fuera positiva; con y hacia abajo, el izquierdo es
+    // el que sube y el superior el horizontal que avanza hacia +x
+    let superior_o_izquierdo = |p: &Vec3, q: &Vec3| {
+        let (p, q) = if area > 0.0 { (p, q) } else { (q, p) };
+        q.y < p.y || (q.y == p.y && q.x > p.x)
+    };
+    let incluye_lado = [superior_o_izquierdo(&b, &c), superior_o_izquierdo(&c, &a), superior_o_izquierdo(&a, &b)];
+    let minimo = a.inf(&b).inf(&c);
+    let maximo = a.sup(&b).sup(&c);
+    let (x0, y0) = (minimo.x.floor().max(0.0) as usize, minimo.y.floor().max(0.0) as usize);
+    let x1 = (maximo.x.ceil().max(0.0) as usize).min(ancho);
+    let y1 = (maximo.y.ceil().max(0.0) as usize).min(alto);
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let pesos = [borde(&b, &c, px, py) / area, borde(&c, &a, px, py) / area, borde(&a, &b, px, py) / area];
+            if pesos.iter().zip(incluye_lado).all(|(&w, incluye)| w > 0.0 || (w == 0.0 && incluye)) {
+                visitar(x, y, pesos);
+            }
+        }
+    }
 }
 
 // =============================================================================
 // MÓDULO: OBJ LOADER
 // =============================================================================
 
+#[derive(Debug)]
 pub struct ModeloOBJ {
     vertices: Vec<Vec3>,
     normales: Vec<Vec3>,
     coordenadas_uv: Vec<Vec3>,
     caras: Vec<[usize; 9]>,
+    grupos: Vec<GrupoMaterial>,
+}
+
+/// Material de un archivo .mtl (modelo de Phong de Wavefront)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialMtl {
+    pub nombre: String,
+    /// `Kd`
+    pub difuso: [f32; 3],
+    /// `Ks`
+    pub especular: [f32; 3],
+    /// `Ns`, exponente especular de 0 a 1000
+    pub brillo: f32,
+    /// `map_Kd`, ya resuelto respecto a la carpeta del .mtl
+    pub mapa_difuso: Option<std::path::PathBuf>,
+}
+
+impl MaterialMtl {
+    /// Lo que el formato supone para lo que un material no escribe
+    fn nuevo(nombre: &str) -> Self {
+        MaterialMtl {
+            nombre: nombre.to_string(),
+            difuso: [0.8; 3],
+            especular: [0.0; 3],
+            brillo: 0.0,
+            mapa_difuso: None,
+        }
+    }
+
+    /// Material metálico-rugoso de glTF aproximado con Phong: el brillo sale de la rugosidad
+    /// y el reflejo especular va del 4 % de los dieléctricos al color base de los metales
+    fn desde_gltf(material: &gltf::Material, carpeta: &std::path::Path) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let metalico = pbr.metallic_factor();
+        let rugosidad = pbr.roughness_factor().max(0.05);
+        let mapa_difuso = pbr.base_color_texture().and_then(|info| match info.texture().source().source() {
+            gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => Some(carpeta.join(uri)),
+            _ => None,
+        });
+        MaterialMtl {
+            nombre: material.name().unwrap_or("glTF").to_string(),
+            difuso: [r, g, b],
+            especular: [r, g, b].map(|canal| 0.04 + (canal - 0.04) * metalico),
+            brillo: (2.0 / rugosidad.powi(4) - 2.0).clamp(0.0, 1000.0),
+            mapa_difuso,
+        }
+    }
+
+    /// Lee los materiales de un .mtl en el orden en que aparecen
+    pub fn cargar(ruta: &std::path::Path) -> Result<Vec<Self>, std::io::Error> {
+        let texto = std::fs::read_to_string(ruta)?;
+        let carpeta = ruta.parent().unwrap_or(std::path::Path::new(""));
+        let color = |partes: &[&str]| {
+            let mut color = [0.0; 3];
+            for (canal, parte) in color.iter_mut().zip(&partes[1..]) {
+                *canal = parte.parse().unwrap_or(0.0);
+            }
+            color
+        };
+        let mut materiales: Vec<MaterialMtl> = Vec::new();
+        for linea in texto.lines() {
+            let partes: Vec<&str> = linea.split_whitespace().collect();
+            if let ["newmtl", nombre, ..] = partes[..] {
+                materiales.push(MaterialMtl::nuevo(nombre));
+                continue;
+            }
+            // Lo que va antes del primer newmtl no pertenece a ningún material
+            let (Some(&clave), Some(material)) = (partes.first(), materiales.last_mut()) else { continue };
+            match clave {
+                "Kd" if partes.len() >= 4 => material.difuso = color(&partes),
+                "Ks" if partes.len() >= 4 => material.especular = color(&partes),
+                "Ns" if partes.len() >= 2 => material.brillo = partes[1].parse().unwrap_or(0.0),
+                // Las opciones (`-s 1 1 1`...) van antes del archivo
+                "map_Kd" if partes.len() >= 2 => material.mapa_difuso = Some(carpeta.join(partes[partes.len() - 1])),
+                _ => {}
+            }
+        }
+        Ok(materiales)
+    }
+}
+
+/// Caras consecutivas del modelo que usan el mismo material (`usemtl`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrupoMaterial {
+    pub material: MaterialMtl,
+    /// Índices en las caras ya trianguladas
+    pub caras: std::ops::Range<usize>,
 }
 
 impl ModeloOBJ {
+    /// Como `cargar` (o `cargar_gltf` para .gltf y .glb), pero un modelo sin caras también es
+    /// un error
+    pub fn abrir(ruta: &std::path::Path) -> Result<Self, String> {
+        let extension = ruta.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+        let modelo = match extension.as_deref() {
+            Some("gltf" | "glb") => ModeloOBJ::cargar_gltf(ruta)?,
+            _ => ModeloOBJ::cargar(&ruta.to_string_lossy()).map_err(|e| e.to_string())?,
+        };
+        if modelo.cantidad_caras() == 0 {
+            return Err("el modelo no tiene caras".to_string());
+        }
+        Ok(modelo)
+    }
+
+    pub fn cantidad_caras(&self) -> usize {
+        self.caras.len()
+    }
+
+    /// Grupos de caras con su material; las caras anteriores al primer `usemtl` no tienen
+    pub fn grupos(&self) -> &[GrupoMaterial] {
+        &self.grupos
+    }
+
     pub fn cargar(ruta_archivo: &str) -> Result<Self, std::io::Error> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-        
-        let archivo = File::open(ruta_archivo)?;
-        let lector = BufReader::new(archivo);
+        let archivo = std::fs::File::open(ruta_archivo)?;
+        ModeloOBJ::leer(std::io::BufReader::new(archivo), ruta_archivo)
+    }
 
+    /// Interpreta el texto de un OBJ; `ruta_archivo` ubica sus `mtllib` y nombra los errores
+    fn leer(lector: impl std::io::BufRead, ruta_archivo: &str) -> Result<Self, std::io::Error> {
         let mut lista_vertices = Vec::new();
         let mut lista_normales = Vec::new();
         let mut lista_uvs = Vec::new();
         let mut lista_caras = Vec::new();
+        let carpeta = std::path::Path::new(ruta_archivo).parent().unwrap_or(std::path::Path::new(""));
+        let mut materiales: Vec<MaterialMtl> = Vec::new();
+        let mut grupos: Vec<GrupoMaterial> = Vec::new();
 
-        for linea in lector.lines() {
+        for (numero, linea) in lector.lines().enumerate() {
             let linea = linea?;
             let partes: Vec<&str> = linea.split_whitespace().collect();
 
@@ -238,564 +1029,15411 @@ impl ModeloOBJ {
             }
 
             match partes[0] {
-                "v" => {
-                    if partes.len() >= 4 {
-                        let x: f32 = partes[1].parse().unwrap_or(0.0);
-                        let y: f32 = partes[2].parse().unwrap_or(0.0);
-                        let z: f32 = partes[3].parse().unwrap_or(0.0);
-                        lista_vertices.push(Vec3::new(x, y, z));
-                    }
+                "v" if partes.len() >= 4 => {
+                    let x: f32 = partes[1].parse().unwrap_or(0.0);
+                    let y: f32 = partes[2].parse().unwrap_or(0.0);
+                    let z: f32 = partes[3].parse().unwrap_or(0.0);
+                    lista_vertices.push(Vec3::new(x, y, z));
                 }
-                "vn" => {
-                    if partes.len() >= 4 {
-                        let x: f32 = partes[1].parse().unwrap_or(0.0);
-                        let y: f32 = partes[2].parse().unwrap_or(0.0);
-                        let z: f32 = partes[3].parse().unwrap_or(0.0);
-                        lista_normales.push(Vec3::new(x, y, z));
-                    }
+                "vn" if partes.len() >= 4 => {
+                    let x: f32 = partes[1].parse().unwrap_or(0.0);
+                    let y: f32 = partes[2].parse().unwrap_or(0.0);
+                    let z: f32 = partes[3].parse().unwrap_or(0.0);
+                    lista_normales.push(Vec3::new(x, y, z));
                 }
-                "vt" => {
-                    if partes.len() >= 3 {
-                        let u: f32 = partes[1].parse().unwrap_or(0.0);
-                        let v: f32 = partes[2].parse().unwrap_or(0.0);
-                        lista_uvs.push(Vec3::new(u, v, 0.0));
-                    }
+                "vt" if partes.len() >= 3 => {
+                    let u: f32 = partes[1].parse().unwrap_or(0.0);
+                    let v: f32 = partes[2].parse().unwrap_or(0.0);
+                    lista_uvs.push(Vec3::new(u, v, 0.0));
                 }
-                "f" => {
-                    if partes.len() >= 4 {
-                        let mut cara = [0; 9];
-                        for (i, parte) in partes.iter().skip(1).take(3).enumerate() {
-                            let indices: Vec<&str> = parte.split('/').collect();
-                            if !indices.is_empty() {
-                                cara[i * 3] = indices[0].parse::<usize>().unwrap_or(1) - 1;
-                            }
-                            if indices.len() > 1 && !indices[1].is_empty() {
-                                cara[i * 3 + 1] = indices[1].parse::<usize>().unwrap_or(1) - 1;
-                            }
-                            if indices.len() > 2 {
-                                cara[i * 3 + 2] = indices[2].parse::<usize>().unwrap_or(1) - 1;
+                "f" if partes.len() >= 4 => {
+                    // posición/uv/normal de cada esquina; un índice negativo cuenta desde el
+                    // último elemento leído hasta esta línea (-1 = el último)
+                    let cantidades = [lista_vertices.len(), lista_uvs.len(), lista_normales.len()];
+                    let esquinas: Vec<[usize; 3]> = partes
+                        .iter()
+                        .skip(1)
+                        .map(|parte| {
+                            let mut esquina = [0, 0, Self::SIN_NORMAL];
+                            for (i, indice) in parte.split('/').take(3).enumerate() {
+                                // `v//vn` no tiene uv
+                                if indice.is_empty() && i > 0 {
+                                    continue;
+                                }
+                                esquina[i] = Self::resolver_indice(indice, cantidades[i]).ok_or_else(|| {
+                                    std::io::Error::new(
+                                        std::io::ErrorKind::InvalidData,
+                                        format!("línea {}: índice de cara inválido '{}'", numero + 1, parte),
+                                    )
+                                })?;
                             }
+                            Ok(esquina)
+                        })
+                        .collect::<Result<_, std::io::Error>>()?;
+                    // Cuadriláteros y n-gonos en abanico desde la primera esquina
+                    for par in esquinas[1..].windows(2) {
+                        let mut cara = [0; 9];
+                        for (i, esquina) in [esquinas[0], par[0], par[1]].iter().enumerate() {
+                            cara[i * 3..i * 3 + 3].copy_from_slice(esquina);
                         }
                         lista_caras.push(cara);
                     }
                 }
+                // Un .mtl que falta no impide ver la malla: sus caras quedan con el material por defecto
+                "mtllib" if partes.len() >= 2 => {
+                    for archivo in &partes[1..] {
+                        match MaterialMtl::cargar(&carpeta.join(archivo)) {
+                            Ok(leidos) => materiales.extend(leidos),
+                            Err(error) => eprintln!("Materiales de {} ignorados: {}: {}", ruta_archivo, archivo, error),
+                        }
+                    }
+                }
+                "usemtl" if partes.len() >= 2 => {
+                    let material = materiales
+                        .iter()
+                        .find(|m| m.nombre == partes[1])
+                        .cloned()
+                        .unwrap_or_else(|| MaterialMtl::nuevo(partes[1]));
+                    if let Some(anterior) = grupos.last_mut() {
+                        anterior.caras.end = lista_caras.len();
+                    }
+                    grupos.push(GrupoMaterial {
+                        material,
+                        caras: lista_caras.len()..lista_caras.len(),
+                    });
+                }
                 _ => {}
             }
         }
+        if let Some(ultimo) = grupos.last_mut() {
+            ultimo.caras.end = lista_caras.len();
+        }
+        grupos.retain(|grupo| !grupo.caras.is_empty());
 
-        Ok(ModeloOBJ {
+        let mut modelo = ModeloOBJ {
             vertices: lista_vertices,
             normales: lista_normales,
             coordenadas_uv: lista_uvs,
             caras: lista_caras,
-        })
+            grupos,
+        };
+        modelo.completar_normales();
+        Ok(modelo)
     }
 
-    pub fn obtener_array_vertices(&self) -> Vec<Vertice> {
-        let mut array_vertices = Vec::new();
-
-        for cara in &self.caras {
-            for i in 0..3 {
-                let idx_posicion = cara[i * 3];
-                let idx_textura = cara[i * 3 + 1];
-                let idx_normal = cara[i * 3 + 2];
-
-                let posicion = self.vertices.get(idx_posicion).copied()
-                    .unwrap_or(Vec3::zeros());
-                let coord_tex = self.coordenadas_uv.get(idx_textura).copied()
-                    .unwrap_or(Vec3::zeros());
-                let normal = self.normales.get(idx_normal).copied()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+    /// Las mallas de triángulos de la escena de un .gltf o .glb (buffers en el .glb, en
+    /// archivos aparte o embebidos), con la transformación de cada nodo aplicada. Cada
+    /// primitiva es un grupo con el color base de su material; sus imágenes no se decodifican
+    pub fn cargar_gltf(ruta: &std::path::Path) -> Result<Self, String> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::open(ruta).map_err(|e| e.to_string())?;
+        let carpeta = ruta.parent().unwrap_or(std::path::Path::new(""));
+        let buffers = gltf::import_buffers(&document, Some(carpeta), blob).map_err(|e| e.to_string())?;
+        let escena = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or("el glTF no tiene escenas")?;
+        let mut modelo = ModeloOBJ {
+            vertices: Vec::new(),
+            normales: Vec::new(),
+            coordenadas_uv: Vec::new(),
+            caras: Vec::new(),
+            grupos: Vec::new(),
+        };
+        let mut nodos: Vec<(gltf::Node, Mat4)> = escena.nodes().map(|nodo| (nodo, Mat4::identity())).collect();
+        while let Some((nodo, padre)) = nodos.pop() {
+            let mundo = padre * Mat4::from(nodo.transform().matrix());
+            nodos.extend(nodo.children().map(|hijo| (hijo, mundo)));
+            let Some(malla) = nodo.mesh() else { continue };
+            let transformar_normal = nalgebra_glm::inverse_transpose(mundo);
+            for primitiva in malla.primitives().filter(|p| p.mode() == gltf::mesh::Mode::Triangles) {
+                let lector = primitiva.reader(|buffer| buffers.get(buffer.index()).map(|datos| &datos.0[..]));
+                let Some(posiciones) = lector.read_positions() else { continue };
+                let base = modelo.vertices.len();
+                modelo
+                    .vertices
+                    .extend(posiciones.map(|[x, y, z]| (mundo * nalgebra_glm::vec4(x, y, z, 1.0)).xyz()));
+                let cantidad = modelo.vertices.len() - base;
+                // Las UV que falten quedan en cero, así comparten el índice de la posición
+                let base_uv = modelo.coordenadas_uv.len();
+                let uvs = lector.read_tex_coords(0).map(|uvs| uvs.into_f32());
+                modelo.coordenadas_uv.extend(uvs.into_iter().flatten().map(|[u, v]| Vec3::new(u, 1.0 - v, 0.0)));
+                modelo.coordenadas_uv.resize(base_uv + cantidad, Vec3::zeros());
+                let base_normal = modelo.normales.len();
+                if let Some(normales) = lector.read_normals() {
+                    modelo.normales.extend(normales.map(|[x, y, z]| {
+                        (transformar_normal * nalgebra_glm::vec4(x, y, z, 0.0)).xyz().normalize()
+                    }));
+                }
+                let con_normales = modelo.normales.len() - base_normal == cantidad;
+                modelo.normales.truncate(if con_normales { base_normal + cantidad } else { base_normal });
 
-                array_vertices.push(Vertice::nuevo(posicion, normal, coord_tex));
+                let indices: Vec<usize> = match lector.read_indices() {
+                    Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                    None => (0..cantidad).collect(),
+                };
+                let inicio = modelo.caras.len();
+                for triangulo in indices.chunks_exact(3).filter(|t| t.iter().all(|&i| i < cantidad)) {
+                    let mut cara = [0; 9];
+                    for (esquina, &i) in triangulo.iter().enumerate() {
+                        let normal = if con_normales { base_normal + i } else { Self::SIN_NORMAL };
+                        cara[esquina * 3..esquina * 3 + 3].copy_from_slice(&[base + i, base_uv + i, normal]);
+                    }
+                    modelo.caras.push(cara);
+                }
+                modelo.grupos.push(GrupoMaterial {
+                    material: MaterialMtl::desde_gltf(&primitiva.material(), carpeta),
+                    caras: inicio..modelo.caras.len(),
+                });
             }
         }
-
-        array_vertices
+        modelo.grupos.retain(|grupo| !grupo.caras.is_empty());
+        modelo.completar_normales();
+        Ok(modelo)
     }
-}
+
+    /// Índice de normal de una esquina que no la trae (`f v` o `f v/vt`)
+    const SIN_NORMAL: usize = usize::MAX;
+
+    /// Da a las esquinas sin normal la normal suave de su posición: la suma de las normales
+    /// de las caras que la usan, pesadas por su área (el largo del producto cruz)
+    fn completar_normales(&mut self) {
+        if !self.caras.iter().any(|cara| (0..3).any(|i| cara[i * 3 + 2] == Self::SIN_NORMAL)) {
+            return;
+        }
+        let mut suaves = vec![Vec3::zeros(); self.vertices.len()];
+        let posicion = |indice: usize| self.vertices.get(indice).copied().unwrap_or(Vec3::zeros());
+        for cara in &self.caras {
+            let [a, b, c] = [cara[0], cara[3], cara[6]];
+            let normal = (posicion(b) - posicion(a)).cross(&(posicion(c) - posicion(a)));
+            for indice in [a, b, c] {
+                if let Some(suma) = suaves.get_mut(indice) {
+                    *suma += normal;
+                }
+            }
+        }
+        let base = self.normales.len();
+        self.normales.extend(suaves.into_iter().map(|suma| {
+            let largo = suma.norm();
+            if largo > f32::EPSILON { suma / largo } else { Vec3::y() }
+        }));
+        for cara in &mut self.caras {
+            for i in 0..3 {
+                if cara[i * 3 + 2] == Self::SIN_NORMAL {
+                    cara[i * 3 + 2] = base + cara[i * 3];
+                }
+            }
+        }
+    }
+
+    /// Índice de OBJ (desde 1, o negativo desde el final de los `cantidad` ya leídos) a base
+    /// 0; `None` si es 0 o cae fuera de los ya leídos
+    fn resolver_indice(texto: &str, cantidad: usize) -> Option<usize> {
+        match texto.parse::<isize>().ok()? {
+            0 => None,
+            positivo if positivo > 0 => (positivo as usize <= cantidad).then(|| positivo as usize - 1),
+            negativo => cantidad.checked_sub(negativo.unsigned_abs()),
+        }
+    }
+
+    pub fn obtener_array_vertices(&self) -> Vec<Vertice> {
+        let mut array_vertices = Vec::new();
+
+        for cara in &self.caras {
+            for i in 0..3 {
+                let idx_posicion = cara[i * 3];
+                let idx_textura = cara[i * 3 + 1];
+                let idx_normal = cara[i * 3 + 2];
+
+                let posicion = self.vertices.get(idx_posicion).copied()
+                    .unwrap_or(Vec3::zeros());
+                let coord_tex = self.coordenadas_uv.get(idx_textura).copied()
+                    .unwrap_or(Vec3::zeros());
+                let normal = self.normales.get(idx_normal).copied()
+                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+
+                array_vertices.push(Vertice::nuevo(posicion, normal, coord_tex));
+            }
+        }
+
+        array_vertices
+    }
+}
 
 // =============================================================================
-// MÓDULO: SHADERS (Simplificado - sin implementación completa de CPU)
+// MÓDULO: SHADERS (los de la GPU están en shader.wgsl; aquí los del pipeline de CPU)
 // =============================================================================
 
+/// Shaders de superficie del fragment principal (`tipo_shader`) con su nombre para la interfaz
+pub const TIPOS_SHADER: [(u32, &str); 7] = [
+    (1, "Sol"),
+    (2, "Rocoso"),
+    (3, "Gaseoso"),
+    (4, "Anillos"),
+    (5, "Volcánico"),
+    (6, "Luna"),
+    (7, "Latón"),
+];
+
+/// Shader de las piezas del planetario mecánico (ver `MecanismoPlanetario`)
+pub const TIPO_LATON: u32 = 7;
+
+/// Bit de `tipo_render` que pide el sombreado de caricatura; los de abajo son el tipo de shader
+const BANDERA_CARICATURA: u32 = 1 << 8;
+
+pub fn nombre_tipo_shader(tipo: u32) -> &'static str {
+    TIPOS_SHADER
+        .iter()
+        .find(|(t, _)| *t == tipo)
+        .map_or("Desconocido", |(_, nombre)| nombre)
+}
+
+pub fn tipo_shader_por_nombre(nombre: &str) -> Option<u32> {
+    TIPOS_SHADER
+        .iter()
+        .find(|(_, n)| n.to_lowercase() == nombre.to_lowercase())
+        .map(|(tipo, _)| *tipo)
+}
+
 pub struct UniformesCPU {
     pub projection_matrix: Mat4,
     pub view_matrix: Mat4,
     pub model_matrix: Mat4,
     pub viewport_matrix: Mat4,
-    pub time: u32,
+    /// Segundos de simulación, como `tiempo` en el WGSL
+    pub time: f32,
+    /// Luz puntual, en espacio de mundo
+    pub luz: Vec3,
+    /// Multiplicador del color y actividad del cuerpo que se dibuja (como `cuerpo()` en el WGSL)
+    pub tinte: Vec3,
+    pub actividad: f32,
 }
 
-// =============================================================================
-// APLICACIÓN PRINCIPAL CON WGPU
-// =============================================================================
+impl UniformesCPU {
+    /// Cámara y luz de un buffer `ancho`×`alto`; la matriz de modelo, el tinte y la actividad
+    /// se cambian por malla
+    pub fn nuevos(camara: &CamaraVirtual, ancho: usize, alto: usize, luz: Vec3, time: f32) -> Self {
+        UniformesCPU {
+            projection_matrix: camara.matriz_proyeccion(ancho as f32 / alto.max(1) as f32),
+            view_matrix: camara.matriz_vista(),
+            model_matrix: Mat4::identity(),
+            viewport_matrix: Self::matriz_viewport(ancho as f32, alto as f32),
+            time,
+            luz,
+            tinte: Vec3::new(1.0, 1.0, 1.0),
+            actividad: 1.0,
+        }
+    }
 
-/// Estructura de uniformes compartida con el GPU
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct DatosUniformes {
-    tiempo_actual: f32,
-    tipo_render: u32,
-    dimension_pantalla: [f32; 2],
-    pos_planeta: [f32; 2],
-    factor_escala: f32,
-    _espaciado: f32,
+    /// De NDC (x e y en [-1, 1], profundidad en [0, 1]) a píxeles con la y hacia abajo
+    pub fn matriz_viewport(ancho: f32, alto: f32) -> Mat4 {
+        Mat4::new(
+            ancho * 0.5, 0.0, 0.0, ancho * 0.5,
+            0.0, -alto * 0.5, 0.0, alto * 0.5,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
 }
 
-/// Estructura de vértice con posición y normal
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct VerticeEsfera {
-    posicion: [f32; 3],
-    normal: [f32; 3],
+/// Shader de fragmentos del pipeline de CPU. Cualquier `Fn(&Fragmento, &UniformesCPU) -> ColorRGB`
+/// también sirve
+pub trait ShaderFragmento {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB;
 }
 
-impl VerticeEsfera {
-    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<VerticeEsfera>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-            ],
+impl<F: Fn(&Fragmento, &UniformesCPU) -> ColorRGB> ShaderFragmento for F {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        self(frag, uniformes)
+    }
+}
+
+/// Shader de CPU equivalente a la rama `tipo_shader` de `fragment_principal`
+pub fn shader_cpu(tipo_shader: u32) -> &'static dyn ShaderFragmento {
+    match tipo_shader {
+        1 => &ShaderSol,
+        2 => &ShaderRocoso,
+        3 => &ShaderGaseoso,
+        4 => &ShaderAnillos,
+        5 => &ShaderVolcanico,
+        6 => &ShaderLuna,
+        TIPO_LATON => &ShaderLaton,
+        _ => &ShaderDesconocido,
+    }
+}
+
+/// Los mismos hash, ruido de valor, fbm y Voronoi que el WGSL, para que los patrones coincidan
+fn hash_3d(p: Vec3) -> f32 {
+    let h = (p.x * 127.1 + p.y * 311.7 + p.z * 74.7).sin() * 43_758.547;
+    h - h.floor()
+}
+
+fn ruido_suave(p: Vec3) -> f32 {
+    let i = p.map(f32::floor);
+    let f = p - i;
+    let u = f.component_mul(&f).component_mul(&(Vec3::new(3.0, 3.0, 3.0) - f * 2.0));
+    let a = hash_3d(i);
+    let b = hash_3d(i + Vec3::new(1.0, 0.0, 0.0));
+    let c = hash_3d(i + Vec3::new(0.0, 1.0, 0.0));
+    let d = hash_3d(i + Vec3::new(1.0, 1.0, 0.0));
+    let x1 = a + (b - a) * u.x;
+    let x2 = c + (d - c) * u.x;
+    x1 + (x2 - x1) * u.y
+}
+
+fn fbm_ruido(p: Vec3, octavas: i32) -> f32 {
+    let (mut valor, mut amplitud, mut frecuencia) = (0.0, 0.5, 1.0);
+    for _ in 0..octavas {
+        valor += amplitud * ruido_suave(p * frecuencia);
+        frecuencia *= 2.0;
+        amplitud *= 0.5;
+    }
+    valor
+}
+
+fn patron_voronoi(p: Vec3) -> f32 {
+    let celda_int = p.map(f32::floor);
+    let celda_frac = p - celda_int;
+    let mut dist_minima: f32 = 2.0;
+    for i in -1..=1 {
+        for j in -1..=1 {
+            let vecino = Vec3::new(i as f32, j as f32, 0.0);
+            let punto = vecino
+                + Vec3::new(
+                    hash_3d(celda_int + vecino),
+                    hash_3d(celda_int + vecino + Vec3::new(0.1, 0.1, 0.1)),
+                    0.0,
+                );
+            let diferencia = punto - celda_frac;
+            dist_minima = dist_minima.min(diferencia.xy().dot(&diferencia.xy()));
         }
     }
+    dist_minima.sqrt()
 }
 
-fn generar_esfera(subdivisiones: u32) -> (Vec<VerticeEsfera>, Vec<u16>) {
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
+fn mezclar(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    a + (b - a) * t
+}
 
-    for latitud in 0..=subdivisiones {
-        let theta = latitud as f32 * std::f32::consts::PI / subdivisiones as f32;
-        let seno_theta = theta.sin();
-        let coseno_theta = theta.cos();
+/// Dirección del fragmento en el marco del cuerpo, como `pos_normalizada` en el WGSL
+fn direccion_cuerpo(frag: &Fragmento) -> Vec3 {
+    frag.posicion_cuerpo.try_normalize(f32::EPSILON).unwrap_or(frag.posicion_cuerpo)
+}
 
-        for longitud in 0..=subdivisiones {
-            let phi = longitud as f32 * 2.0 * std::f32::consts::PI / subdivisiones as f32;
-            let seno_phi = phi.sin();
-            let coseno_phi = phi.cos();
+/// Color de superficie teñido e iluminado: Lambert con una luz ambiente
+fn iluminar(superficie: Vec3, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+    let color = superficie.component_mul(&uniformes.tinte) * (0.08 + 0.92 * frag.intensidad);
+    ColorLineal::from(color).a_srgb()
+}
 
-            let coord_x = seno_theta * coseno_phi;
-            let coord_y = coseno_theta;
-            let coord_z = seno_theta * seno_phi;
+/// Shader 1: plasma animado con manchas y corona; solo emite
+pub struct ShaderSol;
 
-            vertices.push(VerticeEsfera {
-                posicion: [coord_x, coord_y, coord_z],
-                normal: [coord_x, coord_y, coord_z],
-            });
+impl ShaderFragmento for ShaderSol {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let t = uniformes.time;
+        let p = direccion_cuerpo(frag) * 3.0;
+        let plasma1 = fbm_ruido(p + Vec3::new(t, t * 0.5, 0.0), 5);
+        let plasma2 = fbm_ruido(p * 2.0 - Vec3::new(t * 0.7, t * 1.1, t * 0.3), 4);
+        let plasma3 = fbm_ruido(p * 0.5 + Vec3::new((t * 0.5).cos(), (t * 0.5).sin(), t * 0.2), 4);
+        let plasma4 = fbm_ruido(p * 4.0 + Vec3::new(t * 0.3, t * 0.8, 0.0), 3);
+        let combinado = (plasma1 * 0.4 + plasma2 * 0.3 + plasma3 * 0.2 + plasma4 * 0.1).clamp(0.0, 1.0);
+
+        let angulo = p.y.atan2(p.x);
+        let radio = p.xy().norm();
+        let espiral = (angulo * 6.0 + radio * 4.0 - t * 2.0 + combinado * 2.0).sin() * 0.5 + 0.5;
+        let manchas = if fbm_ruido(p * 3.0 + Vec3::new(t * 0.05, 0.0, 0.0), 3) > 0.68 { 0.4 } else { 1.0 };
+        let corona = (1.0 - p.norm() * 0.4).max(0.0).powi(3) * 1.5;
+        let pulso = ((t * 2.0).sin() * 0.2 + 1.2) * uniformes.actividad;
+
+        let temperatura = combinado * espiral * 1.2;
+        let base = match temperatura {
+            x if x > 0.8 => Vec3::new(1.0, 0.95, 0.7),
+            x if x > 0.6 => Vec3::new(1.0, 0.85, 0.3),
+            x if x > 0.4 => Vec3::new(1.0, 0.6, 0.1),
+            _ => Vec3::new(0.95, 0.4, 0.05),
+        };
+        let color = (base * manchas * (1.0 + corona * pulso * 0.8)).component_mul(&uniformes.tinte);
+        ColorLineal::from(color).a_srgb()
+    }
+}
+
+/// Shader 2: terreno de tipo Marte con casquetes que crecen con la actividad y tormentas de polvo
+pub struct ShaderRocoso;
+
+impl ShaderFragmento for ShaderRocoso {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let t = uniformes.time;
+        let p = direccion_cuerpo(frag) * 5.0;
+        let altura = patron_voronoi(p * 0.8) * 0.55 + fbm_ruido(p * 3.0, 3) * 0.3 * 0.3 + fbm_ruido(p * 8.0, 2) * 0.15 * 0.15;
+        let limite_hielo = 0.65 - 0.1 * (uniformes.actividad - 1.0);
+        let mut color = if altura < 0.35 {
+            if fbm_ruido(p * 6.0, 2) > 0.6 { Vec3::new(0.85, 0.35, 0.15) } else { Vec3::new(0.7, 0.25, 0.1) }
+        } else if altura >= limite_hielo {
+            Vec3::new(0.95, 0.95, 1.0)
+        } else if altura >= 0.55 {
+            if fbm_ruido(p * 10.0, 2) > 0.5 { Vec3::new(0.65, 0.22, 0.12) } else { Vec3::new(0.5, 0.18, 0.09) }
+        } else {
+            match fbm_ruido(p * 5.0, 3) {
+                v if v > 0.65 => Vec3::new(0.75, 0.32, 0.12),
+                v if v > 0.4 => Vec3::new(0.6, 0.22, 0.11),
+                _ => Vec3::new(0.45, 0.16, 0.09),
+            }
+        };
+
+        let tormenta1 = fbm_ruido(p * 2.0 + Vec3::new(t * 15.0, 0.0, t * 8.0), 3);
+        let tormenta2 = fbm_ruido(p * 4.0 - Vec3::new(t * 10.0, 0.0, t * 5.0), 2);
+        let polvo = (tormenta1 * 0.7 + tormenta2 * 0.3).clamp(0.0, 1.0);
+        if polvo > 0.6 {
+            let densidad = ((polvo - 0.6) / 0.4).min(1.0);
+            color = mezclar(color, Vec3::new(0.8, 0.4, 0.2), densidad * 0.5);
         }
+        iluminar(color, frag, uniformes)
     }
+}
 
-    for lat in 0..subdivisiones {
-        for lon in 0..subdivisiones {
-            let primero = (lat * (subdivisiones + 1) + lon) as u16;
-            let segundo = primero + subdivisiones as u16 + 1;
+/// Shader 3: gigante gaseoso con bandas turbulentas y una gran mancha roja
+pub struct ShaderGaseoso;
 
-            indices.push(primero);
-            indices.push(segundo);
-            indices.push(primero + 1);
+impl ShaderFragmento for ShaderGaseoso {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let t = uniformes.time;
+        let p = direccion_cuerpo(frag) * 3.5;
+        let turb1 = fbm_ruido(p * 2.0 + Vec3::new(t * 1.5, 0.0, 0.0), 3) * 2.0;
+        let turb2 = fbm_ruido(p * 4.0 - Vec3::new(t * 0.8, 0.0, t * 0.5), 2) * 0.8;
+        let bandas = (p.y * 18.0 + turb1 + turb2).sin() * 0.5 + 0.5;
+        let caos = fbm_ruido(p * 3.0 + Vec3::new(t, 0.0, 0.0), 3);
+        let valor = (bandas * 0.6 + caos * 0.4).clamp(0.0, 1.0);
 
-            indices.push(segundo);
-            indices.push(segundo + 1);
-            indices.push(primero + 1);
+        let mut color = Vec3::from(Paleta::bandas_gas().muestrear_lineal(valor));
+
+        let (dx, dy, dz) = (p.x - 0.6, (p.y + 0.3) * 1.4, p.z);
+        let distancia = (dx * dx + dy * dy + dz * dz).sqrt();
+        if distancia < 0.5 {
+            let factor = (1.0 - distancia / 0.5).max(0.0);
+            let remolino = (dy.atan2(dx) * 5.0 + distancia * 15.0 - t * 3.0).sin() * 0.5 + 0.5;
+            let rojo = if remolino > 0.6 { Vec3::new(1.0, 0.3, 0.15) } else { Vec3::new(0.9, 0.2, 0.1) };
+            color = mezclar(color, rojo, factor * (0.7 + remolino * 0.3) * 0.95);
         }
+        iluminar(color, frag, uniformes)
     }
+}
 
-    (vertices, indices)
+/// Shader 4: bandas púrpuras con la sombra de los anillos sobre el ecuador
+pub struct ShaderAnillos;
+
+impl ShaderFragmento for ShaderAnillos {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let p = direccion_cuerpo(frag) * 3.0;
+        let bandas = (p.y * 20.0 + fbm_ruido(p, 2) * 0.5).sin() * 0.5 + 0.5;
+        let mut color = mezclar(Vec3::new(0.5, 0.3, 0.7), Vec3::new(0.7, 0.5, 0.9), bandas);
+        let dist_anillo = nalgebra_glm::vec2(p.x, p.z).norm();
+        if p.y.abs() < 0.2 && dist_anillo < 0.9 {
+            color *= 0.6 + ((dist_anillo * 50.0).sin() * 0.5 + 0.5) * 0.3;
+        }
+        iluminar(color, frag, uniformes)
+    }
 }
 
-struct EstadoAplicacion {
-    superficie: wgpu::Surface<'static>,
-    dispositivo: wgpu::Device,
-    cola_comandos: wgpu::Queue,
-    configuracion: wgpu::SurfaceConfiguration,
-    tamano_ventana: winit::dpi::PhysicalSize<u32>,
-    pipeline_render: wgpu::RenderPipeline,
-    buffer_vertices: wgpu::Buffer,
-    buffer_indices: wgpu::Buffer,
-    cantidad_indices: u32,
-    buffer_uniformes: wgpu::Buffer,
-    grupo_bind_uniformes: wgpu::BindGroup,
-    datos_uniformes: DatosUniformes,
-    rotacion_camara: [f32; 2],
-    tiempo_inicio: std::time::Instant,
-    posicion_mouse: Option<winit::dpi::PhysicalPosition<f64>>,
-    mouse_presionado: bool,
+/// Shader 5: roca agrietada con lava que pulsa; la lava brilla también en la noche
+pub struct ShaderVolcanico;
+
+impl ShaderFragmento for ShaderVolcanico {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let t = uniformes.time;
+        let p = direccion_cuerpo(frag) * 4.0;
+        let es_lava = patron_voronoi(p * 1.5) < 0.4 || fbm_ruido(p * 8.0 + Vec3::new(t, 0.0, 0.0), 3) > 0.8;
+        let color = if es_lava {
+            let pulso = (t * 5.0).sin() * 0.25 + 0.75;
+            let calor = fbm_ruido(p * 2.0 + Vec3::new(t * 2.0, 0.0, t), 3);
+            Vec3::from(Paleta::lava().escalon(calor)) * pulso
+        } else if fbm_ruido(p * 10.0, 2) > 0.6 {
+            Vec3::new(0.1, 0.2, 0.2)
+        } else {
+            Vec3::new(0.05, 0.1, 0.1)
+        };
+        iluminar(color, frag, uniformes)
+    }
 }
 
-impl EstadoAplicacion {
-    async fn inicializar(ventana: Arc<Window>) -> Self {
-        let tamano_ventana = ventana.inner_size();
+/// Shader 6: hielo con cráteres, mares y tierras altas
+pub struct ShaderLuna;
 
-        let instancia = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
+impl ShaderFragmento for ShaderLuna {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let p = direccion_cuerpo(frag) * 5.0;
+        let color = if patron_voronoi(p * 1.2) < 0.25 {
+            if fbm_ruido(p * 15.0, 2) > 0.6 { Vec3::new(0.65, 0.75, 0.85) } else { Vec3::new(0.7, 0.8, 0.9) }
+        } else if fbm_ruido(p * 0.8, 3) < 0.3 {
+            Vec3::from(Paleta::hielo().escalon(0.0))
+        } else if fbm_ruido(p * 2.0, 2) > 0.7 {
+            Vec3::from(Paleta::hielo().escalon(1.0))
+        } else if fbm_ruido(p * 6.0, 2) > 0.5 {
+            Vec3::new(0.85, 0.92, 0.97)
+        } else {
+            Vec3::from(Paleta::hielo().escalon(0.5))
+        };
+        iluminar(color, frag, uniformes)
+    }
+}
 
-        let superficie = instancia.create_surface(ventana.clone()).unwrap();
+/// Shader 7: latón cepillado; las vetas usan la posición sin normalizar, como en el WGSL
+pub struct ShaderLaton;
 
-        let adaptador = instancia
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&superficie),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+impl ShaderFragmento for ShaderLaton {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        let pos = frag.posicion_cuerpo;
+        let vetas = ruido_suave(pos.component_mul(&Vec3::new(4.0, 4.0, 90.0))) * 0.6
+            + ruido_suave(pos.component_mul(&Vec3::new(90.0, 90.0, 4.0))) * 0.4;
+        iluminar(mezclar(Vec3::new(0.60, 0.42, 0.16), Vec3::new(0.86, 0.68, 0.34), vetas), frag, uniformes)
+    }
+}
 
-        let (dispositivo, cola_comandos) = adaptador
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .unwrap();
-
-        let capacidades_superficie = superficie.get_capabilities(&adaptador);
-        let formato_superficie = capacidades_superficie
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(capacidades_superficie.formats[0]);
-
-        let configuracion = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: formato_superficie,
-            width: tamano_ventana.width,
-            height: tamano_ventana.height,
-            present_mode: capacidades_superficie
-                .present_modes
-                .iter()
-                .copied()
-                .find(|m| m == &wgpu::PresentMode::Fifo)
-                .unwrap_or(capacidades_superficie.present_modes[0]),
-            alpha_mode: capacidades_superficie.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        superficie.configure(&dispositivo, &configuracion);
+/// Magenta, como la rama `default` del WGSL
+struct ShaderDesconocido;
 
-        let (vertices, indices) = generar_esfera(50);
-        let cantidad_indices = indices.len() as u32;
+impl ShaderFragmento for ShaderDesconocido {
+    fn sombrear(&self, frag: &Fragmento, uniformes: &UniformesCPU) -> ColorRGB {
+        iluminar(Vec3::new(1.0, 0.0, 1.0), frag, uniformes)
+    }
+}
 
-        let buffer_vertices = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Buffer de Vértices"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+// =============================================================================
+// MÓDULO: PIPELINE DE CPU
+// =============================================================================
 
-        let buffer_indices = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Buffer de Índices"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+/// Vértice a la salida del vertex shader: en espacio de recorte, con la posición y la normal
+/// de mundo, la posición en el cuerpo y las UV que se interpolan hasta el fragmento
+#[derive(Debug, Clone, Copy)]
+struct VerticeRecorte {
+    recorte: nalgebra_glm::Vec4,
+    mundo: Vec3,
+    normal: Vec3,
+    cuerpo: Vec3,
+    uv: Vec3,
+}
 
-        let datos_uniformes = DatosUniformes {
-            tiempo_actual: 0.0,
-            tipo_render: 1,
-            dimension_pantalla: [tamano_ventana.width as f32, tamano_ventana.height as f32],
-            pos_planeta: [0.0, 0.0],
-            factor_escala: 0.3,
-            _espaciado: 0.0,
-        };
+impl VerticeRecorte {
+    fn mezclar(&self, otro: &Self, t: f32) -> Self {
+        VerticeRecorte {
+            recorte: self.recorte.lerp(&otro.recorte, t),
+            mundo: self.mundo.lerp(&otro.mundo, t),
+            normal: self.normal.lerp(&otro.normal, t),
+            cuerpo: self.cuerpo.lerp(&otro.cuerpo, t),
+            uv: self.uv.lerp(&otro.uv, t),
+        }
+    }
+}
 
-        let buffer_uniformes = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Buffer de Uniformes"),
-            contents: bytemuck::cast_slice(&[datos_uniformes]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+/// Transforma el vértice con las matrices de `uniformes`; deja la posición de mundo y la
+/// normal en sus campos `_transformada`
+pub fn shader_vertices(vertice: &Vertice, uniformes: &UniformesCPU) -> (Vertice, nalgebra_glm::Vec4) {
+    let p = vertice.posicion;
+    let mundo = uniformes.model_matrix * nalgebra_glm::vec4(p.x, p.y, p.z, 1.0);
+    let recorte = uniformes.projection_matrix * uniformes.view_matrix * mundo;
+    let n = vertice.vector_normal;
+    let normal = (uniformes.model_matrix * nalgebra_glm::vec4(n.x, n.y, n.z, 0.0)).xyz();
+    let mut transformado = vertice.clone();
+    transformado.posicion_transformada = mundo.xyz();
+    transformado.normal_transformada = normal.try_normalize(f32::EPSILON).unwrap_or(normal);
+    (transformado, recorte)
+}
 
-        let layout_bind_group_uniformes =
-            dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-                label: Some("Layout de Bind Group de Uniformes"),
-            });
+/// Distancia con signo de un punto de recorte a cada plano del volumen de visión; es
+/// negativa fuera
+const PLANOS_RECORTE: [fn(&nalgebra_glm::Vec4) -> f32; 6] = [
+    |c| c.w + c.x,
+    |c| c.w - c.x,
+    |c| c.w + c.y,
+    |c| c.w - c.y,
+    |c| c.z,
+    |c| c.w - c.z,
+];
 
-        let grupo_bind_uniformes = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &layout_bind_group_uniformes,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer_uniformes.as_entire_binding(),
-            }],
-            label: Some("Bind Group de Uniformes"),
-        });
+/// Sutherland–Hodgman contra los seis planos del volumen de visión (los de `Frustum`, en
+/// espacio de recorte): devuelve el polígono convexo que queda, vacío si nada entra
+fn recortar_poligono(mut poligono: Vec<VerticeRecorte>) -> Vec<VerticeRecorte> {
+    for distancia in PLANOS_RECORTE {
+        if poligono.is_empty() {
+            break;
+        }
+        let mut recortado = Vec::with_capacity(poligono.len() + 1);
+        for (i, actual) in poligono.iter().enumerate() {
+            let siguiente = &poligono[(i + 1) % poligono.len()];
+            let (da, ds) = (distancia(&actual.recorte), distancia(&siguiente.recorte));
+            if da >= 0.0 {
+                recortado.push(*actual);
+            }
+            if (da >= 0.0) != (ds >= 0.0) {
+                recortado.push(actual.mezclar(siguiente, da / (da - ds)));
+            }
+        }
+        poligono = recortado;
+    }
+    poligono
+}
 
-        // Shader WGSL embebido
-        let codigo_shader = include_str!("shader.wgsl");
-        
-        let modulo_shader = dispositivo.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Módulo de Shader Principal"),
-            source: wgpu::ShaderSource::Wgsl(codigo_shader.into()),
-        });
+/// Vértice en píxeles tras la división de perspectiva, listo para
+/// `BufferDePantalla::dibujar_triangulo`: conserva la w de recorte para que los atributos
+/// se interpolen con corrección de perspectiva
+fn a_pantalla(vertice: &VerticeRecorte, viewport: &Mat4) -> Vertice {
+    let c = vertice.recorte;
+    let ndc = nalgebra_glm::vec4(c.x / c.w, c.y / c.w, c.z / c.w, 1.0);
+    let mut pantalla = Vertice::nuevo(vertice.cuerpo, vertice.normal, vertice.uv);
+    pantalla.posicion_transformada = (viewport * ndc).xyz();
+    pantalla.posicion_mundo = vertice.mundo;
+    pantalla.w = c.w;
+    pantalla
+}
 
-        let layout_pipeline_render =
-            dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Layout del Pipeline de Render"),
-                bind_group_layouts: &[&layout_bind_group_uniformes],
-                push_constant_ranges: &[],
-            });
+/// Rasteriza un triángulo en pantalla con `dibujar_triangulo`, prueba la profundidad de cada
+/// fragmento y sombrea solo los que pasan, con la luz difusa en la intensidad
+fn rasterizar_triangulo(
+    buffer: &mut BufferDePantalla,
+    vertices: &[Vertice; 3],
+    uniformes: &UniformesCPU,
+    sombreador: &dyn ShaderFragmento,
+) {
+    for mut fragmento in buffer.dibujar_triangulo(vertices) {
+        let (x, y) = (fragmento.posicion.x as usize, fragmento.posicion.y as usize);
+        if !buffer.pasa_profundidad(x, y, fragmento.profundidad) {
+            continue;
+        }
+        let hacia_luz = (uniformes.luz - fragmento.posicion_vertice).try_normalize(f32::EPSILON).unwrap_or(Vec3::z());
+        fragmento.intensidad = fragmento.normal.dot(&hacia_luz).max(0.0);
+        buffer.establecer_color_actual(sombreador.sombrear(&fragmento, uniformes).a_hexadecimal());
+        buffer.dibujar_punto(x, y, fragmento.profundidad);
+    }
+}
 
-        let pipeline_render = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Pipeline de Renderizado Principal"),
-            layout: Some(&layout_pipeline_render),
-            vertex: wgpu::VertexState {
-                module: &modulo_shader,
-                entry_point: "vertex_principal",
-                buffers: &[VerticeEsfera::descriptor_layout()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &modulo_shader,
-                entry_point: "fragment_principal",
-                targets: &[Some(wgpu::ColorTargetState {
+/// Recorta el segmento `a`–`b` de espacio de recorte al volumen de visión (Liang–Barsky con
+/// los mismos planos que los triángulos); `None` si queda entero afuera
+fn recortar_segmento(a: nalgebra_glm::Vec4, b: nalgebra_glm::Vec4) -> Option<(nalgebra_glm::Vec4, nalgebra_glm::Vec4)> {
+    let (mut desde, mut hasta) = (0.0_f32, 1.0_f32);
+    for distancia in PLANOS_RECORTE {
+        let (da, db) = (distancia(&a), distancia(&b));
+        if da < 0.0 && db < 0.0 {
+            return None;
+        }
+        if da < 0.0 {
+            desde = desde.max(da / (da - db));
+        } else if db < 0.0 {
+            hasta = hasta.min(da / (da - db));
+        }
+    }
+    (desde <= hasta).then(|| (a.lerp(&b, desde), a.lerp(&b, hasta)))
+}
+
+/// Traza con `dibujar_linea`, en el color actual de `buffer`, el segmento entre dos puntos
+/// del marco de `uniformes.model_matrix`; la profundidad es la de NDC, como la de los
+/// triángulos, así las caras tapan lo que pasa por detrás
+pub fn renderizar_segmento_cpu(buffer: &mut BufferDePantalla, a: &Vec3, b: &Vec3, uniformes: &UniformesCPU) {
+    let matriz = uniformes.projection_matrix * uniformes.view_matrix * uniformes.model_matrix;
+    let recorte = |p: &Vec3| matriz * nalgebra_glm::vec4(p.x, p.y, p.z, 1.0);
+    let Some((inicio, fin)) = recortar_segmento(recorte(a), recorte(b)) else { return };
+    let pantalla = |c: nalgebra_glm::Vec4| (uniformes.viewport_matrix * nalgebra_glm::vec4(c.x / c.w, c.y / c.w, c.z / c.w, 1.0)).xyz();
+    let (inicio, fin) = (pantalla(inicio), pantalla(fin));
+    buffer.dibujar_linea(
+        inicio.x.floor() as i32,
+        inicio.y.floor() as i32,
+        fin.x.floor() as i32,
+        fin.y.floor() as i32,
+        (inicio.z, fin.z),
+    );
+}
+
+/// Aristas de cada triángulo de `vertices` (el formato de `renderizar_triangulos_cpu`) en el
+/// color actual de `buffer`, sin rellenar
+pub fn renderizar_alambre_cpu(buffer: &mut BufferDePantalla, vertices: &[Vertice], uniformes: &UniformesCPU) {
+    for triangulo in vertices.chunks_exact(3) {
+        for k in 0..3 {
+            renderizar_segmento_cpu(buffer, &triangulo[k].posicion, &triangulo[(k + 1) % 3].posicion, uniformes);
+        }
+    }
+}
+
+/// Pipeline completo por software para una lista de triángulos (tres `Vertice` por cara, como
+/// los de `ModeloOBJ::obtener_array_vertices`): vertex shader, recorte, división de
+/// perspectiva, viewport, rasterizado, prueba de profundidad y `sombreador` por fragmento.
+/// Sin descarte de caras traseras: la profundidad resuelve la visibilidad
+pub fn renderizar_triangulos_cpu(
+    buffer: &mut BufferDePantalla,
+    vertices: &[Vertice],
+    uniformes: &UniformesCPU,
+    sombreador: &dyn ShaderFragmento,
+) {
+    for triangulo in vertices.chunks_exact(3) {
+        let recorte: Vec<VerticeRecorte> = triangulo
+            .iter()
+            .map(|vertice| {
+                let (transformado, recorte) = shader_vertices(vertice, uniformes);
+                VerticeRecorte {
+                    recorte,
+                    mundo: transformado.posicion_transformada,
+                    normal: transformado.normal_transformada,
+                    cuerpo: vertice.posicion,
+                    uv: vertice.coordenadas_textura,
+                }
+            })
+            .collect();
+        let poligono: Vec<Vertice> = recortar_poligono(recorte)
+            .iter()
+            .map(|vertice| a_pantalla(vertice, &uniformes.viewport_matrix))
+            .collect();
+        // El polígono recortado es convexo: se reparte en abanico
+        for i in 1..poligono.len().saturating_sub(1) {
+            let triangulo = [poligono[0].clone(), poligono[i].clone(), poligono[i + 1].clone()];
+            rasterizar_triangulo(buffer, &triangulo, uniformes, sombreador);
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: ESCENA
+// =============================================================================
+
+/// Kilómetros que equivalen a una unidad de simulación bajo escala realista (1 UA)
+pub const KM_POR_UNIDAD: f64 = 149_597_870.7;
+
+/// Distancia inicial de la cámara al centro de la escena, en unidades de simulación
+pub const DISTANCIA_OBSERVADOR: f32 = 1.8;
+
+/// Órbita circular alrededor del origen (donde está el Sol)
+#[derive(Debug, Clone, Copy)]
+pub struct Orbita {
+    pub semieje_mayor: f32,
+    /// 0 = circular; debe ser menor que 1
+    pub excentricidad: f32,
+    /// Dirección del periapsis sobre el plano de la eclíptica, en radianes
+    pub argumento_periapsis: f32,
+    /// Anomalía media en t = 0
+    pub fase_inicial: f32,
+    /// Segundos de simulación por vuelta
+    pub periodo: f32,
+}
+
+impl Orbita {
+    pub const EXCENTRICIDAD_MAXIMA: f32 = 0.95;
+
+    /// Órbita circular que pasa por `posicion` en t = 0
+    pub fn desde_posicion(posicion: [f32; 2], periodo: f32) -> Self {
+        Orbita {
+            semieje_mayor: (posicion[0] * posicion[0] + posicion[1] * posicion[1]).sqrt(),
+            excentricidad: 0.0,
+            argumento_periapsis: posicion[1].atan2(posicion[0]),
+            fase_inicial: 0.0,
+            periodo,
+        }
+    }
+
+    pub fn periapsis(&self) -> f32 {
+        self.semieje_mayor * (1.0 - self.excentricidad)
+    }
+
+    pub fn apoapsis(&self) -> f32 {
+        self.semieje_mayor * (1.0 + self.excentricidad)
+    }
+
+    /// Rehace la elipse a partir de sus distancias extremas, conservando orientación y periodo
+    pub fn ajustar_extremos(&mut self, periapsis: f32, apoapsis: f32) {
+        let (cerca, lejos) = if periapsis <= apoapsis { (periapsis, apoapsis) } else { (apoapsis, periapsis) };
+        self.semieje_mayor = (cerca + lejos) / 2.0;
+        self.excentricidad = ((lejos - cerca) / (lejos + cerca)).min(Self::EXCENTRICIDAD_MAXIMA);
+    }
+
+    /// Punto de la elipse a una anomalía verdadera dada (ángulo medido desde el periapsis)
+    pub fn punto_en_anomalia(&self, anomalia_verdadera: f32) -> [f32; 2] {
+        let e = self.excentricidad;
+        let r = self.semieje_mayor * (1.0 - e * e) / (1.0 + e * anomalia_verdadera.cos());
+        let angulo = self.argumento_periapsis + anomalia_verdadera;
+        [r * angulo.cos(), r * angulo.sin()]
+    }
+
+    /// Cambia el periodo sin que el cuerpo salte: la anomalía media en `tiempo` se conserva
+    pub fn cambiar_periodo(&mut self, periodo: f32, tiempo: f32) {
+        let vueltas_antes = self.vueltas(tiempo);
+        self.periodo = periodo;
+        self.fase_inicial += (vueltas_antes - self.vueltas(tiempo)) * 2.0 * std::f32::consts::PI;
+    }
+
+    /// Instante de un paso por el periapsis (anomalía media cero)
+    pub fn paso_por_periapsis(&self) -> f32 {
+        -self.fase_inicial / (2.0 * std::f32::consts::PI) * self.periodo
+    }
+
+    /// Área que barre el radio vector por segundo; constante en toda la vuelta (segunda ley)
+    pub fn area_por_segundo(&self) -> f32 {
+        let a = self.semieje_mayor;
+        std::f32::consts::PI * a * a * (1.0 - self.excentricidad * self.excentricidad).sqrt() / self.periodo
+    }
+
+    /// Vueltas (fraccionarias) recorridas en el instante `tiempo`
+    pub fn vueltas(&self, tiempo: f32) -> f32 {
+        tiempo / self.periodo
+    }
+
+    pub fn posicion(&self, tiempo: f32) -> [f32; 2] {
+        let media = (self.fase_inicial + self.vueltas(tiempo) * 2.0 * std::f32::consts::PI)
+            .rem_euclid(2.0 * std::f32::consts::PI);
+        // Ecuación de Kepler E - e·sin(E) = M por Newton
+        let e = self.excentricidad;
+        let mut excentrica = if e > 0.8 { std::f32::consts::PI } else { media };
+        for _ in 0..8 {
+            excentrica -= (excentrica - e * excentrica.sin() - media) / (1.0 - e * excentrica.cos());
+        }
+        let x = self.semieje_mayor * (excentrica.cos() - e);
+        let y = self.semieje_mayor * (1.0 - e * e).sqrt() * excentrica.sin();
+        let (seno, coseno) = self.argumento_periapsis.sin_cos();
+        [x * coseno - y * seno, x * seno + y * coseno]
+    }
+
+    /// Rapidez respecto al foco por vis-viva, con μ = 4π²a³/T²
+    pub fn rapidez(&self, tiempo: f32) -> f32 {
+        let a = self.semieje_mayor;
+        let [x, y] = self.posicion(tiempo);
+        let r = (x * x + y * y).sqrt().max(1e-6);
+        let mu = 4.0 * std::f32::consts::PI * std::f32::consts::PI * a * a * a / (self.periodo * self.periodo);
+        (mu * (2.0 / r - 1.0 / a)).max(0.0).sqrt()
+    }
+}
+
+/// Clase de un cuerpo, usada para agruparlo en capas
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaseCuerpo {
+    Estrella,
+    Planeta,
+    Luna,
+    Asteroide,
+}
+
+/// Partículas que un cuerpo emite sin parar (ver `EfectosParticulas`)
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EfectoCuerpo {
+    Llamaradas,
+    /// Cola de cometa que se aleja de la estrella
+    Cola,
+    Ninguno,
+}
+
+/// Cuerpo celeste de la escena con su configuración de render y datos físicos reales
+#[derive(Debug, Clone)]
+pub struct CuerpoCeleste {
+    pub nombre: String,
+    /// Posición de mundo; la calcula el grafo de escena a partir de `posicion_local`
+    pub posicion: [f32; 2],
+    /// Posición respecto al padre
+    pub posicion_local: [f32; 2],
+    pub escala: f32,
+    pub tipo_shader: u32,
+    pub radio_km: f64,
+    /// Magnitud absoluta H (para el Sol: magnitud aparente a 1 UA)
+    pub magnitud_absoluta: f64,
+    pub orbita: Option<Orbita>,
+    /// Cuerpo del que depende en el árbol de la escena (un planeta para sus lunas)
+    pub padre: Option<usize>,
+    pub visible: bool,
+    pub clase: ClaseCuerpo,
+    /// Intensidad del efecto propio del shader (corona del Sol, casquetes de hielo); 1 = normal
+    pub actividad: f32,
+    /// Multiplicador de color del shader
+    pub tinte: [f32; 3],
+    /// Inclinación del eje de giro respecto al polo de la escena, en radianes
+    pub inclinacion_axial: f32,
+    pub anillo: Option<Anillo>,
+    pub material: MaterialPbr,
+    pub atmosfera: Option<Atmosfera>,
+    /// Malla OBJ que se dibuja en vez de la esfera, con el shader del cuerpo
+    pub modelo: Option<std::path::PathBuf>,
+    pub efecto: EfectoCuerpo,
+    /// Archivo WGSL con su propia `superficie_propia` en vez del shader de `tipo_shader`
+    pub fragmento: Option<std::path::PathBuf>,
+    pub relieve: Option<Relieve>,
+    pub campo_magnetico: Option<CampoMagnetico>,
+    /// Sombreado de caricatura aunque el resto de la escena no lo use
+    pub caricatura: bool,
+}
+
+/// Material físico del cuerpo para la BRDF de Cook-Torrance. `albedo` y `emisivo`
+/// multiplican el color procedural del shader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialPbr {
+    pub albedo: [f32; 3],
+    pub metalico: f32,
+    pub rugosidad: f32,
+    pub emisivo: [f32; 3],
+}
+
+impl MaterialPbr {
+    /// Material por defecto de cada tipo de shader: el Sol solo emite, la lava brilla un poco
+    /// y el hielo de la luna es más liso que la roca
+    pub fn por_tipo(tipo_shader: u32) -> Self {
+        let (rugosidad, emision) = match tipo_shader {
+            1 => (1.0, 1.0),
+            2 => (0.9, 0.0),
+            5 => (0.7, 0.2),
+            6 => (0.35, 0.0),
+            TIPO_LATON => (0.35, 0.0),
+            _ => (0.6, 0.0),
+        };
+        MaterialPbr {
+            albedo: if tipo_shader == 1 { [0.0; 3] } else { [1.0; 3] },
+            metalico: if tipo_shader == TIPO_LATON { 0.85 } else { 0.0 },
+            rugosidad,
+            emisivo: [emision; 3],
+        }
+    }
+}
+
+/// Atmósfera de dispersión simple en una cáscara alrededor del cuerpo. Los coeficientes son
+/// por radio del cuerpo; los valores por defecto dan un cielo azul de tipo terrestre
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Atmosfera {
+    /// Altura de la cáscara en radios del cuerpo
+    pub espesor: f32,
+    /// Dispersión de Rayleigh por canal (el azul se dispersa más)
+    pub rayleigh: [f32; 3],
+    /// Dispersión de Mie de la bruma, igual en los tres canales
+    pub mie: f32,
+    /// Asimetría de Henyey-Greenstein de la bruma: 0 isótropa, cerca de 1 hacia adelante
+    pub anisotropia_mie: f32,
+    pub intensidad: f32,
+}
+
+impl Default for Atmosfera {
+    fn default() -> Self {
+        Atmosfera {
+            espesor: 0.15,
+            rayleigh: [0.6, 1.4, 3.4],
+            mie: 0.4,
+            anisotropia_mie: 0.76,
+            intensidad: 6.0,
+        }
+    }
+}
+
+impl Atmosfera {
+    fn validar(&self) -> Result<(), String> {
+        if self.espesor <= 0.0 {
+            return Err("el espesor de la atmósfera debe ser positivo".to_string());
+        }
+        if self.rayleigh.iter().any(|&c| c < 0.0) || self.mie < 0.0 || self.intensidad < 0.0 {
+            return Err("rayleigh, mie e intensidad de la atmósfera no pueden ser negativos".to_string());
+        }
+        if !(-1.0 < self.anisotropia_mie && self.anisotropia_mie < 1.0) {
+            return Err("anisotropia_mie debe estar entre -1 y 1 (sin incluirlos)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Mapa de alturas que desplaza los vértices de la esfera del cuerpo (p. ej. un modelo
+/// digital de elevación de Marte o de la Luna)
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Relieve {
+    /// Imagen equirectangular en escala de grises: negro = la superficie, blanco = `altura`
+    pub mapa: std::path::PathBuf,
+    /// Altura del blanco en radios del cuerpo
+    pub altura: f32,
+}
+
+impl Relieve {
+    fn validar(&self) -> Result<(), String> {
+        if !(0.0 < self.altura && self.altura <= 1.0) {
+            return Err(format!("la altura del relieve ({}) debe estar entre 0 y 1 radios", self.altura));
+        }
+        Ok(())
+    }
+}
+
+/// Campo dipolar de un cuerpo, dibujado como líneas de campo; distancias en radios del cuerpo
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Magnetosfera {
+    /// Distancia a la que cada familia de líneas cruza el ecuador magnético (la L de McIlwain)
+    pub capas: Vec<f32>,
+    /// Planos meridianos con una línea de cada capa, repartidos alrededor del eje
+    pub meridianos: u32,
+    /// Inclinación del dipolo respecto al eje de giro
+    pub inclinacion_grados: f32,
+}
+
+impl Default for Magnetosfera {
+    fn default() -> Self {
+        Magnetosfera {
+            capas: vec![2.0, 3.5, 6.0],
+            meridianos: 8,
+            inclinacion_grados: 0.0,
+        }
+    }
+}
+
+impl Magnetosfera {
+    pub const MAXIMO_MERIDIANOS: u32 = 64;
+
+    fn validar(&self) -> Result<(), String> {
+        if self.capas.is_empty() || self.capas.iter().any(|&capa| capa <= 1.0) {
+            return Err("la magnetosfera necesita capas de más de 1 radio".to_string());
+        }
+        if !(1..=Self::MAXIMO_MERIDIANOS).contains(&self.meridianos) {
+            return Err(format!("meridianos debe estar entre 1 y {}", Self::MAXIMO_MERIDIANOS));
+        }
+        Ok(())
+    }
+}
+
+/// Punto de una línea de campo en el espacio del modelo (radio 1, eje de giro en Y)
+#[derive(Debug, Clone, Copy)]
+pub struct PuntoCampo {
+    pub posicion: Vec3,
+    /// |B| relativo al del ecuador en la superficie
+    pub intensidad: f32,
+}
+
+/// Líneas de campo de una `Magnetosfera`, calculadas una vez al cargar la escena
+#[derive(Debug, Clone)]
+pub struct CampoMagnetico {
+    pub lineas: Vec<Vec<PuntoCampo>>,
+}
+
+impl CampoMagnetico {
+    const PASOS: usize = 48;
+
+    /// Cada línea sigue r = L·sen²θ de una superficie a la otra por el plano de su meridiano,
+    /// con |B| ∝ √(1 + 3·cos²θ) / r³
+    pub fn nuevo(magnetosfera: &Magnetosfera) -> Self {
+        use std::f32::consts::{PI, TAU};
+        let eje_inclinacion = Vec3::z();
+        let inclinacion = magnetosfera.inclinacion_grados.to_radians();
+        let mut lineas = Vec::new();
+        for &capa in &magnetosfera.capas {
+            let desde = (1.0 / capa).sqrt().asin();
+            for meridiano in 0..magnetosfera.meridianos {
+                let (seno_fi, coseno_fi) = (meridiano as f32 / magnetosfera.meridianos as f32 * TAU).sin_cos();
+                let linea = (0..=Self::PASOS)
+                    .map(|paso| {
+                        let theta = desde + (PI - 2.0 * desde) * paso as f32 / Self::PASOS as f32;
+                        let (seno, coseno) = theta.sin_cos();
+                        let radio = capa * seno * seno;
+                        let local = Vec3::new(radio * seno * coseno_fi, radio * coseno, radio * seno * seno_fi);
+                        PuntoCampo {
+                            posicion: nalgebra_glm::rotate_vec3(&local, inclinacion, &eje_inclinacion),
+                            intensidad: (1.0 + 3.0 * coseno * coseno).sqrt() / radio.powi(3),
+                        }
+                    })
+                    .collect();
+                lineas.push(linea);
+            }
+        }
+        CampoMagnetico { lineas }
+    }
+}
+
+/// Anillo plano en el ecuador de un cuerpo; radios en radios del cuerpo
+#[derive(Debug, Clone, Copy)]
+pub struct Anillo {
+    pub radio_interno: f32,
+    pub radio_externo: f32,
+    pub visible: bool,
+}
+
+impl CuerpoCeleste {
+    pub fn nuevo(
+        nombre: &str,
+        posicion: [f32; 2],
+        escala: f32,
+        tipo_shader: u32,
+        radio_km: f64,
+        magnitud_absoluta: f64,
+    ) -> Self {
+        CuerpoCeleste {
+            nombre: nombre.to_string(),
+            posicion,
+            posicion_local: posicion,
+            escala,
+            tipo_shader,
+            radio_km,
+            magnitud_absoluta,
+            orbita: None,
+            padre: None,
+            visible: true,
+            clase: if tipo_shader == 1 { ClaseCuerpo::Estrella } else { ClaseCuerpo::Planeta },
+            actividad: 1.0,
+            tinte: [1.0, 1.0, 1.0],
+            inclinacion_axial: 0.0,
+            anillo: None,
+            material: MaterialPbr::por_tipo(tipo_shader),
+            atmosfera: None,
+            modelo: None,
+            fragmento: None,
+            relieve: None,
+            campo_magnetico: None,
+            caricatura: false,
+            efecto: if tipo_shader == 1 { EfectoCuerpo::Llamaradas } else { EfectoCuerpo::Ninguno },
+        }
+    }
+
+    /// Pone al cuerpo en órbita circular alrededor de su padre a partir de su posición local
+    pub fn con_periodo(mut self, periodo: f32) -> Self {
+        self.orbita = Some(Orbita::desde_posicion(self.posicion_local, periodo));
+        self
+    }
+
+    pub fn hijo_de(mut self, padre: usize) -> Self {
+        self.padre = Some(padre);
+        self
+    }
+
+    pub fn con_clase(mut self, clase: ClaseCuerpo) -> Self {
+        self.clase = clase;
+        self
+    }
+
+    pub fn con_inclinacion(mut self, grados: f32) -> Self {
+        self.inclinacion_axial = grados.to_radians();
+        self
+    }
+
+    pub fn con_anillo(mut self, radio_interno: f32, radio_externo: f32) -> Self {
+        self.anillo = Some(Anillo {
+            radio_interno,
+            radio_externo,
+            visible: true,
+        });
+        self
+    }
+
+    pub fn es_estrella(&self) -> bool {
+        self.tipo_shader == 1
+    }
+
+    pub fn posicion_3d(&self) -> Vec3 {
+        Vec3::new(self.posicion[0], self.posicion[1], 0.0)
+    }
+
+    /// Visible si ni el cuerpo `indice` de `cuerpos` ni ninguno de sus ancestros están ocultos
+    pub fn visible_en(cuerpos: &[CuerpoCeleste], mut indice: usize) -> bool {
+        loop {
+            let cuerpo = &cuerpos[indice];
+            if !cuerpo.visible {
+                return false;
+            }
+            match cuerpo.padre {
+                Some(padre) => indice = padre,
+                None => return true,
+            }
+        }
+    }
+
+    /// Radio de la esfera que encierra todo lo que se dibuja del cuerpo: la superficie con
+    /// su relieve, la cáscara de atmósfera y el anillo
+    pub fn radio_envolvente(&self) -> f32 {
+        let atmosfera = self.atmosfera.map_or(1.0, |a| 1.0 + a.espesor);
+        let anillo = self.anillo.map_or(1.0, |a| a.radio_externo);
+        self.radio_superficie().max(self.escala * atmosfera.max(anillo))
+    }
+
+    /// Radio hasta lo más alto de la superficie, con el relieve
+    pub fn radio_superficie(&self) -> f32 {
+        self.escala * self.relieve.as_ref().map_or(1.0, |r| 1.0 + r.altura)
+    }
+
+    /// Orientación propia del cuerpo: escala, giro sobre sí mismo con el tiempo e inclinación del eje.
+    /// No la heredan sus hijos; la traslación la pone su nodo en el grafo de escena
+    pub fn matriz_orientacion(&self, tiempo: f32) -> Mat4 {
+        let inclinacion = nalgebra_glm::rotation(self.inclinacion_axial, &Vec3::x());
+        let rotacion = nalgebra_glm::rotation(-tiempo * 0.3, &Vec3::y());
+        let escala = nalgebra_glm::scaling(&Vec3::repeat(self.escala));
+        inclinacion * rotacion * escala
+    }
+
+    /// Diámetro angular aparente (radianes) visto desde `observador`
+    pub fn diametro_angular(&self, observador: &Vec3) -> f64 {
+        let distancia_km = (self.posicion_3d() - observador).norm() as f64 * KM_POR_UNIDAD;
+        2.0 * (self.radio_km / distancia_km.max(self.radio_km)).atan()
+    }
+
+    /// Magnitud visual aproximada: H + 5·log10(r·Δ) con una fase lambertiana simple
+    pub fn magnitud_aparente(&self, observador: &Vec3, estrella: Option<&CuerpoCeleste>) -> f64 {
+        let posicion = self.posicion_3d();
+        let delta = ((posicion - observador).norm() as f64).max(1e-6);
+
+        match estrella {
+            Some(sol) if !self.es_estrella() => {
+                let hacia_sol = sol.posicion_3d() - posicion;
+                let hacia_observador = observador - posicion;
+                let r = (hacia_sol.norm() as f64).max(1e-6);
+                let coseno_fase = hacia_sol.normalize().dot(&hacia_observador.normalize()) as f64;
+                let fase = ((1.0 + coseno_fase) * 0.5).max(1e-4);
+                self.magnitud_absoluta + 5.0 * (r * delta).log10() - 2.5 * fase.log10()
+            }
+            _ => self.magnitud_absoluta + 5.0 * delta.log10(),
+        }
+    }
+
+    /// Distancia en unidades de simulación hasta otro cuerpo
+    pub fn distancia_a(&self, otro: &CuerpoCeleste) -> f32 {
+        let dx = self.posicion[0] - otro.posicion[0];
+        let dy = self.posicion[1] - otro.posicion[1];
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// Cuerpo tal como se escribe en la escena (`[[cuerpo]]` de `escena.toml`)
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinicionCuerpo {
+    pub nombre: String,
+    /// Posición respecto al padre (o al origen si no tiene), en unidades de escena
+    pub posicion: [f32; 2],
+    pub escala: f32,
+    /// Nombre de uno de `TIPOS_SHADER`, sin distinguir mayúsculas
+    pub shader: String,
+    pub radio_km: f64,
+    /// Magnitud absoluta H (para una estrella: magnitud aparente a 1 UA)
+    pub magnitud_absoluta: f64,
+    /// Segundos por vuelta de una órbita circular que pasa por `posicion`; sin él, el cuerpo queda fijo
+    pub periodo: Option<f32>,
+    /// Nombre de un cuerpo definido antes en el archivo
+    pub padre: Option<String>,
+    /// Por defecto: estrella si usa el shader del Sol, planeta si no
+    pub clase: Option<ClaseCuerpo>,
+    /// Multiplicador de color del shader
+    pub color: Option<[f32; 3]>,
+    #[serde(default)]
+    pub inclinacion_grados: f32,
+    pub anillo: Option<DefinicionAnillo>,
+    /// Los campos que falten toman el material por defecto del shader
+    pub material: Option<DefinicionMaterial>,
+    /// Los campos que falten toman los de `Atmosfera::default`
+    pub atmosfera: Option<Atmosfera>,
+    /// Archivo .obj, .gltf o .glb con la malla del cuerpo
+    pub modelo: Option<std::path::PathBuf>,
+    /// Por defecto: llamaradas si usa el shader del Sol, ninguno si no
+    pub efecto: Option<EfectoCuerpo>,
+    /// Archivo WGSL que define `superficie_propia`; se recarga al guardarlo
+    pub fragmento: Option<std::path::PathBuf>,
+    pub relieve: Option<Relieve>,
+    /// Los campos que falten toman los de `Magnetosfera::default`
+    pub magnetosfera: Option<Magnetosfera>,
+    /// Bandas de luz y contorno en vez de la BRDF
+    #[serde(default)]
+    pub caricatura: bool,
+}
+
+/// Material tal como se escribe en la escena; todo es opcional
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinicionMaterial {
+    pub albedo: Option<[f32; 3]>,
+    pub metalico: Option<f32>,
+    pub rugosidad: Option<f32>,
+    pub emisivo: Option<[f32; 3]>,
+}
+
+impl DefinicionMaterial {
+    /// Completa `base` con los campos escritos, comprobando sus rangos
+    fn resolver(&self, base: MaterialPbr) -> Result<MaterialPbr, String> {
+        let fraccion = |valor: f32| (0.0..=1.0).contains(&valor);
+        if let Some(albedo) = self.albedo.filter(|a| !a.iter().copied().all(fraccion)) {
+            return Err(format!("el albedo {:?} debe estar entre 0 y 1", albedo));
+        }
+        if let Some(metalico) = self.metalico.filter(|&m| !fraccion(m)) {
+            return Err(format!("metalico = {} debe estar entre 0 y 1", metalico));
+        }
+        if let Some(rugosidad) = self.rugosidad.filter(|&r| !fraccion(r)) {
+            return Err(format!("rugosidad = {} debe estar entre 0 y 1", rugosidad));
+        }
+        if let Some(emisivo) = self.emisivo.filter(|e| e.iter().any(|&c| c < 0.0)) {
+            return Err(format!("el emisivo {:?} no puede ser negativo", emisivo));
+        }
+        Ok(MaterialPbr {
+            albedo: self.albedo.unwrap_or(base.albedo),
+            metalico: self.metalico.unwrap_or(base.metalico),
+            rugosidad: self.rugosidad.unwrap_or(base.rugosidad),
+            emisivo: self.emisivo.unwrap_or(base.emisivo),
+        })
+    }
+}
+
+/// Radios del anillo en radios del cuerpo
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinicionAnillo {
+    pub interno: f32,
+    pub externo: f32,
+}
+
+/// Cinturón de asteroides alrededor del origen; la GPU genera cada asteroide a partir de su índice
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinicionCinturon {
+    pub cantidad: u32,
+    pub radio_interno: f32,
+    pub radio_externo: f32,
+    pub espesor: f32,
+    /// Segundos por vuelta en el borde interno
+    pub periodo: f32,
+    /// Radio del asteroide más grande
+    pub tamano: f32,
+    #[serde(default)]
+    pub semilla: u32,
+}
+
+impl DefinicionCinturon {
+    pub const MAXIMO: u32 = 1 << 20;
+
+    fn validar(&self) -> Result<(), String> {
+        if self.cantidad == 0 || self.cantidad > Self::MAXIMO {
+            return Err(format!("la cantidad debe estar entre 1 y {}", Self::MAXIMO));
+        }
+        if !(0.0 < self.radio_interno && self.radio_interno < self.radio_externo) {
+            return Err("debe cumplir 0 < radio_interno < radio_externo".to_string());
+        }
+        if self.espesor < 0.0 || self.periodo <= 0.0 || self.tamano <= 0.0 {
+            return Err("el espesor no puede ser negativo, y periodo y tamano deben ser positivos".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl DefinicionCuerpo {
+    /// Convierte la definición en un cuerpo; `anteriores` son los cuerpos ya definidos
+    pub fn resolver(&self, anteriores: &[CuerpoCeleste]) -> Result<CuerpoCeleste, String> {
+        let contexto = format!("cuerpo '{}'", self.nombre);
+        if anteriores.iter().any(|c| c.nombre == self.nombre) {
+            return Err(format!("{}: el nombre está repetido", contexto));
+        }
+        if self.escala <= 0.0 {
+            return Err(format!("{}: la escala debe ser positiva", contexto));
+        }
+        let tipo_shader = tipo_shader_por_nombre(&self.shader).ok_or_else(|| {
+            let opciones: Vec<String> = TIPOS_SHADER.iter().map(|(_, n)| n.to_lowercase()).collect();
+            format!("{}: shader '{}' desconocido (opciones: {})", contexto, self.shader, opciones.join(", "))
+        })?;
+
+        let mut cuerpo = CuerpoCeleste::nuevo(
+            &self.nombre,
+            self.posicion,
+            self.escala,
+            tipo_shader,
+            self.radio_km,
+            self.magnitud_absoluta,
+        )
+        .con_inclinacion(self.inclinacion_grados);
+        if let Some(padre) = &self.padre {
+            let indice = anteriores.iter().position(|c| &c.nombre == padre).ok_or_else(|| {
+                format!("{}: el padre '{}' no existe o se define después", contexto, padre)
+            })?;
+            cuerpo = cuerpo.hijo_de(indice);
+        }
+        if let Some(periodo) = self.periodo {
+            if periodo <= 0.0 {
+                return Err(format!("{}: el periodo debe ser positivo", contexto));
+            }
+            if self.posicion == [0.0, 0.0] {
+                return Err(format!("{}: para orbitar necesita una posición distinta de [0, 0]", contexto));
+            }
+            cuerpo = cuerpo.con_periodo(periodo);
+        }
+        if let Some(anillo) = self.anillo {
+            if !(0.0 < anillo.interno && anillo.interno < anillo.externo) {
+                return Err(format!("{}: el anillo debe cumplir 0 < interno < externo", contexto));
+            }
+            cuerpo = cuerpo.con_anillo(anillo.interno, anillo.externo);
+        }
+        if let Some(clase) = self.clase {
+            cuerpo = cuerpo.con_clase(clase);
+        }
+        if let Some(color) = self.color {
+            cuerpo.tinte = color;
+        }
+        if let Some(material) = &self.material {
+            cuerpo.material = material.resolver(cuerpo.material).map_err(|e| format!("{}: {}", contexto, e))?;
+        }
+        if let Some(atmosfera) = self.atmosfera {
+            atmosfera.validar().map_err(|e| format!("{}: {}", contexto, e))?;
+            cuerpo.atmosfera = Some(atmosfera);
+        }
+        if self.modelo.is_some() && self.relieve.is_some() {
+            return Err(format!("{}: el relieve desplaza la esfera; un cuerpo con modelo no lo usa", contexto));
+        }
+        cuerpo.modelo = self.modelo.clone();
+        cuerpo.fragmento = self.fragmento.clone();
+        if let Some(relieve) = &self.relieve {
+            relieve.validar().map_err(|e| format!("{}: {}", contexto, e))?;
+            cuerpo.relieve = Some(relieve.clone());
+        }
+        if let Some(magnetosfera) = &self.magnetosfera {
+            magnetosfera.validar().map_err(|e| format!("{}: {}", contexto, e))?;
+            cuerpo.campo_magnetico = Some(CampoMagnetico::nuevo(magnetosfera));
+        }
+        if let Some(efecto) = self.efecto {
+            cuerpo.efecto = efecto;
+        }
+        cuerpo.caricatura = self.caricatura;
+        Ok(cuerpo)
+    }
+}
+
+/// Nodo del grafo de escena: su padre y la transformación relativa a él
+#[derive(Debug, Clone, Copy)]
+pub struct NodoEscena {
+    pub padre: Option<usize>,
+    pub local: Mat4,
+    pub mundo: Mat4,
+}
+
+/// Jerarquía de transformaciones de la escena, un nodo por cuerpo: las lunas orbitan a su
+/// planeta mientras el planeta orbita al Sol. Un padre siempre tiene índice menor que sus hijos
+#[derive(Debug, Clone, Default)]
+pub struct GrafoEscena {
+    pub nodos: Vec<NodoEscena>,
+    /// Cuerpo que queda fijo en el origen (p. ej. geocéntrico); `None` = el marco de la escena
+    pub marco: Option<usize>,
+    /// Dónde cae el origen de la escena en el marco actual
+    desplazamiento: Vec3,
+}
+
+impl GrafoEscena {
+    /// Padre válido de un cuerpo; uno que no le preceda se trata como raíz
+    fn padre(cuerpos: &[CuerpoCeleste], indice: usize) -> Option<usize> {
+        cuerpos[indice].padre.filter(|&padre| padre < indice)
+    }
+
+    /// Recalcula las matrices de mundo desde las posiciones locales y deja a cada cuerpo en la suya
+    pub fn actualizar(&mut self, cuerpos: &mut [CuerpoCeleste]) {
+        self.nodos.clear();
+        for indice in 0..cuerpos.len() {
+            let padre = Self::padre(cuerpos, indice);
+            let [x, y] = cuerpos[indice].posicion_local;
+            let local = nalgebra_glm::translation(&Vec3::new(x, y, 0.0));
+            let mundo = padre.map_or(local, |p| self.nodos[p].mundo * local);
+            self.nodos.push(NodoEscena { padre, local, mundo });
+            cuerpos[indice].posicion = [mundo[(0, 3)], mundo[(1, 3)]];
+        }
+
+        // En el marco de un cuerpo todo se traslada cada cuadro para dejarlo en el origen
+        self.desplazamiento = Vec3::zeros();
+        let Some(marco) = self.marco.filter(|&marco| marco < cuerpos.len()) else { return };
+        let [x, y] = cuerpos[marco].posicion;
+        self.desplazamiento = Vec3::new(-x, -y, 0.0);
+        let traslacion = nalgebra_glm::translation(&self.desplazamiento);
+        for (nodo, cuerpo) in self.nodos.iter_mut().zip(cuerpos.iter_mut()) {
+            nodo.mundo = traslacion * nodo.mundo;
+            cuerpo.posicion = [nodo.mundo[(0, 3)], nodo.mundo[(1, 3)]];
+        }
+    }
+
+    /// Origen de la escena (el centro del cinturón y del disco de la eclíptica) en el marco actual
+    pub fn centro_escena(&self) -> Vec3 {
+        self.desplazamiento
+    }
+
+    /// Pasa posiciones de mundo (las de la física) a posiciones relativas al padre
+    pub fn localizar(cuerpos: &mut [CuerpoCeleste]) {
+        for indice in 0..cuerpos.len() {
+            let origen = Self::padre(cuerpos, indice).map_or([0.0, 0.0], |p| cuerpos[p].posicion);
+            let [x, y] = cuerpos[indice].posicion;
+            cuerpos[indice].posicion_local = [x - origen[0], y - origen[1]];
+        }
+    }
+
+    /// Matriz de modelo que se sube por cuerpo: el mundo de su nodo por su orientación propia
+    pub fn matriz_modelo(&self, indice: usize, cuerpo: &CuerpoCeleste, tiempo: f32) -> Mat4 {
+        let mundo = self.nodos.get(indice).map_or_else(Mat4::identity, |nodo| nodo.mundo);
+        mundo * cuerpo.matriz_orientacion(tiempo)
+    }
+
+    /// Centro de la órbita de un cuerpo: la posición de mundo de su padre
+    pub fn origen(&self, indice: usize) -> Vec3 {
+        self.nodos
+            .get(indice)
+            .and_then(|nodo| nodo.padre)
+            .map_or(Vec3::zeros(), |padre| {
+                let mundo = self.nodos[padre].mundo;
+                Vec3::new(mundo[(0, 3)], mundo[(1, 3)], mundo[(2, 3)])
+            })
+    }
+}
+
+/// Modo de comparación de tamaños: el reloj se detiene y los cuerpos dejan sus órbitas para
+/// formar una fila sobre el eje X, de mayor a menor y a escala real entre ellos (el mayor
+/// conserva su radio dibujado). Entra y sale con una transición suave
+#[derive(Debug, Default)]
+pub struct FilaTamanos {
+    pub activa: bool,
+    /// 0 = en sus órbitas, 1 = en la fila
+    progreso: f32,
+    /// Pausa del reloj al entrar, para devolverla al salir
+    pausa_previa: bool,
+    /// Radios de la escena mientras la fila los reemplaza (ver `restaurar`)
+    escalas: Vec<f32>,
+    /// Cuerpos de la fila, de mayor a menor
+    orden: Vec<usize>,
+    unidades_por_km: f64,
+    /// Extremo izquierdo de la fila, sobre su eje
+    inicio: Vec3,
+}
+
+impl FilaTamanos {
+    /// Segundos reales de cada transición
+    const DURACION: f32 = 1.5;
+    /// Hueco entre cuerpos vecinos, en unidades de escena; deja sitio a las etiquetas
+    const HUECO: f32 = 0.2;
+
+    pub fn alternar(&mut self, reloj: &mut RelojSimulacion) {
+        self.activa = !self.activa;
+        if self.activa {
+            self.pausa_previa = reloj.pausado;
+            reloj.pausado = true;
+        } else {
+            reloj.pausado = self.pausa_previa;
+        }
+    }
+
+    /// Avanza la transición en segundos reales: con el reloj detenido no hay tiempo de simulación
+    pub fn avanzar(&mut self, segundos: f32) {
+        let paso = segundos / Self::DURACION;
+        self.progreso = if self.activa {
+            (self.progreso + paso).min(1.0)
+        } else {
+            (self.progreso - paso).max(0.0)
+        };
+    }
+
+    pub fn visible(&self) -> bool {
+        self.progreso > 0.0
+    }
+
+    /// Progreso con entrada y salida suaves
+    fn mezcla(&self) -> f32 {
+        self.progreso * self.progreso * (3.0 - 2.0 * self.progreso)
+    }
+
+    /// Devuelve los radios de la escena antes de simular y animar el cuadro
+    pub fn restaurar(&mut self, cuerpos: &mut [CuerpoCeleste]) {
+        for (cuerpo, escala) in cuerpos.iter_mut().zip(self.escalas.drain(..)) {
+            cuerpo.escala = escala;
+        }
+    }
+
+    /// Olvida los radios guardados: son de una escena que ya no está
+    pub fn descartar(&mut self) {
+        self.escalas.clear();
+        self.orden.clear();
+    }
+
+    /// Lleva a los `incluidos` de su posición de mundo hacia su lugar en la fila, alrededor de
+    /// `centro`, y de su radio dibujado al real. Sobrescribe los nodos del grafo del cuadro
+    pub fn aplicar(&mut self, grafo: &mut GrafoEscena, cuerpos: &mut [CuerpoCeleste], incluidos: &[usize], centro: Vec3) {
+        self.orden.clear();
+        if !self.visible() {
+            return;
+        }
+        self.escalas = cuerpos.iter().map(|c| c.escala).collect();
+        self.orden = incluidos.to_vec();
+        self.orden.sort_by(|&a, &b| cuerpos[b].radio_km.total_cmp(&cuerpos[a].radio_km));
+        let Some(&mayor) = self.orden.first() else { return };
+        self.unidades_por_km = cuerpos[mayor].escala as f64 / cuerpos[mayor].radio_km.max(f64::EPSILON);
+
+        // Radio real y radio envolvente (anillos, atmósfera) proporcional a él
+        let radios: Vec<(f32, f32)> = self
+            .orden
+            .iter()
+            .map(|&i| {
+                let cuerpo = &cuerpos[i];
+                let radio = (cuerpo.radio_km * self.unidades_por_km) as f32;
+                (radio, radio * cuerpo.radio_envolvente() / cuerpo.escala.max(f32::EPSILON))
+            })
+            .collect();
+        let largo = radios.iter().map(|(_, envolvente)| 2.0 * envolvente).sum::<f32>()
+            + Self::HUECO * radios.len().saturating_sub(1) as f32;
+        self.inicio = centro - Vec3::new(largo * 0.5, 0.0, 0.0);
+
+        let mezcla = self.mezcla();
+        let mut x = self.inicio.x;
+        for (&indice, &(radio, envolvente)) in self.orden.iter().zip(&radios) {
+            x += envolvente;
+            let cuerpo = &mut cuerpos[indice];
+            let [ox, oy] = cuerpo.posicion;
+            let posicion = Vec3::new(ox, oy, 0.0).lerp(&Vec3::new(x, centro.y, 0.0), mezcla);
+            cuerpo.posicion = [posicion.x, posicion.y];
+            cuerpo.escala += (radio - cuerpo.escala) * mezcla;
+            if let Some(nodo) = grafo.nodos.get_mut(indice) {
+                nodo.mundo[(0, 3)] = posicion.x;
+                nodo.mundo[(1, 3)] = posicion.y;
+            }
+            x += envolvente + Self::HUECO;
+        }
+    }
+
+    /// Cuerpos de la fila del cuadro, de mayor a menor
+    pub fn orden(&self) -> &[usize] {
+        &self.orden
+    }
+
+    /// Barra de escala común bajo la fila: inicio, largo en unidades de escena y kilómetros
+    pub fn barra(&self, cuerpos: &[CuerpoCeleste]) -> Option<(Vec3, f32, f64)> {
+        let mayor = &cuerpos[*self.orden.first()?];
+        let kilometros = valor_redondo(mayor.radio_km);
+        let debajo = Vec3::new(0.0, -(mayor.escala * 1.3 + 0.05), 0.0);
+        Some((self.inicio + debajo, (kilometros * self.unidades_por_km) as f32, kilometros))
+    }
+
+    pub fn describir(&self) -> Option<String> {
+        self.activa.then(|| format!("Fila de tamaños: escala real, 1 u = {:.0} km", 1.0 / self.unidades_por_km))
+    }
+}
+
+/// Punto de una estela: posición respecto al padre, rapidez en ese punto e instante
+#[derive(Debug, Clone, Copy)]
+pub struct MuestraEstela {
+    pub posicion: [f32; 2],
+    pub rapidez: f32,
+    pub tiempo: f32,
+}
+
+/// Rastro reciente de cada cuerpo relativo a su padre (las lunas dibujan la elipse alrededor
+/// de su planeta); se dibuja como una cinta coloreada por la rapidez
+#[derive(Debug, Default)]
+pub struct EstelasOrbitales {
+    estelas: Vec<std::collections::VecDeque<MuestraEstela>>,
+    /// Última posición local vista de cada cuerpo, para la rapidez sin órbita (modo N-cuerpos)
+    ultimas: Vec<Option<([f32; 2], f32)>>,
+}
+
+impl EstelasOrbitales {
+    /// Separación mínima entre muestras consecutivas
+    const DISTANCIA_MINIMA: f32 = 0.008;
+    const MAX_MUESTRAS: usize = 512;
+    /// Fracción del periodo que abarca la estela (sin órbita, `DURACION_SIN_ORBITA` segundos)
+    const FRACCION_PERIODO: f32 = 0.8;
+    const DURACION_SIN_ORBITA: f32 = 30.0;
+    /// Un salto mayor (editor, cambio de modo) corta la estela
+    const SALTO_MAXIMO: f32 = 0.3;
+
+    /// Agrega una muestra por cuerpo; con `usar_orbitas` la rapidez sale de la órbita (vis-viva).
+    /// Con `en_marco` las posiciones son las del marco de un cuerpo (ver `GrafoEscena::marco`) y
+    /// no las relativas al padre: así se ven los lazos retrógrados de los planetas
+    pub fn registrar(&mut self, cuerpos: &[CuerpoCeleste], tiempo: f32, usar_orbitas: bool, en_marco: bool) {
+        self.estelas.resize_with(cuerpos.len(), Default::default);
+        self.ultimas.resize(cuerpos.len(), None);
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            let posicion = if en_marco { cuerpo.posicion } else { cuerpo.posicion_local };
+            let estimada = self.ultimas[indice].and_then(|(anterior, instante)| {
+                let intervalo = (tiempo - instante).abs();
+                (intervalo > f32::EPSILON).then(|| distancia_plano(posicion, anterior) / intervalo)
+            });
+            self.ultimas[indice] = Some((posicion, tiempo));
+            let rapidez = match cuerpo.orbita {
+                Some(orbita) if usar_orbitas => orbita.rapidez(tiempo),
+                _ => match estimada {
+                    Some(rapidez) => rapidez,
+                    None => continue,
+                },
+            };
+
+            let estela = &mut self.estelas[indice];
+            if let Some(ultima) = estela.back() {
+                let salto = distancia_plano(posicion, ultima.posicion);
+                if salto > Self::SALTO_MAXIMO {
+                    estela.clear();
+                } else if salto < Self::DISTANCIA_MINIMA {
+                    continue;
+                }
+            }
+            estela.push_back(MuestraEstela { posicion, rapidez, tiempo });
+
+            let duracion = cuerpo
+                .orbita
+                .map_or(Self::DURACION_SIN_ORBITA, |orbita| orbita.periodo * Self::FRACCION_PERIODO);
+            while estela.len() > Self::MAX_MUESTRAS
+                || estela.front().is_some_and(|m| (tiempo - m.tiempo).abs() > duracion)
+            {
+                estela.pop_front();
+            }
+        }
+    }
+
+    pub fn vaciar(&mut self) {
+        self.estelas.clear();
+        self.ultimas.clear();
+    }
+
+    /// Muestras de un cuerpo, de la más antigua a la más reciente
+    pub fn estela(&self, indice: usize) -> impl Iterator<Item = &MuestraEstela> {
+        self.estelas.get(indice).into_iter().flatten()
+    }
+}
+
+fn distancia_plano(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+/// Unidades con que se muestran las distancias de la escena
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscalaDistancias {
+    /// Unidades de simulación tal cual
+    Simulacion,
+    /// Millones de km, tomando una unidad como 1 UA
+    Realista,
+}
+
+impl EscalaDistancias {
+    pub fn alternar(self) -> Self {
+        match self {
+            EscalaDistancias::Simulacion => EscalaDistancias::Realista,
+            EscalaDistancias::Realista => EscalaDistancias::Simulacion,
+        }
+    }
+
+    /// Convierte unidades de simulación a la unidad mostrada
+    pub fn convertir(self, unidades: f64) -> f64 {
+        match self {
+            EscalaDistancias::Simulacion => unidades,
+            EscalaDistancias::Realista => unidades * KM_POR_UNIDAD / 1.0e6,
+        }
+    }
+
+    pub fn sufijo(self) -> &'static str {
+        match self {
+            EscalaDistancias::Simulacion => "u",
+            EscalaDistancias::Realista => "M km",
+        }
+    }
+}
+
+/// Redondea hacia abajo a 1, 2 o 5 por una potencia de diez
+pub fn valor_redondo(valor: f64) -> f64 {
+    if valor <= 0.0 {
+        return 0.0;
+    }
+    let potencia = 10f64.powf(valor.log10().floor());
+    let mantisa = valor / potencia;
+    let redondo = if mantisa >= 5.0 {
+        5.0
+    } else if mantisa >= 2.0 {
+        2.0
+    } else {
+        1.0
+    };
+    redondo * potencia
+}
+
+/// Formatea un ángulo en radianes como grados, minutos o segundos de arco
+pub fn formatear_angulo(radianes: f64) -> String {
+    let grados = radianes.to_degrees();
+    if grados >= 1.0 {
+        format!("{:.2}°", grados)
+    } else if grados * 60.0 >= 1.0 {
+        format!("{:.2}′", grados * 60.0)
+    } else {
+        format!("{:.2}″", grados * 3600.0)
+    }
+}
+
+/// Herramienta de medición: guarda hasta dos cuerpos seleccionados con click
+#[derive(Default)]
+pub struct HerramientaMedicion {
+    pub activa: bool,
+    pub seleccion: Vec<usize>,
+}
+
+impl HerramientaMedicion {
+    pub fn alternar(&mut self) {
+        self.activa = !self.activa;
+        self.seleccion.clear();
+    }
+
+    /// Registra un cuerpo; un tercer click reinicia la medición
+    pub fn registrar(&mut self, indice: usize) {
+        if self.seleccion.len() == 2 {
+            self.seleccion.clear();
+        }
+        if !self.seleccion.contains(&indice) {
+            self.seleccion.push(indice);
+        }
+    }
+
+    pub fn par_seleccionado(&self) -> Option<(usize, usize)> {
+        match self.seleccion.as_slice() {
+            [a, b] => Some((*a, *b)),
+            _ => None,
+        }
+    }
+
+    pub fn describir(&self, cuerpos: &[CuerpoCeleste]) -> Option<String> {
+        if !self.activa {
+            return None;
+        }
+        match self.par_seleccionado() {
+            Some((a, b)) => {
+                let distancia = cuerpos[a].distancia_a(&cuerpos[b]);
+                Some(format!(
+                    "{} ↔ {}: {:.3} u ({:.0} km)",
+                    cuerpos[a].nombre,
+                    cuerpos[b].nombre,
+                    distancia,
+                    distancia as f64 * KM_POR_UNIDAD
+                ))
+            }
+            None => Some("Medición: selecciona dos cuerpos".to_string()),
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: AJUSTES
+// =============================================================================
+
+/// Ajustes de usuario cargados desde `ajustes.toml` (todos los campos son opcionales)
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ajustes {
+    pub estrellas: AjustesEstrellas,
+    pub galaxias: AjustesGalaxias,
+    pub constelaciones: AjustesConstelaciones,
+    pub cuadriculas: AjustesCuadriculas,
+    pub capas: AjustesCapas,
+    pub memoria: AjustesMemoria,
+    pub gpu: AjustesGpu,
+    pub texturas: AjustesTexturas,
+    pub vertices: AjustesVertices,
+    pub dibujo: AjustesDibujo,
+    pub grabacion: AjustesGrabacion,
+    pub pantalla: AjustesPantalla,
+    pub posproceso: AjustesPosproceso,
+    pub simulacion: AjustesSimulacion,
+    /// Escena elegida con `--escena` (o la predeterminada); se carga aparte de `ajustes.toml`
+    #[serde(skip)]
+    pub escena: ArchivoEscena,
+    /// Archivo del que se leyeron (y al que se guardan las capas)
+    #[serde(skip)]
+    pub ruta: Option<std::path::PathBuf>,
+}
+
+/// Campo de estrellas de fondo
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesEstrellas {
+    /// Estrellas en toda la esfera celeste
+    pub cantidad: u32,
+    /// 0 = uniformes en el cielo, mayor = concentradas hacia el plano galáctico
+    pub concentracion_galactica: f32,
+    /// Fracción (0-1) de estrellas agrupadas en cúmulos
+    pub agrupamiento: f32,
+    pub semilla: u64,
+}
+
+impl Default for AjustesEstrellas {
+    fn default() -> Self {
+        AjustesEstrellas {
+            cantidad: 3000,
+            concentracion_galactica: 0.0,
+            agrupamiento: 0.0,
+            semilla: 231156,
+        }
+    }
+}
+
+/// Galaxias lejanas de fondo (sprites procedurales tenues)
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesGalaxias {
+    pub cantidad: u32,
+    /// Brillo máximo de cada galaxia (se mezclan de forma aditiva)
+    pub brillo: f32,
+    pub semilla: u64,
+}
+
+impl Default for AjustesGalaxias {
+    fn default() -> Self {
+        AjustesGalaxias {
+            cantidad: 30,
+            brillo: 0.25,
+            semilla: 1156,
+        }
+    }
+}
+
+/// Figuras de constelaciones sobre la esfera celeste
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesConstelaciones {
+    pub archivo: String,
+    pub visibles: bool,
+}
+
+impl Default for AjustesConstelaciones {
+    fn default() -> Self {
+        AjustesConstelaciones {
+            archivo: "constelaciones.toml".to_string(),
+            visibles: false,
+        }
+    }
+}
+
+/// Cuadrículas de coordenadas sobre el cielo y disco del plano eclíptico
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesCuadriculas {
+    pub ecliptica: bool,
+    pub ecuatorial: bool,
+    /// Separación entre líneas de la cuadrícula, en grados
+    pub paso_grados: f32,
+    /// Radio del disco del plano eclíptico, en unidades de escena
+    pub radio_disco: f32,
+}
+
+impl Default for AjustesCuadriculas {
+    fn default() -> Self {
+        AjustesCuadriculas {
+            ecliptica: false,
+            ecuatorial: false,
+            paso_grados: 30.0,
+            radio_disco: 1.0,
+        }
+    }
+}
+
+/// Filtrado de las texturas de superficie
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesTexturas {
+    /// Cadena de mipmaps generada en la GPU para mapas y superficies horneadas
+    pub mipmaps: bool,
+    /// Muestras anisotrópicas máximas (1 a 16; 1 la desactiva). Si el adaptador no
+    /// la soporta se ignora
+    pub anisotropia: u16,
+    /// Hilos del grupo que decodifica recursos en segundo plano; 0 = uno menos que núcleos
+    pub hilos: usize,
+    /// Ancho de las superficies procedurales horneadas al cargar (el alto es la mitad);
+    /// 0 las calcula por fragmento cada cuadro, sin gastar memoria
+    pub resolucion_horneado: u32,
+}
+
+impl Default for AjustesTexturas {
+    fn default() -> Self {
+        AjustesTexturas {
+            mipmaps: true,
+            anisotropia: 16,
+            hilos: 0,
+            resolucion_horneado: 1024,
+        }
+    }
+}
+
+/// Formato de los vértices de las esferas
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesVertices {
+    /// 16 bytes por vértice (media precisión y normal octaédrica) en vez de 32; desactivarlo
+    /// sirve para comparar que no se pierde calidad
+    pub compactos: bool,
+    /// Franjas de latitud y de longitud de la esfera; pasadas las 255 los índices son de 32 bits
+    pub subdivisiones_esfera: u32,
+    /// Niveles del quadtree de parches que afina la superficie al acercarse (0 = siempre la
+    /// esfera). Con vértices compactos no pasa de `MallaLod::NIVELES_COMPACTOS`
+    pub niveles_lod: u8,
+}
+
+impl Default for AjustesVertices {
+    fn default() -> Self {
+        AjustesVertices {
+            compactos: true,
+            subdivisiones_esfera: 50,
+            niveles_lod: 8,
+        }
+    }
+}
+
+/// Cómo llegan a la GPU los datos de cada cuerpo y cómo se sombrean
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesDibujo {
+    /// Constantes de empuje por dibujo en vez de una franja de uniformes por cuerpo, si el
+    /// adaptador las tiene; desactivarlo sirve para comparar los dos caminos
+    pub constantes_empuje: bool,
+    /// Sombreado de caricatura en todos los cuerpos al arrancar
+    pub caricatura: bool,
+}
+
+impl Default for AjustesDibujo {
+    fn default() -> Self {
+        AjustesDibujo { constantes_empuje: true, caricatura: false }
+    }
+}
+
+/// Ritmo de la simulación con ventana
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesSimulacion {
+    /// Pasos fijos por segundo real, con los cuerpos interpolados al presentar; 0 = un paso
+    /// por cuadro
+    pub pasos_por_segundo: u32,
+}
+
+/// Grabación de cuadros con F9 (o `--grabar <destino>`)
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesGrabacion {
+    /// Paso fijo del reloj: cada cuadro grabado avanza 1/cuadros_por_segundo segundos reales
+    pub cuadros_por_segundo: u32,
+    /// Con F9, enviar los cuadros a ffmpeg (un .mp4) en vez de guardar PNG numerados
+    pub ffmpeg: bool,
+    /// Carpeta donde F9 crea cada grabación
+    pub carpeta: std::path::PathBuf,
+}
+
+impl Default for AjustesGrabacion {
+    fn default() -> Self {
+        AjustesGrabacion {
+            cuadros_por_segundo: 60,
+            ffmpeg: false,
+            carpeta: "grabaciones".into(),
+        }
+    }
+}
+
+/// Pantalla completa con F11
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesPantalla {
+    /// Arrancar en pantalla completa
+    pub completa: bool,
+    /// Pantalla completa exclusiva (cambia el modo de video del monitor) en vez de sin bordes
+    pub exclusiva: bool,
+    /// Índice del monitor; sin él, el de la ventana
+    pub monitor: Option<usize>,
+    /// Solo exclusiva: `[ancho, alto]`; sin ella, la mayor del monitor
+    pub resolucion: Option<[u32; 2]>,
+    /// Solo exclusiva: sin ella, la mayor que ofrece el monitor para esa resolución
+    pub frecuencia_hz: Option<u32>,
+    /// Un solo cuadro en cola, y la espera del refresco antes de leer la entrada (ver
+    /// `EstadoAplicacion::adelantar_cuadro`)
+    pub latencia_baja: bool,
+}
+
+impl AjustesPantalla {
+    /// Imágenes que la superficie deja en cola delante de la que se muestra
+    pub fn cuadros_en_cola(&self) -> u32 {
+        if self.latencia_baja {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Modo de pantalla completa pedido para `ventana`; si el monitor o el modo de video no
+    /// existen, el error enumera los disponibles
+    pub fn modo(&self, ventana: &Window) -> Result<winit::window::Fullscreen, String> {
+        let monitor = match self.monitor {
+            Some(indice) => ventana.available_monitors().nth(indice).ok_or_else(|| {
+                let nombres: Vec<String> = ventana
+                    .available_monitors()
+                    .enumerate()
+                    .map(|(i, m)| format!("{} = {}", i, m.name().unwrap_or_else(|| "sin nombre".into())))
+                    .collect();
+                format!("no hay monitor {} (disponibles: {})", indice, nombres.join(", "))
+            })?,
+            None => ventana
+                .current_monitor()
+                .or_else(|| ventana.primary_monitor())
+                .ok_or("no se encontró el monitor de la ventana")?,
+        };
+        if !self.exclusiva {
+            return Ok(winit::window::Fullscreen::Borderless(Some(monitor)));
+        }
+
+        let frecuencia = |modo: &winit::monitor::VideoMode| modo.refresh_rate_millihertz().div_ceil(1000);
+        let modos: Vec<winit::monitor::VideoMode> = monitor.video_modes().collect();
+        let elegido = modos
+            .iter()
+            .filter(|modo| self.resolucion.is_none_or(|[ancho, alto]| modo.size() == (ancho, alto).into()))
+            .filter(|modo| self.frecuencia_hz.is_none_or(|hz| frecuencia(modo) == hz))
+            .max_by_key(|modo| (modo.size().width * modo.size().height, modo.refresh_rate_millihertz(), modo.bit_depth()));
+        match elegido {
+            Some(modo) => Ok(winit::window::Fullscreen::Exclusive(modo.clone())),
+            None => {
+                let mut disponibles: Vec<String> = modos
+                    .iter()
+                    .map(|modo| format!("{}x{}@{}", modo.size().width, modo.size().height, frecuencia(modo)))
+                    .collect();
+                disponibles.sort();
+                disponibles.dedup();
+                Err(format!("el monitor no ofrece ese modo (disponibles: {})", disponibles.join(", ")))
+            }
+        }
+    }
+}
+
+/// Filtro de pantalla completa sobre la imagen de la escena (la interfaz queda sin filtrar)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FiltroPosproceso {
+    #[default]
+    Ninguno,
+    /// Televisor de tubo: distorsión de barril, líneas de barrido y aberración cromática
+    Crt,
+}
+
+impl FiltroPosproceso {
+    pub const TODOS: [FiltroPosproceso; 2] = [FiltroPosproceso::Ninguno, FiltroPosproceso::Crt];
+
+    pub fn nombre(self) -> &'static str {
+        match self {
+            FiltroPosproceso::Ninguno => "Ninguno",
+            FiltroPosproceso::Crt => "CRT",
+        }
+    }
+}
+
+/// Posproceso de la imagen antes de presentarla
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesPosproceso {
+    /// Filtro al arrancar; el panel (F1) lo cambia
+    pub filtro: FiltroPosproceso,
+    /// Píxeles de la ventana por píxel de la escena: con más de 1 se dibuja a baja
+    /// resolución y se amplía sin suavizar (estética retro, y alivio para GPU lentas)
+    pub pixelado: u32,
+    /// Con el pixelado, reducir los colores a una paleta de 16 con tramado ordenado
+    pub paleta: bool,
+}
+
+impl Default for AjustesPosproceso {
+    fn default() -> Self {
+        AjustesPosproceso { filtro: FiltroPosproceso::Ninguno, pixelado: 1, paleta: false }
+    }
+}
+
+/// Memoria de video que pueden ocupar las texturas de superficie
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesMemoria {
+    /// Presupuesto en MB; 0 lo elige según el tipo de adaptador
+    pub presupuesto_texturas_mb: u32,
+}
+
+impl AjustesMemoria {
+    /// wgpu no informa la VRAM disponible: sin valor explícito se supone una fracción
+    /// prudente según el tipo de adaptador (una GPU dedicada de 2 GB queda en la mitad)
+    pub fn presupuesto_bytes(&self, tipo: wgpu::DeviceType) -> u64 {
+        let megas = match (self.presupuesto_texturas_mb, tipo) {
+            (0, wgpu::DeviceType::DiscreteGpu) => 1024,
+            (0, wgpu::DeviceType::IntegratedGpu) => 512,
+            (0, _) => 256,
+            (megas, _) => megas,
+        };
+        megas as u64 * 1024 * 1024
+    }
+}
+
+/// Adaptador gráfico en equipos con más de uno (p. ej. integrada y dedicada en un portátil);
+/// el panel de depuración lo cambia sin reiniciar, salvo con OpenGL
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesGpu {
+    /// Parte del nombre del adaptador (sin distinguir mayúsculas), o su etiqueta del panel;
+    /// sin él, el que elige wgpu
+    pub adaptador: Option<String>,
+}
+
+impl AjustesGpu {
+    /// Nombre y backend, p. ej. "Intel(R) UHD Graphics (Vulkan)"
+    pub fn etiqueta(info: &wgpu::AdapterInfo) -> String {
+        format!("{} ({:?})", info.name, info.backend)
+    }
+
+    pub fn elige(&self, info: &wgpu::AdapterInfo) -> bool {
+        self.adaptador.as_deref().is_some_and(|pedido| {
+            Self::etiqueta(info) == pedido || info.name.to_lowercase().contains(&pedido.to_lowercase())
+        })
+    }
+}
+
+/// Capas que se pueden mostrar u ocultar por separado; se guardan al cambiarlas
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AjustesCapas {
+    pub planetas: bool,
+    pub lunas: bool,
+    pub asteroides: bool,
+    pub orbitas: bool,
+    /// Estelas de los cuerpos coloreadas por rapidez
+    pub estelas: bool,
+    pub etiquetas: bool,
+    /// Nombre de cada cuerpo sobre él, desvaneciéndose con la distancia
+    pub nombres: bool,
+    pub cuadriculas: bool,
+    /// Dispersión de las atmósferas definidas en la escena
+    pub atmosferas: bool,
+    /// Llamaradas, colas de cometas y escombros (de impactos y de los anillos)
+    pub particulas: bool,
+    /// Líneas de campo de los cuerpos con magnetosfera
+    pub magnetosferas: bool,
+    /// Ejes, eje de giro y ecuador del cuerpo seleccionado
+    pub ejes: bool,
+}
+
+impl Default for AjustesCapas {
+    fn default() -> Self {
+        AjustesCapas {
+            planetas: true,
+            lunas: true,
+            asteroides: true,
+            orbitas: false,
+            estelas: false,
+            etiquetas: true,
+            nombres: true,
+            cuadriculas: true,
+            atmosferas: true,
+            particulas: true,
+            magnetosferas: false,
+            ejes: false,
+        }
+    }
+}
+
+impl AjustesCapas {
+    pub fn muestra(&self, clase: ClaseCuerpo) -> bool {
+        match clase {
+            ClaseCuerpo::Estrella | ClaseCuerpo::Planeta => self.planetas,
+            ClaseCuerpo::Luna => self.lunas,
+            ClaseCuerpo::Asteroide => self.asteroides,
+        }
+    }
+
+    pub fn campos_mut(&mut self) -> [(&'static str, &mut bool); 12] {
+        [
+            ("planetas", &mut self.planetas),
+            ("lunas", &mut self.lunas),
+            ("asteroides", &mut self.asteroides),
+            ("orbitas", &mut self.orbitas),
+            ("estelas", &mut self.estelas),
+            ("etiquetas", &mut self.etiquetas),
+            ("nombres", &mut self.nombres),
+            ("cuadriculas", &mut self.cuadriculas),
+            ("atmosferas", &mut self.atmosferas),
+            ("particulas", &mut self.particulas),
+            ("magnetosferas", &mut self.magnetosferas),
+            ("ejes", &mut self.ejes),
+        ]
+    }
+
+    /// Escribe la tabla `[capas]` en `ruta` conservando el resto del archivo y sus comentarios
+    pub fn guardar(&self, ruta: &std::path::Path) -> Result<(), String> {
+        let texto = if ruta.exists() {
+            std::fs::read_to_string(ruta)
+                .map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?
+        } else {
+            String::new()
+        };
+        let mut documento: toml_edit::DocumentMut = texto
+            .parse()
+            .map_err(|e| format!("{} no es válido: {}", ruta.display(), e))?;
+        if !documento.contains_table("capas") {
+            documento["capas"] = toml_edit::table();
+        }
+        let tabla = &mut documento["capas"];
+        let mut capas = self.clone();
+        for (clave, valor) in capas.campos_mut() {
+            match tabla[clave].as_value_mut() {
+                Some(actual) => {
+                    let decoracion = actual.decor().clone();
+                    *actual = toml_edit::Value::from(*valor);
+                    *actual.decor_mut() = decoracion;
+                }
+                None => tabla[clave] = toml_edit::value(*valor),
+            }
+        }
+        std::fs::write(ruta, documento.to_string())
+            .map_err(|e| format!("no se pudo escribir {}: {}", ruta.display(), e))
+    }
+}
+
+impl Ajustes {
+    pub const RUTA_PREDETERMINADA: &'static str = "ajustes.toml";
+
+    pub fn cargar(ruta: &std::path::Path) -> Result<Self, String> {
+        let texto = std::fs::read_to_string(ruta)
+            .map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+        let mut ajustes: Ajustes =
+            toml::from_str(&texto).map_err(|e| format!("{} no es válido: {}", ruta.display(), e))?;
+        ajustes.ruta = Some(ruta.to_path_buf());
+        Ok(ajustes)
+    }
+
+    /// Usa `--ajustes <ruta>` si se indicó; si no, `ajustes.toml` cuando existe
+    pub fn desde_argumentos(argumentos: &[String]) -> Result<Self, String> {
+        match valor_argumento(argumentos, "--ajustes") {
+            Some(ruta) => Ajustes::cargar(std::path::Path::new(ruta)),
+            None => {
+                let ruta = std::path::Path::new(Ajustes::RUTA_PREDETERMINADA);
+                if ruta.exists() {
+                    Ajustes::cargar(ruta)
+                } else {
+                    Ok(Ajustes::default())
+                }
+            }
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: ANIMACIÓN
+// =============================================================================
+
+/// Propiedad de un cuerpo que se puede animar con claves
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PropiedadAnimable {
+    Escala,
+    Actividad,
+    Color,
+}
+
+/// Curva de suavizado del tramo que termina en una clave
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Curva {
+    #[default]
+    Lineal,
+    /// Acelera y frena (smoothstep)
+    Suave,
+    /// Arranca lento (cuadrática)
+    Entrada,
+    /// Termina lento (cuadrática)
+    Salida,
+    /// Salta al valor de la clave al llegar a ella
+    Escalon,
+}
+
+impl Curva {
+    pub fn aplicar(self, u: f32) -> f32 {
+        let u = u.clamp(0.0, 1.0);
+        match self {
+            Curva::Lineal => u,
+            Curva::Suave => u * u * (3.0 - 2.0 * u),
+            Curva::Entrada => u * u,
+            Curva::Salida => u * (2.0 - u),
+            Curva::Escalon => if u >= 1.0 { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+/// Valor de una clave: un número o un color RGB
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ValorAnimado {
+    Escalar(f32),
+    Color([f32; 3]),
+}
+
+impl ValorAnimado {
+    fn interpolar(self, otro: ValorAnimado, u: f32) -> ValorAnimado {
+        match (self, otro) {
+            (ValorAnimado::Escalar(a), ValorAnimado::Escalar(b)) => ValorAnimado::Escalar(a + (b - a) * u),
+            (ValorAnimado::Color(a), ValorAnimado::Color(b)) => {
+                ValorAnimado::Color(std::array::from_fn(|i| a[i] + (b[i] - a[i]) * u))
+            }
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Clave {
+    /// Segundos desde el inicio de la animación
+    pub t: f32,
+    pub valor: ValorAnimado,
+    #[serde(default)]
+    pub curva: Curva,
+}
+
+/// Pista de claves sobre una propiedad de un cuerpo, tal como se escribe en la escena
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinicionAnimacion {
+    pub cuerpo: String,
+    pub propiedad: PropiedadAnimable,
+    pub claves: Vec<Clave>,
+    #[serde(default)]
+    pub repetir: bool,
+}
+
+/// Pista validada y ligada al índice de su cuerpo
+#[derive(Debug, Clone)]
+pub struct Animacion {
+    pub cuerpo: usize,
+    pub propiedad: PropiedadAnimable,
+    claves: Vec<Clave>,
+    repetir: bool,
+}
+
+impl Animacion {
+    pub fn resolver(definicion: DefinicionAnimacion, cuerpos: &[CuerpoCeleste]) -> Result<Self, String> {
+        let contexto = format!("animación de {} ({:?})", definicion.cuerpo, definicion.propiedad);
+        let cuerpo = cuerpos
+            .iter()
+            .position(|c| c.nombre == definicion.cuerpo)
+            .ok_or_else(|| format!("{}: cuerpo desconocido", contexto))?;
+        if definicion.claves.is_empty() {
+            return Err(format!("{}: no tiene claves", contexto));
+        }
+        if definicion.claves.windows(2).any(|par| par[1].t <= par[0].t) {
+            return Err(format!("{}: las claves deben ir en orden creciente de t", contexto));
+        }
+        let espera_color = definicion.propiedad == PropiedadAnimable::Color;
+        for clave in &definicion.claves {
+            if matches!(clave.valor, ValorAnimado::Color(_)) != espera_color {
+                let esperado = if espera_color { "un color [r, g, b]" } else { "un número" };
+                return Err(format!("{}: la clave en t = {} debe ser {}", contexto, clave.t, esperado));
+            }
+        }
+        Ok(Animacion {
+            cuerpo,
+            propiedad: definicion.propiedad,
+            claves: definicion.claves,
+            repetir: definicion.repetir,
+        })
+    }
+
+    /// Valor en el instante `tiempo`; fuera del rango se mantiene la clave extrema
+    pub fn evaluar(&self, tiempo: f32) -> ValorAnimado {
+        let primera = &self.claves[0];
+        let ultima = &self.claves[self.claves.len() - 1];
+        let duracion = ultima.t - primera.t;
+        let t = if self.repetir && duracion > 0.0 {
+            primera.t + (tiempo - primera.t).rem_euclid(duracion)
+        } else {
+            tiempo
+        };
+
+        match self.claves.iter().position(|clave| clave.t > t) {
+            None => ultima.valor,
+            Some(0) => primera.valor,
+            Some(siguiente) => {
+                let (a, b) = (&self.claves[siguiente - 1], &self.claves[siguiente]);
+                let u = b.curva.aplicar((t - a.t) / (b.t - a.t));
+                a.valor.interpolar(b.valor, u)
+            }
+        }
+    }
+
+    pub fn aplicar(&self, cuerpos: &mut [CuerpoCeleste], tiempo: f32) {
+        let cuerpo = &mut cuerpos[self.cuerpo];
+        match (self.propiedad, self.evaluar(tiempo)) {
+            (PropiedadAnimable::Escala, ValorAnimado::Escalar(valor)) => cuerpo.escala = valor,
+            (PropiedadAnimable::Actividad, ValorAnimado::Escalar(valor)) => cuerpo.actividad = valor,
+            (PropiedadAnimable::Color, ValorAnimado::Color(valor)) => cuerpo.tinte = valor,
+            _ => {}
+        }
+    }
+}
+
+/// Mapa de superficie de un cuerpo en `[texturas]`
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum MapaSuperficie {
+    /// Equirectangular (2:1) o las seis caras de un cubo en cruz horizontal (4:3)
+    Imagen(std::path::PathBuf),
+    /// Una imagen cuadrada por cara del cubo: +X, -X, +Y (polo norte), -Y, +Z, -Z
+    Caras([std::path::PathBuf; 6]),
+}
+
+impl MapaSuperficie {
+    /// Archivos de los que se lee
+    pub fn rutas(&self) -> &[std::path::PathBuf] {
+        match self {
+            MapaSuperficie::Imagen(ruta) => std::slice::from_ref(ruta),
+            MapaSuperficie::Caras(caras) => caras,
+        }
+    }
+}
+
+/// Cómo se presenta la escena
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EstiloEscena {
+    #[default]
+    Natural,
+    /// Maqueta mecánica: brazos y ejes de latón unen cada cuerpo a su centro, sobre una base
+    Planetario,
+}
+
+/// Archivo de escena (`escena.toml`)
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchivoEscena {
+    /// Cuerpos en orden: un padre debe aparecer antes que sus hijos
+    pub cuerpo: Vec<DefinicionCuerpo>,
+    pub animacion: Vec<DefinicionAnimacion>,
+    /// Mapas de superficie (PNG, JPEG o HDR) por nombre de cuerpo
+    pub texturas: std::collections::BTreeMap<String, MapaSuperficie>,
+    pub cinturon: Option<DefinicionCinturon>,
+    pub estilo: EstiloEscena,
+    /// Archivo del que se leyó; `None` para la copia incluida
+    #[serde(skip)]
+    pub ruta: Option<std::path::PathBuf>,
+}
+
+impl ArchivoEscena {
+    pub const RUTA_PREDETERMINADA: &'static str = "escena.toml";
+    /// Copia de `escena.toml` incluida en el ejecutable, para cuando no está el archivo
+    const INCLUIDA: &'static str = include_str!("../escena.toml");
+
+    pub fn cargar(ruta: &std::path::Path) -> Result<Self, String> {
+        let texto = std::fs::read_to_string(ruta)
+            .map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+        let mut escena = Self::interpretar(&texto, &ruta.display().to_string())?;
+        escena.ruta = Some(ruta.to_path_buf());
+        Ok(escena)
+    }
+
+    fn interpretar(texto: &str, origen: &str) -> Result<Self, String> {
+        let escena: ArchivoEscena = toml::from_str(texto).map_err(|e| format!("{} no es válido: {}", origen, e))?;
+        escena.cuerpos().map_err(|e| format!("{}: {}", origen, e))?;
+        if let Some(cinturon) = &escena.cinturon {
+            cinturon.validar().map_err(|e| format!("{}: cinturón: {}", origen, e))?;
+        }
+        Ok(escena)
+    }
+
+    /// Usa `--escena <ruta>` si se indicó; si no, `escena.toml` cuando existe y, si tampoco,
+    /// la copia incluida. Un archivo mal escrito es un error, no se ignora
+    pub fn desde_argumentos(argumentos: &[String]) -> Result<Self, String> {
+        match valor_argumento(argumentos, "--escena") {
+            Some(ruta) => ArchivoEscena::cargar(std::path::Path::new(ruta)),
+            None => {
+                let ruta = std::path::Path::new(ArchivoEscena::RUTA_PREDETERMINADA);
+                if ruta.exists() {
+                    ArchivoEscena::cargar(ruta)
+                } else {
+                    ArchivoEscena::interpretar(Self::INCLUIDA, "escena incluida")
+                }
+            }
+        }
+    }
+
+    /// Crea los cuerpos en el orden del archivo
+    pub fn cuerpos(&self) -> Result<Vec<CuerpoCeleste>, String> {
+        if self.cuerpo.is_empty() {
+            return Err("no define ningún cuerpo ([[cuerpo]])".to_string());
+        }
+        let mut cuerpos = Vec::with_capacity(self.cuerpo.len());
+        for definicion in &self.cuerpo {
+            let cuerpo = definicion.resolver(&cuerpos)?;
+            cuerpos.push(cuerpo);
+        }
+        Ok(cuerpos)
+    }
+
+    /// Liga las animaciones a los cuerpos de la escena
+    pub fn animaciones(&self, cuerpos: &[CuerpoCeleste]) -> Result<Vec<Animacion>, String> {
+        self.animacion
+            .iter()
+            .map(|definicion| Animacion::resolver(definicion.clone(), cuerpos))
+            .collect()
+    }
+}
+
+// =============================================================================
+// MÓDULO: RELOJ
+// =============================================================================
+
+/// Reloj de simulación independiente del reloj de pared: se puede pausar, acelerar e invertir
+pub struct RelojSimulacion {
+    /// Segundos de simulación transcurridos
+    pub tiempo: f64,
+    pub escala: f64,
+    pub pausado: bool,
+    pub invertido: bool,
+}
+
+impl RelojSimulacion {
+    pub const ESCALA_MINIMA: f64 = 0.1;
+    pub const ESCALA_MAXIMA: f64 = 1000.0;
+
+    pub fn nuevo() -> Self {
+        RelojSimulacion {
+            tiempo: 0.0,
+            escala: 1.0,
+            pausado: false,
+            invertido: false,
+        }
+    }
+
+    /// Avanza según `segundos_reales` de reloj de pared
+    pub fn avanzar(&mut self, segundos_reales: f64) {
+        if !self.pausado {
+            let signo = if self.invertido { -1.0 } else { 1.0 };
+            self.tiempo += segundos_reales * self.escala * signo;
+        }
+    }
+
+    /// Multiplica la escala de tiempo, respetando sus límites
+    pub fn ajustar_escala(&mut self, factor: f64) {
+        self.escala = (self.escala * factor).clamp(Self::ESCALA_MINIMA, Self::ESCALA_MAXIMA);
+    }
+
+    /// Estado del reloj para mostrar; `None` si corre normal (1x hacia adelante)
+    pub fn describir(&self) -> Option<String> {
+        if !self.pausado && !self.invertido && self.escala == 1.0 {
+            return None;
+        }
+        let mut texto = format!("Tiempo x{}", self.escala);
+        if self.invertido {
+            texto.push_str(" (reversa)");
+        }
+        if self.pausado {
+            texto.push_str(" (pausa)");
+        }
+        Some(texto)
+    }
+}
+
+/// Depuración cuadro a cuadro: activa, la simulación solo avanza un cuadro por cada pedido
+/// y siempre con `PASO` segundos reales, sin importar cuánto tardó el cuadro
+#[derive(Debug, Clone, Default)]
+pub struct PasoAPaso {
+    pub activo: bool,
+    /// Cuadros pedidos que aún no se simularon
+    pendientes: u32,
+    /// Cuadros avanzados desde que se activó
+    pub cuadros: u64,
+    /// Segundos de simulación que avanzó el último cuadro (negativo en reversa)
+    pub ultimo_paso: f64,
+    /// El último cuadro no se simuló: repite la imagen anterior
+    pub congelado: bool,
+}
+
+impl PasoAPaso {
+    pub const PASO: f64 = 1.0 / 60.0;
+
+    pub fn alternar(&mut self) {
+        *self = PasoAPaso {
+            activo: !self.activo,
+            ..PasoAPaso::default()
+        };
+    }
+
+    pub fn pedir(&mut self) {
+        if self.activo {
+            self.pendientes += 1;
+        }
+    }
+
+    /// `true` si este cuadro se simula; consume un pedido
+    pub fn avanzar(&mut self) -> bool {
+        self.congelado = self.activo && self.pendientes == 0;
+        if self.activo && !self.congelado {
+            self.pendientes -= 1;
+            self.cuadros += 1;
+        }
+        !self.congelado
+    }
+
+    pub fn describir(&self) -> Option<String> {
+        self.activo
+            .then(|| format!("Paso a paso: cuadro {} · dt {:.4} s", self.cuadros, self.ultimo_paso))
+    }
+}
+
+/// Posiciones de mundo de los cuerpos y matrices de sus nodos tras un paso de simulación
+#[derive(Debug, Clone)]
+struct InstantaneaCuerpos {
+    tiempo: f32,
+    posiciones: Vec<[f32; 2]>,
+    mundos: Vec<Mat4>,
+}
+
+impl InstantaneaCuerpos {
+    fn tomar(tiempo: f32, cuerpos: &[CuerpoCeleste], grafo: &GrafoEscena) -> Self {
+        InstantaneaCuerpos {
+            tiempo,
+            posiciones: cuerpos.iter().map(|c| c.posicion).collect(),
+            mundos: grafo.nodos.iter().map(|n| n.mundo).collect(),
+        }
+    }
+}
+
+/// Simulación a paso fijo: con `pasos_por_segundo` > 0 la simulación avanza en pasos de
+/// duración fija y, al presentar, los cuerpos se dibujan interpolados entre los dos últimos,
+/// así el movimiento sigue suave aunque la pantalla refresque más rápido que la simulación.
+/// Cada paso recalcula las posiciones desde el reloj o la física, así que lo interpolado
+/// nunca se acumula
+#[derive(Debug, Default)]
+pub struct PasoFijo {
+    /// 0 = un paso por cuadro, sin interpolar
+    pub pasos_por_segundo: u32,
+    /// Segundos reales que aún no completan un paso
+    acumulado: f64,
+    anterior: Option<InstantaneaCuerpos>,
+    actual: Option<InstantaneaCuerpos>,
+}
+
+impl PasoFijo {
+    /// Tope de pasos por cuadro: tras una pausa larga el reloj salta en vez de ponerse al día
+    const MAXIMO_PASOS: u32 = 8;
+
+    pub fn activo(&self) -> bool {
+        self.pasos_por_segundo > 0
+    }
+
+    pub fn paso(&self) -> f64 {
+        1.0 / self.pasos_por_segundo.max(1) as f64
+    }
+
+    /// Suma `segundos_reales` y devuelve cuántos pasos completos hay que simular
+    fn acumular(&mut self, segundos_reales: f64) -> u32 {
+        let paso = self.paso();
+        self.acumulado += segundos_reales;
+        let pasos = (self.acumulado / paso).floor() as u32;
+        self.acumulado -= pasos as f64 * paso;
+        if pasos > Self::MAXIMO_PASOS {
+            self.acumulado = 0.0;
+        }
+        pasos.min(Self::MAXIMO_PASOS)
+    }
+
+    /// Guarda el estado del paso recién simulado; el anterior pasa a ser el punto de partida
+    fn registrar(&mut self, instantanea: InstantaneaCuerpos) {
+        self.anterior = self.actual.replace(instantanea);
+    }
+
+    /// Deja en `cuerpos` y en el grafo el estado entre los dos últimos pasos según lo que se
+    /// acumuló del siguiente; devuelve el tiempo interpolado. Sin dos pasos compatibles (la
+    /// escena cambió) deja el último
+    fn interpolar(&self, cuerpos: &mut [CuerpoCeleste], grafo: &mut GrafoEscena) -> Option<f32> {
+        let (anterior, actual) = (self.anterior.as_ref()?, self.actual.as_ref()?);
+        if anterior.posiciones.len() != cuerpos.len()
+            || actual.posiciones.len() != cuerpos.len()
+            || anterior.mundos.len() != grafo.nodos.len()
+            || actual.mundos.len() != grafo.nodos.len()
+        {
+            return None;
+        }
+        let t = (self.acumulado / self.paso()).clamp(0.0, 1.0) as f32;
+        for (cuerpo, (a, b)) in cuerpos.iter_mut().zip(anterior.posiciones.iter().zip(&actual.posiciones)) {
+            cuerpo.posicion = [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t];
+        }
+        for (nodo, (a, b)) in grafo.nodos.iter_mut().zip(anterior.mundos.iter().zip(&actual.mundos)) {
+            nodo.mundo = a + (b - a) * t;
+        }
+        Some(anterior.tiempo + (actual.tiempo - anterior.tiempo) * t)
+    }
+
+    /// Olvida los pasos guardados (p. ej. al cambiar a un cuadro por paso)
+    pub fn reiniciar(&mut self) {
+        self.acumulado = 0.0;
+        self.anterior = None;
+        self.actual = None;
+    }
+}
+
+/// Frecuencia de refresco del monitor de la ventana. Con vsync cada cuadro dura casi un
+/// múltiplo del período, más el ruido del planificador; `cuantizar` lleva el paso medido al
+/// múltiplo más cercano y arrastra la diferencia al siguiente, así a 30, 60 o 144 Hz las
+/// animaciones avanzan parejas y en total tanto como el reloj de pared
+#[derive(Debug, Clone, Default)]
+pub struct FrecuenciaPantalla {
+    /// Hz del monitor; `None` si la plataforma no la informa
+    pub hz: Option<f64>,
+    /// Segundos medidos que todavía no se entregaron
+    residuo: f64,
+}
+
+impl FrecuenciaPantalla {
+    /// Desvío aceptado respecto del múltiplo más cercano, en fracciones del período
+    const TOLERANCIA: f64 = 0.2;
+
+    pub fn del_monitor(ventana: &Window) -> Option<f64> {
+        let milihertz = ventana.current_monitor()?.refresh_rate_millihertz()?;
+        (milihertz > 0).then(|| milihertz as f64 / 1000.0)
+    }
+
+    /// Segundos entre refrescos
+    pub fn periodo(&self) -> Option<f64> {
+        self.hz.map(|hz| 1.0 / hz)
+    }
+
+    /// Paso de animación para un cuadro que midió `medido` segundos de pared. Sin frecuencia,
+    /// o con un cuadro que no se parece a ningún múltiplo (una pausa, un tirón), se usa tal cual
+    pub fn cuantizar(&mut self, medido: f64) -> f64 {
+        let Some(periodo) = self.periodo() else { return medido };
+        let total = medido + self.residuo;
+        let refrescos = (total / periodo).round();
+        let cuantizado = refrescos * periodo;
+        if refrescos >= 1.0 && (total - cuantizado).abs() <= Self::TOLERANCIA * periodo {
+            self.residuo = total - cuantizado;
+            cuantizado
+        } else {
+            self.residuo = 0.0;
+            total
+        }
+    }
+}
+
+/// Tiempo desde la primera entrada que mueve la cámara (arrastre o rueda) hasta que se
+/// presenta el cuadro que la muestra; falta el barrido del monitor, igual en ambos modos
+#[derive(Debug, Clone, Default)]
+pub struct LatenciaEntrada {
+    /// Entrada más vieja que todavía no se presentó
+    pendiente: Option<std::time::Instant>,
+    /// Promedio móvil exponencial, en segundos
+    pub promedio: Option<f64>,
+}
+
+impl LatenciaEntrada {
+    /// Peso de cada medición nueva en el promedio
+    const PESO: f64 = 0.1;
+
+    pub fn entrada(&mut self) {
+        self.pendiente.get_or_insert_with(std::time::Instant::now);
+    }
+
+    pub fn presentado(&mut self) {
+        let Some(inicio) = self.pendiente.take() else { return };
+        let medida = inicio.elapsed().as_secs_f64();
+        self.promedio = Some(self.promedio.map_or(medida, |promedio| promedio + (medida - promedio) * Self::PESO));
+    }
+
+    pub fn reiniciar(&mut self) {
+        *self = LatenciaEntrada::default();
+    }
+}
+
+// =============================================================================
+// MÓDULO: ENLACES
+// =============================================================================
+
+/// Vista compartible: pose de la cámara, cuerpo enfocado, marco de referencia y estado del
+/// reloj. Se escribe como texto compacto (`v1/g0.6/c0.3/r3.5/fMarte/t120/e2/p`) que otra persona puede
+/// pasar con `--vista` para reproducir exactamente la misma vista
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnlaceVista {
+    pub guinada: f32,
+    pub cabeceo: f32,
+    pub radio: f32,
+    /// Objetivo de la cámara; solo se usa si no hay cuerpo enfocado
+    pub objetivo: [f32; 3],
+    /// Nombre del cuerpo enfocado (en el texto, con codificación por ciento como en una URL)
+    pub foco: Option<String>,
+    /// Cuerpo en el origen del marco de referencia; sin él, el de la escena
+    pub marco: Option<String>,
+    pub tiempo: f64,
+    pub escala: f64,
+    pub pausado: bool,
+    pub invertido: bool,
+}
+
+impl EnlaceVista {
+    const VERSION: &'static str = "v1";
+
+    /// Lee un enlace; los campos ausentes conservan el valor de `base`
+    pub fn interpretar(texto: &str, base: EnlaceVista) -> Result<Self, String> {
+        let mut campos = texto.trim().split('/');
+        if campos.next() != Some(Self::VERSION) {
+            return Err(format!("el enlace debe empezar con '{}/'", Self::VERSION));
+        }
+        let mut enlace = EnlaceVista { marco: None, pausado: false, invertido: false, ..base };
+        for campo in campos.filter(|c| !c.is_empty()) {
+            let (clave, valor) = campo.split_at(campo.chars().next().map_or(0, char::len_utf8));
+            // `NaN` e `inf` se leen como números, pero dejarían la cámara inservible
+            let numero = |valor: &str| -> Result<f64, String> {
+                valor
+                    .parse()
+                    .ok()
+                    .filter(|numero: &f64| numero.is_finite())
+                    .ok_or_else(|| format!("valor inválido en el campo '{}' del enlace", campo))
+            };
+            match clave {
+                "g" => enlace.guinada = numero(valor)? as f32,
+                "c" => enlace.cabeceo = numero(valor)? as f32,
+                "r" => enlace.radio = numero(valor)? as f32,
+                "o" => {
+                    let componentes = valor.split(',').map(numero).collect::<Result<Vec<_>, _>>()?;
+                    let [x, y, z] = componentes[..] else {
+                        return Err(format!("el objetivo '{}' debe tener tres componentes", valor));
+                    };
+                    enlace.objetivo = [x as f32, y as f32, z as f32];
+                }
+                "f" => enlace.foco = Some(Self::decodificar_nombre(valor)?),
+                "m" => enlace.marco = Some(Self::decodificar_nombre(valor)?),
+                "t" => enlace.tiempo = numero(valor)?,
+                "e" => enlace.escala = numero(valor)?
+                    .clamp(RelojSimulacion::ESCALA_MINIMA, RelojSimulacion::ESCALA_MAXIMA),
+                "p" if valor.is_empty() => enlace.pausado = true,
+                "i" if valor.is_empty() => enlace.invertido = true,
+                _ => return Err(format!("campo desconocido '{}' en el enlace", campo)),
+            }
+        }
+        Ok(enlace)
+    }
+
+    /// Deja tal cual letras ASCII, dígitos y `-._~`, y escribe el resto de los bytes como
+    /// `%XX`; así un nombre con `/`, `_` o `%` vuelve a leerse igual
+    fn codificar_nombre(nombre: &str) -> String {
+        nombre
+            .bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect()
+    }
+
+    fn decodificar_nombre(valor: &str) -> Result<String, String> {
+        let invalido = || format!("nombre mal codificado '{}' en el enlace", valor);
+        let mut bytes = Vec::with_capacity(valor.len());
+        let mut resto = valor.as_bytes();
+        while let Some((&byte, siguientes)) = resto.split_first() {
+            if byte == b'%' {
+                let digito = |i: usize| siguientes.get(i).and_then(|&b| (b as char).to_digit(16));
+                let (Some(alto), Some(bajo)) = (digito(0), digito(1)) else { return Err(invalido()) };
+                bytes.push((alto * 16 + bajo) as u8);
+                resto = &siguientes[2..];
+            } else {
+                bytes.push(byte);
+                resto = siguientes;
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| invalido())
+    }
+}
+
+/// Los números usan la representación más corta que se vuelve a leer sin pérdida
+impl fmt::Display for EnlaceVista {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/g{}/c{}/r{}", Self::VERSION, self.guinada, self.cabeceo, self.radio)?;
+        match &self.foco {
+            Some(nombre) => write!(f, "/f{}", Self::codificar_nombre(nombre))?,
+            None => {
+                let [x, y, z] = self.objetivo;
+                write!(f, "/o{},{},{}", x, y, z)?;
+            }
+        }
+        if let Some(nombre) = &self.marco {
+            write!(f, "/m{}", Self::codificar_nombre(nombre))?;
+        }
+        write!(f, "/t{}/e{}", self.tiempo, self.escala)?;
+        if self.pausado {
+            write!(f, "/p")?;
+        }
+        if self.invertido {
+            write!(f, "/i")?;
+        }
+        Ok(())
+    }
+}
+
+/// Texto del enlace pasado con `--vista`; `-` lo lee de la entrada estándar
+fn enlace_desde_argumentos(argumentos: &[String]) -> Result<Option<String>, String> {
+    match valor_argumento(argumentos, "--vista") {
+        Some("-") => {
+            let mut linea = String::new();
+            std::io::stdin()
+                .read_line(&mut linea)
+                .map_err(|e| format!("no se pudo leer el enlace de la entrada: {}", e))?;
+            Ok(Some(linea.trim().to_string()))
+        }
+        Some(texto) => Ok(Some(texto.to_string())),
+        None => Ok(None),
+    }
+}
+
+// =============================================================================
+// MÓDULO: FÍSICA
+// =============================================================================
+
+/// Método de integración del modo N cuerpos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrador {
+    Euler,
+    Verlet,
+    Rk4,
+}
+
+impl Integrador {
+    pub const TODOS: [Integrador; 3] = [Integrador::Euler, Integrador::Verlet, Integrador::Rk4];
+
+    pub fn nombre(self) -> &'static str {
+        match self {
+            Integrador::Euler => "Euler",
+            Integrador::Verlet => "Verlet",
+            Integrador::Rk4 => "RK4",
+        }
+    }
+}
+
+/// Cantidades conservadas del sistema en un instante
+#[derive(Debug, Clone, Copy)]
+pub struct MuestraConservacion {
+    pub tiempo: f64,
+    pub cinetica: f64,
+    pub potencial: f64,
+    /// Norma del momento angular total respecto al origen
+    pub momento_angular: f64,
+}
+
+impl MuestraConservacion {
+    pub fn energia(&self) -> f64 {
+        self.cinetica + self.potencial
+    }
+}
+
+/// Modo N cuerpos: los cuerpos se mueven por gravitación mutua en vez de seguir sus órbitas.
+/// Las masas son proporcionales al volumen (misma densidad), con la estrella como unidad.
+pub struct SimulacionNCuerpos {
+    pub activa: bool,
+    /// En unidades de escena³ / (masa de la estrella · s²)
+    pub constante_gravitacional: f64,
+    pub integrador: Integrador,
+    /// Paso de integración en segundos de simulación
+    pub paso: f64,
+    /// Criterio de apertura θ de Barnes–Hut: un nodo de lado s a distancia d cuenta como un
+    /// solo cuerpo si s / d < θ (0 = exacto)
+    pub apertura: f64,
+    masas: Vec<f64>,
+    posiciones: Vec<DVec3>,
+    velocidades: Vec<DVec3>,
+    /// Instante de simulación del estado actual
+    tiempo: f64,
+    energia_inicial: f64,
+    /// Diagnóstico de conservación, una muestra cada `INTERVALO_HISTORIAL` segundos de simulación
+    pub historial: std::collections::VecDeque<MuestraConservacion>,
+}
+
+impl SimulacionNCuerpos {
+    pub const INTERVALO_HISTORIAL: f64 = 0.1;
+    pub const MAX_HISTORIAL: usize = 2000;
+    /// Suavizado de la distancia para que los acercamientos no disparen la aceleración
+    const SUAVIZADO: f64 = 0.02;
+    /// Cantidad de cuerpos a partir de la cual la gravedad se aproxima con un octree
+    pub const UMBRAL_BARNES_HUT: usize = 64;
+    /// Si un cuadro pide más pasos, el estado salta al instante pedido sin integrarlo
+    const MAX_PASOS_POR_CUADRO: usize = 20_000;
+
+    pub fn nueva() -> Self {
+        SimulacionNCuerpos {
+            activa: false,
+            constante_gravitacional: 0.01,
+            integrador: Integrador::Verlet,
+            paso: 0.01,
+            apertura: 0.5,
+            masas: Vec::new(),
+            posiciones: Vec::new(),
+            velocidades: Vec::new(),
+            tiempo: 0.0,
+            energia_inicial: 0.0,
+            historial: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Parte de las posiciones actuales con velocidades circulares alrededor del padre de cada
+    /// cuerpo (o de la estrella si no tiene), sumadas a la velocidad del padre
+    pub fn iniciar(&mut self, cuerpos: &[CuerpoCeleste], tiempo: f64) {
+        let radio_estrella = cuerpos
+            .iter()
+            .find(|c| c.es_estrella())
+            .map_or(1.0, |c| c.radio_km);
+        self.masas = cuerpos.iter().map(|c| (c.radio_km / radio_estrella).powi(3)).collect();
+        self.posiciones = cuerpos
+            .iter()
+            .map(|c| DVec3::new(c.posicion[0] as f64, c.posicion[1] as f64, 0.0))
+            .collect();
+        let estrella = cuerpos.iter().position(|c| c.es_estrella());
+        self.velocidades = Vec::with_capacity(cuerpos.len());
+        for i in 0..cuerpos.len() {
+            let centro = GrafoEscena::padre(cuerpos, i).or(estrella.filter(|&e| e < i));
+            let velocidad = match centro {
+                Some(centro) => {
+                    let relativa = self.posiciones[i] - self.posiciones[centro];
+                    let distancia = relativa.norm();
+                    let rapidez = (self.constante_gravitacional * self.masas[centro] / distancia).sqrt();
+                    // Perpendicular en el plano de la eclíptica, en sentido antihorario
+                    self.velocidades[centro] + DVec3::new(-relativa.y, relativa.x, 0.0) / distancia * rapidez
+                }
+                None => DVec3::zeros(),
+            };
+            self.velocidades.push(velocidad);
+        }
+
+        // Sin momento total, el sistema no deriva por la pantalla
+        let masa_total: f64 = self.masas.iter().sum();
+        let momento = self
+            .velocidades
+            .iter()
+            .zip(&self.masas)
+            .fold(DVec3::zeros(), |suma, (v, m)| suma + v * *m);
+        let deriva = momento / masa_total;
+        for velocidad in &mut self.velocidades {
+            *velocidad -= deriva;
+        }
+
+        self.tiempo = tiempo;
+        self.reiniciar_energia();
+    }
+
+    /// Toma la energía actual como referencia de la deriva y vacía el historial
+    pub fn reiniciar_energia(&mut self) {
+        self.energia_inicial = self.energia();
+        self.historial.clear();
+        self.registrar();
+    }
+
+    /// Energía cinética más potencial gravitatoria
+    pub fn energia(&self) -> f64 {
+        self.muestra().energia()
+    }
+
+    /// Energías y momento angular del estado actual
+    pub fn muestra(&self) -> MuestraConservacion {
+        let cinetica: f64 = self
+            .velocidades
+            .iter()
+            .zip(&self.masas)
+            .map(|(v, m)| 0.5 * m * v.norm_squared())
+            .sum();
+        let momento_angular = self
+            .posiciones
+            .iter()
+            .zip(&self.velocidades)
+            .zip(&self.masas)
+            .fold(DVec3::zeros(), |suma, ((x, v), m)| suma + x.cross(v) * *m);
+        let mut potencial = 0.0;
+        for i in 0..self.masas.len() {
+            for j in i + 1..self.masas.len() {
+                let distancia2 = (self.posiciones[i] - self.posiciones[j]).norm_squared();
+                let distancia = (distancia2 + Self::SUAVIZADO * Self::SUAVIZADO).sqrt();
+                potencial -= self.constante_gravitacional * self.masas[i] * self.masas[j] / distancia;
+            }
+        }
+        MuestraConservacion {
+            tiempo: self.tiempo,
+            cinetica,
+            potencial,
+            momento_angular: momento_angular.norm(),
+        }
+    }
+
+    fn registrar(&mut self) {
+        if self.historial.len() == Self::MAX_HISTORIAL {
+            self.historial.pop_front();
+        }
+        let muestra = self.muestra();
+        self.historial.push_back(muestra);
+    }
+
+    /// Cambio relativo de la energía desde la referencia
+    pub fn deriva_energia(&self) -> f64 {
+        if self.energia_inicial == 0.0 {
+            return 0.0;
+        }
+        (self.energia() - self.energia_inicial) / self.energia_inicial.abs()
+    }
+
+    /// Suma directa para pocos cuerpos, Barnes–Hut a partir de `UMBRAL_BARNES_HUT`
+    fn aceleraciones(&self, posiciones: &[DVec3]) -> Vec<DVec3> {
+        if posiciones.len() > Self::UMBRAL_BARNES_HUT {
+            let arbol = Octree::construir(posiciones, &self.masas);
+            let mut pendientes = Vec::new();
+            return posiciones
+                .iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    arbol.aceleracion(i, x, posiciones, &self.masas, self.apertura, &mut pendientes)
+                        * self.constante_gravitacional
+                })
+                .collect();
+        }
+        let mut aceleraciones = vec![DVec3::zeros(); posiciones.len()];
+        for i in 0..posiciones.len() {
+            for j in i + 1..posiciones.len() {
+                let relativa = posiciones[j] - posiciones[i];
+                let distancia2 = relativa.norm_squared() + Self::SUAVIZADO * Self::SUAVIZADO;
+                let factor = self.constante_gravitacional / (distancia2 * distancia2.sqrt());
+                aceleraciones[i] += relativa * (factor * self.masas[j]);
+                aceleraciones[j] -= relativa * (factor * self.masas[i]);
+            }
+        }
+        aceleraciones
+    }
+
+    fn integrar(&mut self, dt: f64) {
+        match self.integrador {
+            Integrador::Euler => {
+                let aceleraciones = self.aceleraciones(&self.posiciones);
+                for ((x, v), a) in self.posiciones.iter_mut().zip(&mut self.velocidades).zip(&aceleraciones) {
+                    *x += *v * dt;
+                    *v += a * dt;
+                }
+            }
+            Integrador::Verlet => {
+                let iniciales = self.aceleraciones(&self.posiciones);
+                for ((x, v), a) in self.posiciones.iter_mut().zip(&mut self.velocidades).zip(&iniciales) {
+                    *v += a * (dt * 0.5);
+                    *x += *v * dt;
+                }
+                let finales = self.aceleraciones(&self.posiciones);
+                for (v, a) in self.velocidades.iter_mut().zip(&finales) {
+                    *v += a * (dt * 0.5);
+                }
+            }
+            Integrador::Rk4 => {
+                let desplazar = |base: &[DVec3], derivada: &[DVec3], h: f64| -> Vec<DVec3> {
+                    base.iter().zip(derivada).map(|(b, d)| b + d * h).collect()
+                };
+                let (x0, v0) = (self.posiciones.clone(), self.velocidades.clone());
+                let a1 = self.aceleraciones(&x0);
+                let x2 = desplazar(&x0, &v0, dt * 0.5);
+                let v2 = desplazar(&v0, &a1, dt * 0.5);
+                let a2 = self.aceleraciones(&x2);
+                let x3 = desplazar(&x0, &v2, dt * 0.5);
+                let v3 = desplazar(&v0, &a2, dt * 0.5);
+                let a3 = self.aceleraciones(&x3);
+                let x4 = desplazar(&x0, &v3, dt);
+                let v4 = desplazar(&v0, &a3, dt);
+                let a4 = self.aceleraciones(&x4);
+                for i in 0..x0.len() {
+                    self.posiciones[i] = x0[i] + (v0[i] + v2[i] * 2.0 + v3[i] * 2.0 + v4[i]) * (dt / 6.0);
+                    self.velocidades[i] = v0[i] + (a1[i] + a2[i] * 2.0 + a3[i] * 2.0 + a4[i]) * (dt / 6.0);
+                }
+            }
+        }
+    }
+
+    /// Integra en pasos fijos hasta `tiempo` (hacia atrás si el reloj va en reversa)
+    pub fn avanzar_hasta(&mut self, tiempo: f64) {
+        let restante = tiempo - self.tiempo;
+        let pasos = (restante.abs() / self.paso).floor() as usize;
+        if pasos > Self::MAX_PASOS_POR_CUADRO {
+            self.tiempo = tiempo;
+            return;
+        }
+        let paso = self.paso.copysign(restante);
+        for _ in 0..pasos {
+            self.integrar(paso);
+            self.tiempo += paso;
+            let ultimo = self.historial.back().map_or(f64::NEG_INFINITY, |m| m.tiempo);
+            // Medio paso de holgura para que el redondeo no salte muestras
+            if (self.tiempo - ultimo).abs() >= Self::INTERVALO_HISTORIAL - 0.5 * self.paso {
+                self.registrar();
+            }
+        }
+    }
+
+    pub fn cantidad_cuerpos(&self) -> usize {
+        self.masas.len()
+    }
+
+    /// Copia las posiciones integradas a los cuerpos
+    pub fn aplicar(&self, cuerpos: &mut [CuerpoCeleste]) {
+        for (cuerpo, posicion) in cuerpos.iter_mut().zip(&self.posiciones) {
+            cuerpo.posicion = [posicion.x as f32, posicion.y as f32];
+        }
+    }
+}
+
+/// Nodo del octree: cubo de semilado `mitad`, con su masa total y centro de masa
+struct NodoOctree {
+    mitad: f64,
+    masa: f64,
+    centro_masa: DVec3,
+    hijos: Vec<usize>,
+    /// Cuerpos de una hoja, como rango de `Octree::orden`
+    primero: usize,
+    cantidad: usize,
+}
+
+/// Octree de Barnes–Hut sobre las posiciones de un paso: se reconstruye en cada evaluación
+struct Octree {
+    nodos: Vec<NodoOctree>,
+    /// Índices de cuerpos ordenados de forma que cada nodo cubre un tramo contiguo
+    orden: Vec<usize>,
+}
+
+impl Octree {
+    /// Cuerpos coincidentes no se pueden separar: a esta profundidad la hoja se queda con todos
+    const PROFUNDIDAD_MAXIMA: u32 = 32;
+    /// Hasta esta cantidad de cuerpos la hoja no se subdivide y se suma directo
+    const CUERPOS_POR_HOJA: usize = 8;
+
+    fn construir(posiciones: &[DVec3], masas: &[f64]) -> Self {
+        let (minimo, maximo) = posiciones.iter().fold(
+            (DVec3::repeat(f64::INFINITY), DVec3::repeat(f64::NEG_INFINITY)),
+            |(minimo, maximo), x| (minimo.inf(x), maximo.sup(x)),
+        );
+        let centro = (minimo + maximo) * 0.5;
+        let mitad = ((maximo - minimo) * 0.5).max() * 1.001 + 1e-9;
+        let mut arbol = Octree {
+            nodos: Vec::new(),
+            orden: (0..posiciones.len()).collect(),
+        };
+        arbol.construir_nodo(posiciones, masas, 0..posiciones.len(), centro, mitad, 0);
+        arbol
+    }
+
+    fn construir_nodo(
+        &mut self,
+        posiciones: &[DVec3],
+        masas: &[f64],
+        tramo: std::ops::Range<usize>,
+        centro: DVec3,
+        mitad: f64,
+        profundidad: u32,
+    ) -> usize {
+        let (primero, cantidad) = (tramo.start, tramo.len());
+        let masa: f64 = self.orden[tramo.clone()].iter().map(|&i| masas[i]).sum();
+        let centro_masa = if masa > 0.0 {
+            self.orden[tramo.clone()]
+                .iter()
+                .fold(DVec3::zeros(), |suma, &i| suma + posiciones[i] * masas[i])
+                / masa
+        } else {
+            centro
+        };
+        let indice = self.nodos.len();
+        self.nodos.push(NodoOctree {
+            mitad,
+            masa,
+            centro_masa,
+            hijos: Vec::new(),
+            primero,
+            cantidad,
+        });
+        if cantidad <= Self::CUERPOS_POR_HOJA || profundidad >= Self::PROFUNDIDAD_MAXIMA {
+            return indice;
+        }
+
+        let octante = |x: &DVec3| {
+            (x.x >= centro.x) as usize | ((x.y >= centro.y) as usize) << 1 | ((x.z >= centro.z) as usize) << 2
+        };
+        self.orden[tramo.clone()].sort_unstable_by_key(|&i| octante(&posiciones[i]));
+        let mut hijos = Vec::new();
+        let mut inicio = primero;
+        for numero in 0..8 {
+            let fin = inicio + self.orden[inicio..tramo.end]
+                .iter()
+                .take_while(|&&i| octante(&posiciones[i]) == numero)
+                .count();
+            if fin > inicio {
+                let signo = |bit: usize| if numero & bit != 0 { 0.5 } else { -0.5 };
+                let centro_hijo = centro + DVec3::new(signo(1), signo(2), signo(4)) * mitad;
+                hijos.push(self.construir_nodo(posiciones, masas, inicio..fin, centro_hijo, mitad * 0.5, profundidad + 1));
+            }
+            inicio = fin;
+        }
+        self.nodos[indice].hijos = hijos;
+        indice
+    }
+
+    /// Aceleración sobre el cuerpo `cuerpo` en `x`, sin la constante gravitacional.
+    /// `pendientes` es la pila del recorrido, reutilizada entre cuerpos.
+    fn aceleracion(
+        &self,
+        cuerpo: usize,
+        x: &DVec3,
+        posiciones: &[DVec3],
+        masas: &[f64],
+        apertura: f64,
+        pendientes: &mut Vec<usize>,
+    ) -> DVec3 {
+        let suavizado2 = SimulacionNCuerpos::SUAVIZADO * SimulacionNCuerpos::SUAVIZADO;
+        let atraccion = |hacia: &DVec3, masa: f64| {
+            let relativa = hacia - x;
+            let distancia2 = relativa.norm_squared() + suavizado2;
+            relativa * (masa / (distancia2 * distancia2.sqrt()))
+        };
+        let mut total = DVec3::zeros();
+        pendientes.clear();
+        pendientes.push(0);
+        while let Some(indice) = pendientes.pop() {
+            let nodo = &self.nodos[indice];
+            if nodo.hijos.is_empty() {
+                for &otro in &self.orden[nodo.primero..nodo.primero + nodo.cantidad] {
+                    if otro != cuerpo {
+                        total += atraccion(&posiciones[otro], masas[otro]);
+                    }
+                }
+                continue;
+            }
+            let distancia = (nodo.centro_masa - x).norm();
+            if 2.0 * nodo.mitad < apertura * distancia {
+                total += atraccion(&nodo.centro_masa, nodo.masa);
+            } else {
+                pendientes.extend_from_slice(&nodo.hijos);
+            }
+        }
+        total
+    }
+}
+
+// =============================================================================
+// MÓDULO: EDITOR
+// =============================================================================
+
+/// Extremo de una órbita que se puede arrastrar en el editor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtremoOrbita {
+    Periapsis,
+    Apoapsis,
+}
+
+impl ExtremoOrbita {
+    pub const TODOS: [ExtremoOrbita; 2] = [ExtremoOrbita::Periapsis, ExtremoOrbita::Apoapsis];
+
+    /// Anomalía verdadera del extremo
+    pub fn anomalia(self) -> f32 {
+        match self {
+            ExtremoOrbita::Periapsis => 0.0,
+            ExtremoOrbita::Apoapsis => std::f32::consts::PI,
+        }
+    }
+
+    pub fn etiqueta(self) -> &'static str {
+        match self {
+            ExtremoOrbita::Periapsis => "PERIAPSIS",
+            ExtremoOrbita::Apoapsis => "APOAPSIS",
+        }
+    }
+}
+
+/// Modo editor: asas en el periapsis y el apoapsis de la órbita seleccionada
+#[derive(Default)]
+pub struct EditorOrbitas {
+    pub activo: bool,
+    /// Extremo que se está arrastrando con el mouse
+    pub arrastre: Option<ExtremoOrbita>,
+}
+
+impl EditorOrbitas {
+    /// Radio de las asas en píxeles, también usado para acertarles con el cursor
+    pub const RADIO_ASA: f32 = 8.0;
+
+    pub fn alternar(&mut self) {
+        self.activo = !self.activo;
+        self.arrastre = None;
+    }
+
+    /// Mueve el extremo arrastrado a `distancia` del centro, sin cruzar al extremo opuesto
+    pub fn arrastrar(&self, orbita: &mut Orbita, distancia: f32) {
+        const DISTANCIA_MINIMA: f32 = 0.01;
+        match self.arrastre {
+            Some(ExtremoOrbita::Periapsis) => {
+                let apoapsis = orbita.apoapsis();
+                orbita.ajustar_extremos(distancia.clamp(DISTANCIA_MINIMA, apoapsis), apoapsis);
+            }
+            Some(ExtremoOrbita::Apoapsis) => {
+                let periapsis = orbita.periapsis();
+                orbita.ajustar_extremos(periapsis, distancia.max(periapsis));
+            }
+            None => {}
+        }
+    }
+
+    pub fn describir(&self, cuerpo: Option<&CuerpoCeleste>) -> Option<String> {
+        if !self.activo {
+            return None;
+        }
+        match cuerpo.and_then(|c| c.orbita.map(|o| (c, o))) {
+            Some((cuerpo, orbita)) => Some(format!(
+                "Editor: {} a = {:.3} u · e = {:.3}",
+                cuerpo.nombre, orbita.semieje_mayor, orbita.excentricidad
+            )),
+            None => Some("Editor: selecciona un cuerpo con órbita".to_string()),
+        }
+    }
+}
+
+/// Modo de las leyes de Kepler: sectores de igual duración barridos por el radio vector del
+/// cuerpo seleccionado y la gráfica de T² contra a³ de todas las órbitas
+#[derive(Default)]
+pub struct LeyesKepler {
+    pub activo: bool,
+}
+
+impl LeyesKepler {
+    /// Sectores de igual duración en que se divide la vuelta
+    pub const SECTORES: usize = 12;
+
+    pub fn alternar(&mut self) {
+        self.activo = !self.activo;
+    }
+
+    pub fn describir(&self, cuerpo: Option<&CuerpoCeleste>) -> Option<String> {
+        if !self.activo {
+            return None;
+        }
+        match cuerpo.and_then(|c| c.orbita.map(|o| (c, o))) {
+            Some((cuerpo, orbita)) => Some(format!(
+                "Kepler: {} barre {:.4} u²/s · T²/a³ = {:.1}",
+                cuerpo.nombre,
+                orbita.area_por_segundo(),
+                orbita.periodo * orbita.periodo / orbita.semieje_mayor.powi(3)
+            )),
+            None => Some("Kepler: selecciona un cuerpo con órbita".to_string()),
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: CIELO
+// =============================================================================
+
+/// Oblicuidad de la eclíptica (J2000), en grados
+pub const OBLICUIDAD_ECLIPTICA: f32 = 23.439;
+
+/// Vector unitario a partir de longitud y latitud en grados, en el marco que las define
+pub fn direccion_esferica(longitud: f32, latitud: f32) -> Vec3 {
+    let (l, b) = (longitud.to_radians(), latitud.to_radians());
+    Vec3::new(b.cos() * l.cos(), b.cos() * l.sin(), b.sin())
+}
+
+/// Dirección en la esfera celeste a partir de coordenadas ecuatoriales (grados).
+/// El marco de la escena es eclíptico: el plano XY es la eclíptica y +Z su polo norte.
+pub fn direccion_ecuatorial(ascension_recta: f32, declinacion: f32) -> Vec3 {
+    let ecuatorial = direccion_esferica(ascension_recta, declinacion);
+    let epsilon = OBLICUIDAD_ECLIPTICA.to_radians();
+    Vec3::new(
+        ecuatorial.x,
+        ecuatorial.y * epsilon.cos() + ecuatorial.z * epsilon.sin(),
+        -ecuatorial.y * epsilon.sin() + ecuatorial.z * epsilon.cos(),
+    )
+}
+
+/// Estrella del catálogo con coordenadas ecuatoriales J2000 en grados
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EstrellaCatalogo {
+    pub nombre: String,
+    pub ar: f32,
+    pub dec: f32,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DefinicionConstelacion {
+    nombre: String,
+    estrellas: Vec<EstrellaCatalogo>,
+    lineas: Vec<[String; 2]>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ArchivoConstelaciones {
+    constelacion: Vec<DefinicionConstelacion>,
+}
+
+/// Figura de constelación lista para proyectar: direcciones y pares de índices
+#[derive(Debug, Clone)]
+pub struct Constelacion {
+    pub nombre: String,
+    pub direcciones: Vec<Vec3>,
+    pub segmentos: Vec<(usize, usize)>,
+}
+
+/// Carga figuras de constelaciones; cada línea debe nombrar estrellas de su constelación
+pub fn cargar_constelaciones(ruta: &std::path::Path) -> Result<Vec<Constelacion>, String> {
+    let texto = std::fs::read_to_string(ruta)
+        .map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+    let archivo: ArchivoConstelaciones =
+        toml::from_str(&texto).map_err(|e| format!("{} no es válido: {}", ruta.display(), e))?;
+
+    archivo
+        .constelacion
+        .into_iter()
+        .map(|definicion| {
+            let buscar = |nombre: &str| {
+                definicion
+                    .estrellas
+                    .iter()
+                    .position(|e| e.nombre == nombre)
+                    .ok_or_else(|| format!("{}: estrella desconocida '{}'", definicion.nombre, nombre))
+            };
+            let segmentos = definicion
+                .lineas
+                .iter()
+                .map(|[a, b]| Ok((buscar(a)?, buscar(b)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(Constelacion {
+                direcciones: definicion
+                    .estrellas
+                    .iter()
+                    .map(|e| direccion_ecuatorial(e.ar, e.dec))
+                    .collect(),
+                segmentos,
+                nombre: definicion.nombre,
+            })
+        })
+        .collect()
+}
+
+/// Sistema de coordenadas de una cuadrícula celeste
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SistemaCoordenadas {
+    Ecliptico,
+    Ecuatorial,
+}
+
+/// Cuadrícula de círculos máximos y paralelos sobre la esfera celeste
+pub struct CuadriculaCeleste {
+    /// Polilíneas de direcciones unitarias en el marco de la escena
+    pub polilineas: Vec<Vec<Vec3>>,
+    /// Círculo fundamental (eclíptica o ecuador celeste), resaltado al dibujar
+    pub circulo_fundamental: Vec<Vec3>,
+    pub etiquetas: Vec<(String, Vec3)>,
+}
+
+impl CuadriculaCeleste {
+    const MUESTRAS_MERIDIANO: usize = 24;
+    const MUESTRAS_PARALELO: usize = 48;
+
+    pub fn nueva(sistema: SistemaCoordenadas, paso_grados: f32) -> Self {
+        let paso = paso_grados.clamp(5.0, 90.0);
+        let direccion = |longitud: f32, latitud: f32| match sistema {
+            SistemaCoordenadas::Ecliptico => direccion_esferica(longitud, latitud),
+            SistemaCoordenadas::Ecuatorial => direccion_ecuatorial(longitud, latitud),
+        };
+        let mut polilineas = Vec::new();
+        let mut etiquetas = Vec::new();
+
+        // Meridianos de polo a polo
+        let meridianos = (360.0 / paso).round() as usize;
+        for i in 0..meridianos {
+            let longitud = i as f32 * paso;
+            polilineas.push(
+                (0..=Self::MUESTRAS_MERIDIANO)
+                    .map(|k| {
+                        let latitud = -90.0 + 180.0 * k as f32 / Self::MUESTRAS_MERIDIANO as f32;
+                        direccion(longitud, latitud)
+                    })
+                    .collect(),
+            );
+            let texto = match sistema {
+                SistemaCoordenadas::Ecliptico => format!("{:.0}°", longitud),
+                SistemaCoordenadas::Ecuatorial => format!("{:.0}H", longitud / 15.0),
+            };
+            etiquetas.push((texto, direccion(longitud, 0.0)));
+        }
+
+        // Paralelos (sin el círculo fundamental, que se guarda aparte)
+        let mut latitud = paso;
+        while latitud < 90.0 - 1e-3 {
+            for signo in [1.0, -1.0] {
+                polilineas.push(Self::paralelo(&direccion, signo * latitud));
+                etiquetas.push((format!("{:+.0}°", signo * latitud), direccion(0.0, signo * latitud)));
+            }
+            latitud += paso;
+        }
+
+        CuadriculaCeleste {
+            polilineas,
+            circulo_fundamental: Self::paralelo(&direccion, 0.0),
+            etiquetas,
+        }
+    }
+
+    fn paralelo(direccion: &impl Fn(f32, f32) -> Vec3, latitud: f32) -> Vec<Vec3> {
+        (0..=Self::MUESTRAS_PARALELO)
+            .map(|k| direccion(360.0 * k as f32 / Self::MUESTRAS_PARALELO as f32, latitud))
+            .collect()
+    }
+}
+
+/// Fondo de estrellas en un cubemap: se dibuja primero en un triángulo de pantalla completa
+/// muestreado con la dirección de cada píxel, así que queda en el infinito al mover la cámara
+pub struct CieloEstrellado {
+    pipeline: wgpu::RenderPipeline,
+    grupo: wgpu::BindGroup,
+}
+
+impl CieloEstrellado {
+    /// Lado en píxeles de cada cara del cubemap
+    const RESOLUCION: u32 = 1024;
+    /// Polo norte galáctico en coordenadas eclípticas (λ 180°, β +29.8°)
+    const POLO_GALACTICO: [f32; 3] = [-0.8678, 0.0, 0.4970];
+
+    fn nuevo(
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        formato: wgpu::TextureFormat,
+        modulo_shader: &wgpu::ShaderModule,
+        layout_uniformes: &wgpu::BindGroupLayout,
+        ajustes: &AjustesEstrellas,
+    ) -> Self {
+        let caras = Self::generar_caras(ajustes);
+        let textura = dispositivo.create_texture_with_data(
+            cola,
+            &wgpu::TextureDescriptor {
+                label: Some("Cubemap del Cielo Estrellado"),
+                size: wgpu::Extent3d {
+                    width: Self::RESOLUCION,
+                    height: Self::RESOLUCION,
+                    depth_or_array_layers: 6,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &caras,
+        );
+        let vista = textura.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let muestreador = dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Muestreador del Cielo"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Cielo"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group del Cielo"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&vista),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&muestreador),
+                },
+            ],
+        });
+
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline del Cielo"),
+            bind_group_layouts: &[layout_uniformes, &layout],
+            push_constant_ranges: &[],
+        });
+        // Sin mezcla: el cielo pinta el fondo de todo el cuadro
+        let pipeline = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline del Cielo Estrellado"),
+            layout: Some(&layout_pipeline),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_cielo",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_cielo",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        CieloEstrellado { pipeline, grupo }
+    }
+
+    /// Cara del cubemap (+X, -X, +Y, -Y, +Z, -Z) y coordenadas (0-1) hacia las que apunta una dirección
+    fn cara_cubo(d: Vec3) -> (usize, f32, f32) {
+        let (x, y, z) = (d.x.abs(), d.y.abs(), d.z.abs());
+        let (cara, s, t, mayor) = if x >= y && x >= z {
+            if d.x > 0.0 { (0, -d.z, -d.y, x) } else { (1, d.z, -d.y, x) }
+        } else if y >= z {
+            if d.y > 0.0 { (2, d.x, d.z, y) } else { (3, d.x, -d.z, y) }
+        } else if d.z > 0.0 {
+            (4, d.x, -d.y, z)
+        } else {
+            (5, -d.x, -d.y, z)
+        };
+        (cara, (s / mayor + 1.0) * 0.5, (t / mayor + 1.0) * 0.5)
+    }
+
+    /// Direcciones de las estrellas: uniformes, concentradas hacia el plano galáctico o en cúmulos
+    fn direcciones(ajustes: &AjustesEstrellas, generador: &mut GeneradorAleatorio) -> Vec<Vec3> {
+        let polo = Vec3::from(Self::POLO_GALACTICO).normalize();
+        let eje_x = polo.cross(&Vec3::z()).normalize();
+        let eje_y = polo.cross(&eje_x);
+        let exponente = 1.0 + ajustes.concentracion_galactica.max(0.0);
+        let direccion_galactica = |generador: &mut GeneradorAleatorio| {
+            let alto = generador.flotante() * 2.0 - 1.0;
+            let alto = alto.signum() * alto.abs().powf(exponente);
+            let angulo = generador.flotante() * std::f32::consts::TAU;
+            let radio = (1.0 - alto * alto).sqrt();
+            (eje_x * angulo.cos() + eje_y * angulo.sin()) * radio + polo * alto
+        };
+
+        let cantidad_cumulos = (ajustes.cantidad / 500).max(1) as usize;
+        let cumulos: Vec<Vec3> = (0..cantidad_cumulos).map(|_| direccion_galactica(generador)).collect();
+        (0..ajustes.cantidad)
+            .map(|_| {
+                if generador.flotante() < ajustes.agrupamiento {
+                    // Desplazamiento aproximadamente gaussiano (~5°) alrededor de un cúmulo
+                    let centro = cumulos[generador.rango(cumulos.len())];
+                    let mut desviacion = || (generador.flotante() + generador.flotante() + generador.flotante() - 1.5) * 0.08;
+                    (centro + Vec3::new(desviacion(), desviacion(), desviacion())).normalize()
+                } else {
+                    direccion_galactica(generador)
+                }
+            })
+            .collect()
+    }
+
+    /// Pinta cada estrella como una mancha gaussiana en su cara, con un leve tono según su temperatura
+    fn generar_caras(ajustes: &AjustesEstrellas) -> Vec<u8> {
+        let lado = Self::RESOLUCION as usize;
+        let mut brillo = vec![[0.0f32; 3]; 6 * lado * lado];
+        let mut generador = GeneradorAleatorio::nuevo(ajustes.semilla);
+        for direccion in Self::direcciones(ajustes, &mut generador) {
+            let (cara, u, v) = Self::cara_cubo(direccion);
+            let sigma = 0.5 + generador.flotante() * 0.9;
+            let intensidad = 0.4 + generador.flotante() * 0.6;
+            let calidez = generador.flotante();
+            let color = [0.8 + 0.2 * calidez, 0.85 + 0.1 * calidez, 1.0 - 0.25 * calidez];
+            let (cx, cy) = (u * lado as f32, v * lado as f32);
+            let alcance = (sigma * 3.0).ceil() as i32;
+            for dy in -alcance..=alcance {
+                for dx in -alcance..=alcance {
+                    let (px, py) = (cx as i32 + dx, cy as i32 + dy);
+                    if px < 0 || py < 0 || px >= lado as i32 || py >= lado as i32 {
+                        continue;
+                    }
+                    let (ex, ey) = (px as f32 + 0.5 - cx, py as f32 + 0.5 - cy);
+                    let peso = intensidad * (-(ex * ex + ey * ey) / (2.0 * sigma * sigma)).exp();
+                    let pixel = &mut brillo[(cara * lado + py as usize) * lado + px as usize];
+                    for (canal, tono) in pixel.iter_mut().zip(color) {
+                        *canal += peso * tono;
+                    }
+                }
+            }
+        }
+        brillo
+            .iter()
+            .flat_map(|&[r, g, b]| [r, g, b, 1.0].map(|c| (c.min(1.0) * 255.0).round() as u8))
+            .collect()
+    }
+
+    fn dibujar<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, grupo_uniformes: &'a wgpu::BindGroup) {
+        pase.set_pipeline(&self.pipeline);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, &self.grupo, &[]);
+        pase.draw(0..3, 0..1);
+    }
+}
+
+// =============================================================================
+// MÓDULO: ALEATORIO
+// =============================================================================
+
+/// Generador pseudo-aleatorio xorshift64* (suficiente para efectos y juegos)
+pub struct GeneradorAleatorio {
+    estado: u64,
+}
+
+impl GeneradorAleatorio {
+    pub fn nuevo(semilla: u64) -> Self {
+        GeneradorAleatorio { estado: semilla.max(1) }
+    }
+
+    pub fn desde_reloj() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        GeneradorAleatorio::nuevo(nanos)
+    }
+
+    pub fn siguiente(&mut self) -> u64 {
+        self.estado ^= self.estado >> 12;
+        self.estado ^= self.estado << 25;
+        self.estado ^= self.estado >> 27;
+        self.estado.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Entero uniforme en `0..limite`
+    pub fn rango(&mut self, limite: usize) -> usize {
+        (self.siguiente() % limite.max(1) as u64) as usize
+    }
+
+    /// Flotante uniforme en `[0, 1)`
+    pub fn flotante(&mut self) -> f32 {
+        (self.siguiente() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Flotante uniforme entre los dos extremos
+    pub fn entre(&mut self, [minimo, maximo]: [f32; 2]) -> f32 {
+        minimo + (maximo - minimo) * self.flotante()
+    }
+
+    /// Dirección unitaria uniforme sobre la esfera
+    pub fn direccion(&mut self) -> Vec3 {
+        loop {
+            let punto = Vec3::new(self.flotante(), self.flotante(), self.flotante()) * 2.0 - Vec3::repeat(1.0);
+            let largo = punto.norm();
+            if largo > 1e-3 && largo <= 1.0 {
+                return punto / largo;
+            }
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: QUIZ
+// =============================================================================
+
+/// Pregunta activa del modo educativo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreguntaQuiz {
+    /// Se muestra el nombre y hay que hacer click en el cuerpo
+    SenalarCuerpo(usize),
+    /// Se resalta el cuerpo y hay que elegir su nombre con las teclas numéricas
+    NombrarCuerpo(usize),
+}
+
+/// Modo quiz: alterna preguntas de ambos tipos y lleva el puntaje
+pub struct ModoQuiz {
+    pub activo: bool,
+    pub pregunta: Option<PreguntaQuiz>,
+    pub aciertos: u32,
+    pub intentos: u32,
+    ultimo_acierto: Option<bool>,
+    generador: GeneradorAleatorio,
+}
+
+impl ModoQuiz {
+    pub fn nuevo() -> Self {
+        ModoQuiz {
+            activo: false,
+            pregunta: None,
+            aciertos: 0,
+            intentos: 0,
+            ultimo_acierto: None,
+            generador: GeneradorAleatorio::desde_reloj(),
+        }
+    }
+
+    pub fn alternar(&mut self, cantidad_cuerpos: usize) {
+        self.activo = !self.activo;
+        self.aciertos = 0;
+        self.intentos = 0;
+        self.ultimo_acierto = None;
+        self.pregunta = None;
+        if self.activo {
+            self.nueva_pregunta(cantidad_cuerpos);
+        }
+    }
+
+    fn nueva_pregunta(&mut self, cantidad_cuerpos: usize) {
+        if cantidad_cuerpos == 0 {
+            self.pregunta = None;
+            return;
+        }
+        // Evitar repetir el mismo cuerpo dos veces seguidas cuando hay opciones
+        let anterior = self.cuerpo_objetivo();
+        let mut objetivo = self.generador.rango(cantidad_cuerpos);
+        if cantidad_cuerpos > 1 && Some(objetivo) == anterior {
+            objetivo = (objetivo + 1 + self.generador.rango(cantidad_cuerpos - 1)) % cantidad_cuerpos;
+        }
+        self.pregunta = Some(if self.generador.rango(2) == 0 {
+            PreguntaQuiz::SenalarCuerpo(objetivo)
+        } else {
+            PreguntaQuiz::NombrarCuerpo(objetivo)
+        });
+    }
+
+    pub fn cuerpo_objetivo(&self) -> Option<usize> {
+        match self.pregunta? {
+            PreguntaQuiz::SenalarCuerpo(i) | PreguntaQuiz::NombrarCuerpo(i) => Some(i),
+        }
+    }
+
+    /// Cuerpo a resaltar en pantalla (sólo en preguntas de nombrar)
+    pub fn cuerpo_resaltado(&self) -> Option<usize> {
+        match self.pregunta? {
+            PreguntaQuiz::NombrarCuerpo(i) => Some(i),
+            PreguntaQuiz::SenalarCuerpo(_) => None,
+        }
+    }
+
+    /// Respuesta por click; devuelve `false` si la pregunta activa no es de señalar
+    pub fn responder_click(&mut self, cuerpo: Option<usize>, cantidad_cuerpos: usize) -> bool {
+        match self.pregunta {
+            Some(PreguntaQuiz::SenalarCuerpo(objetivo)) => {
+                self.calificar(cuerpo == Some(objetivo), cantidad_cuerpos);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Respuesta por número de opción (0 = primera)
+    pub fn responder_nombre(&mut self, opcion: usize, cantidad_cuerpos: usize) {
+        if let Some(PreguntaQuiz::NombrarCuerpo(objetivo)) = self.pregunta {
+            if opcion < cantidad_cuerpos {
+                self.calificar(opcion == objetivo, cantidad_cuerpos);
+            }
+        }
+    }
+
+    fn calificar(&mut self, correcto: bool, cantidad_cuerpos: usize) {
+        self.intentos += 1;
+        if correcto {
+            self.aciertos += 1;
+        }
+        self.ultimo_acierto = Some(correcto);
+        self.nueva_pregunta(cantidad_cuerpos);
+    }
+
+    pub fn describir(&self, cuerpos: &[CuerpoCeleste]) -> Option<String> {
+        if !self.activo {
+            return None;
+        }
+        let resultado = match self.ultimo_acierto {
+            Some(true) => " ✓",
+            Some(false) => " ✗",
+            None => "",
+        };
+        let enunciado = match self.pregunta? {
+            PreguntaQuiz::SenalarCuerpo(i) => format!("¿Dónde está {}? (click)", cuerpos[i].nombre),
+            PreguntaQuiz::NombrarCuerpo(_) => {
+                let opciones: Vec<String> = cuerpos
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{}:{}", i + 1, c.nombre))
+                    .collect();
+                format!("¿Qué cuerpo está resaltado? {}", opciones.join(" "))
+            }
+        };
+        Some(format!("Quiz {}/{}{} · {}", self.aciertos, self.intentos, resultado, enunciado))
+    }
+}
+
+/// Índice de opción asociado a las teclas numéricas 1-9
+fn opcion_de_tecla(tecla: KeyCode) -> Option<usize> {
+    let teclas = [
+        KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
+        KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6,
+        KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    teclas.iter().position(|t| *t == tecla)
+}
+
+// =============================================================================
+// MÓDULO: EVENTOS
+// =============================================================================
+
+type ObservadorSeleccion = Box<dyn FnMut(Option<&CuerpoCeleste>)>;
+type ObservadorEclipse = Box<dyn FnMut(&CuerpoCeleste, &CuerpoCeleste)>;
+type ObservadorOrbita = Box<dyn FnMut(&CuerpoCeleste, u32)>;
+type ObservadorImpacto = Box<dyn FnMut(&CuerpoCeleste, &CuerpoCeleste, f32)>;
+
+/// Registro de callbacks para reaccionar a eventos de la simulación sin sondear el estado
+#[derive(Default)]
+pub struct ObservadoresEventos {
+    seleccion: Vec<ObservadorSeleccion>,
+    eclipse: Vec<ObservadorEclipse>,
+    orbita_completada: Vec<ObservadorOrbita>,
+    impacto: Vec<ObservadorImpacto>,
+}
+
+impl ObservadoresEventos {
+    /// Se llama al cambiar la selección (`None` al deseleccionar)
+    pub fn on_seleccion(&mut self, callback: impl FnMut(Option<&CuerpoCeleste>) + 'static) {
+        self.seleccion.push(Box::new(callback));
+    }
+
+    /// Se llama cuando un cuerpo (primer argumento) empieza a tapar la luz del Sol a otro
+    pub fn on_eclipse(&mut self, callback: impl FnMut(&CuerpoCeleste, &CuerpoCeleste) + 'static) {
+        self.eclipse.push(Box::new(callback));
+    }
+
+    /// Se llama cada vez que un cuerpo completa una vuelta (con el total de vueltas)
+    pub fn on_orbita_completada(&mut self, callback: impl FnMut(&CuerpoCeleste, u32) + 'static) {
+        self.orbita_completada.push(Box::new(callback));
+    }
+
+    /// Se llama cuando dos cuerpos entran en contacto (mayor, menor, rapidez relativa)
+    pub fn on_impacto(&mut self, callback: impl FnMut(&CuerpoCeleste, &CuerpoCeleste, f32) + 'static) {
+        self.impacto.push(Box::new(callback));
+    }
+
+    fn emitir_seleccion(&mut self, cuerpo: Option<&CuerpoCeleste>) {
+        for callback in &mut self.seleccion {
+            callback(cuerpo);
+        }
+    }
+
+    fn emitir_eclipse(&mut self, ocultante: &CuerpoCeleste, eclipsado: &CuerpoCeleste) {
+        for callback in &mut self.eclipse {
+            callback(ocultante, eclipsado);
+        }
+    }
+
+    fn emitir_orbita_completada(&mut self, cuerpo: &CuerpoCeleste, vueltas: u32) {
+        for callback in &mut self.orbita_completada {
+            callback(cuerpo, vueltas);
+        }
+    }
+
+    fn emitir_impacto(&mut self, mayor: &CuerpoCeleste, menor: &CuerpoCeleste, rapidez: f32) {
+        for callback in &mut self.impacto {
+            callback(mayor, menor, rapidez);
+        }
+    }
+}
+
+/// Contacto nuevo entre dos cuerpos, visto desde el mayor
+#[derive(Debug, Clone, Copy)]
+pub struct Impacto {
+    pub mayor: usize,
+    pub menor: usize,
+    /// Centro del cuerpo mayor
+    pub centro: Vec3,
+    /// Punto de su superficie que mira al menor
+    pub punto: Vec3,
+    pub normal: Vec3,
+    /// Rapidez relativa en unidades de escena por segundo de simulación
+    pub rapidez: f32,
+}
+
+/// Detector de eventos de simulación: vueltas completadas, eclipses e impactos
+#[derive(Default)]
+pub struct DetectorEventos {
+    vueltas: Vec<u32>,
+    eclipses_activos: Vec<(usize, usize)>,
+    contactos: Vec<(usize, usize)>,
+    /// Posiciones e instante de la revisión anterior, para estimar velocidades
+    posiciones_previas: Vec<[f32; 2]>,
+    tiempo_previo: f32,
+}
+
+impl DetectorEventos {
+    /// Avisa a los observadores y devuelve los impactos que empiezan en esta revisión
+    pub fn revisar(&mut self, cuerpos: &[CuerpoCeleste], tiempo: f32, observadores: &mut ObservadoresEventos) -> Vec<Impacto> {
+        let impactos = self.revisar_impactos(cuerpos, tiempo, observadores);
+        self.vueltas.resize(cuerpos.len(), 0);
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            let Some(orbita) = cuerpo.orbita else { continue };
+            // Con el tiempo en reversa las vueltas bajan sin avisar
+            let vueltas = orbita.vueltas(tiempo).max(0.0) as u32;
+            if vueltas > self.vueltas[indice] {
+                observadores.emitir_orbita_completada(cuerpo, vueltas);
+            }
+            self.vueltas[indice] = vueltas;
+        }
+
+        let Some(sol) = cuerpos.iter().position(|c| c.es_estrella()) else { return impactos };
+        let mut eclipses = Vec::new();
+        for (ocultante, a) in cuerpos.iter().enumerate() {
+            for (eclipsado, b) in cuerpos.iter().enumerate() {
+                if ocultante == eclipsado || ocultante == sol || eclipsado == sol {
+                    continue;
+                }
+                if proyecta_sombra(&cuerpos[sol], a, b) {
+                    eclipses.push((ocultante, eclipsado));
+                    if !self.eclipses_activos.contains(&(ocultante, eclipsado)) {
+                        observadores.emitir_eclipse(a, b);
+                    }
+                }
+            }
+        }
+        self.eclipses_activos = eclipses;
+        impactos
+    }
+
+    /// Pares visibles cuyas esferas se tocan; solo cuenta el primer cuadro del contacto
+    fn revisar_impactos(&mut self, cuerpos: &[CuerpoCeleste], tiempo: f32, observadores: &mut ObservadoresEventos) -> Vec<Impacto> {
+        // Sin revisión previa (o tras cambiar la escena) no hay velocidades: solo se anotan los contactos
+        let primera = self.posiciones_previas.len() != cuerpos.len();
+        let intervalo = tiempo - self.tiempo_previo;
+        let velocidad = |indice: usize| {
+            if primera || intervalo.abs() <= f32::EPSILON {
+                return Vec3::zeros();
+            }
+            let [x, y] = self.posiciones_previas[indice];
+            (cuerpos[indice].posicion_3d() - Vec3::new(x, y, 0.0)) / intervalo
+        };
+
+        let mut contactos = Vec::new();
+        let mut impactos = Vec::new();
+        for (i, a) in cuerpos.iter().enumerate() {
+            for (j, b) in cuerpos.iter().enumerate().skip(i + 1) {
+                if !a.visible || !b.visible || a.distancia_a(b) >= a.escala + b.escala {
+                    continue;
+                }
+                contactos.push((i, j));
+                if primera || self.contactos.contains(&(i, j)) {
+                    continue;
+                }
+                let (mayor, menor) = if a.escala >= b.escala { (i, j) } else { (j, i) };
+                let centro = cuerpos[mayor].posicion_3d();
+                let separacion = cuerpos[menor].posicion_3d() - centro;
+                let normal = if separacion.norm() > f32::EPSILON { separacion.normalize() } else { Vec3::z() };
+                let rapidez = (velocidad(menor) - velocidad(mayor)).norm();
+                observadores.emitir_impacto(&cuerpos[mayor], &cuerpos[menor], rapidez);
+                impactos.push(Impacto {
+                    mayor,
+                    menor,
+                    centro,
+                    punto: centro + normal * cuerpos[mayor].escala,
+                    normal,
+                    rapidez,
+                });
+            }
+        }
+        self.contactos = contactos;
+        self.posiciones_previas = cuerpos.iter().map(|c| c.posicion).collect();
+        self.tiempo_previo = tiempo;
+        impactos
+    }
+}
+
+/// `true` si `ocultante` está entre el Sol y `eclipsado` y su disco cubre la línea de luz
+fn proyecta_sombra(sol: &CuerpoCeleste, ocultante: &CuerpoCeleste, eclipsado: &CuerpoCeleste) -> bool {
+    let hacia_eclipsado = eclipsado.posicion_3d() - sol.posicion_3d();
+    let hacia_ocultante = ocultante.posicion_3d() - sol.posicion_3d();
+    let distancia = hacia_eclipsado.norm();
+    if distancia <= f32::EPSILON {
+        return false;
+    }
+    let direccion = hacia_eclipsado / distancia;
+    let avance = hacia_ocultante.dot(&direccion);
+    if avance <= 0.0 || avance >= distancia {
+        return false;
+    }
+    let separacion = (hacia_ocultante - direccion * avance).norm();
+    separacion < ocultante.escala + eclipsado.escala
+}
+
+// =============================================================================
+// MÓDULO: TEXTO
+// =============================================================================
+
+/// Ancho y alto de un glifo de la fuente bitmap, en píxeles
+const GLIFO_ANCHO: u32 = 5;
+const GLIFO_ALTO: u32 = 7;
+/// Avance horizontal entre glifos: ancho más un píxel de separación
+const AVANCE_GLIFO: u32 = GLIFO_ANCHO + 1;
+
+/// Capacidad del lote de texto (en vértices, 6 por carácter)
+const MAX_VERTICES_TEXTO: usize = 6 * 4096;
+
+/// Fuente de 5x7 píxeles: cada fila usa los 5 bits bajos, el bit 4 es la columna izquierda
+const FUENTE_5X7: [(char, [u8; 7]); 64] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    ('"', [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('\'', [0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('*', [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    (';', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('=', [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('A', [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('[', [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E]),
+    (']', [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+    ('|', [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('°', [0x06, 0x09, 0x09, 0x06, 0x00, 0x00, 0x00]),
+    ('·', [0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00]),
+    ('↔', [0x00, 0x04, 0x08, 0x1F, 0x08, 0x04, 0x00]),
+    ('�', [0x1F, 0x11, 0x15, 0x15, 0x11, 0x15, 0x1F]),
+];
+
+/// Lleva un carácter al repertorio de la fuente (mayúsculas, sin tildes)
+fn normalizar_caracter(caracter: char) -> char {
+    match caracter {
+        'á' | 'à' | 'Á' | 'À' => 'A',
+        'é' | 'è' | 'É' | 'È' => 'E',
+        'í' | 'ì' | 'Í' | 'Ì' => 'I',
+        'ó' | 'ò' | 'Ó' | 'Ò' => 'O',
+        'ú' | 'ù' | 'ü' | 'Ú' | 'Ù' | 'Ü' => 'U',
+        'ñ' | 'Ñ' => 'N',
+        '′' => '\'',
+        '″' => '"',
+        _ => caracter.to_ascii_uppercase(),
+    }
+}
+
+fn indice_glifo(caracter: char) -> usize {
+    let normalizado = normalizar_caracter(caracter);
+    FUENTE_5X7
+        .iter()
+        .position(|(c, _)| *c == normalizado)
+        .unwrap_or(FUENTE_5X7.len() - 1)
+}
+
+/// Sprites que comparten atlas con la fuente, para dibujarse en el mismo lote que el texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sprite {
+    /// Disco difuso: impostor de los cuerpos demasiado pequeños para verse
+    Halo,
+    IconoEstrella,
+    IconoPlaneta,
+    IconoLuna,
+    IconoAsteroide,
+}
+
+impl Sprite {
+    const TODOS: [Sprite; 5] = [
+        Sprite::Halo,
+        Sprite::IconoEstrella,
+        Sprite::IconoPlaneta,
+        Sprite::IconoLuna,
+        Sprite::IconoAsteroide,
+    ];
+    const ANCHO_HALO: u32 = 16;
+
+    pub fn icono(clase: ClaseCuerpo) -> Self {
+        match clase {
+            ClaseCuerpo::Estrella => Sprite::IconoEstrella,
+            ClaseCuerpo::Planeta => Sprite::IconoPlaneta,
+            ClaseCuerpo::Luna => Sprite::IconoLuna,
+            ClaseCuerpo::Asteroide => Sprite::IconoAsteroide,
+        }
+    }
+
+    /// Región del sprite en el atlas: van después de los glifos
+    fn indice(self) -> usize {
+        FUENTE_5X7.len() + self as usize
+    }
+
+    /// Ancho, alto y píxeles de un canal
+    fn generar(self) -> (u32, u32, Vec<u8>) {
+        // Iconos de 7x7 con el bit 6 como columna izquierda, como la fuente
+        let filas: [u8; 7] = match self {
+            Sprite::Halo => {
+                let lado = Self::ANCHO_HALO;
+                let centro = (lado as f32 - 1.0) * 0.5;
+                let pixeles = (0..lado * lado)
+                    .map(|i| {
+                        let (x, y) = ((i % lado) as f32 - centro, (i / lado) as f32 - centro);
+                        let cercania = (1.0 - (x * x + y * y).sqrt() / (centro + 0.5)).max(0.0);
+                        (cercania * cercania * 255.0) as u8
+                    })
+                    .collect();
+                return (lado, lado, pixeles);
+            }
+            Sprite::IconoEstrella => [0x1C, 0x22, 0x41, 0x49, 0x41, 0x22, 0x1C],
+            Sprite::IconoPlaneta => [0x1C, 0x3E, 0x7F, 0x7F, 0x7F, 0x3E, 0x1C],
+            Sprite::IconoLuna => [0x1C, 0x30, 0x60, 0x60, 0x60, 0x30, 0x1C],
+            Sprite::IconoAsteroide => [0x00, 0x18, 0x3C, 0x3E, 0x1E, 0x0C, 0x00],
+        };
+        (7, 7, mapa_de_bits(&filas, 7))
+    }
+}
+
+/// Expande filas de bits (el bit `ancho - 1` es la columna izquierda) a píxeles de un canal
+fn mapa_de_bits(filas: &[u8], ancho: u32) -> Vec<u8> {
+    filas
+        .iter()
+        .flat_map(|bits| (0..ancho).map(move |columna| if bits & (1 << (ancho - 1 - columna)) != 0 { 255 } else { 0 }))
+        .collect()
+}
+
+/// Empaquetador por estantes: los rectángulos se colocan de izquierda a derecha en filas
+/// cuya altura fija el primero que entra; si no caben en ninguna se abre otra debajo
+struct EmpaquetadorEstantes {
+    ancho: u32,
+    alto: u32,
+    /// (y, alto, x libre) de cada estante
+    estantes: Vec<(u32, u32, u32)>,
+}
+
+impl EmpaquetadorEstantes {
+    /// Espacio entre rectángulos para que el filtrado no mezcle vecinos
+    const SEPARACION: u32 = 1;
+
+    fn nuevo(ancho: u32) -> Self {
+        EmpaquetadorEstantes {
+            ancho,
+            alto: 0,
+            estantes: Vec::new(),
+        }
+    }
+
+    /// Esquina superior izquierda asignada; `None` si es más ancho que el atlas
+    fn colocar(&mut self, ancho: u32, alto: u32) -> Option<[u32; 2]> {
+        let ocupado = ancho + Self::SEPARACION;
+        if ocupado > self.ancho {
+            return None;
+        }
+        let libre = self
+            .estantes
+            .iter_mut()
+            .find(|(_, alto_estante, x)| alto <= *alto_estante && x + ocupado <= self.ancho);
+        if let Some((y, _, x)) = libre {
+            let esquina = [*x, *y];
+            *x += ocupado;
+            return Some(esquina);
+        }
+        let y = self.alto;
+        self.alto += alto + Self::SEPARACION;
+        self.estantes.push((y, alto, ocupado));
+        Some([0, y])
+    }
+}
+
+/// Ubicación de un glifo o sprite dentro del atlas
+#[derive(Debug, Clone, Copy, Default)]
+struct RegionAtlas {
+    uv0: [f32; 2],
+    uv1: [f32; 2],
+    ancho: u32,
+    alto: u32,
+}
+
+/// Atlas de un canal (255 = encendido) con la fuente y los sprites; todo el lote de
+/// texto se dibuja con un único bind group
+struct AtlasSprites {
+    pixeles: Vec<u8>,
+    ancho: u32,
+    alto: u32,
+    /// Glifos en el orden de `FUENTE_5X7`, seguidos de `Sprite::TODOS`
+    regiones: std::sync::Arc<[RegionAtlas]>,
+}
+
+impl AtlasSprites {
+    const ANCHO: u32 = 128;
+
+    fn construir() -> Self {
+        let mut imagenes: Vec<(usize, u32, u32, Vec<u8>)> = FUENTE_5X7
+            .iter()
+            .enumerate()
+            .map(|(indice, (_, filas))| (indice, GLIFO_ANCHO, GLIFO_ALTO, mapa_de_bits(filas, GLIFO_ANCHO)))
+            .chain(Sprite::TODOS.iter().map(|sprite| {
+                let (ancho, alto, pixeles) = sprite.generar();
+                (sprite.indice(), ancho, alto, pixeles)
+            }))
+            .collect();
+        // Los estantes se aprovechan mejor colocando primero lo más alto
+        imagenes.sort_by_key(|(indice, _, alto, _)| (std::cmp::Reverse(*alto), *indice));
+
+        let mut empaquetador = EmpaquetadorEstantes::nuevo(Self::ANCHO);
+        let esquinas: Vec<[u32; 2]> = imagenes
+            .iter()
+            .map(|(_, ancho, alto, _)| {
+                empaquetador
+                    .colocar(*ancho, *alto)
+                    .expect("sprite más ancho que el atlas")
+            })
+            .collect();
+        let (ancho, alto) = (Self::ANCHO, empaquetador.alto.max(1));
+        let mut pixeles = vec![0u8; (ancho * alto) as usize];
+        let mut regiones = vec![RegionAtlas::default(); FUENTE_5X7.len() + Sprite::TODOS.len()];
+        for ((indice, ancho_imagen, alto_imagen, datos), [x, y]) in imagenes.iter().zip(esquinas) {
+            for fila in 0..*alto_imagen {
+                let origen = (fila * ancho_imagen) as usize;
+                let destino = ((y + fila) * ancho + x) as usize;
+                pixeles[destino..destino + *ancho_imagen as usize]
+                    .copy_from_slice(&datos[origen..origen + *ancho_imagen as usize]);
+            }
+            regiones[*indice] = RegionAtlas {
+                uv0: [x as f32 / ancho as f32, y as f32 / alto as f32],
+                uv1: [(x + ancho_imagen) as f32 / ancho as f32, (y + alto_imagen) as f32 / alto as f32],
+                ancho: *ancho_imagen,
+                alto: *alto_imagen,
+            };
+        }
+        AtlasSprites {
+            pixeles,
+            ancho,
+            alto,
+            regiones: regiones.into(),
+        }
+    }
+}
+
+/// Vértice de un quad de texto en coordenadas de pantalla normalizadas
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VerticeTexto {
+    posicion: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+impl VerticeTexto {
+    const ATRIBUTOS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32x4,
+    ];
+
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<VerticeTexto>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATRIBUTOS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlineacionTexto {
+    Izquierda,
+    Centro,
+}
+
+/// Lote de quads de texto construido en CPU para un cuadro
+pub struct LoteTexto {
+    vertices: Vec<VerticeTexto>,
+    resolucion: [f32; 2],
+    regiones: std::sync::Arc<[RegionAtlas]>,
+}
+
+impl LoteTexto {
+    fn nuevo(resolucion: [f32; 2], regiones: std::sync::Arc<[RegionAtlas]>) -> Self {
+        LoteTexto {
+            vertices: Vec::new(),
+            resolucion,
+            regiones,
+        }
+    }
+
+    /// Ancho en NDC de `texto` con la escala dada
+    pub fn ancho_texto(&self, texto: &str, escala: f32) -> f32 {
+        let pixel_x = 2.0 * escala / self.resolucion[0].max(1.0);
+        texto.chars().count() as f32 * AVANCE_GLIFO as f32 * pixel_x
+    }
+
+    fn agregar_quad(&mut self, region: RegionAtlas, esquina: [f32; 2], tamano: [f32; 2], color: [f32; 4]) {
+        if self.vertices.len() + 6 > MAX_VERTICES_TEXTO {
+            return;
+        }
+        let ([x, y], [x1, y1]) = (esquina, [esquina[0] + tamano[0], esquina[1] - tamano[1]]);
+        let ([u0, v0], [u1, v1]) = (region.uv0, region.uv1);
+        let esquinas = [
+            ([x, y], [u0, v0]),
+            ([x1, y], [u1, v0]),
+            ([x1, y1], [u1, v1]),
+            ([x, y], [u0, v0]),
+            ([x1, y1], [u1, v1]),
+            ([x, y1], [u0, v1]),
+        ];
+        for (posicion, uv) in esquinas {
+            self.vertices.push(VerticeTexto { posicion, uv, color });
+        }
+    }
+
+    /// Agrega `sprite` centrado en `centro` (NDC) con `alto` píxeles, conservando su proporción
+    pub fn agregar_sprite(&mut self, sprite: Sprite, centro: [f32; 2], alto: f32, color: [f32; 4]) {
+        let region = self.regiones[sprite.indice()];
+        let alto_ndc = 2.0 * alto / self.resolucion[1].max(1.0);
+        let ancho_ndc = 2.0 * alto * region.ancho as f32 / region.alto as f32 / self.resolucion[0].max(1.0);
+        let esquina = [centro[0] - ancho_ndc * 0.5, centro[1] + alto_ndc * 0.5];
+        self.agregar_quad(region, esquina, [ancho_ndc, alto_ndc], color);
+    }
+
+    pub fn vaciar(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Agrega `texto` anclado en `posicion` (NDC); `escala` multiplica el tamaño en píxeles
+    pub fn agregar(
+        &mut self,
+        texto: &str,
+        posicion: [f32; 2],
+        escala: f32,
+        color: [f32; 4],
+        alineacion: AlineacionTexto,
+    ) {
+        // Tamaño de un píxel de fuente en NDC
+        let pixel_x = 2.0 * escala / self.resolucion[0].max(1.0);
+        let pixel_y = 2.0 * escala / self.resolucion[1].max(1.0);
+        let avance = AVANCE_GLIFO as f32 * pixel_x;
+        let tamano = [GLIFO_ANCHO as f32 * pixel_x, GLIFO_ALTO as f32 * pixel_y];
+
+        let mut x = match alineacion {
+            AlineacionTexto::Izquierda => posicion[0],
+            AlineacionTexto::Centro => posicion[0] - self.ancho_texto(texto, escala) * 0.5,
+        };
+        for caracter in texto.chars() {
+            let region = self.regiones[indice_glifo(caracter)];
+            self.agregar_quad(region, [x, posicion[1]], tamano, color);
+            x += avance;
+        }
+    }
+}
+
+/// Recursos GPU del texto: atlas de glifos y sprites, pipeline y buffer de quads
+pub struct RenderizadorTexto {
+    pipeline: wgpu::RenderPipeline,
+    grupo_bind_atlas: wgpu::BindGroup,
+    buffer_vertices: wgpu::Buffer,
+    regiones: std::sync::Arc<[RegionAtlas]>,
+    cantidad_vertices: u32,
+}
+
+impl RenderizadorTexto {
+    fn nuevo(
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        formato: wgpu::TextureFormat,
+        modulo_shader: &wgpu::ShaderModule,
+        layout_uniformes: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let atlas = AtlasSprites::construir();
+        let textura_atlas = dispositivo.create_texture_with_data(
+            cola,
+            &wgpu::TextureDescriptor {
+                label: Some("Atlas de Glifos y Sprites"),
+                size: wgpu::Extent3d {
+                    width: atlas.ancho,
+                    height: atlas.alto,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &atlas.pixeles,
+        );
+        let vista_atlas = textura_atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        let muestreador = dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Muestreador de Glifos"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let layout_atlas = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Atlas de Glifos"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let grupo_bind_atlas = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group del Atlas de Glifos"),
+            layout: &layout_atlas,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&vista_atlas),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&muestreador),
+                },
+            ],
+        });
+
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Texto"),
+            bind_group_layouts: &[layout_uniformes, &layout_atlas],
+            push_constant_ranges: &[],
+        });
+        let pipeline = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Texto"),
+            layout: Some(&layout_pipeline),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_texto",
+                buffers: &[VerticeTexto::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_texto",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let buffer_vertices = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Quads de Texto"),
+            size: (MAX_VERTICES_TEXTO * std::mem::size_of::<VerticeTexto>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        RenderizadorTexto {
+            pipeline,
+            grupo_bind_atlas,
+            buffer_vertices,
+            regiones: atlas.regiones,
+            cantidad_vertices: 0,
+        }
+    }
+
+    fn nuevo_lote(&self, resolucion: [f32; 2]) -> LoteTexto {
+        LoteTexto::nuevo(resolucion, self.regiones.clone())
+    }
+
+    /// Sube el lote del cuadro; debe llamarse antes de abrir el pase de render
+    fn preparar(
+        &mut self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        lote: &LoteTexto,
+    ) {
+        self.cantidad_vertices = lote.vertices.len() as u32;
+        anillo.subir(dispositivo, codificador, &self.buffer_vertices, 0, bytemuck::cast_slice(&lote.vertices));
+    }
+
+    fn dibujar<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, grupo_uniformes: &'a wgpu::BindGroup) {
+        if self.cantidad_vertices == 0 {
+            return;
+        }
+        pase.set_pipeline(&self.pipeline);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, &self.grupo_bind_atlas, &[]);
+        pase.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+        pase.draw(0..self.cantidad_vertices, 0..1);
+    }
+}
+
+// =============================================================================
+// MÓDULO: DECODIFICACIÓN EN SEGUNDO PLANO
+// =============================================================================
+
+/// Grupo de hilos compartido por los decodificadores; `hilos` = 0 deja un núcleo libre
+/// para el bucle de eventos
+pub fn grupo_decodificacion(hilos: usize) -> Arc<rayon::ThreadPool> {
+    let hilos = if hilos == 0 {
+        std::thread::available_parallelism().map_or(1, |n| n.get().saturating_sub(1).max(1))
+    } else {
+        hilos
+    };
+    let grupo = rayon::ThreadPoolBuilder::new()
+        .num_threads(hilos)
+        .thread_name(|indice| format!("decodificador {}", indice))
+        .build()
+        .expect("no se pudo crear el grupo de hilos de decodificación");
+    Arc::new(grupo)
+}
+
+/// Resultado de un pedido: generación en que se hizo, clave y recurso decodificado
+type Decodificado<T> = (u64, usize, Result<T, String>);
+
+/// Decodifica recursos (imágenes, modelos OBJ) en el grupo de hilos y los entrega por un
+/// canal al hilo principal, el único que sube a la GPU. `reiniciar` descarta los pedidos
+/// que sigan en vuelo, p. ej. al cambiar de escena
+pub struct DecodificadorRecursos<T> {
+    grupo: Arc<rayon::ThreadPool>,
+    enviar: std::sync::mpsc::Sender<Decodificado<T>>,
+    recibir: std::sync::mpsc::Receiver<Decodificado<T>>,
+    generacion: u64,
+    pendientes: usize,
+}
+
+impl<T: Send + 'static> DecodificadorRecursos<T> {
+    pub fn nuevo(grupo: Arc<rayon::ThreadPool>) -> Self {
+        let (enviar, recibir) = std::sync::mpsc::channel();
+        DecodificadorRecursos {
+            grupo,
+            enviar,
+            recibir,
+            generacion: 0,
+            pendientes: 0,
+        }
+    }
+
+    pub fn reiniciar(&mut self) {
+        self.generacion += 1;
+        self.pendientes = 0;
+    }
+
+    pub fn pedir(&mut self, clave: usize, trabajo: impl FnOnce() -> Result<T, String> + Send + 'static) {
+        let enviar = self.enviar.clone();
+        let generacion = self.generacion;
+        self.pendientes += 1;
+        self.grupo.spawn(move || {
+            // Si el receptor ya no existe nadie espera el resultado
+            let _ = enviar.send((generacion, clave, trabajo()));
+        });
+    }
+
+    pub fn pendientes(&self) -> usize {
+        self.pendientes
+    }
+
+    /// Grupo de hilos en que se decodifica, para compartirlo con otro decodificador
+    pub fn grupo(&self) -> Arc<rayon::ThreadPool> {
+        Arc::clone(&self.grupo)
+    }
+
+    /// Resultados ya terminados, sin bloquear
+    pub fn recibir(&mut self) -> Vec<(usize, Result<T, String>)> {
+        let listos: Vec<_> = self.recibir.try_iter().collect();
+        self.vigentes(listos)
+    }
+
+    /// Bloquea hasta que terminan todos los pedidos vigentes
+    pub fn esperar(&mut self) -> Vec<(usize, Result<T, String>)> {
+        let mut listos = Vec::new();
+        while self.pendientes > 0 {
+            let Ok(resultado) = self.recibir.recv() else { break };
+            listos.extend(self.vigentes(vec![resultado]));
+        }
+        listos
+    }
+
+    fn vigentes(&mut self, listos: Vec<Decodificado<T>>) -> Vec<(usize, Result<T, String>)> {
+        listos
+            .into_iter()
+            .filter(|(generacion, _, _)| *generacion == self.generacion)
+            .map(|(_, clave, resultado)| {
+                self.pendientes -= 1;
+                (clave, resultado)
+            })
+            .collect()
+    }
+}
+
+// =============================================================================
+// MÓDULO: TEXTURAS
+// =============================================================================
+
+/// Mapas de superficie equirectangulares o en cubo y mapas de relieve de los cuerpos (grupo
+/// 1 del pipeline de planetas). Un cuerpo sin mapa usa texturas transparentes y conserva su
+/// color procedural; uno sin relieve, una altura nula.
+pub struct TexturasPlanetas {
+    layout: wgpu::BindGroupLayout,
+    muestreador: wgpu::Sampler,
+    formato: wgpu::TextureFormat,
+    transparente: wgpu::TextureView,
+    cubo_transparente: wgpu::TextureView,
+    sin_relieve: wgpu::TextureView,
+    predeterminada: wgpu::BindGroup,
+    por_cuerpo: Vec<Option<wgpu::BindGroup>>,
+    /// Mapas cargados por cuerpo; en la GPU solo vive el nivel que pide `transmitir`
+    mapas: Vec<Option<MapaTransmitido>>,
+    /// Bytes de VRAM que pueden ocupar las texturas de superficie
+    presupuesto: u64,
+    /// `None` si el adaptador no tiene compute shaders
+    horneador: Option<HorneadorSuperficies>,
+    horneadas: Vec<Option<SuperficieHorneada>>,
+    /// `None` si los mipmaps están desactivados en los ajustes
+    generador: Option<GeneradorMipmaps>,
+    /// Los mapas se decodifican fuera del hilo principal y llegan con `recibir_mapas`
+    decodificador: DecodificadorRecursos<MapaTransmitido>,
+    /// Relieve residente por cuerpo con sus bytes; no se transmite por niveles
+    relieves: Vec<Option<(wgpu::TextureView, u64)>>,
+    decodificador_relieves: DecodificadorRecursos<image::GrayImage>,
+}
+
+impl TexturasPlanetas {
+    /// Bytes de mejoras de nivel que se suben por llamada a `transmitir`
+    const SUBIDA_POR_CUADRO: u64 = 16 * 1024 * 1024;
+
+    pub fn nuevas(
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        formato_superficie: wgpu::TextureFormat,
+        horneador: Option<HorneadorSuperficies>,
+        generador: Option<GeneradorMipmaps>,
+        ajustes: &AjustesTexturas,
+        presupuesto: u64,
+    ) -> Self {
+        let anisotropia = ajustes.anisotropia;
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Texturas de Planeta"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        // La longitud da la vuelta; la latitud no. La anisotropía exige filtrado lineal en todo
+        let muestreador = dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Muestreador de Superficies"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp: anisotropia.clamp(1, 16),
+            ..Default::default()
+        });
+        let formato = Self::formato_mapas(formato_superficie);
+        let transparente = Self::crear_textura(dispositivo, cola, formato, "Superficie Transparente", (1, 1, 1), &[0; 4])
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let cubo_transparente =
+            Self::crear_textura(dispositivo, cola, formato, "Cubo Transparente", (1, 1, 6), &[0; 4 * 6])
+                .create_view(&Self::descriptor_vista(6));
+        let formato_relieve = wgpu::TextureFormat::R8Unorm;
+        let sin_relieve = Self::crear_textura(dispositivo, cola, formato_relieve, "Sin Relieve", (1, 1, 1), &[0])
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let grupo = grupo_decodificacion(ajustes.hilos);
+        TexturasPlanetas {
+            predeterminada: Self::crear_grupo(
+                dispositivo,
+                &layout,
+                &muestreador,
+                &transparente,
+                &cubo_transparente,
+                &transparente,
+                &sin_relieve,
+            ),
+            layout,
+            muestreador,
+            formato,
+            transparente,
+            cubo_transparente,
+            sin_relieve,
+            por_cuerpo: Vec::new(),
+            mapas: Vec::new(),
+            presupuesto,
+            horneador,
+            horneadas: Vec::new(),
+            generador,
+            decodificador: DecodificadorRecursos::nuevo(Arc::clone(&grupo)),
+            relieves: Vec::new(),
+            decodificador_relieves: DecodificadorRecursos::nuevo(grupo),
+        }
+    }
+
+    /// Los mapas se guardan como sRGB si la superficie también lo es
+    pub fn formato_mapas(formato_superficie: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        if formato_superficie.is_srgb() {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Pide los mapas indicados por nombre de cuerpo y los relieves de los cuerpos; mientras
+    /// se decodifican el cuerpo usa su color procedural y queda liso. Los cuerpos horneables
+    /// sin mapa reciben una superficie horneada. Los pedidos de una carga anterior que sigan
+    /// en vuelo se descartan
+    pub fn cargar(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        cuerpos: &[CuerpoCeleste],
+        rutas: &std::collections::BTreeMap<String, MapaSuperficie>,
+    ) {
+        for nombre in rutas.keys() {
+            if !cuerpos.iter().any(|c| &c.nombre == nombre) {
+                eprintln!("Textura ignorada: no hay un cuerpo llamado '{}'", nombre);
+            }
+        }
+        let limite = dispositivo.limits().max_texture_dimension_2d;
+        let con_mipmaps = self.generador.is_some();
+        self.decodificador.reiniciar();
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            if let Some(mapa) = rutas.get(&cuerpo.nombre).cloned() {
+                self.decodificador.pedir(indice, move || MapaTransmitido::cargar(&mapa, limite, con_mipmaps));
+            }
+        }
+        self.decodificador_relieves.reiniciar();
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            if let Some(relieve) = cuerpo.relieve.clone() {
+                self.decodificador_relieves.pedir(indice, move || Self::cargar_relieve(&relieve.mapa, limite));
+            }
+        }
+        self.relieves = cuerpos.iter().map(|_| None).collect();
+        self.mapas = cuerpos.iter().map(|_| None).collect();
+        self.horneadas = cuerpos
+            .iter()
+            .map(|cuerpo| {
+                (!rutas.contains_key(&cuerpo.nombre))
+                    .then(|| self.nueva_horneada(dispositivo, cuerpo))
+                    .flatten()
+            })
+            .collect();
+        self.por_cuerpo = cuerpos.iter().map(|_| None).collect();
+        for indice in 0..cuerpos.len() {
+            self.actualizar_grupo(dispositivo, indice);
+        }
+        // Hasta saber qué tan grande se ve cada cuerpo se sube el nivel más bajo
+        self.transmitir(dispositivo, cola, &vec![0.0; cuerpos.len()]);
+        self.hornear_pendientes(dispositivo, cola, cuerpos);
+    }
+
+    /// Pide un mapa nuevo para un cuerpo ya cargado; el anterior se usa hasta que llega
+    pub fn asignar_mapa(&mut self, dispositivo: &wgpu::Device, indice: usize, mapa: MapaSuperficie) {
+        let limite = dispositivo.limits().max_texture_dimension_2d;
+        let con_mipmaps = self.generador.is_some();
+        self.decodificador.pedir(indice, move || MapaTransmitido::cargar(&mapa, limite, con_mipmaps));
+    }
+
+    /// Vuelve a leer el relieve de un cuerpo ya cargado; el anterior se usa hasta que llega
+    pub fn asignar_relieve(&mut self, dispositivo: &wgpu::Device, indice: usize, relieve: &Relieve) {
+        let limite = dispositivo.limits().max_texture_dimension_2d;
+        let mapa = relieve.mapa.clone();
+        self.decodificador_relieves.pedir(indice, move || Self::cargar_relieve(&mapa, limite));
+    }
+
+    /// Alturas de un mapa de relieve, reducido si no cabe en una textura
+    fn cargar_relieve(ruta: &std::path::Path, limite: u32) -> Result<image::GrayImage, String> {
+        let imagen = image::open(ruta).map_err(|e| format!("no se pudo abrir {}: {}", ruta.display(), e))?;
+        let imagen = if imagen.width() > limite || imagen.height() > limite {
+            imagen.resize(limite, limite, image::imageops::FilterType::Triangle)
+        } else {
+            imagen
+        };
+        Ok(imagen.to_luma8())
+    }
+
+    /// Hace lugar para un cuerpo agregado después de `cargar`
+    pub fn agregar_cuerpo(&mut self, dispositivo: &wgpu::Device, cuerpo: &CuerpoCeleste) {
+        self.relieves.push(None);
+        self.mapas.push(None);
+        self.horneadas.push(self.nueva_horneada(dispositivo, cuerpo));
+        self.por_cuerpo.push(None);
+        self.actualizar_grupo(dispositivo, self.por_cuerpo.len() - 1);
+    }
+
+    /// Recoge los mapas ya decodificados; `transmitir` los sube. Un mapa que no se pudo
+    /// decodificar se avisa y su cuerpo pasa a la superficie horneada, si la admite
+    pub fn recibir_mapas(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, cuerpos: &[CuerpoCeleste]) {
+        let listos = self.decodificador.recibir();
+        self.entregar(dispositivo, cuerpos, listos);
+        let relieves = self.decodificador_relieves.recibir();
+        self.entregar_relieves(dispositivo, cola, cuerpos, relieves);
+    }
+
+    /// Como `recibir_mapas`, pero bloquea hasta tener todos (cuadros exportados)
+    pub fn esperar_mapas(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, cuerpos: &[CuerpoCeleste]) {
+        let listos = self.decodificador.esperar();
+        self.entregar(dispositivo, cuerpos, listos);
+        let relieves = self.decodificador_relieves.esperar();
+        self.entregar_relieves(dispositivo, cola, cuerpos, relieves);
+    }
+
+    /// Sube los relieves decodificados enteros; uno que no se pudo abrir deja liso al cuerpo
+    fn entregar_relieves(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        cuerpos: &[CuerpoCeleste],
+        listos: Vec<(usize, Result<image::GrayImage, String>)>,
+    ) {
+        for (indice, resultado) in listos {
+            let Some(cuerpo) = cuerpos.get(indice).filter(|_| indice < self.relieves.len()) else {
+                continue;
+            };
+            match resultado {
+                Ok(alturas) => {
+                    let etiqueta = format!("Relieve de {}", cuerpo.nombre);
+                    let tamano = (alturas.width(), alturas.height(), 1);
+                    let vista = Self::crear_textura(dispositivo, cola, wgpu::TextureFormat::R8Unorm, &etiqueta, tamano, &alturas)
+                        .create_view(&wgpu::TextureViewDescriptor::default());
+                    self.relieves[indice] = Some((vista, alturas.len() as u64));
+                    self.actualizar_grupo(dispositivo, indice);
+                }
+                Err(error) => eprintln!("Relieve de {} ignorado: {}", cuerpo.nombre, error),
+            }
+        }
+    }
+
+    fn entregar(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        cuerpos: &[CuerpoCeleste],
+        listos: Vec<(usize, Result<MapaTransmitido, String>)>,
+    ) {
+        for (indice, resultado) in listos {
+            let Some(cuerpo) = cuerpos.get(indice).filter(|_| indice < self.mapas.len()) else {
+                continue;
+            };
+            match resultado {
+                Ok(mapa) => {
+                    // Un mapa soltado sobre un cuerpo horneado reemplaza a la superficie horneada
+                    self.mapas[indice] = Some(mapa);
+                    self.horneadas[indice] = None;
+                }
+                Err(error) => {
+                    eprintln!("Textura de {} ignorada: {}", cuerpo.nombre, error);
+                    self.horneadas[indice] = self.nueva_horneada(dispositivo, cuerpo);
+                    self.actualizar_grupo(dispositivo, indice);
+                }
+            }
+        }
+    }
+
+    fn nueva_horneada(&self, dispositivo: &wgpu::Device, cuerpo: &CuerpoCeleste) -> Option<SuperficieHorneada> {
+        let horneador = self.horneador.as_ref()?;
+        HorneadorSuperficies::es_horneable(cuerpo.tipo_shader)
+            .then(|| horneador.nueva_superficie(dispositivo, &cuerpo.nombre, self.generador.is_some()))
+    }
+
+    /// Bytes de VRAM que ocupan ahora las superficies (mapas residentes, horneadas y relieves)
+    pub fn memoria_usada(&self) -> u64 {
+        let mapas: u64 = self.mapas.iter().flatten().filter_map(MapaTransmitido::bytes_residentes).sum();
+        mapas + self.memoria_fija()
+    }
+
+    pub fn describir_memoria(&self) -> String {
+        let mega = 1024.0 * 1024.0;
+        let mut texto = format!(
+            "Texturas: {:.1} de {:.0} MB",
+            self.memoria_usada() as f64 / mega,
+            self.presupuesto as f64 / mega
+        );
+        let pendientes = self.decodificador.pendientes() + self.decodificador_relieves.pendientes();
+        if pendientes > 0 {
+            texto += &format!(" ({} decodificándose)", pendientes);
+        }
+        texto
+    }
+
+    /// Bytes de lo que no se transmite por niveles: superficies horneadas y relieves
+    fn memoria_fija(&self) -> u64 {
+        let relieves: u64 = self.relieves.iter().flatten().map(|(_, bytes)| bytes).sum();
+        let Some(horneador) = &self.horneador else { return relieves };
+        relieves + self.horneadas.iter().flatten().count() as u64 * horneador.bytes_superficie(self.generador.is_some())
+    }
+
+    /// Sube o baja el nivel residente de cada mapa según el diámetro en píxeles con que se
+    /// ve su cuerpo (0 si no está en pantalla), sin pasarse del presupuesto. Como mucho sube
+    /// `SUBIDA_POR_CUADRO` bytes de mejoras por llamada para no trabar el cuadro
+    pub fn transmitir(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, diametros: &[f32]) {
+        let objetivos = self.niveles_objetivo(diametros);
+        let mut usada = self.memoria_usada();
+        let mut subido = 0;
+        for (indice, objetivo) in objetivos.into_iter().enumerate() {
+            let (Some(objetivo), Some(mapa)) = (objetivo, &self.mapas[indice]) else { continue };
+            let bytes = mapa.bytes(objetivo);
+            let cambiar = match mapa.nivel_residente() {
+                None => true,
+                // Mejorar: siempre una por llamada, más si caben en la cuota
+                Some(actual) if objetivo < actual => subido == 0 || subido + bytes <= Self::SUBIDA_POR_CUADRO,
+                // Empeorar: con un nivel de holgura para no alternar, salvo si falta memoria
+                Some(actual) => objetivo > actual + 1 || (objetivo > actual && usada > self.presupuesto),
+            };
+            if !cambiar {
+                continue;
+            }
+            let anterior = mapa.bytes_residentes().unwrap_or(0);
+            let etiqueta = format!("Superficie de {} (nivel {})", mapa.nombre, objetivo);
+            let vista = self
+                .crear_mapa(dispositivo, cola, &etiqueta, &mapa.niveles[objetivo..], mapa.capas)
+                .create_view(&Self::descriptor_vista(mapa.capas));
+            if let Some(mapa) = &mut self.mapas[indice] {
+                mapa.residente = Some((objetivo, vista));
+            }
+            usada = usada - anterior + bytes;
+            if bytes > anterior {
+                subido += bytes;
+            }
+            self.actualizar_grupo(dispositivo, indice);
+        }
+    }
+
+    /// Nivel deseado por mapa: el más bajo que cubre el ecuador visible en pantalla; si
+    /// no caben todos, se rebaja primero el que más memoria gasta por píxel en pantalla
+    fn niveles_objetivo(&self, diametros: &[f32]) -> Vec<Option<usize>> {
+        let diametro = |indice: usize| diametros.get(indice).copied().unwrap_or(0.0);
+        let mut niveles: Vec<Option<usize>> = self
+            .mapas
+            .iter()
+            .enumerate()
+            .map(|(indice, mapa)| {
+                // El mapa da la vuelta completa; el medio ecuador visible mide π/2 diámetros
+                Some(mapa.as_ref()?.nivel_para(std::f32::consts::PI * diametro(indice)))
+            })
+            .collect();
+        let mut total = self.memoria_fija()
+            + niveles
+                .iter()
+                .zip(&self.mapas)
+                .filter_map(|(nivel, mapa)| Some(mapa.as_ref()?.bytes((*nivel)?)))
+                .sum::<u64>();
+        while total > self.presupuesto {
+            let candidato = niveles
+                .iter()
+                .enumerate()
+                .filter_map(|(indice, nivel)| {
+                    let (nivel, mapa) = ((*nivel)?, self.mapas[indice].as_ref()?);
+                    let densidad = mapa.bytes(nivel) as f32 / (diametro(indice).powi(2) + 1.0);
+                    (nivel + 1 < mapa.niveles.len()).then_some((indice, nivel, densidad))
+                })
+                .max_by(|a, b| a.2.total_cmp(&b.2));
+            let Some((indice, nivel, _)) = candidato else { break };
+            let mapa = self.mapas[indice].as_ref().unwrap();
+            total = total - mapa.bytes(nivel) + mapa.bytes(nivel + 1);
+            niveles[indice] = Some(nivel + 1);
+        }
+        niveles
+    }
+
+    /// Sube el primero de `niveles` (las `capas` apiladas en vertical). Con mipmaps activos,
+    /// la GPU genera el resto de la cadena de un mapa plano; la de un cubo sale de los niveles
+    /// siguientes de la pirámide, porque GL no deja muestrear una cara suelta para filtrarla
+    fn crear_mapa(
+        &self,
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        etiqueta: &str,
+        niveles: &[image::RgbaImage],
+        capas: u32,
+    ) -> wgpu::Texture {
+        let imagen = &niveles[0];
+        let (ancho, alto) = (imagen.width(), imagen.height() / capas);
+        let Some(generador) = &self.generador else {
+            return Self::crear_textura(dispositivo, cola, self.formato, etiqueta, (ancho, alto, capas), imagen);
+        };
+        let tamano = wgpu::Extent3d {
+            width: ancho,
+            height: alto,
+            depth_or_array_layers: capas,
+        };
+        let subidos = if capas == 1 { 1 } else { niveles.len() };
+        let textura = dispositivo.create_texture(&wgpu::TextureDescriptor {
+            label: Some(etiqueta),
+            size: tamano,
+            mip_level_count: if capas == 1 { GeneradorMipmaps::niveles(ancho, alto) } else { subidos as u32 },
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.formato,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        for (nivel, imagen) in niveles.iter().take(subidos).enumerate() {
+            let (ancho, alto) = (imagen.width(), imagen.height() / capas);
+            cola.write_texture(
+                wgpu::ImageCopyTexture {
+                    mip_level: nivel as u32,
+                    ..textura.as_image_copy()
+                },
+                imagen,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ancho * 4),
+                    rows_per_image: Some(alto),
+                },
+                wgpu::Extent3d {
+                    width: ancho,
+                    height: alto,
+                    depth_or_array_layers: capas,
+                },
+            );
+        }
+        if capas == 1 {
+            let mut codificador = dispositivo.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Codificador de Mipmaps"),
+            });
+            generador.codificar(dispositivo, &mut codificador, &textura);
+            cola.submit(std::iter::once(codificador.finish()));
+        }
+        textura
+    }
+
+    /// Rehace el grupo del cuerpo `indice` con su nivel residente y su superficie horneada
+    fn actualizar_grupo(&mut self, dispositivo: &wgpu::Device, indice: usize) {
+        let mapa = self.mapas[indice].as_ref().and_then(|mapa| Some((mapa.vista()?, mapa.capas == 6)));
+        let (plano, cubico) = match mapa {
+            Some((vista, true)) => (None, Some(vista)),
+            Some((vista, false)) => (Some(vista), None),
+            None => (None, None),
+        };
+        let horneada = self.horneadas[indice].as_ref().map(|h| &h.vista);
+        let relieve = self.relieves[indice].as_ref().map(|(vista, _)| vista);
+        self.por_cuerpo[indice] = (mapa.is_some() || horneada.is_some() || relieve.is_some()).then(|| {
+            Self::crear_grupo(
+                dispositivo,
+                &self.layout,
+                &self.muestreador,
+                plano.unwrap_or(&self.transparente),
+                cubico.unwrap_or(&self.cubo_transparente),
+                horneada.unwrap_or(&self.transparente),
+                relieve.unwrap_or(&self.sin_relieve),
+            )
+        });
+    }
+
+    /// Vista de un mapa: plana o de cubo según sus capas
+    fn descriptor_vista(capas: u32) -> wgpu::TextureViewDescriptor<'static> {
+        wgpu::TextureViewDescriptor {
+            dimension: Some(if capas == 6 {
+                wgpu::TextureViewDimension::Cube
+            } else {
+                wgpu::TextureViewDimension::D2
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Vuelve a hornear las superficies cuyos parámetros cambiaron desde el último horneado
+    pub fn hornear_pendientes(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, cuerpos: &[CuerpoCeleste]) {
+        let Some(horneador) = &self.horneador else { return };
+        let mut codificador: Option<wgpu::CommandEncoder> = None;
+        for (cuerpo, horneada) in cuerpos.iter().zip(&mut self.horneadas) {
+            let Some(horneada) = horneada else { continue };
+            if !horneada.desactualizada(cuerpo) {
+                continue;
+            }
+            let codificador = codificador.get_or_insert_with(|| {
+                dispositivo.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Codificador de Horneado"),
+                })
+            });
+            horneador.hornear(cola, codificador, horneada, cuerpo);
+            if let Some(generador) = &self.generador {
+                generador.codificar(dispositivo, codificador, &horneada.textura);
+            }
+        }
+        if let Some(codificador) = codificador {
+            cola.submit(std::iter::once(codificador.finish()));
+        }
+    }
+
+    /// Hilos de decodificación, para que otros recursos (modelos) los compartan
+    pub fn grupo_decodificacion(&self) -> Arc<rayon::ThreadPool> {
+        self.decodificador.grupo()
+    }
+
+    /// Grupo sin mapas, para lo que no es un cuerpo de la escena
+    pub fn predeterminado(&self) -> &wgpu::BindGroup {
+        &self.predeterminada
+    }
+
+    /// Grupo de texturas con que se dibuja el cuerpo `indice`
+    pub fn grupo(&self, indice: usize) -> &wgpu::BindGroup {
+        self.por_cuerpo
+            .get(indice)
+            .and_then(Option::as_ref)
+            .unwrap_or(&self.predeterminada)
+    }
+
+    fn crear_textura(
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        formato: wgpu::TextureFormat,
+        etiqueta: &str,
+        (ancho, alto, capas): (u32, u32, u32),
+        pixeles: &[u8],
+    ) -> wgpu::Texture {
+        dispositivo.create_texture_with_data(
+            cola,
+            &wgpu::TextureDescriptor {
+                label: Some(etiqueta),
+                size: wgpu::Extent3d {
+                    width: ancho,
+                    height: alto,
+                    depth_or_array_layers: capas,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: formato,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            pixeles,
+        )
+    }
+
+    fn crear_grupo(
+        dispositivo: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        muestreador: &wgpu::Sampler,
+        mapa: &wgpu::TextureView,
+        cubico: &wgpu::TextureView,
+        horneada: &wgpu::TextureView,
+        relieve: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Textura de Planeta"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(mapa),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(muestreador),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(horneada),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(cubico),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(relieve),
+                },
+            ],
+        })
+    }
+}
+
+/// Mapa de superficie con su pirámide de resoluciones en memoria de CPU; en la GPU vive
+/// solo el nivel elegido
+struct MapaTransmitido {
+    nombre: String,
+    /// El nivel 0 es la imagen completa y cada uno mide la mitad del anterior. Un cubo
+    /// guarda cada nivel como una tira vertical de sus seis caras, en el orden de las capas
+    niveles: Vec<image::RgbaImage>,
+    /// 1 = equirectangular, 6 = cubo
+    capas: u32,
+    residente: Option<(usize, wgpu::TextureView)>,
+    /// El nivel residente lleva su cadena de mipmaps (un tercio más de memoria)
+    con_mipmaps: bool,
+}
+
+impl MapaTransmitido {
+    /// La pirámide no baja de este ancho
+    const ANCHO_MINIMO: u32 = 64;
+
+    fn cargar(mapa: &MapaSuperficie, limite: u32, con_mipmaps: bool) -> Result<Self, String> {
+        let abrir = |ruta: &std::path::Path| {
+            image::open(ruta).map_err(|e| format!("no se pudo abrir {}: {}", ruta.display(), e))
+        };
+        let (nombre, imagen, capas) = match mapa {
+            MapaSuperficie::Imagen(ruta) => {
+                let imagen = abrir(ruta)?;
+                match Self::caras_de_cruz(&imagen) {
+                    Some(caras) => (ruta.display().to_string(), Self::apilar(&caras, limite)?, 6),
+                    None => {
+                        let imagen = if imagen.width() > limite || imagen.height() > limite {
+                            imagen.resize(limite, limite, image::imageops::FilterType::Triangle)
+                        } else {
+                            imagen
+                        };
+                        (ruta.display().to_string(), imagen.to_rgba8(), 1)
+                    }
+                }
+            }
+            MapaSuperficie::Caras(rutas) => {
+                let caras = rutas.iter().map(|ruta| abrir(ruta)).collect::<Result<Vec<_>, _>>()?;
+                (rutas[0].display().to_string(), Self::apilar(&caras, limite)?, 6)
+            }
+        };
+        let mut niveles = vec![imagen];
+        while let Some(anterior) = niveles.last().filter(|n| n.width() > Self::ANCHO_MINIMO && n.height() > capas) {
+            niveles.push(Self::reducir(anterior, capas));
+        }
+        Ok(MapaTransmitido {
+            nombre,
+            niveles,
+            capas,
+            residente: None,
+            con_mipmaps,
+        })
+    }
+
+    /// Las caras de una cruz horizontal (4:3), en el orden de las capas del cubo:
+    ///       +Y
+    ///   -X  +Z  +X  -Z
+    ///       -Y
+    fn caras_de_cruz(imagen: &image::DynamicImage) -> Option<Vec<image::DynamicImage>> {
+        let lado = imagen.width() / 4;
+        if lado == 0 || imagen.width() != lado * 4 || imagen.height() != lado * 3 {
+            return None;
+        }
+        let posiciones = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+        Some(posiciones.iter().map(|&(x, y)| imagen.crop_imm(x * lado, y * lado, lado, lado)).collect())
+    }
+
+    /// Las seis caras una debajo de otra, del mismo lado y sin pasar de `limite`
+    fn apilar(caras: &[image::DynamicImage], limite: u32) -> Result<image::RgbaImage, String> {
+        let lado = caras[0].width();
+        if caras.iter().any(|cara| cara.width() != lado || cara.height() != lado) {
+            return Err("las caras del cubo deben ser cuadradas y del mismo tamaño".to_string());
+        }
+        let lado_final = lado.min(limite);
+        let mut tira = image::RgbaImage::new(lado_final, lado_final * 6);
+        for (capa, cara) in caras.iter().enumerate() {
+            let cara = cara.resize_exact(lado_final, lado_final, image::imageops::FilterType::Triangle);
+            image::imageops::replace(&mut tira, &cara.to_rgba8(), 0, lado_final as i64 * capa as i64);
+        }
+        Ok(tira)
+    }
+
+    /// El nivel siguiente de la pirámide; las caras de un cubo se reducen por separado
+    fn reducir(imagen: &image::RgbaImage, capas: u32) -> image::RgbaImage {
+        let (ancho, alto) = (imagen.width() / 2, (imagen.height() / capas / 2).max(1));
+        let mut reducida = image::RgbaImage::new(ancho, alto * capas);
+        let alto_capa = imagen.height() / capas;
+        for capa in 0..capas {
+            let cara = image::imageops::crop_imm(imagen, 0, capa * alto_capa, imagen.width(), alto_capa).to_image();
+            let cara = image::imageops::resize(&cara, ancho, alto, image::imageops::FilterType::Triangle);
+            image::imageops::replace(&mut reducida, &cara, 0, (capa * alto) as i64);
+        }
+        reducida
+    }
+
+    fn bytes(&self, nivel: usize) -> u64 {
+        let (ancho, alto) = self.niveles[nivel].dimensions();
+        let base = ancho as u64 * alto as u64 * 4;
+        if self.con_mipmaps {
+            base * 4 / 3
+        } else {
+            base
+        }
+    }
+
+    fn nivel_residente(&self) -> Option<usize> {
+        self.residente.as_ref().map(|(nivel, _)| *nivel)
+    }
+
+    fn bytes_residentes(&self) -> Option<u64> {
+        self.nivel_residente().map(|nivel| self.bytes(nivel))
+    }
+
+    fn vista(&self) -> Option<&wgpu::TextureView> {
+        self.residente.as_ref().map(|(_, vista)| vista)
+    }
+
+    /// Nivel más bajo cuya vuelta al ecuador alcanza `ancho` píxeles (en un cubo, cuatro caras)
+    fn nivel_para(&self, ancho: f32) -> usize {
+        let vuelta = if self.capas == 6 { 4 } else { 1 };
+        (0..self.niveles.len())
+            .rev()
+            .find(|&nivel| (self.niveles[nivel].width() * vuelta) as f32 >= ancho)
+            .unwrap_or(0)
+    }
+}
+
+/// Superficie procedural horneada de un cuerpo y los parámetros con que se horneó
+pub struct SuperficieHorneada {
+    textura: wgpu::Texture,
+    vista: wgpu::TextureView,
+    buffer_uniformes: wgpu::Buffer,
+    grupo_uniformes: wgpu::BindGroup,
+    grupo_salida: wgpu::BindGroup,
+    /// Tipo de shader y actividad del último horneado; `None` hasta el primero
+    horneada_con: Option<(u32, f32)>,
+}
+
+impl SuperficieHorneada {
+    /// Cambio de actividad a partir del cual se vuelve a hornear
+    const TOLERANCIA_ACTIVIDAD: f32 = 0.02;
+
+    /// Un cuerpo que pasó a un shader sin parte horneable no usa la superficie
+    fn desactualizada(&self, cuerpo: &CuerpoCeleste) -> bool {
+        HorneadorSuperficies::es_horneable(cuerpo.tipo_shader)
+            && self.horneada_con.is_none_or(|(tipo, actividad)| {
+                tipo != cuerpo.tipo_shader || (actividad - cuerpo.actividad).abs() > Self::TOLERANCIA_ACTIVIDAD
+            })
+    }
+}
+
+/// Pase de cómputo que hornea la parte estática del color procedural y su rugosidad
+/// (`hornear_superficie`) en mapas de `ancho` x `alto`
+pub struct HorneadorSuperficies {
+    pipeline: wgpu::ComputePipeline,
+    layout_uniformes: wgpu::BindGroupLayout,
+    layout_salida: wgpu::BindGroupLayout,
+    ancho: u32,
+    alto: u32,
+}
+
+impl HorneadorSuperficies {
+    pub const FORMATO: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+    const GRUPO_TRABAJO: u32 = 8;
+
+    /// Tipos de shader cuyo color no depende del tiempo: terreno rocoso y luna
+    pub fn es_horneable(tipo_shader: u32) -> bool {
+        matches!(tipo_shader, 2 | 6)
+    }
+
+    /// `ancho` se ajusta al límite de texturas del dispositivo
+    pub fn nuevo(dispositivo: &wgpu::Device, modulo_shader: &wgpu::ShaderModule, ancho: u32) -> Self {
+        let ancho = ancho.clamp(2, dispositivo.limits().max_texture_dimension_2d);
+        let layout_uniformes = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Uniformes de Horneado"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let layout_salida = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Horneado"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: Self::FORMATO,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+        });
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Horneado"),
+            bind_group_layouts: &[&layout_uniformes, &layout_salida],
+            push_constant_ranges: &[],
+        });
+        let pipeline = dispositivo.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Pipeline de Horneado de Superficies"),
+            layout: Some(&layout_pipeline),
+            module: modulo_shader,
+            entry_point: "hornear_superficie",
+        });
+        HorneadorSuperficies {
+            pipeline,
+            layout_uniformes,
+            layout_salida,
+            ancho,
+            alto: ancho / 2,
+        }
+    }
+
+    /// VRAM de una superficie horneada
+    pub fn bytes_superficie(&self, con_mipmaps: bool) -> u64 {
+        let nivel_0 = self.ancho as u64 * self.alto as u64 * 4;
+        if con_mipmaps {
+            nivel_0 * 4 / 3
+        } else {
+            nivel_0
+        }
+    }
+
+    fn nueva_superficie(&self, dispositivo: &wgpu::Device, nombre: &str, con_mipmaps: bool) -> SuperficieHorneada {
+        // Se escribe y se lee en lineal: guarda el mismo valor que daría el cálculo por fragmento
+        let textura = dispositivo.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("Superficie Horneada de {}", nombre)),
+            size: wgpu::Extent3d {
+                width: self.ancho,
+                height: self.alto,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: if con_mipmaps { GeneradorMipmaps::niveles(self.ancho, self.alto) } else { 1 },
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMATO,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let vista = textura.create_view(&wgpu::TextureViewDescriptor::default());
+        // El compute escribe solo el nivel 0; el resto lo filtra `GeneradorMipmaps`
+        let vista_salida = textura.create_view(&wgpu::TextureViewDescriptor {
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        let buffer_uniformes = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Uniformes de Horneado"),
+            size: std::mem::size_of::<DatosUniformes>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let grupo_uniformes = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Uniformes de Horneado"),
+            layout: &self.layout_uniformes,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer_uniformes.as_entire_binding(),
+            }],
+        });
+        let grupo_salida = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Salida de Horneado"),
+            layout: &self.layout_salida,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&vista_salida),
+            }],
+        });
+        SuperficieHorneada {
+            textura,
+            vista,
+            buffer_uniformes,
+            grupo_uniformes,
+            grupo_salida,
+            horneada_con: None,
+        }
+    }
+
+    fn hornear(
+        &self,
+        cola: &wgpu::Queue,
+        codificador: &mut wgpu::CommandEncoder,
+        superficie: &mut SuperficieHorneada,
+        cuerpo: &CuerpoCeleste,
+    ) {
+        let uniformes = DatosUniformes {
+            tipo_render: cuerpo.tipo_shader,
+            actividad: cuerpo.actividad,
+            ..bytemuck::Zeroable::zeroed()
+        };
+        cola.write_buffer(&superficie.buffer_uniformes, 0, bytemuck::cast_slice(&[uniformes]));
+        let mut pase = codificador.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Pase de Horneado"),
+            timestamp_writes: None,
+        });
+        pase.set_pipeline(&self.pipeline);
+        pase.set_bind_group(0, &superficie.grupo_uniformes, &[]);
+        pase.set_bind_group(1, &superficie.grupo_salida, &[]);
+        pase.dispatch_workgroups(
+            self.ancho.div_ceil(Self::GRUPO_TRABAJO),
+            self.alto.div_ceil(Self::GRUPO_TRABAJO),
+            1,
+        );
+        superficie.horneada_con = Some((cuerpo.tipo_shader, cuerpo.actividad));
+    }
+}
+
+/// Genera en la GPU la cadena de mipmaps de una textura (wgpu no lo hace solo): cada
+/// nivel se dibuja filtrando el anterior (`vertex_mipmap` / `fragment_mipmap`)
+pub struct GeneradorMipmaps {
+    layout: wgpu::BindGroupLayout,
+    muestreador: wgpu::Sampler,
+    /// Un pipeline por formato de destino
+    pipelines: Vec<(wgpu::TextureFormat, wgpu::RenderPipeline)>,
+}
+
+impl GeneradorMipmaps {
+    /// Niveles de la cadena completa hasta 1x1
+    pub fn niveles(ancho: u32, alto: u32) -> u32 {
+        u32::BITS - ancho.max(alto).max(1).leading_zeros()
+    }
+
+    pub fn nuevo(dispositivo: &wgpu::Device, modulo_shader: &wgpu::ShaderModule, formatos: &[wgpu::TextureFormat]) -> Self {
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Mipmaps"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let muestreador = dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Muestreador de Mipmaps"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Mipmaps"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipelines = formatos
+            .iter()
+            .map(|&formato| {
+                let pipeline = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Pipeline de Mipmaps"),
+                    layout: Some(&layout_pipeline),
+                    vertex: wgpu::VertexState {
+                        module: modulo_shader,
+                        entry_point: "vertex_mipmap",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: modulo_shader,
+                        entry_point: "fragment_mipmap",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: formato,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                });
+                (formato, pipeline)
+            })
+            .collect();
+        GeneradorMipmaps {
+            layout,
+            muestreador,
+            pipelines,
+        }
+    }
+
+    /// Rellena los niveles 1.. de `textura` a partir del nivel 0; la textura debe admitir
+    /// `RENDER_ATTACHMENT` y su formato debe estar entre los de `nuevo`
+    pub fn codificar(&self, dispositivo: &wgpu::Device, codificador: &mut wgpu::CommandEncoder, textura: &wgpu::Texture) {
+        let Some((_, pipeline)) = self.pipelines.iter().find(|(f, _)| *f == textura.format()) else {
+            eprintln!("Mipmaps omitidos: formato {:?} sin pipeline", textura.format());
+            return;
+        };
+        let vista_nivel = |nivel: u32| {
+            textura.create_view(&wgpu::TextureViewDescriptor {
+                base_mip_level: nivel,
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        };
+        for nivel in 1..textura.mip_level_count() {
+            let origen = vista_nivel(nivel - 1);
+            let destino = vista_nivel(nivel);
+            let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group de Mipmap"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&origen),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.muestreador),
+                    },
+                ],
+            });
+            let mut pase = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pase de Mipmap"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &destino,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pase.set_pipeline(pipeline);
+            pase.set_bind_group(0, &grupo, &[]);
+            pase.draw(0..3, 0..1);
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: PARTÍCULAS
+// =============================================================================
+
+/// Partícula simulada en la GPU; el mismo buffer es almacenamiento del compute y
+/// buffer de instancias del dibujo
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticulaGpu {
+    posicion: [f32; 3],
+    /// Segundos que le quedan; 0 o menos = libre
+    vida: f32,
+    velocidad: [f32; 3],
+    duracion: f32,
+    /// Centro hacia el que cae (el cuerpo impactado)
+    atractor: [f32; 3],
+    gravedad: f32,
+    color: [f32; 3],
+    tamano: f32,
+}
+
+impl ParticulaGpu {
+    const ATRIBUTOS: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x4,
+        3 => Float32x4,
+        4 => Float32x4,
+    ];
+
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticulaGpu>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATRIBUTOS,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParametrosParticulas {
+    paso: f32,
+    cantidad: u32,
+    _relleno: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParametrosOrden {
+    ojo: [f32; 3],
+    cantidad: u32,
+    adelante: [f32; 3],
+    _relleno: u32,
+}
+
+/// Orden bitónico de pares (clave, índice) en la GPU, de menor a mayor clave. Quien lo usa
+/// llena las claves (binding 3) en su propio compute y lee los índices ordenados
+pub struct OrdenadorBitonico {
+    pipeline: wgpu::ComputePipeline,
+    buffer_claves: wgpu::Buffer,
+    grupo: wgpu::BindGroup,
+    /// Separación de cada paso en el buffer de uniformes (offset dinámico)
+    separacion: u32,
+    pasos: u32,
+    cantidad: u32,
+}
+
+impl OrdenadorBitonico {
+    const GRUPO_TRABAJO: u32 = 64;
+
+    /// `cantidad` debe ser potencia de dos
+    fn nuevo(dispositivo: &wgpu::Device, modulo_shader: &wgpu::ShaderModule, cantidad: u32) -> Self {
+        assert!(cantidad.is_power_of_two(), "el orden bitónico requiere una potencia de dos");
+        let buffer_claves = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Claves de Orden"),
+            size: cantidad as wgpu::BufferAddress * 8,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        // Un (j, k) por despacho: k = 2, 4, ..., cantidad y j = k/2, ..., 1
+        let separacion = dispositivo.limits().min_uniform_buffer_offset_alignment.max(8);
+        let mut datos = Vec::new();
+        let mut k = 2;
+        while k <= cantidad {
+            let mut j = k / 2;
+            while j > 0 {
+                datos.extend_from_slice(bytemuck::cast_slice(&[j, k]));
+                datos.resize(datos.len().next_multiple_of(separacion as usize), 0);
+                j /= 2;
+            }
+            k *= 2;
+        }
+        let pasos = (datos.len() / separacion as usize) as u32;
+        let buffer_pasos = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Pasos Bitónicos"),
+            contents: &datos,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Orden Bitónico"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(8),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group del Orden Bitónico"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: buffer_claves.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &buffer_pasos,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(8),
+                    }),
+                },
+            ],
+        });
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Orden Bitónico"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = dispositivo.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Pipeline de Orden Bitónico"),
+            layout: Some(&layout_pipeline),
+            module: modulo_shader,
+            entry_point: "ordenar_bitonico",
+        });
+
+        OrdenadorBitonico {
+            pipeline,
+            buffer_claves,
+            grupo,
+            separacion,
+            pasos,
+            cantidad,
+        }
+    }
+
+    /// Todos los pasos en el pase de quien lo usa; entre despachos las escrituras ya son visibles
+    fn codificar<'a>(&'a self, pase: &mut wgpu::ComputePass<'a>) {
+        pase.set_pipeline(&self.pipeline);
+        for paso in 0..self.pasos {
+            pase.set_bind_group(0, &self.grupo, &[paso * self.separacion]);
+            pase.dispatch_workgroups(self.cantidad.div_ceil(Self::GRUPO_TRABAJO), 1, 1);
+        }
+    }
+}
+
+/// Plumas de material expulsado en los impactos: ráfaga radial que cae hacia el cuerpo
+/// impactado y se apaga. Las partículas viven en un buffer circular de la GPU; cada cuadro
+/// se ordenan ahí mismo por profundidad en una copia para mezclarlas con alfa
+pub struct PlumasImpacto {
+    pipeline_simulacion: wgpu::ComputePipeline,
+    pipeline_dibujo: wgpu::RenderPipeline,
+    pipeline_claves: wgpu::ComputePipeline,
+    pipeline_reunir: wgpu::ComputePipeline,
+    buffer_particulas: wgpu::Buffer,
+    /// Copia ordenada de atrás hacia adelante; es la que se dibuja
+    buffer_ordenadas: wgpu::Buffer,
+    buffer_parametros: wgpu::Buffer,
+    buffer_orden: wgpu::Buffer,
+    grupo_simulacion: wgpu::BindGroup,
+    grupo_orden: wgpu::BindGroup,
+    ordenador: OrdenadorBitonico,
+    /// Próxima ranura del buffer circular
+    siguiente: usize,
+    /// Tiempo hasta que se apague la última partícula; en 0 no se simula ni se dibuja
+    vida_restante: f32,
+    generador: GeneradorAleatorio,
+}
+
+impl PlumasImpacto {
+    const MAX_PARTICULAS: usize = 16384;
+    const GRUPO_TRABAJO: u32 = 64;
+    /// Paso máximo de cada despacho del compute
+    const PASO_MAXIMO: f32 = 0.05;
+
+    fn nuevas(
+        dispositivo: &wgpu::Device,
+        modulo_shader: &wgpu::ShaderModule,
+        formato: wgpu::TextureFormat,
+        layout_uniformes: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let buffer_particulas = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Partículas de Impacto"),
+            size: (Self::MAX_PARTICULAS * std::mem::size_of::<ParticulaGpu>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let buffer_ordenadas = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Partículas Ordenadas"),
+            size: (Self::MAX_PARTICULAS * std::mem::size_of::<ParticulaGpu>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+        let buffer_orden = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Parámetros de Orden"),
+            size: std::mem::size_of::<ParametrosOrden>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let ordenador = OrdenadorBitonico::nuevo(dispositivo, modulo_shader, Self::MAX_PARTICULAS as u32);
+        let buffer_parametros = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Parámetros de Partículas"),
+            size: std::mem::size_of::<ParametrosParticulas>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout_simulacion = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Simulación de Partículas"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let grupo_simulacion = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Simulación de Partículas"),
+            layout: &layout_simulacion,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buffer_particulas.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer_parametros.as_entire_binding(),
+                },
+            ],
+        });
+        let layout_simulacion = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Simulación de Partículas"),
+            bind_group_layouts: &[&layout_simulacion],
+            push_constant_ranges: &[],
+        });
+        let pipeline_simulacion = dispositivo.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Pipeline de Simulación de Partículas"),
+            layout: Some(&layout_simulacion),
+            module: modulo_shader,
+            entry_point: "simular_particulas",
+        });
+
+        let almacenamiento = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout_orden = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Orden de Partículas"),
+            entries: &[
+                almacenamiento(1),
+                almacenamiento(3),
+                almacenamiento(4),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let grupo_orden = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Orden de Partículas"),
+            layout: &layout_orden,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buffer_particulas.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: ordenador.buffer_claves.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: buffer_ordenadas.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: buffer_orden.as_entire_binding(),
+                },
+            ],
+        });
+        let layout_orden = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Orden de Partículas"),
+            bind_group_layouts: &[&layout_orden],
+            push_constant_ranges: &[],
+        });
+        let pipeline_orden = |etiqueta, entrada| {
+            dispositivo.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(etiqueta),
+                layout: Some(&layout_orden),
+                module: modulo_shader,
+                entry_point: entrada,
+            })
+        };
+        let pipeline_claves = pipeline_orden("Pipeline de Claves de Partículas", "claves_particulas");
+        let pipeline_reunir = pipeline_orden("Pipeline de Reunión de Partículas", "reunir_particulas");
+
+        let layout_dibujo = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Partículas"),
+            bind_group_layouts: &[layout_uniformes],
+            push_constant_ranges: &[],
+        });
+        let pipeline_dibujo = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Partículas de Impacto"),
+            layout: Some(&layout_dibujo),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_particula",
+                buffers: &[ParticulaGpu::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_particula",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        PlumasImpacto {
+            pipeline_simulacion,
+            pipeline_dibujo,
+            pipeline_claves,
+            pipeline_reunir,
+            buffer_particulas,
+            buffer_ordenadas,
+            buffer_parametros,
+            buffer_orden,
+            grupo_simulacion,
+            grupo_orden,
+            ordenador,
+            siguiente: 0,
+            vida_restante: 0.0,
+            generador: GeneradorAleatorio::desde_reloj(),
+        }
+    }
+
+    /// Lanza una pluma; cantidad, rapidez y duración crecen con la rapidez del impacto
+    pub fn emitir(&mut self, cola: &wgpu::Queue, impacto: &Impacto, escala_mayor: f32) {
+        let cantidad = (300.0 + impacto.rapidez * 2000.0).min(4096.0) as usize;
+        let rapidez_salida = 0.1 + impacto.rapidez * 1.2;
+        let duracion_maxima = 1.2 + (impacto.rapidez * 4.0).min(1.8);
+        let gravedad = escala_mayor * 0.6;
+
+        let generador = &mut self.generador;
+        let particulas: Vec<ParticulaGpu> = (0..cantidad)
+            .map(|_| {
+                // Dirección al azar en el hemisferio que mira hacia afuera, inclinada hacia la normal
+                let mut direccion = Vec3::new(
+                    generador.flotante() * 2.0 - 1.0,
+                    generador.flotante() * 2.0 - 1.0,
+                    generador.flotante() * 2.0 - 1.0,
+                );
+                if direccion.dot(&impacto.normal) < 0.0 {
+                    direccion = -direccion;
+                }
+                let direccion = (direccion.normalize() + impacto.normal * 0.6).normalize();
+                let calor = generador.flotante();
+                let duracion = duracion_maxima * (0.5 + generador.flotante() * 0.5);
+                ParticulaGpu {
+                    posicion: impacto.punto.into(),
+                    vida: duracion,
+                    velocidad: (direccion * rapidez_salida * (0.3 + generador.flotante() * 0.9)).into(),
+                    duracion,
+                    atractor: impacto.centro.into(),
+                    gravedad,
+                    color: [1.0, 0.45 + 0.4 * calor, 0.15 + 0.5 * calor * calor],
+                    tamano: escala_mayor * (0.015 + generador.flotante() * 0.02),
+                }
+            })
+            .collect();
+
+        // El buffer es circular: si no cabe hasta el final, sigue desde el principio
+        let tamano = std::mem::size_of::<ParticulaGpu>();
+        let hasta_final = (Self::MAX_PARTICULAS - self.siguiente).min(particulas.len());
+        let (primera, resto) = particulas.split_at(hasta_final);
+        cola.write_buffer(&self.buffer_particulas, (self.siguiente * tamano) as u64, bytemuck::cast_slice(primera));
+        if !resto.is_empty() {
+            cola.write_buffer(&self.buffer_particulas, 0, bytemuck::cast_slice(resto));
+        }
+        self.siguiente = (self.siguiente + particulas.len()) % Self::MAX_PARTICULAS;
+        self.vida_restante = self.vida_restante.max(duracion_maxima);
+    }
+
+    /// Avanza todas las partículas `paso` segundos, en despachos de a lo sumo `PASO_MAXIMO`
+    pub fn simular(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, paso: f32) {
+        if self.vida_restante <= 0.0 || paso <= 0.0 {
+            return;
+        }
+        let despachos = (paso / Self::PASO_MAXIMO).ceil().min(100.0) as u32;
+        let parametros = ParametrosParticulas {
+            paso: paso / despachos as f32,
+            cantidad: Self::MAX_PARTICULAS as u32,
+            _relleno: [0; 2],
+        };
+        cola.write_buffer(&self.buffer_parametros, 0, bytemuck::cast_slice(&[parametros]));
+
+        let mut codificador = dispositivo.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Codificador de Partículas"),
+        });
+        {
+            let mut pase = codificador.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Pase de Simulación de Partículas"),
+                timestamp_writes: None,
+            });
+            pase.set_pipeline(&self.pipeline_simulacion);
+            pase.set_bind_group(0, &self.grupo_simulacion, &[]);
+            for _ in 0..despachos {
+                pase.dispatch_workgroups((Self::MAX_PARTICULAS as u32).div_ceil(Self::GRUPO_TRABAJO), 1, 1);
+            }
+        }
+        cola.submit(std::iter::once(codificador.finish()));
+        self.vida_restante = (self.vida_restante - paso).max(0.0);
+    }
+
+    /// Copia las partículas ordenadas de atrás hacia adelante según la cámara (`ojo` y su
+    /// dirección `adelante`): claves de profundidad, orden bitónico y reunión, sin pasar por la CPU
+    pub fn ordenar(
+        &self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        ojo: Vec3,
+        adelante: Vec3,
+    ) {
+        if self.vida_restante <= 0.0 {
+            return;
+        }
+        let parametros = ParametrosOrden {
+            ojo: ojo.into(),
+            cantidad: Self::MAX_PARTICULAS as u32,
+            adelante: adelante.into(),
+            _relleno: 0,
+        };
+        anillo.subir(dispositivo, codificador, &self.buffer_orden, 0, bytemuck::cast_slice(&[parametros]));
+
+        let grupos = (Self::MAX_PARTICULAS as u32).div_ceil(Self::GRUPO_TRABAJO);
+        let mut pase = codificador.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Pase de Orden de Partículas"),
+            timestamp_writes: None,
+        });
+        pase.set_pipeline(&self.pipeline_claves);
+        pase.set_bind_group(0, &self.grupo_orden, &[]);
+        pase.dispatch_workgroups(grupos, 1, 1);
+        self.ordenador.codificar(&mut pase);
+        pase.set_pipeline(&self.pipeline_reunir);
+        pase.set_bind_group(0, &self.grupo_orden, &[]);
+        pase.dispatch_workgroups(grupos, 1, 1);
+    }
+
+    fn dibujar<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, grupo_uniformes: &'a wgpu::BindGroup) {
+        if self.vida_restante <= 0.0 {
+            return;
+        }
+        pase.set_pipeline(&self.pipeline_dibujo);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_vertex_buffer(0, self.buffer_ordenadas.slice(..));
+        pase.draw(0..6, 0..Self::MAX_PARTICULAS as u32);
+    }
+}
+
+/// Billboard de una partícula simulada en la CPU
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanciaParticula {
+    posicion: [f32; 3],
+    tamano: f32,
+    /// Alfa premultiplicado; con alfa 0 suma luz sin tapar lo de atrás
+    color: [f32; 4],
+}
+
+impl InstanciaParticula {
+    const ATRIBUTOS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x4,
+    ];
+
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanciaParticula>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATRIBUTOS,
+        }
+    }
+}
+
+/// Comportamiento de un `SistemaParticulas`. Rapideces y tamaños van en múltiplos de la
+/// escala que se pasa al emitir (el radio del cuerpo emisor)
+#[derive(Debug, Clone)]
+pub struct ConfigParticulas {
+    /// Partículas por segundo de cada fuente de `emitir_continuo`
+    pub tasa: f32,
+    /// Segundos de vida, al azar entre los dos
+    pub vida: [f32; 2],
+    pub rapidez: [f32; 2],
+    /// 0 = salen en la dirección pedida; 1 = en cualquiera
+    pub dispersion: f32,
+    /// Fracción de la velocidad que se pierde por segundo
+    pub arrastre: f32,
+    /// Al nacer y al morir
+    pub tamano: [f32; 2],
+    /// Color según la fracción de vida consumida
+    pub color: Paleta,
+    pub opacidad: [f32; 2],
+    /// Suma luz (fuego, gas) en vez de tapar lo de atrás (polvo)
+    pub aditiva: bool,
+    pub maximo: usize,
+}
+
+struct Particula {
+    posicion: Vec3,
+    velocidad: Vec3,
+    edad: f32,
+    vida: f32,
+    escala: f32,
+    /// Cuerpo junto al que se dibuja
+    dueno: usize,
+}
+
+/// Partículas simuladas en la CPU: emisión continua o en ráfagas, movimiento con arrastre,
+/// y color, opacidad y tamaño según la edad. Se dibujan con `EfectosParticulas`
+pub struct SistemaParticulas {
+    pub config: ConfigParticulas,
+    particulas: Vec<Particula>,
+    /// Fracción de partícula que le quedó pendiente a cada fuente de `emitir_continuo`
+    acumulado: Vec<f32>,
+    generador: GeneradorAleatorio,
+}
+
+impl SistemaParticulas {
+    pub fn nuevo(config: ConfigParticulas, semilla: u64) -> Self {
+        SistemaParticulas {
+            config,
+            particulas: Vec::new(),
+            acumulado: Vec::new(),
+            generador: GeneradorAleatorio::nuevo(semilla),
+        }
+    }
+
+    /// Ráfaga de `cantidad` partículas del cuerpo `dueno`; `muestra` da el origen y la
+    /// dirección de salida de cada una
+    pub fn emitir(
+        &mut self,
+        dueno: usize,
+        cantidad: usize,
+        escala: f32,
+        muestra: impl FnMut(&mut GeneradorAleatorio) -> (Vec3, Vec3),
+    ) {
+        self.emitir_repartidas(dueno, cantidad, escala, 0.0, muestra);
+    }
+
+    /// Emite lo que corresponde a `paso` segundos según la tasa; la fuente es el cuerpo `dueno`
+    pub fn emitir_continuo(
+        &mut self,
+        dueno: usize,
+        paso: f32,
+        escala: f32,
+        muestra: impl FnMut(&mut GeneradorAleatorio) -> (Vec3, Vec3),
+    ) {
+        if self.acumulado.len() <= dueno {
+            self.acumulado.resize(dueno + 1, 0.0);
+        }
+        let total = self.acumulado[dueno] + self.config.tasa * paso;
+        let cantidad = total.floor();
+        self.acumulado[dueno] = total - cantidad;
+        self.emitir_repartidas(dueno, cantidad as usize, escala, paso, muestra);
+    }
+
+    /// Las nacidas durante `paso` ya vivieron una parte al azar de él, así el rastro no
+    /// depende de cuántos cuadros hubo
+    fn emitir_repartidas(
+        &mut self,
+        dueno: usize,
+        cantidad: usize,
+        escala: f32,
+        paso: f32,
+        mut muestra: impl FnMut(&mut GeneradorAleatorio) -> (Vec3, Vec3),
+    ) {
+        let libres = self.config.maximo.saturating_sub(self.particulas.len());
+        let config = &self.config;
+        let generador = &mut self.generador;
+        for _ in 0..cantidad.min(libres) {
+            let (origen, direccion) = muestra(generador);
+            let azar = generador.direccion();
+            let direccion = direccion.lerp(&azar, config.dispersion);
+            let direccion = if direccion.norm() > 1e-4 { direccion.normalize() } else { azar };
+            let mut particula = Particula {
+                posicion: origen,
+                velocidad: direccion * generador.entre(config.rapidez) * escala,
+                edad: 0.0,
+                vida: generador.entre(config.vida),
+                escala,
+                dueno,
+            };
+            Self::avanzar(&mut particula, config.arrastre, generador.flotante() * paso);
+            if particula.edad < particula.vida {
+                self.particulas.push(particula);
+            }
+        }
+    }
+
+    fn avanzar(particula: &mut Particula, arrastre: f32, paso: f32) {
+        particula.posicion += particula.velocidad * paso;
+        particula.velocidad *= (-arrastre * paso).exp();
+        particula.edad += paso;
+    }
+
+    pub fn simular(&mut self, paso: f32) {
+        let arrastre = self.config.arrastre;
+        for particula in &mut self.particulas {
+            Self::avanzar(particula, arrastre, paso);
+        }
+        self.particulas.retain(|p| p.edad < p.vida);
+    }
+
+    pub fn cantidad(&self) -> usize {
+        self.particulas.len()
+    }
+
+    /// Descarta las partículas, p. ej. al cambiar de escena
+    pub fn vaciar(&mut self) {
+        self.particulas.clear();
+        self.acumulado.clear();
+    }
+
+    /// Billboards con su cuerpo dueño
+    fn instancias(&self) -> impl Iterator<Item = (usize, InstanciaParticula)> + '_ {
+        let config = &self.config;
+        self.particulas.iter().map(move |particula| {
+            let t = (particula.edad / particula.vida).clamp(0.0, 1.0);
+            let mezclar = |[inicio, fin]: [f32; 2]| inicio + (fin - inicio) * t;
+            let opacidad = mezclar(config.opacidad);
+            let [r, g, b] = config.color.muestrear(t).a_flotante();
+            let instancia = InstanciaParticula {
+                posicion: particula.posicion.into(),
+                tamano: mezclar(config.tamano) * particula.escala,
+                color: [r * opacidad, g * opacidad, b * opacidad, if config.aditiva { 0.0 } else { opacidad }],
+            };
+            (particula.dueno, instancia)
+        })
+    }
+}
+
+/// Efectos de partículas de la escena con un solo pipeline de billboards y un buffer de
+/// instancias: llamaradas de las estrellas, colas de cometas y escombros de impactos (estos
+/// solo sin compute; si no, los hace `PlumasImpacto`). Las partículas de cada cuerpo se
+/// dibujan en dos mitades, las de detrás antes que él y las de delante después
+pub struct EfectosParticulas {
+    pipeline: wgpu::RenderPipeline,
+    buffer_instancias: wgpu::Buffer,
+    /// Por cuerpo: tramo del buffer detrás de su centro y tramo delante
+    tramos: Vec<[std::ops::Range<u32>; 2]>,
+    pub llamaradas: SistemaParticulas,
+    pub colas: SistemaParticulas,
+    pub escombros: SistemaParticulas,
+}
+
+impl EfectosParticulas {
+    fn nuevos(
+        dispositivo: &wgpu::Device,
+        modulo_shader: &wgpu::ShaderModule,
+        formato: wgpu::TextureFormat,
+        layout_uniformes: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let llamaradas = SistemaParticulas::nuevo(
+            ConfigParticulas {
+                tasa: 400.0,
+                vida: [0.5, 1.5],
+                rapidez: [0.1, 0.35],
+                dispersion: 0.35,
+                arrastre: 0.8,
+                tamano: [0.08, 0.16],
+                color: Paleta::nuevo(vec![
+                    (0.0, ColorRGB::nuevo(255, 240, 170)),
+                    (0.4, ColorRGB::nuevo(255, 140, 30)),
+                    (1.0, ColorRGB::nuevo(170, 30, 10)),
+                ]),
+                opacidad: [0.6, 0.0],
+                aditiva: true,
+                maximo: 3000,
+            },
+            0x11A3,
+        );
+        let colas = SistemaParticulas::nuevo(
+            ConfigParticulas {
+                tasa: 150.0,
+                vida: [1.5, 3.0],
+                rapidez: [3.0, 6.0],
+                dispersion: 0.12,
+                arrastre: 0.3,
+                tamano: [0.6, 2.0],
+                color: Paleta::nuevo(vec![
+                    (0.0, ColorRGB::nuevo(235, 245, 255)),
+                    (1.0, ColorRGB::nuevo(90, 150, 255)),
+                ]),
+                opacidad: [0.12, 0.0],
+                aditiva: true,
+                maximo: 3000,
+            },
+            0xC0E7A,
+        );
+        let escombros = SistemaParticulas::nuevo(
+            ConfigParticulas {
+                tasa: 0.0,
+                vida: [1.0, 2.5],
+                rapidez: [0.3, 1.2],
+                dispersion: 0.7,
+                arrastre: 0.9,
+                tamano: [0.04, 0.02],
+                color: Paleta::nuevo(vec![
+                    (0.0, ColorRGB::nuevo(255, 170, 60)),
+                    (0.3, ColorRGB::nuevo(120, 110, 100)),
+                    (1.0, ColorRGB::nuevo(90, 85, 80)),
+                ]),
+                opacidad: [0.9, 0.0],
+                aditiva: false,
+                maximo: 2000,
+            },
+            0xE5C0,
+        );
+
+        let capacidad = llamaradas.config.maximo + colas.config.maximo + escombros.config.maximo;
+        let buffer_instancias = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Instancias de Partículas"),
+            size: (capacidad * std::mem::size_of::<InstanciaParticula>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let layout = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Billboards"),
+            bind_group_layouts: &[layout_uniformes],
+            push_constant_ranges: &[],
+        });
+        let pipeline = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Billboards de Partículas"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_billboard",
+                buffers: &[InstanciaParticula::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_particula",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        EfectosParticulas {
+            pipeline,
+            buffer_instancias,
+            tramos: Vec::new(),
+            llamaradas,
+            colas,
+            escombros,
+        }
+    }
+
+    fn sistemas_mut(&mut self) -> [&mut SistemaParticulas; 3] {
+        [&mut self.llamaradas, &mut self.colas, &mut self.escombros]
+    }
+
+    /// Avanza `paso` segundos y emite lo nuevo: los cuerpos visibles según su efecto y,
+    /// con `escombros`, una ráfaga por impacto
+    pub fn simular(&mut self, cuerpos: &[CuerpoCeleste], impactos: &[Impacto], escombros: bool, paso: f32) {
+        for sistema in self.sistemas_mut() {
+            sistema.simular(paso);
+        }
+        let estrella = cuerpos.iter().find(|c| c.es_estrella()).map(CuerpoCeleste::posicion_3d);
+        for (indice, cuerpo) in cuerpos.iter().enumerate().filter(|(_, c)| c.visible) {
+            let (centro, radio) = (cuerpo.posicion_3d(), cuerpo.escala);
+            match cuerpo.efecto {
+                EfectoCuerpo::Llamaradas => self.llamaradas.emitir_continuo(indice, paso, radio, |generador| {
+                    let normal = generador.direccion();
+                    (centro + normal * radio, normal)
+                }),
+                // Hacia afuera de la estrella, desde la cara que la mira
+                EfectoCuerpo::Cola => {
+                    let Some(estrella) = estrella else { continue };
+                    let lejos = (centro - estrella).normalize();
+                    self.colas.emitir_continuo(indice, paso, radio, |generador| {
+                        let normal = generador.direccion();
+                        (centro + normal * radio * 0.8, lejos)
+                    });
+                }
+                EfectoCuerpo::Ninguno => {}
+            }
+        }
+        if escombros {
+            for impacto in impactos {
+                let cantidad = (40.0 + impacto.rapidez * 400.0).min(600.0) as usize;
+                let escala = cuerpos[impacto.mayor].escala;
+                self.escombros
+                    .emitir(impacto.mayor, cantidad, escala, |_| (impacto.punto, impacto.normal));
+            }
+        }
+    }
+
+    pub fn cantidad(&self) -> usize {
+        self.llamaradas.cantidad() + self.colas.cantidad() + self.escombros.cantidad()
+    }
+
+    pub fn vaciar(&mut self) {
+        for sistema in self.sistemas_mut() {
+            sistema.vaciar();
+        }
+    }
+
+    /// Sube los billboards agrupados por cuerpo y mitad, cada grupo de atrás hacia adelante
+    /// según la cámara (`ojo` y su dirección `adelante`)
+    pub fn preparar(
+        &mut self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        cuerpos: &[CuerpoCeleste],
+        ojo: Vec3,
+        adelante: Vec3,
+    ) {
+        let profundidad = |punto: Vec3| (punto - ojo).dot(&adelante);
+        let centros: Vec<f32> = cuerpos.iter().map(|c| profundidad(c.posicion_3d())).collect();
+        let mut ordenadas: Vec<(usize, bool, f32, InstanciaParticula)> = [&self.llamaradas, &self.colas, &self.escombros]
+            .into_iter()
+            .flat_map(SistemaParticulas::instancias)
+            .filter(|(dueno, _)| *dueno < cuerpos.len())
+            .map(|(dueno, instancia)| {
+                let lejos = profundidad(Vec3::from(instancia.posicion));
+                (dueno, lejos < centros[dueno], lejos, instancia)
+            })
+            .collect();
+        ordenadas.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(b.2.total_cmp(&a.2)));
+
+        self.tramos = vec![[0..0, 0..0]; cuerpos.len()];
+        for (posicion, (dueno, cerca, _, _)) in ordenadas.iter().enumerate() {
+            let tramo = &mut self.tramos[*dueno][*cerca as usize];
+            if tramo.start == tramo.end {
+                tramo.start = posicion as u32;
+            }
+            tramo.end = posicion as u32 + 1;
+        }
+        let instancias: Vec<InstanciaParticula> = ordenadas.into_iter().map(|(_, _, _, instancia)| instancia).collect();
+        if !instancias.is_empty() {
+            anillo.subir(dispositivo, codificador, &self.buffer_instancias, 0, bytemuck::cast_slice(&instancias));
+        }
+    }
+
+    /// Las partículas del cuerpo `indice` detrás de su centro (`lejanas`) o delante. Usa el
+    /// grupo 0 ya puesto y deja el buffer de vértices en el slot 0 sin restaurar
+    fn dibujar_mitad<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, indice: usize, lejanas: bool) -> bool {
+        let Some(tramo) = self.tramos.get(indice).map(|t| t[!lejanas as usize].clone()) else {
+            return false;
+        };
+        if tramo.is_empty() {
+            return false;
+        }
+        pase.set_pipeline(&self.pipeline);
+        pase.set_vertex_buffer(0, self.buffer_instancias.slice(..));
+        pase.draw(0..6, tramo);
+        true
+    }
+}
+
+// =============================================================================
+// MÓDULO: CINTURÓN DE ASTEROIDES
+// =============================================================================
+
+/// Copia de `DefinicionCinturon` con el layout del `Cinturon` del shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CinturonGpu {
+    radio_interno: f32,
+    radio_externo: f32,
+    espesor: f32,
+    periodo: f32,
+    tamano: f32,
+    semilla: u32,
+    cantidad: u32,
+    _relleno: u32,
+    /// Origen de la escena en el marco de referencia actual
+    centro: [f32; 2],
+    _relleno_centro: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParametrosCulling {
+    vista_proyeccion: [[f32; 4]; 4],
+    ojo: [f32; 3],
+    tiempo: f32,
+    cinturon: CinturonGpu,
+    margen: f32,
+    cantidad_oclusores: u32,
+    _relleno: [u32; 2],
+    oclusores: [[f32; 4]; ParametrosCulling::MAX_OCLUSORES],
+}
+
+impl ParametrosCulling {
+    const MAX_OCLUSORES: usize = 8;
+}
+
+/// Cinturón de miles de asteroides que la CPU nunca recorre: cada cuadro un compute recorta
+/// por frustum y por los cuerpos que los tapan, escribe los índices visibles y los argumentos
+/// de un `draw_indirect`, y el vertex shader reconstruye cada asteroide desde su índice
+pub struct CinturonAsteroides {
+    pipeline_culling: wgpu::ComputePipeline,
+    pipeline_dibujo: wgpu::RenderPipeline,
+    buffer_parametros: wgpu::Buffer,
+    buffer_visibles: wgpu::Buffer,
+    buffer_argumentos: wgpu::Buffer,
+    /// El cinturón que lee el vertex shader; su centro sigue al marco de referencia
+    buffer_cinturon: wgpu::Buffer,
+    grupo_culling: wgpu::BindGroup,
+    grupo_dibujo: wgpu::BindGroup,
+    cinturon: CinturonGpu,
+}
+
+impl CinturonAsteroides {
+    const GRUPO_TRABAJO: u32 = 64;
+
+    /// Requiere compute y dibujo indirecto
+    fn soportado(capacidades: &wgpu::DownlevelCapabilities) -> bool {
+        capacidades
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS | wgpu::DownlevelFlags::INDIRECT_EXECUTION)
+    }
+
+    fn nuevo(
+        dispositivo: &wgpu::Device,
+        modulo_shader: &wgpu::ShaderModule,
+        formato: wgpu::TextureFormat,
+        layout_uniformes: &wgpu::BindGroupLayout,
+        definicion: &DefinicionCinturon,
+    ) -> Self {
+        let cinturon = CinturonGpu {
+            radio_interno: definicion.radio_interno,
+            radio_externo: definicion.radio_externo,
+            espesor: definicion.espesor,
+            periodo: definicion.periodo,
+            tamano: definicion.tamano,
+            semilla: definicion.semilla,
+            cantidad: definicion.cantidad,
+            _relleno: 0,
+            centro: [0.0; 2],
+            _relleno_centro: [0.0; 2],
+        };
+        let buffer_parametros = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Parámetros de Culling"),
+            size: std::mem::size_of::<ParametrosCulling>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let buffer_visibles = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Asteroides Visibles"),
+            size: definicion.cantidad as wgpu::BufferAddress * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+        let buffer_argumentos = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Argumentos Indirectos del Cinturón"),
+            size: std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let buffer_cinturon = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer del Cinturón"),
+            contents: bytemuck::cast_slice(&[cinturon]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let entrada = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let almacenamiento = wgpu::BufferBindingType::Storage { read_only: false };
+        let layout_culling = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group de Culling"),
+            entries: &[
+                entrada(0, wgpu::BufferBindingType::Uniform),
+                entrada(1, almacenamiento),
+                entrada(2, almacenamiento),
+            ],
+        });
+        let grupo_culling = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group de Culling"),
+            layout: &layout_culling,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer_parametros.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buffer_visibles.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer_argumentos.as_entire_binding(),
+                },
+            ],
+        });
+        let layout_pipeline_culling = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Culling"),
+            bind_group_layouts: &[&layout_culling],
+            push_constant_ranges: &[],
+        });
+        let pipeline_culling = dispositivo.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Pipeline de Culling de Asteroides"),
+            layout: Some(&layout_pipeline_culling),
+            module: modulo_shader,
+            entry_point: "recortar_asteroides",
+        });
+
+        let layout_dibujo = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Cinturón"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let grupo_dibujo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group del Cinturón"),
+            layout: &layout_dibujo,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer_cinturon.as_entire_binding(),
+            }],
+        });
+        let layout_pipeline_dibujo = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline del Cinturón"),
+            bind_group_layouts: &[layout_uniformes, &layout_dibujo],
+            push_constant_ranges: &[],
+        });
+        let pipeline_dibujo = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline del Cinturón de Asteroides"),
+            layout: Some(&layout_pipeline_dibujo),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_asteroide",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: 4,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Uint32],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_asteroide",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        CinturonAsteroides {
+            pipeline_culling,
+            pipeline_dibujo,
+            buffer_parametros,
+            buffer_visibles,
+            buffer_argumentos,
+            buffer_cinturon,
+            grupo_culling,
+            grupo_dibujo,
+            cinturon,
+        }
+    }
+
+    /// Reinicia los argumentos indirectos y codifica el culling para la cámara de `uniformes`;
+    /// `oclusores` son esferas (centro, radio) que se dibujan encima del cinturón
+    fn recortar(
+        &self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        uniformes: &DatosUniformes,
+        centro: Vec3,
+        oclusores: &[[f32; 4]],
+    ) {
+        let vista_proyeccion =
+            Mat4::from(uniformes.matriz_proyeccion) * Mat4::from(uniformes.matriz_vista);
+        let cinturon = CinturonGpu { centro: [centro.x, centro.y], ..self.cinturon };
+        let mut parametros = ParametrosCulling {
+            vista_proyeccion: vista_proyeccion.into(),
+            ojo: uniformes.ojo,
+            tiempo: uniformes.tiempo_actual,
+            cinturon,
+            margen: uniformes.matriz_proyeccion[0][0].max(uniformes.matriz_proyeccion[1][1]),
+            cantidad_oclusores: oclusores.len().min(ParametrosCulling::MAX_OCLUSORES) as u32,
+            _relleno: [0; 2],
+            oclusores: [[0.0; 4]; ParametrosCulling::MAX_OCLUSORES],
+        };
+        for (destino, oclusor) in parametros.oclusores.iter_mut().zip(oclusores) {
+            *destino = *oclusor;
+        }
+        let argumentos = wgpu::util::DrawIndirectArgs {
+            vertex_count: 6,
+            instance_count: 0,
+            first_vertex: 0,
+            first_instance: 0,
+        };
+        anillo.subir(dispositivo, codificador, &self.buffer_argumentos, 0, argumentos.as_bytes());
+        anillo.subir(dispositivo, codificador, &self.buffer_parametros, 0, bytemuck::cast_slice(&[parametros]));
+        anillo.subir(dispositivo, codificador, &self.buffer_cinturon, 0, bytemuck::cast_slice(&[cinturon]));
+
+        let mut pase = codificador.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Pase de Culling de Asteroides"),
+            timestamp_writes: None,
+        });
+        pase.set_pipeline(&self.pipeline_culling);
+        pase.set_bind_group(0, &self.grupo_culling, &[]);
+        pase.dispatch_workgroups(self.cinturon.cantidad.div_ceil(Self::GRUPO_TRABAJO), 1, 1);
+    }
+
+    fn dibujar<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, grupo_uniformes: &'a wgpu::BindGroup) {
+        pase.set_pipeline(&self.pipeline_dibujo);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, &self.grupo_dibujo, &[]);
+        pase.set_vertex_buffer(0, self.buffer_visibles.slice(..));
+        pase.draw_indirect(&self.buffer_argumentos, 0);
+    }
+}
+
+// =============================================================================
+// MÓDULO: TELESCOPIO
+// =============================================================================
+
+/// Recuadro circular con una vista aumentada del último cuerpo bajo el cursor: la escena se
+/// dibuja en una textura con un campo estrecho desde el ojo de la cámara y se compone encima
+pub struct Telescopio {
+    pub activo: bool,
+    pub objetivo: Option<usize>,
+    vista: wgpu::TextureView,
+    pipeline: wgpu::RenderPipeline,
+    grupo: wgpu::BindGroup,
+    buffer_vertices: wgpu::Buffer,
+}
+
+impl Telescopio {
+    /// Lado en píxeles de la textura del telescopio
+    pub const RESOLUCION: u32 = 384;
+    /// Diámetro del recuadro y separación del borde de la ventana, en píxeles
+    const DIAMETRO: f32 = 260.0;
+    const MARGEN: f32 = 16.0;
+    /// Fracción del campo que ocupa el diámetro del cuerpo
+    const OCUPACION: f32 = 0.6;
+
+    fn nuevo(
+        dispositivo: &wgpu::Device,
+        modulo_shader: &wgpu::ShaderModule,
+        formato: wgpu::TextureFormat,
+        layout_uniformes: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let textura = dispositivo.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Textura del Telescopio"),
+            size: wgpu::Extent3d {
+                width: Self::RESOLUCION,
+                height: Self::RESOLUCION,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: formato,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let vista = textura.create_view(&wgpu::TextureViewDescriptor::default());
+        let muestreador = dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Muestreador del Telescopio"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Telescopio"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group del Telescopio"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&vista),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&muestreador),
+                },
+            ],
+        });
+
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline del Telescopio"),
+            bind_group_layouts: &[layout_uniformes, &layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline del Recuadro del Telescopio"),
+            layout: Some(&layout_pipeline),
+            vertex: wgpu::VertexState {
+                module: modulo_shader,
+                entry_point: "vertex_texto",
+                buffers: &[VerticeTexto::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: modulo_shader,
+                entry_point: "fragment_telescopio",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: formato,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let buffer_vertices = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer del Recuadro del Telescopio"),
+            size: (6 * std::mem::size_of::<VerticeTexto>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Telescopio {
+            activo: false,
+            objetivo: None,
+            vista,
+            pipeline,
+            grupo,
+            buffer_vertices,
+        }
+    }
+
+    pub fn alternar(&mut self) {
+        self.activo = !self.activo;
+    }
+
+    /// Toma lo elegido en el de un dispositivo anterior
+    pub fn heredar(&mut self, viejo: &Telescopio) {
+        self.activo = viejo.activo;
+        self.objetivo = viejo.objetivo;
+    }
+
+    /// Campo de visión con que el cuerpo ocupa `OCUPACION` del recuadro
+    fn campo_vision(camara: &CamaraVirtual, cuerpo: &CuerpoCeleste) -> f32 {
+        let distancia = (cuerpo.posicion_3d() - camara.ojo).norm().max(cuerpo.escala * 1.01);
+        (2.0 * (cuerpo.escala / Self::OCUPACION / distancia).atan()).clamp(1e-4, camara.campo_vision)
+    }
+
+    /// Vista y proyección desde el ojo de la cámara apuntando al cuerpo
+    fn encuadre(camara: &CamaraVirtual, cuerpo: &CuerpoCeleste) -> (Mat4, Mat4) {
+        let vista = nalgebra_glm::look_at(&camara.ojo, &cuerpo.posicion_3d(), &camara.vector_arriba);
+        let campo = Self::campo_vision(camara, cuerpo);
+        let proyeccion = nalgebra_glm::perspective_rh_zo(1.0, campo, camara.plano_cercano, camara.plano_lejano);
+        (vista, proyeccion)
+    }
+
+    /// Aumento respecto a la vista principal
+    pub fn aumento(camara: &CamaraVirtual, cuerpo: &CuerpoCeleste) -> f32 {
+        camara.campo_vision / Self::campo_vision(camara, cuerpo)
+    }
+
+    /// Centro y radio (por eje) del recuadro en NDC, en la esquina inferior derecha
+    pub fn recuadro(resolucion: [f32; 2]) -> ([f32; 2], [f32; 2]) {
+        let [ancho, alto] = resolucion;
+        let radio_pixeles = Self::DIAMETRO * 0.5;
+        let centro = [ancho - Self::MARGEN - radio_pixeles, alto - Self::MARGEN - radio_pixeles];
+        (
+            [centro[0] / ancho * 2.0 - 1.0, 1.0 - centro[1] / alto * 2.0],
+            [radio_pixeles / ancho * 2.0, radio_pixeles / alto * 2.0],
+        )
+    }
+
+    fn preparar(
+        &self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        resolucion: [f32; 2],
+    ) {
+        let ([cx, cy], [rx, ry]) = Self::recuadro(resolucion);
+        let esquina = |u: f32, v: f32| VerticeTexto {
+            posicion: [cx + (u * 2.0 - 1.0) * rx, cy - (v * 2.0 - 1.0) * ry],
+            uv: [u, v],
+            color: [1.0; 4],
+        };
+        let vertices = [
+            esquina(0.0, 0.0),
+            esquina(1.0, 0.0),
+            esquina(1.0, 1.0),
+            esquina(0.0, 0.0),
+            esquina(1.0, 1.0),
+            esquina(0.0, 1.0),
+        ];
+        anillo.subir(dispositivo, codificador, &self.buffer_vertices, 0, bytemuck::cast_slice(&vertices));
+    }
+
+    fn dibujar<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, grupo_uniformes: &'a wgpu::BindGroup) {
+        pase.set_pipeline(&self.pipeline);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, &self.grupo, &[]);
+        pase.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+        pase.draw(0..6, 0..1);
+    }
+}
+
+// =============================================================================
+// MÓDULO: POSPROCESO
+// =============================================================================
+
+/// Parámetros del pase de posproceso (`ParametrosPosproceso` en el WGSL)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParametrosPosproceso {
+    /// 1 = reducir a la paleta de 16 colores con tramado ordenado
+    paleta: u32,
+    /// 1 si el destino es sRGB: la paleta se compara en sRGB y vuelve a lineal
+    srgb: u32,
+    _relleno: [u32; 2],
+}
+
+/// Con un filtro o el modo pixelado activos, la escena se dibuja en una textura intermedia
+/// (del tamaño de la ventana, o `escala` veces más chica) y un pase de pantalla completa la
+/// copia al destino aplicándolos; la interfaz se dibuja después, sin filtrar
+pub struct Posproceso {
+    pub filtro: FiltroPosproceso,
+    /// Píxeles de la ventana por píxel de la escena (1 = resolución completa)
+    pub escala: u32,
+    /// Con el pixelado, reducir los colores a una paleta fija con tramado
+    pub paleta: bool,
+    formato: wgpu::TextureFormat,
+    layout: wgpu::BindGroupLayout,
+    muestreador_lineal: wgpu::Sampler,
+    muestreador_cercano: wgpu::Sampler,
+    buffer_parametros: wgpu::Buffer,
+    pipeline_crt: wgpu::RenderPipeline,
+    pipeline_copia: wgpu::RenderPipeline,
+    /// Vista de la textura intermedia, su bind group, su tamaño y la escala con que se hizo;
+    /// se rehace al cambiar alguno
+    intermedia: Option<(wgpu::TextureView, wgpu::BindGroup, [u32; 2], u32)>,
+}
+
+impl Posproceso {
+    /// Escala máxima del modo pixelado
+    pub const ESCALA_MAXIMA: u32 = 16;
+
+    fn nuevo(
+        dispositivo: &wgpu::Device,
+        modulo_shader: &wgpu::ShaderModule,
+        formato: wgpu::TextureFormat,
+        layout_uniformes: &wgpu::BindGroupLayout,
+        ajustes: &AjustesPosproceso,
+    ) -> Self {
+        let muestreador = |nombre: &str, filtro: wgpu::FilterMode| {
+            dispositivo.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(nombre),
+                mag_filter: filtro,
+                min_filter: filtro,
+                ..Default::default()
+            })
+        };
+        let muestreador_lineal = muestreador("Muestreador Lineal del Posproceso", wgpu::FilterMode::Linear);
+        let muestreador_cercano = muestreador("Muestreador Pixelado del Posproceso", wgpu::FilterMode::Nearest);
+        let buffer_parametros = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Parámetros del Posproceso"),
+            size: std::mem::size_of::<ParametrosPosproceso>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let layout = dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout de Bind Group del Posproceso"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout_pipeline = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline del Posproceso"),
+            bind_group_layouts: &[layout_uniformes, &layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |nombre: &str, entrada: &str| {
+            dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(nombre),
+                layout: Some(&layout_pipeline),
+                vertex: wgpu::VertexState {
+                    module: modulo_shader,
+                    entry_point: "vertex_posproceso",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: modulo_shader,
+                    entry_point: entrada,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: formato,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let pipeline_crt = pipeline("Pipeline del Filtro CRT", "fragment_crt");
+        let pipeline_copia = pipeline("Pipeline de Copia del Posproceso", "fragment_copia");
+
+        Posproceso {
+            filtro: ajustes.filtro,
+            escala: ajustes.pixelado.clamp(1, Self::ESCALA_MAXIMA),
+            paleta: ajustes.paleta,
+            formato,
+            layout,
+            muestreador_lineal,
+            muestreador_cercano,
+            buffer_parametros,
+            pipeline_crt,
+            pipeline_copia,
+            intermedia: None,
+        }
+    }
+
+    fn activo(&self) -> bool {
+        self.filtro != FiltroPosproceso::Ninguno || self.escala > 1
+    }
+
+    /// Toma lo elegido en el de un dispositivo anterior
+    pub fn heredar(&mut self, viejo: &Posproceso) {
+        self.filtro = viejo.filtro;
+        self.escala = viejo.escala;
+        self.paleta = viejo.paleta;
+    }
+
+    /// Tamaño de la textura intermedia para un destino de `tamano`
+    fn tamano_interno(&self, [ancho, alto]: [u32; 2]) -> [u32; 2] {
+        [ancho.div_ceil(self.escala).max(1), alto.div_ceil(self.escala).max(1)]
+    }
+
+    /// Crea o rehace la textura intermedia para un destino de `tamano` si el posproceso está
+    /// activo, y sube sus parámetros
+    fn preparar(&mut self, dispositivo: &wgpu::Device, cola: &wgpu::Queue, tamano: [u32; 2]) {
+        if !self.activo() {
+            self.intermedia = None;
+            return;
+        }
+        let interno = self.tamano_interno(tamano);
+        let escala = self.escala;
+        if self.intermedia.as_ref().is_none_or(|(_, _, actual, con)| *actual != interno || *con != escala) {
+            let textura = dispositivo.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Textura Intermedia del Posproceso"),
+                size: wgpu::Extent3d {
+                    width: interno[0],
+                    height: interno[1],
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.formato,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let vista = textura.create_view(&wgpu::TextureViewDescriptor::default());
+            // Pixelado: cada píxel de la escena queda como un bloque de bordes nítidos
+            let muestreador = if escala > 1 { &self.muestreador_cercano } else { &self.muestreador_lineal };
+            let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group del Posproceso"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&vista),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(muestreador),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.buffer_parametros.as_entire_binding(),
+                    },
+                ],
+            });
+            self.intermedia = Some((vista, grupo, interno, escala));
+        }
+        let parametros = ParametrosPosproceso {
+            paleta: (escala > 1 && self.paleta) as u32,
+            srgb: self.formato.is_srgb() as u32,
+            _relleno: [0; 2],
+        };
+        cola.write_buffer(&self.buffer_parametros, 0, bytemuck::bytes_of(&parametros));
+    }
+
+    /// Vista en la que se dibuja la escena: la intermedia, o ninguna para dibujar directo
+    fn intermedia(&self) -> Option<&wgpu::TextureView> {
+        self.intermedia.as_ref().map(|(vista, ..)| vista)
+    }
+
+    /// Pasa la textura intermedia a `destino` con el filtro (o solo ampliada); sin
+    /// posproceso activo no hace nada
+    fn aplicar(
+        &self,
+        codificador: &mut wgpu::CommandEncoder,
+        destino: &wgpu::TextureView,
+        grupo_uniformes: &wgpu::BindGroup,
+    ) {
+        let Some((_, grupo, ..)) = &self.intermedia else { return };
+        let pipeline = match self.filtro {
+            FiltroPosproceso::Ninguno => &self.pipeline_copia,
+            FiltroPosproceso::Crt => &self.pipeline_crt,
+        };
+        let mut pase = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pase de Posproceso"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: destino,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pase.set_pipeline(pipeline);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, grupo, &[]);
+        pase.draw(0..3, 0..1);
+    }
+}
+
+// =============================================================================
+// MÓDULO: COMPILACIÓN DE PIPELINES
+// =============================================================================
+
+/// Pipeline de planetas con un fragment shader dado (el resto del estado es común)
+fn crear_pipeline_planetas(
+    dispositivo: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    modulo: &wgpu::ShaderModule,
+    fragmento: &str,
+    formato: wgpu::TextureFormat,
+    vertices: FormatoVertices,
+) -> wgpu::RenderPipeline {
+    dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Pipeline de Renderizado Principal"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: modulo,
+            entry_point: vertices.punto_entrada(),
+            buffers: &[vertices.descriptor_layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: modulo,
+            entry_point: fragmento,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: formato,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// Variante del pipeline de planetas: la genérica lee el tipo del uniforme; cada
+/// especializada fija `TIPO_ESPECIALIZADO` en el WGSL; una propia lleva la función
+/// `superficie_propia` de un archivo de la escena, identificada por el hash de su texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariantePlaneta {
+    Generica,
+    Tipo(u32),
+    Propia(u64),
+}
+
+impl VariantePlaneta {
+    const CONSTANTE: &'static str = "const TIPO_ESPECIALIZADO: u32 = 0u;";
+    const CONSTANTE_PROPIA: &'static str = "const SUPERFICIE_PROPIA: bool = false;";
+    const INICIO_PROPIA: &'static str = "// INICIO DE SUPERFICIE PROPIA";
+    const FIN_PROPIA: &'static str = "// FIN DE SUPERFICIE PROPIA";
+
+    /// Fuente WGSL de la variante; `fragmento` es el texto de una propia
+    fn especializar(self, fuente: &str, fragmento: Option<&str>) -> Result<String, String> {
+        match self {
+            VariantePlaneta::Generica => Ok(fuente.to_owned()),
+            VariantePlaneta::Tipo(tipo) if fuente.contains(Self::CONSTANTE) => Ok(fuente.replacen(
+                Self::CONSTANTE,
+                &format!("const TIPO_ESPECIALIZADO: u32 = {}u;", tipo),
+                1,
+            )),
+            VariantePlaneta::Tipo(_) => Err(format!("el shader no declara `{}`", Self::CONSTANTE)),
+            VariantePlaneta::Propia(_) => {
+                let fragmento = fragmento.ok_or("falta el texto del fragmento propio")?;
+                let (Some(inicio), Some(fin)) = (fuente.find(Self::INICIO_PROPIA), fuente.find(Self::FIN_PROPIA)) else {
+                    return Err(format!("el shader no marca `{}` y `{}`", Self::INICIO_PROPIA, Self::FIN_PROPIA));
+                };
+                if !fuente.contains(Self::CONSTANTE_PROPIA) {
+                    return Err(format!("el shader no declara `{}`", Self::CONSTANTE_PROPIA));
+                }
+                let cosido = format!("{}{}\n{}", &fuente[..inicio], fragmento, &fuente[fin..]);
+                Ok(cosido.replacen(Self::CONSTANTE_PROPIA, "const SUPERFICIE_PROPIA: bool = true;", 1))
+            }
+        }
+    }
+}
+
+/// Una variante pedida, con el texto del fragmento si es propia
+type PeticionPipeline = (VariantePlaneta, Option<Arc<str>>);
+
+/// Lo necesario para compilar una variante del pipeline de planetas
+struct RecetaPipeline {
+    dispositivo: Arc<wgpu::Device>,
+    fuente: String,
+    layout: wgpu::PipelineLayout,
+    formato: wgpu::TextureFormat,
+    vertices: FormatoVertices,
+}
+
+impl RecetaPipeline {
+    fn compilar(&self, (variante, fragmento): &PeticionPipeline) -> Result<wgpu::RenderPipeline, String> {
+        let codigo = variante.especializar(&self.fuente, fragmento.as_deref())?;
+        self.dispositivo.push_error_scope(wgpu::ErrorFilter::Validation);
+        let modulo = self.dispositivo.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Módulo de Shader Especializado"),
+            source: wgpu::ShaderSource::Wgsl(codigo.into()),
+        });
+        let pipeline = crear_pipeline_planetas(
+            &self.dispositivo,
+            &self.layout,
+            &modulo,
+            "fragment_principal",
+            self.formato,
+            self.vertices,
+        );
+        match pollster::block_on(self.dispositivo.pop_error_scope()) {
+            Some(error) => Err(error.to_string()),
+            None => Ok(pipeline),
+        }
+    }
+}
+
+/// Dónde se compilan las variantes pedidas
+enum ModoCompilacion {
+    /// Hilo aparte; el resultado vuelve por `terminados`
+    Hilo {
+        peticiones: std::sync::mpsc::Sender<PeticionPipeline>,
+        terminados: std::sync::mpsc::Receiver<(VariantePlaneta, Result<wgpu::RenderPipeline, String>)>,
+    },
+    /// En GL todo pasa por un único contexto (y su cerrojo): compilar en otro hilo no
+    /// gana nada y puede agotar la espera del cerrojo, así que se compila una variante por cuadro
+    EnLinea {
+        receta: RecetaPipeline,
+        cola: std::collections::VecDeque<PeticionPipeline>,
+    },
+}
+
+/// Compila las variantes del pipeline de planetas sin frenar el cuadro. Mientras una no
+/// está lista, sus cuerpos usan la genérica y, si tampoco lo está, el material provisional
+pub struct CompiladorPipelines {
+    modo: ModoCompilacion,
+    listos: std::collections::HashMap<VariantePlaneta, wgpu::RenderPipeline>,
+    /// Pedidas y todavía sin respuesta
+    pendientes: std::collections::HashSet<VariantePlaneta>,
+    /// Las que fallaron no se vuelven a pedir
+    fallidas: std::collections::HashSet<VariantePlaneta>,
+    provisional: wgpu::RenderPipeline,
+}
+
+impl CompiladorPipelines {
+    pub fn nuevo(
+        dispositivo: Arc<wgpu::Device>,
+        backend: wgpu::Backend,
+        fuente: String,
+        layout: wgpu::PipelineLayout,
+        formato: wgpu::TextureFormat,
+        vertices: FormatoVertices,
+        provisional: wgpu::RenderPipeline,
+    ) -> Self {
+        let receta = RecetaPipeline {
+            dispositivo,
+            fuente,
+            layout,
+            formato,
+            vertices,
+        };
+        let modo = if backend == wgpu::Backend::Gl {
+            ModoCompilacion::EnLinea {
+                receta,
+                cola: std::collections::VecDeque::new(),
+            }
+        } else {
+            let (peticiones, recibidas) = std::sync::mpsc::channel::<PeticionPipeline>();
+            let (listo, terminados) = std::sync::mpsc::channel();
+            // El hilo termina cuando se suelta el compilador (se cierra el canal de peticiones)
+            std::thread::Builder::new()
+                .name("compilador de pipelines".into())
+                .spawn(move || {
+                    for peticion in recibidas {
+                        let resultado = receta.compilar(&peticion);
+                        if listo.send((peticion.0, resultado)).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("no se pudo crear el hilo compilador de pipelines");
+            ModoCompilacion::Hilo { peticiones, terminados }
+        };
+        CompiladorPipelines {
+            modo,
+            listos: std::collections::HashMap::new(),
+            pendientes: std::collections::HashSet::new(),
+            fallidas: std::collections::HashSet::new(),
+            provisional,
+        }
+    }
+
+    fn pedir(&mut self, variante: VariantePlaneta, fragmento: Option<Arc<str>>) {
+        if self.listos.contains_key(&variante) || self.pendientes.contains(&variante) || self.fallidas.contains(&variante) {
+            return;
+        }
+        let pedida = match &mut self.modo {
+            ModoCompilacion::Hilo { peticiones, .. } => peticiones.send((variante, fragmento)).is_ok(),
+            ModoCompilacion::EnLinea { cola, .. } => {
+                cola.push_back((variante, fragmento));
+                true
+            }
+        };
+        if pedida {
+            self.pendientes.insert(variante);
+        }
+    }
+
+    /// Un resultado terminado, esperándolo si `bloquear`; `None` si no hay ninguno
+    fn siguiente(&mut self, bloquear: bool) -> Option<(VariantePlaneta, Result<wgpu::RenderPipeline, String>)> {
+        match &mut self.modo {
+            ModoCompilacion::Hilo { terminados, .. } if bloquear => terminados.recv().ok(),
+            ModoCompilacion::Hilo { terminados, .. } => terminados.try_recv().ok(),
+            ModoCompilacion::EnLinea { receta, cola } => {
+                let peticion = cola.pop_front()?;
+                let resultado = receta.compilar(&peticion);
+                Some((peticion.0, resultado))
+            }
+        }
+    }
+
+    fn recibir(&mut self, variante: VariantePlaneta, resultado: Result<wgpu::RenderPipeline, String>) {
+        self.pendientes.remove(&variante);
+        match resultado {
+            Ok(pipeline) => {
+                self.listos.insert(variante, pipeline);
+            }
+            Err(error) => {
+                eprintln!("No se compiló el pipeline {:?}: {}", variante, error);
+                self.fallidas.insert(variante);
+            }
+        }
+    }
+
+    /// Recoge lo que terminó el hilo y pide la genérica y las variantes de `tipos` que falten
+    pub fn preparar(&mut self, tipos: impl IntoIterator<Item = u32>) {
+        if matches!(self.modo, ModoCompilacion::Hilo { .. }) {
+            while let Some((variante, resultado)) = self.siguiente(false) {
+                self.recibir(variante, resultado);
+            }
+        } else if let Some((variante, resultado)) = self.siguiente(false) {
+            self.recibir(variante, resultado);
+        }
+        self.pedir(VariantePlaneta::Generica, None);
+        for tipo in tipos {
+            self.pedir(VariantePlaneta::Tipo(tipo), None);
+        }
+    }
+
+    /// Pide la variante propia de un fragmento; se compila junto con las demás
+    pub fn preparar_propia(&mut self, version: u64, fragmento: &Arc<str>) {
+        self.pedir(VariantePlaneta::Propia(version), Some(fragmento.clone()));
+    }
+
+    /// La variante propia lista; `None` si aún compila o falló
+    pub fn propia(&self, version: u64) -> Option<&wgpu::RenderPipeline> {
+        self.listos.get(&VariantePlaneta::Propia(version))
+    }
+
+    pub fn fallo_propia(&self, version: u64) -> bool {
+        self.fallidas.contains(&VariantePlaneta::Propia(version))
+    }
+
+    /// Suelta una versión propia reemplazada por otra
+    pub fn descartar_propia(&mut self, version: u64) {
+        self.listos.remove(&VariantePlaneta::Propia(version));
+        self.fallidas.remove(&VariantePlaneta::Propia(version));
+    }
+
+    /// Bloquea hasta que no quede nada pendiente (exportación sin ventana)
+    pub fn esperar(&mut self) {
+        while !self.pendientes.is_empty() {
+            match self.siguiente(true) {
+                Some((variante, resultado)) => self.recibir(variante, resultado),
+                None => break,
+            }
+        }
+    }
+
+    pub fn para(&self, tipo: u32) -> &wgpu::RenderPipeline {
+        self.listos
+            .get(&VariantePlaneta::Tipo(tipo))
+            .or_else(|| self.listos.get(&VariantePlaneta::Generica))
+            .unwrap_or(&self.provisional)
+    }
+
+    pub fn describir(&self) -> String {
+        let mut texto = format!("Pipelines de planetas: {} listos", self.listos.len());
+        if !self.pendientes.is_empty() {
+            texto.push_str(&format!(", {} compilando", self.pendientes.len()));
+        }
+        if !self.fallidas.is_empty() {
+            texto.push_str(&format!(", {} con error", self.fallidas.len()));
+        }
+        texto
+    }
+}
+
+/// Un archivo `fragmento` de la escena y la versión que se dibuja
+struct FragmentoPropio {
+    texto: Arc<str>,
+    /// Hash del texto leído por última vez
+    version: u64,
+    /// Última versión que compiló: mientras la nueva compila, o si falla, se sigue usando
+    vigente: Option<u64>,
+    modificado: Option<std::time::SystemTime>,
+    fallo_avisado: bool,
+}
+
+impl FragmentoPropio {
+    fn leer(ruta: &std::path::Path) -> Result<Self, String> {
+        use std::hash::{Hash, Hasher};
+        let texto = std::fs::read_to_string(ruta).map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+        let mut hash = std::collections::hash_map::DefaultHasher::new();
+        texto.hash(&mut hash);
+        Ok(FragmentoPropio {
+            texto: texto.into(),
+            version: hash.finish(),
+            vigente: None,
+            modificado: std::fs::metadata(ruta).and_then(|m| m.modified()).ok(),
+            fallo_avisado: false,
+        })
+    }
+}
+
+/// Fragmentos WGSL propios de los cuerpos (`fragmento` en la escena): cada uno se cose en
+/// el shader como su propia variante del pipeline de planetas y se recarga al guardarlo
+#[derive(Default)]
+pub struct ShadersPropios {
+    archivos: std::collections::HashMap<std::path::PathBuf, FragmentoPropio>,
+    /// Los que no se pudieron leer, para avisar una sola vez
+    ilegibles: std::collections::HashSet<std::path::PathBuf>,
+    ultima_revision: Option<std::time::Instant>,
+}
+
+impl ShadersPropios {
+    const INTERVALO_REVISION: std::time::Duration = std::time::Duration::from_millis(500);
+
+    /// Lee los fragmentos que usan los cuerpos, suelta los que ya nadie usa y, cada
+    /// `INTERVALO_REVISION`, relee los que cambiaron en disco
+    pub fn actualizar(&mut self, cuerpos: &[CuerpoCeleste], pipelines: &mut CompiladorPipelines) {
+        let usados: std::collections::HashSet<&std::path::Path> =
+            cuerpos.iter().filter_map(|c| c.fragmento.as_deref()).collect();
+        self.archivos.retain(|ruta, fragmento| {
+            let sigue = usados.contains(ruta.as_path());
+            if !sigue {
+                pipelines.descartar_propia(fragmento.version);
+                if let Some(vigente) = fragmento.vigente {
+                    pipelines.descartar_propia(vigente);
+                }
+            }
+            sigue
+        });
+        self.ilegibles.retain(|ruta| usados.contains(ruta.as_path()));
+        let revisar = self.ultima_revision.is_none_or(|instante| instante.elapsed() >= Self::INTERVALO_REVISION);
+        if revisar {
+            self.ultima_revision = Some(std::time::Instant::now());
+        }
+
+        for ruta in usados {
+            match self.archivos.get_mut(ruta) {
+                None if !self.ilegibles.contains(ruta) => match FragmentoPropio::leer(ruta) {
+                    Ok(fragmento) => {
+                        self.archivos.insert(ruta.to_path_buf(), fragmento);
+                    }
+                    Err(error) => {
+                        eprintln!("Shader propio: {}", error);
+                        self.ilegibles.insert(ruta.to_path_buf());
+                    }
+                },
+                Some(actual) if revisar => {
+                    let modificado = std::fs::metadata(ruta).and_then(|m| m.modified()).ok();
+                    if modificado == actual.modificado {
+                        continue;
+                    }
+                    // A medio guardar el archivo puede no leerse: se reintenta en la próxima revisión
+                    let Ok(nuevo) = FragmentoPropio::leer(ruta) else {
+                        continue;
+                    };
+                    actual.modificado = nuevo.modificado;
+                    if nuevo.version != actual.version {
+                        if actual.vigente != Some(actual.version) {
+                            pipelines.descartar_propia(actual.version);
+                        }
+                        actual.texto = nuevo.texto;
+                        actual.version = nuevo.version;
+                        actual.fallo_avisado = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (ruta, fragmento) in &mut self.archivos {
+            pipelines.preparar_propia(fragmento.version, &fragmento.texto);
+            if fragmento.vigente != Some(fragmento.version) && pipelines.propia(fragmento.version).is_some() {
+                if let Some(anterior) = fragmento.vigente {
+                    pipelines.descartar_propia(anterior);
+                    println!("Shader propio recargado: {}", ruta.display());
+                }
+                fragmento.vigente = Some(fragmento.version);
+            }
+            if pipelines.fallo_propia(fragmento.version) && !fragmento.fallo_avisado {
+                fragmento.fallo_avisado = true;
+                let respaldo = if fragmento.vigente.is_some() { "la versión anterior" } else { "el shader de su tipo" };
+                eprintln!("Shader propio {} no compila; se dibuja con {}", ruta.display(), respaldo);
+            }
+        }
+    }
+
+    /// Pipeline en uso del fragmento de `ruta`; `None` si nunca compiló
+    pub fn pipeline<'a>(&self, ruta: &std::path::Path, pipelines: &'a CompiladorPipelines) -> Option<&'a wgpu::RenderPipeline> {
+        pipelines.propia(self.archivos.get(ruta)?.vigente?)
+    }
+}
+
+/// Lo que `RecargaEscena::revisar` encontró cambiado en disco
+#[derive(Debug, Default, PartialEq)]
+pub struct CambiosEscena {
+    /// El archivo de la escena
+    pub escena: bool,
+    /// Mapas, relieves y modelos que nombra la escena
+    pub recursos: Vec<std::path::PathBuf>,
+}
+
+/// Vigila con `notify` el archivo de la escena y los que nombra (mapas de superficie,
+/// relieves y modelos). Se vigilan sus carpetas y no los archivos, porque muchos editores
+/// guardan escribiendo otro y renombrándolo; los eventos llegan por un canal desde el hilo
+/// de `notify` y cada cuadro solo se vacía
+pub struct RecargaEscena {
+    pub activa: bool,
+    /// Se crea en el primer `revisar` con la recarga activa y se suelta al desactivarla
+    vigilancia: Option<(notify::RecommendedWatcher, std::sync::mpsc::Receiver<notify::Result<notify::Event>>)>,
+    /// Rutas de la escena en la última revisión, con la forma en que las nombra `notify`
+    rutas: Vec<(std::path::PathBuf, std::path::PathBuf)>,
+    carpetas: std::collections::HashSet<std::path::PathBuf>,
+    /// Archivos con eventos y cuándo llegó el último
+    pendientes: std::collections::HashMap<std::path::PathBuf, std::time::Instant>,
+}
+
+impl RecargaEscena {
+    /// Un archivo se entrega cuando pasa este tiempo sin eventos nuevos: un guardado suele
+    /// traer varios y, antes del último, el archivo puede estar a medio escribir
+    const ESPERA: std::time::Duration = std::time::Duration::from_millis(150);
+
+    pub fn nueva(activa: bool) -> Self {
+        RecargaEscena {
+            activa,
+            vigilancia: None,
+            rutas: Vec::new(),
+            carpetas: std::collections::HashSet::new(),
+            pendientes: std::collections::HashMap::new(),
+        }
+    }
+
+    fn rutas(escena: &ArchivoEscena) -> Vec<&std::path::Path> {
+        let mapas = escena.texturas.values().flat_map(MapaSuperficie::rutas);
+        let por_cuerpo = escena
+            .cuerpo
+            .iter()
+            .flat_map(|cuerpo| cuerpo.modelo.iter().chain(cuerpo.relieve.as_ref().map(|r| &r.mapa)));
+        escena.ruta.iter().chain(mapas).chain(por_cuerpo).map(|ruta| ruta.as_path()).collect()
+    }
+
+    /// La ruta como la entregan los eventos: absoluta y, si existe, sin enlaces simbólicos
+    fn clave(ruta: &std::path::Path) -> std::path::PathBuf {
+        std::fs::canonicalize(ruta)
+            .or_else(|_| std::path::absolute(ruta))
+            .unwrap_or_else(|_| ruta.to_path_buf())
+    }
+
+    /// Los archivos de `escena` que se guardaron desde la revisión anterior
+    pub fn revisar(&mut self, escena: &ArchivoEscena) -> CambiosEscena {
+        if !self.activa {
+            self.vigilancia = None;
+            self.carpetas.clear();
+            self.pendientes.clear();
+            return CambiosEscena::default();
+        }
+        if self.vigilancia.is_none() {
+            let (emisor, receptor) = std::sync::mpsc::channel();
+            match notify::recommended_watcher(emisor) {
+                Ok(vigilante) => self.vigilancia = Some((vigilante, receptor)),
+                Err(error) => {
+                    eprintln!("Recarga en vivo desactivada: {}", error);
+                    self.activa = false;
+                    return CambiosEscena::default();
+                }
+            }
+            self.rutas.clear();
+            self.carpetas.clear();
+        }
+        self.vigilar(escena);
+        let ahora = std::time::Instant::now();
+        let eventos: Vec<_> = self.vigilancia.iter().flat_map(|(_, eventos)| eventos.try_iter()).collect();
+        for evento in eventos {
+            self.anotar(evento, ahora);
+        }
+        self.entregar(escena, ahora)
+    }
+
+    /// Si cambió la lista de archivos de `escena`, vigila las carpetas nuevas y deja las que
+    /// ya no hacen falta
+    fn vigilar(&mut self, escena: &ArchivoEscena) {
+        let rutas = Self::rutas(escena);
+        if rutas.iter().copied().eq(self.rutas.iter().map(|(ruta, _)| ruta.as_path())) {
+            return;
+        }
+        self.rutas = rutas.iter().map(|ruta| (ruta.to_path_buf(), Self::clave(ruta))).collect();
+        let carpetas: std::collections::HashSet<_> =
+            self.rutas.iter().filter_map(|(_, clave)| clave.parent().map(std::path::Path::to_path_buf)).collect();
+        if let Some((vigilante, _)) = &mut self.vigilancia {
+            for carpeta in self.carpetas.difference(&carpetas) {
+                let _ = notify::Watcher::unwatch(vigilante, carpeta);
+            }
+            for carpeta in carpetas.difference(&self.carpetas) {
+                if let Err(error) = notify::Watcher::watch(vigilante, carpeta, notify::RecursiveMode::NonRecursive) {
+                    eprintln!("No se puede vigilar {}: {}", carpeta.display(), error);
+                }
+            }
+        }
+        self.carpetas = carpetas;
+    }
+
+    /// Deja pendientes los archivos que un evento de `notify` creó o modificó en `ahora`
+    fn anotar(&mut self, evento: notify::Result<notify::Event>, ahora: std::time::Instant) {
+        match evento {
+            Ok(evento) if matches!(evento.kind, notify::EventKind::Create(_) | notify::EventKind::Modify(_)) => {
+                for ruta in evento.paths {
+                    self.pendientes.insert(ruta, ahora);
+                }
+            }
+            Ok(_) => {}
+            Err(error) => eprintln!("Error al vigilar la escena: {}", error),
+        }
+    }
+
+    /// Los pendientes de `escena` que en `ahora` llevan `ESPERA` sin eventos; los de otros
+    /// archivos de las mismas carpetas se descartan
+    fn entregar(&mut self, escena: &ArchivoEscena, ahora: std::time::Instant) -> CambiosEscena {
+        let mut cambios = CambiosEscena::default();
+        let rutas = &self.rutas;
+        self.pendientes.retain(|clave, instante| {
+            if ahora.duration_since(*instante) < Self::ESPERA {
+                return true;
+            }
+            let Some((ruta, _)) = rutas.iter().find(|(_, otra)| otra == clave) else { return false };
+            if escena.ruta.as_ref() == Some(ruta) {
+                cambios.escena = true;
+            } else if !cambios.recursos.contains(ruta) {
+                cambios.recursos.push(ruta.clone());
+            }
+            false
+        });
+        cambios
+    }
+}
+
+// =============================================================================
+// MÓDULO: GRABACIÓN
+// =============================================================================
+
+/// A dónde van los cuadros grabados
+enum DestinoGrabacion {
+    /// PNG numerados dentro de una carpeta
+    Imagenes(std::path::PathBuf),
+    /// Cuadros RGBA crudos por la entrada estándar de ffmpeg, que escribe `archivo`
+    Ffmpeg {
+        proceso: std::process::Child,
+        archivo: std::path::PathBuf,
+        tamano: (u32, u32),
+    },
+}
+
+/// Graba cada cuadro dibujado. Mientras graba, el reloj avanza un paso fijo por cuadro,
+/// así el video sale fluido aunque el render vaya más lento que en tiempo real
+pub struct Grabacion {
+    destino: DestinoGrabacion,
+    cuadros_por_segundo: u32,
+    cuadros: u32,
+}
+
+impl Grabacion {
+    const EXTENSIONES_VIDEO: [&'static str; 4] = ["mp4", "mkv", "mov", "webm"];
+
+    /// Un destino con extensión de video se graba con ffmpeg; cualquier otro es una carpeta de PNG
+    pub fn iniciar(destino: &std::path::Path, cuadros_por_segundo: u32, tamano: (u32, u32)) -> Result<Self, String> {
+        let es_video = destino
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| Self::EXTENSIONES_VIDEO.contains(&e.to_ascii_lowercase().as_str()));
+        let cuadros_por_segundo = cuadros_por_segundo.max(1);
+        let destino = if es_video {
+            if let Some(carpeta) = destino.parent().filter(|c| !c.as_os_str().is_empty()) {
+                std::fs::create_dir_all(carpeta).map_err(|e| format!("{}: {}", carpeta.display(), e))?;
+            }
+            let proceso = std::process::Command::new("ffmpeg")
+                .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+                .args(["-s", &format!("{}x{}", tamano.0, tamano.1)])
+                .args(["-r", &cuadros_por_segundo.to_string(), "-i", "-"])
+                .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+                .arg(destino)
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("no se pudo iniciar ffmpeg: {}", e))?;
+            DestinoGrabacion::Ffmpeg {
+                proceso,
+                archivo: destino.into(),
+                tamano,
+            }
+        } else {
+            std::fs::create_dir_all(destino).map_err(|e| format!("{}: {}", destino.display(), e))?;
+            DestinoGrabacion::Imagenes(destino.into())
+        };
+        Ok(Grabacion {
+            destino,
+            cuadros_por_segundo,
+            cuadros: 0,
+        })
+    }
+
+    /// Segundos reales que representa cada cuadro grabado
+    pub fn paso(&self) -> f64 {
+        1.0 / self.cuadros_por_segundo as f64
+    }
+
+    pub fn agregar(&mut self, imagen: &image::RgbaImage) -> Result<(), String> {
+        match &mut self.destino {
+            DestinoGrabacion::Imagenes(carpeta) => {
+                let ruta = carpeta.join(format!("cuadro_{:05}.png", self.cuadros));
+                imagen.save(&ruta).map_err(|e| format!("{}: {}", ruta.display(), e))?;
+            }
+            DestinoGrabacion::Ffmpeg { proceso, tamano, .. } => {
+                // ffmpeg recibió el tamaño al empezar: un cambio de ventana corta la grabación
+                if imagen.dimensions() != *tamano {
+                    return Err("la ventana cambió de tamaño durante la grabación".into());
+                }
+                let entrada = proceso.stdin.as_mut().ok_or("ffmpeg cerró su entrada")?;
+                std::io::Write::write_all(entrada, imagen.as_raw()).map_err(|e| format!("ffmpeg: {}", e))?;
+            }
+        }
+        self.cuadros += 1;
+        Ok(())
+    }
+
+    /// Cierra la grabación (espera a que ffmpeg termine el archivo) y la describe
+    pub fn terminar(self) -> Result<String, String> {
+        let (ruta, cuadros) = match self.destino {
+            DestinoGrabacion::Imagenes(carpeta) => (carpeta, self.cuadros),
+            DestinoGrabacion::Ffmpeg { mut proceso, archivo, .. } => {
+                drop(proceso.stdin.take());
+                let estado = proceso.wait().map_err(|e| format!("ffmpeg: {}", e))?;
+                if !estado.success() {
+                    return Err(format!("ffmpeg terminó con {}", estado));
+                }
+                (archivo, self.cuadros)
+            }
+        };
+        Ok(format!(
+            "{} cuadros ({:.1} s a {} cps) en {}",
+            cuadros,
+            cuadros as f64 / self.cuadros_por_segundo as f64,
+            self.cuadros_por_segundo,
+            ruta.display()
+        ))
+    }
+}
+
+// =============================================================================
+// MÓDULO: VIGILANCIA GPU
+// =============================================================================
+
+/// Paso de recuperación que se intenta cuando la GPU se bloquea varios cuadros seguidos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recuperacion {
+    /// Volver a configurar la superficie de la ventana
+    Superficie,
+    /// Crear de nuevo el dispositivo y todos sus recursos
+    Dispositivo,
+    /// Recrear el dispositivo sobre el adaptador de software
+    Software,
+}
+
+impl Recuperacion {
+    fn siguiente(self) -> Option<Self> {
+        match self {
+            Recuperacion::Superficie => Some(Recuperacion::Dispositivo),
+            Recuperacion::Dispositivo => Some(Recuperacion::Software),
+            Recuperacion::Software => None,
+        }
+    }
+}
+
+/// Estado compartido entre el hilo de render y el hilo vigilante
+#[derive(Default)]
+struct EstadoVigilancia {
+    adaptador: String,
+    /// Operación que puede bloquearse en curso y su inicio
+    operacion: Option<(&'static str, std::time::Instant)>,
+    /// Desde cuándo hay trabajo en la cola que la GPU no ha terminado
+    trabajo_pendiente: Option<std::time::Instant>,
+    /// Ya se reportó el bloqueo actual; evita repetir el aviso en cada revisión
+    avisado: bool,
+    cuadros: u64,
+    bloqueo_en_cuadro: bool,
+    bloqueos_seguidos: u32,
+    cuadros_estables: u32,
+    ultima_recuperacion: Option<Recuperacion>,
+}
+
+/// Vigila `get_current_texture` y los envíos a la cola. Un hilo aparte reporta las
+/// operaciones que pasan de `UMBRAL` aunque el hilo de render siga bloqueado; al terminar
+/// cada cuadro `revisar_cuadro` decide si hace falta recuperarse
+#[derive(Clone)]
+pub struct VigilanteGpu {
+    compartido: Arc<std::sync::Mutex<EstadoVigilancia>>,
+}
+
+impl VigilanteGpu {
+    pub const UMBRAL: std::time::Duration = std::time::Duration::from_secs(2);
+    const INTERVALO_REVISION: std::time::Duration = std::time::Duration::from_millis(250);
+    /// Cuadros bloqueados seguidos antes de pasar al siguiente paso de recuperación
+    const BLOQUEOS_PARA_RECUPERAR: u32 = 3;
+    /// Cuadros sanos seguidos tras los que se olvidan las recuperaciones anteriores
+    const CUADROS_PARA_ESTABILIZAR: u32 = 600;
+
+    /// El hilo termina solo cuando se suelta el último vigilante
+    pub fn nuevo(adaptador: &wgpu::AdapterInfo) -> Self {
+        let compartido = Arc::new(std::sync::Mutex::new(EstadoVigilancia {
+            adaptador: format!("{} ({:?}, {:?})", adaptador.name, adaptador.backend, adaptador.device_type),
+            ..Default::default()
+        }));
+        let debil = Arc::downgrade(&compartido);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Self::INTERVALO_REVISION);
+            let Some(compartido) = debil.upgrade() else {
+                break;
+            };
+            let mut estado = compartido.lock().unwrap();
+            let bloqueo = match (estado.operacion, estado.trabajo_pendiente) {
+                (Some((operacion, inicio)), _) => Some((operacion, inicio.elapsed())),
+                (None, Some(inicio)) => Some(("trabajo enviado a la GPU", inicio.elapsed())),
+                (None, None) => None,
+            };
+            if let Some((operacion, transcurrido)) = bloqueo.filter(|&(_, t)| t > Self::UMBRAL) {
+                if !estado.avisado {
+                    estado.avisado = true;
+                    eprintln!(
+                        "Vigilante GPU: '{}' lleva {:.1} s sin responder (adaptador {}, cuadro {}, {} bloqueos seguidos, última recuperación {:?})",
+                        operacion,
+                        transcurrido.as_secs_f32(),
+                        estado.adaptador,
+                        estado.cuadros,
+                        estado.bloqueos_seguidos,
+                        estado.ultima_recuperacion,
+                    );
+                }
+            }
+        });
+        VigilanteGpu { compartido }
+    }
+
+    /// Ejecuta una operación que puede bloquearse; cuenta un bloqueo si pasa de `UMBRAL`
+    pub fn vigilar<T>(&self, operacion: &'static str, f: impl FnOnce() -> T) -> T {
+        let inicio = std::time::Instant::now();
+        self.compartido.lock().unwrap().operacion = Some((operacion, inicio));
+        let resultado = f();
+        let mut estado = self.compartido.lock().unwrap();
+        estado.operacion = None;
+        estado.avisado = false;
+        if inicio.elapsed() > Self::UMBRAL {
+            estado.bloqueo_en_cuadro = true;
+        }
+        resultado
+    }
+
+    /// Cuenta un bloqueo que la propia API ya reportó (p. ej. `SurfaceError::Timeout`)
+    pub fn registrar_bloqueo(&self) {
+        self.compartido.lock().unwrap().bloqueo_en_cuadro = true;
+    }
+
+    /// Marca el trabajo enviado en este cuadro para saber si la GPU llega a terminarlo
+    pub fn trabajo_enviado(&self, cola: &wgpu::Queue) {
+        {
+            let mut estado = self.compartido.lock().unwrap();
+            estado.cuadros += 1;
+            estado.trabajo_pendiente.get_or_insert_with(std::time::Instant::now);
+        }
+        let compartido = self.compartido.clone();
+        cola.on_submitted_work_done(move || {
+            let mut estado = compartido.lock().unwrap();
+            estado.trabajo_pendiente = None;
+            estado.avisado = false;
+        });
+    }
+
+    /// Cierra el cuadro y devuelve el paso de recuperación a intentar, si corresponde
+    pub fn revisar_cuadro(&self) -> Option<Recuperacion> {
+        let mut estado = self.compartido.lock().unwrap();
+        if estado.trabajo_pendiente.is_some_and(|inicio| inicio.elapsed() > Self::UMBRAL) {
+            estado.bloqueo_en_cuadro = true;
+        }
+        if !std::mem::take(&mut estado.bloqueo_en_cuadro) {
+            estado.bloqueos_seguidos = 0;
+            estado.cuadros_estables += 1;
+            if estado.cuadros_estables >= Self::CUADROS_PARA_ESTABILIZAR {
+                estado.ultima_recuperacion = None;
+            }
+            return None;
+        }
+        estado.cuadros_estables = 0;
+        estado.bloqueos_seguidos += 1;
+        if estado.bloqueos_seguidos < Self::BLOQUEOS_PARA_RECUPERAR {
+            return None;
+        }
+        estado.bloqueos_seguidos = 0;
+        let siguiente = match estado.ultima_recuperacion {
+            None => Some(Recuperacion::Superficie),
+            Some(anterior) => anterior.siguiente(),
+        };
+        match siguiente {
+            Some(recuperacion) => {
+                estado.ultima_recuperacion = Some(recuperacion);
+                eprintln!(
+                    "Vigilante GPU: {} cuadros bloqueados en {}; se intenta recuperar: {:?}",
+                    Self::BLOQUEOS_PARA_RECUPERAR,
+                    estado.adaptador,
+                    recuperacion
+                );
+            }
+            None => eprintln!("Vigilante GPU: la GPU sigue sin responder y no quedan recuperaciones"),
+        }
+        siguiente
+    }
+
+    /// Conserva el historial de recuperaciones de un vigilante anterior (tras recrear el dispositivo)
+    pub fn heredar(&self, anterior: &VigilanteGpu) {
+        let anterior = anterior.compartido.lock().unwrap();
+        let mut estado = self.compartido.lock().unwrap();
+        estado.cuadros = anterior.cuadros;
+        estado.ultima_recuperacion = anterior.ultima_recuperacion;
+    }
+}
+
+// =============================================================================
+// MÓDULO: CACHÉ DE BIND GROUPS
+// =============================================================================
+
+/// Recurso de una entrada de bind group, identificado por el id global de wgpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RecursoEnlazado {
+    Buffer(wgpu::Id<wgpu::Buffer>, wgpu::BufferAddress, Option<wgpu::BufferSize>),
+    Vista(wgpu::Id<wgpu::TextureView>),
+    Muestreador(wgpu::Id<wgpu::Sampler>),
+}
+
+impl RecursoEnlazado {
+    /// `None` para los arreglos de recursos, que no se cachean
+    fn de(recurso: &wgpu::BindingResource) -> Option<Self> {
+        match recurso {
+            wgpu::BindingResource::Buffer(enlace) => {
+                Some(RecursoEnlazado::Buffer(enlace.buffer.global_id(), enlace.offset, enlace.size))
+            }
+            wgpu::BindingResource::TextureView(vista) => Some(RecursoEnlazado::Vista(vista.global_id())),
+            wgpu::BindingResource::Sampler(muestreador) => Some(RecursoEnlazado::Muestreador(muestreador.global_id())),
+            _ => None,
+        }
+    }
+}
+
+type ClaveBindGroup = (wgpu::Id<wgpu::BindGroupLayout>, Vec<(u32, RecursoEnlazado)>);
+
+/// Bind groups reutilizados entre cuadros mientras su layout y sus recursos sean los mismos.
+/// Un grupo retiene sus recursos, así que los que dejan de pedirse se sueltan a los
+/// `CUADROS_SIN_USO` cuadros
+pub struct CacheBindGroups {
+    /// Grupo y último cuadro en que se pidió
+    grupos: std::collections::HashMap<ClaveBindGroup, (Arc<wgpu::BindGroup>, u64)>,
+    cuadro: u64,
+    aciertos: u32,
+    fallos: u32,
+    /// Aciertos y fallos del último cuadro terminado
+    ultimo_cuadro: (u32, u32),
+}
+
+impl CacheBindGroups {
+    const CUADROS_SIN_USO: u64 = 120;
+
+    pub fn nueva() -> Self {
+        CacheBindGroups {
+            grupos: std::collections::HashMap::new(),
+            cuadro: 0,
+            aciertos: 0,
+            fallos: 0,
+            ultimo_cuadro: (0, 0),
+        }
+    }
+
+    pub fn obtener(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        etiqueta: &str,
+        layout: &wgpu::BindGroupLayout,
+        entradas: &[wgpu::BindGroupEntry],
+    ) -> Arc<wgpu::BindGroup> {
+        let crear = || {
+            Arc::new(dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(etiqueta),
+                layout,
+                entries: entradas,
+            }))
+        };
+        let recursos: Option<Vec<_>> = entradas
+            .iter()
+            .map(|entrada| RecursoEnlazado::de(&entrada.resource).map(|recurso| (entrada.binding, recurso)))
+            .collect();
+        let Some(recursos) = recursos else {
+            self.fallos += 1;
+            return crear();
+        };
+        let cuadro = self.cuadro;
+        match self.grupos.entry((layout.global_id(), recursos)) {
+            std::collections::hash_map::Entry::Occupied(mut ocupada) => {
+                self.aciertos += 1;
+                ocupada.get_mut().1 = cuadro;
+                ocupada.get().0.clone()
+            }
+            std::collections::hash_map::Entry::Vacant(vacia) => {
+                self.fallos += 1;
+                vacia.insert((crear(), cuadro)).0.clone()
+            }
+        }
+    }
+
+    /// Cierra los contadores del cuadro y suelta los grupos que nadie pide hace tiempo
+    pub fn terminar_cuadro(&mut self) {
+        self.ultimo_cuadro = (self.aciertos, self.fallos);
+        self.aciertos = 0;
+        self.fallos = 0;
+        let cuadro = self.cuadro;
+        self.grupos.retain(|_, (_, ultimo_uso)| cuadro - *ultimo_uso < Self::CUADROS_SIN_USO);
+        self.cuadro += 1;
+    }
+
+    pub fn describir(&self) -> String {
+        let (aciertos, fallos) = self.ultimo_cuadro;
+        format!(
+            "Bind groups en caché: {} · último cuadro: {} aciertos, {} fallos",
+            self.grupos.len(),
+            aciertos,
+            fallos
+        )
+    }
+}
+
+// =============================================================================
+// MÓDULO: ANILLO DE SUBIDAS
+// =============================================================================
+
+/// Segmento de staging del anillo; queda mapeado desde que la GPU lo suelta hasta el
+/// envío del cuadro que lo usa
+struct SegmentoSubida {
+    buffer: wgpu::Buffer,
+    /// Lo escribe el callback de `map_async`, que hace de fence: la GPU terminó las copias
+    estado: Arc<std::sync::atomic::AtomicU8>,
+}
+
+/// Subidas de los datos que cambian cada cuadro (uniformes, vértices de líneas, cintas, texto
+/// y parámetros de los compute). La CPU solo escribe en un segmento de staging que la GPU ya
+/// soltó, y cada subida es una copia dentro del codificador del cuadro, en orden con los pases
+/// que leen el destino: un buffer puede tener un contenido para el telescopio y otro para la
+/// vista principal sin que ninguna escritura pise lo que la GPU todavía lee
+pub struct AnilloSubidas {
+    segmentos: Vec<SegmentoSubida>,
+    actual: usize,
+    cursor: u64,
+    /// Cuadros que esperaron a la GPU para reusar un segmento
+    esperas: u64,
+    /// Subidas que no cupieron en el segmento y usaron un buffer aparte
+    desbordes: u64,
+}
+
+impl AnilloSubidas {
+    /// Segmentos en vuelo: el de la CPU y hasta dos cuadros que la GPU todavía procesa
+    const SEGMENTOS: usize = 3;
+    const TAMANO_SEGMENTO: u64 = 2 << 20;
+    const PENDIENTE: u8 = 0;
+    const MAPEADO: u8 = 1;
+    const FALLIDO: u8 = 2;
+
+    fn nuevo(dispositivo: &wgpu::Device) -> Self {
+        AnilloSubidas {
+            segmentos: (0..Self::SEGMENTOS).map(|_| Self::segmento(dispositivo)).collect(),
+            actual: Self::SEGMENTOS - 1,
+            cursor: 0,
+            esperas: 0,
+            desbordes: 0,
+        }
+    }
+
+    fn segmento(dispositivo: &wgpu::Device) -> SegmentoSubida {
+        SegmentoSubida {
+            buffer: dispositivo.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Segmento del Anillo de Subidas"),
+                size: Self::TAMANO_SEGMENTO,
+                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            }),
+            estado: Arc::new(std::sync::atomic::AtomicU8::new(Self::MAPEADO)),
+        }
+    }
+
+    /// Pasa al segmento siguiente, esperando a la GPU si todavía lee de él
+    fn empezar(&mut self, dispositivo: &wgpu::Device) {
+        use std::sync::atomic::Ordering;
+        self.actual = (self.actual + 1) % Self::SEGMENTOS;
+        self.cursor = 0;
+        let estado = &self.segmentos[self.actual].estado;
+        if estado.load(Ordering::Acquire) == Self::PENDIENTE {
+            self.esperas += 1;
+            while estado.load(Ordering::Acquire) == Self::PENDIENTE {
+                dispositivo.poll(wgpu::Maintain::Wait);
+            }
+        }
+        if estado.load(Ordering::Acquire) == Self::FALLIDO {
+            self.segmentos[self.actual] = Self::segmento(dispositivo);
+        }
+    }
+
+    /// Copia `datos` a `destino` (desde `desplazamiento`) como parte de `codificador`
+    fn subir(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        destino: &wgpu::Buffer,
+        desplazamiento: u64,
+        datos: &[u8],
+    ) {
+        if datos.is_empty() {
+            return;
+        }
+        let tamano = datos.len() as u64;
+        debug_assert!(tamano.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT), "las copias van en múltiplos de 4 bytes");
+        let segmento = &self.segmentos[self.actual];
+        if self.cursor + tamano <= Self::TAMANO_SEGMENTO {
+            segmento
+                .buffer
+                .slice(self.cursor..self.cursor + tamano)
+                .get_mapped_range_mut()
+                .copy_from_slice(datos);
+            codificador.copy_buffer_to_buffer(&segmento.buffer, self.cursor, destino, desplazamiento, tamano);
+            self.cursor += tamano;
+        } else {
+            self.desbordes += 1;
+            let aparte = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Subida Desbordada"),
+                contents: datos,
+                usage: wgpu::BufferUsages::COPY_SRC,
+            });
+            codificador.copy_buffer_to_buffer(&aparte, 0, destino, desplazamiento, tamano);
+        }
+    }
+
+    /// Suelta el segmento, envía el cuadro y pide volver a mapearlo cuando la GPU termine
+    fn enviar(&mut self, cola: &wgpu::Queue, codificador: wgpu::CommandEncoder) {
+        use std::sync::atomic::Ordering;
+        let segmento = &self.segmentos[self.actual];
+        segmento.buffer.unmap();
+        segmento.estado.store(Self::PENDIENTE, Ordering::Release);
+        cola.submit(std::iter::once(codificador.finish()));
+        let estado = segmento.estado.clone();
+        segmento.buffer.slice(..).map_async(wgpu::MapMode::Write, move |resultado| {
+            let nuevo = if resultado.is_ok() { Self::MAPEADO } else { Self::FALLIDO };
+            estado.store(nuevo, Ordering::Release);
+        });
+    }
+
+    pub fn describir(&self) -> String {
+        format!(
+            "Subidas: {} × {} MB (último cuadro {} KB), {} esperas, {} desbordes",
+            Self::SEGMENTOS,
+            Self::TAMANO_SEGMENTO >> 20,
+            self.cursor.div_ceil(1024),
+            self.esperas,
+            self.desbordes
+        )
+    }
+}
+
+// =============================================================================
+// MÓDULO: DIÁLOGOS
+// =============================================================================
+
+/// Diálogos nativos de abrir y guardar. Bloquean el bucle de eventos mientras están
+/// abiertos; devuelven `None` si se cancelan o si el sistema no ofrece diálogos
+mod dialogos {
+    use std::path::{Path, PathBuf};
+
+    /// Diálogo que arranca en la carpeta de `ruta` con su nombre de archivo propuesto
+    fn en(ruta: &Path) -> rfd::FileDialog {
+        let mut dialogo = rfd::FileDialog::new();
+        if let Some(carpeta) = ruta.parent().filter(|c| c.is_dir()) {
+            dialogo = dialogo.set_directory(carpeta);
+        }
+        if let Some(nombre) = ruta.file_name() {
+            dialogo = dialogo.set_file_name(nombre.to_string_lossy());
+        }
+        dialogo
+    }
+
+    pub fn abrir_escena(actual: &Path) -> Option<PathBuf> {
+        en(actual)
+            .set_title("Abrir escena")
+            .add_filter("Escena", &["toml"])
+            .pick_file()
+    }
+
+    pub fn destino_captura(sugerida: &Path) -> Option<PathBuf> {
+        en(sugerida)
+            .set_title("Guardar captura")
+            .add_filter("Imagen PNG", &["png"])
+            .save_file()
+    }
+
+    /// Con ffmpeg se graba un video; si no, una carpeta de PNG numerados
+    pub fn destino_grabacion(sugerido: &Path, ffmpeg: bool) -> Option<PathBuf> {
+        if ffmpeg {
+            en(sugerido)
+                .set_title("Grabar video")
+                .add_filter("Video MP4", &["mp4"])
+                .save_file()
+        } else {
+            let mut dialogo = rfd::FileDialog::new().set_title("Carpeta de la grabación");
+            if let Some(carpeta) = sugerido.parent().filter(|c| c.is_dir()) {
+                dialogo = dialogo.set_directory(carpeta);
+            }
+            dialogo.pick_folder()
+        }
+    }
+}
+
+// =============================================================================
+// MÓDULO: PORTAPAPELES
+// =============================================================================
+
+/// Portapapeles del sistema. En Linux el texto copiado solo se ofrece mientras vive el
+/// `arboard::Clipboard`, así que se abre la primera vez que se usa y se conserva
+#[derive(Default)]
+pub struct Portapapeles {
+    sistema: Option<arboard::Clipboard>,
+}
+
+impl Portapapeles {
+    fn abrir(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        let sistema = match self.sistema.take() {
+            Some(sistema) => sistema,
+            None => arboard::Clipboard::new().map_err(|e| format!("no hay portapapeles: {}", e))?,
+        };
+        Ok(self.sistema.insert(sistema))
+    }
+
+    pub fn copiar(&mut self, texto: &str) -> Result<(), String> {
+        self.abrir()?
+            .set_text(texto)
+            .map_err(|e| format!("no se pudo copiar: {}", e))
+    }
+
+    pub fn pegar(&mut self) -> Result<String, String> {
+        self.abrir()?
+            .get_text()
+            .map_err(|e| format!("el portapapeles no tiene texto: {}", e))
+    }
+}
+
+// =============================================================================
+// MÓDULO: ENTRADA
+// =============================================================================
+
+/// Entrada de teclado y mouse que llega a la escena (la que egui no consumió). Es la única
+/// vía por la que cambian el estado, así una sesión grabada se repite igual con `--replay`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "tipo", rename_all = "lowercase")]
+pub enum EventoEntrada {
+    Tecla { tecla: KeyCode },
+    Modificadores { control: bool, mayus: bool, alt: bool },
+    Cursor { x: f64, y: f64 },
+    Click { presionado: bool },
+    /// Líneas de la rueda; los píxeles de un touchpad ya vienen convertidos
+    Rueda { pasos: f32 },
+    Soltar { ruta: std::path::PathBuf },
+    /// Fin de un cuadro: segundos reales que avanzó la simulación
+    Cuadro { paso: f64 },
+    Cerrar,
+}
+
+impl EventoEntrada {
+    pub fn desde_ventana(evento: &WindowEvent) -> Option<Self> {
+        Some(match evento {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        ..
+                    },
+                ..
+            } => EventoEntrada::Cerrar,
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(tecla),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => EventoEntrada::Tecla { tecla: *tecla },
+            WindowEvent::ModifiersChanged(modificadores) => {
+                let estado = modificadores.state();
+                EventoEntrada::Modificadores {
+                    control: estado.control_key(),
+                    mayus: estado.shift_key(),
+                    alt: estado.alt_key(),
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => EventoEntrada::Cursor { x: position.x, y: position.y },
+            WindowEvent::MouseInput { state, button: winit::event::MouseButton::Left, .. } => {
+                EventoEntrada::Click { presionado: *state == ElementState::Pressed }
+            }
+            WindowEvent::MouseWheel { delta, .. } => EventoEntrada::Rueda {
+                pasos: match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => *y,
+                    winit::event::MouseScrollDelta::PixelDelta(posicion) => posicion.y as f32 / 50.0,
+                },
+            },
+            WindowEvent::DroppedFile(ruta) => EventoEntrada::Soltar { ruta: ruta.clone() },
+            _ => return None,
+        })
+    }
+}
+
+/// Evento tal como se guarda: segundos desde que empezó la grabación y el evento
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EventoGrabado {
+    t: f64,
+    #[serde(flatten)]
+    entrada: EventoEntrada,
+}
+
+/// Cabecera de una sesión grabada; los eventos van después como `[[evento]]`
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SesionEntrada {
+    /// Enlace de la vista al empezar (ver `EnlaceVista`)
+    vista: String,
+    /// Tamaño de la ventana: las posiciones del cursor solo valen con el mismo
+    tamano: [u32; 2],
+    #[serde(default, skip_serializing)]
+    evento: Vec<EventoGrabado>,
+}
+
+/// Graba la entrada en un TOML que crece evento a evento, así sobrevive a un cierre abrupto
+pub struct GrabadorEntrada {
+    archivo: std::fs::File,
+    inicio: std::time::Instant,
+}
+
+impl GrabadorEntrada {
+    pub fn crear(ruta: &std::path::Path, vista: &EnlaceVista, tamano: [u32; 2]) -> Result<Self, String> {
+        let cabecera = SesionEntrada {
+            vista: vista.to_string(),
+            tamano,
+            evento: Vec::new(),
+        };
+        let texto = toml::to_string(&cabecera).map_err(|e| e.to_string())?;
+        let mut archivo = std::fs::File::create(ruta)
+            .map_err(|e| format!("no se pudo crear {}: {}", ruta.display(), e))?;
+        std::io::Write::write_all(&mut archivo, texto.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(GrabadorEntrada {
+            archivo,
+            inicio: std::time::Instant::now(),
+        })
+    }
+
+    pub fn anotar(&mut self, entrada: &EventoEntrada) -> Result<(), String> {
+        let grabado = EventoGrabado {
+            t: self.inicio.elapsed().as_secs_f64(),
+            entrada: entrada.clone(),
+        };
+        let texto = toml::to_string(&grabado).map_err(|e| e.to_string())?;
+        std::io::Write::write_all(&mut self.archivo, format!("\n[[evento]]\n{}", texto).as_bytes())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Sesión grabada que se reproduce cuadro a cuadro con el paso de tiempo original, de modo
+/// que la simulación pasa por los mismos estados
+pub struct RepeticionEntrada {
+    pub vista: String,
+    pub tamano: [u32; 2],
+    eventos: std::collections::VecDeque<EventoEntrada>,
+}
+
+impl RepeticionEntrada {
+    pub fn cargar(ruta: &std::path::Path) -> Result<Self, String> {
+        let texto = std::fs::read_to_string(ruta)
+            .map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+        let sesion: SesionEntrada = toml::from_str(&texto).map_err(|e| format!("{}: {}", ruta.display(), e))?;
+        Ok(RepeticionEntrada {
+            vista: sesion.vista,
+            tamano: sesion.tamano,
+            eventos: sesion.evento.into_iter().map(|e| e.entrada).collect(),
+        })
+    }
+
+    /// Eventos hasta el próximo fin de cuadro, y el paso de ese cuadro; `None` al terminar
+    pub fn siguiente_cuadro(&mut self) -> Option<(Vec<EventoEntrada>, Option<f64>)> {
+        if self.eventos.is_empty() {
+            return None;
+        }
+        let mut eventos = Vec::new();
+        while let Some(evento) = self.eventos.pop_front() {
+            match evento {
+                EventoEntrada::Cuadro { paso } => return Some((eventos, Some(paso))),
+                otro => eventos.push(otro),
+            }
+        }
+        Some((eventos, None))
+    }
+}
+
+// =============================================================================
+// MÓDULO: INTERFAZ
+// =============================================================================
+
+/// Superposición de egui: contexto, entrada de winit y renderizador wgpu
+pub struct InterfazEgui {
+    contexto: egui::Context,
+    estado_winit: egui_winit::State,
+    renderizador: egui_wgpu::Renderer,
+    pub visible: bool,
+    /// Paso cuantizado del cuadro: egui anima sus transiciones con él
+    pub paso_cuadro: f32,
+    /// Reloj de la interfaz, suma de los pasos entregados
+    tiempo: f64,
+}
+
+impl InterfazEgui {
+    fn nueva(ventana: &Window, dispositivo: &wgpu::Device, formato: wgpu::TextureFormat) -> Self {
+        let contexto = egui::Context::default();
+        let estado_winit = egui_winit::State::new(
+            contexto.clone(),
+            egui::ViewportId::ROOT,
+            ventana,
+            Some(ventana.scale_factor() as f32),
+            Some(dispositivo.limits().max_texture_dimension_2d as usize),
+        );
+        InterfazEgui {
+            contexto,
+            estado_winit,
+            renderizador: egui_wgpu::Renderer::new(dispositivo, formato, None, 1),
+            visible: true,
+            paso_cuadro: FrecuenciaPantalla::del_monitor(ventana).map_or(1.0 / 60.0, |hz| (1.0 / hz) as f32),
+            tiempo: 0.0,
+        }
+    }
+
+    /// Toma la visibilidad y el paso de la de un dispositivo anterior
+    fn heredar(&mut self, vieja: &InterfazEgui) {
+        self.visible = vieja.visible;
+        self.paso_cuadro = vieja.paso_cuadro;
+    }
+
+    /// Entrega el evento a egui; devuelve `true` si egui lo consumió
+    fn procesar_evento(&mut self, ventana: &Window, evento: &WindowEvent) -> bool {
+        let respuesta = self.estado_winit.on_window_event(ventana, evento);
+        self.visible && respuesta.consumed
+    }
+
+    /// Ejecuta un cuadro de la interfaz; `construir` arma los paneles
+    fn ejecutar(&mut self, ventana: &Window, construir: impl FnOnce(&egui::Context)) -> egui::FullOutput {
+        let mut entrada = self.estado_winit.take_egui_input(ventana);
+        // Con el tiempo de pared egui usaría el paso medido, con su ruido, en cada animación
+        self.tiempo += self.paso_cuadro as f64;
+        entrada.time = Some(self.tiempo);
+        entrada.predicted_dt = self.paso_cuadro;
+        self.contexto.run(entrada, construir)
+    }
+
+    /// Dibuja sobre `vista` la salida de `ejecutar`
+    fn dibujar(
+        &mut self,
+        ventana: &Window,
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        vista: &wgpu::TextureView,
+        salida: egui::FullOutput,
+    ) {
+        self.estado_winit.handle_platform_output(ventana, salida.platform_output);
+
+        let primitivas = self.contexto.tessellate(salida.shapes, salida.pixels_per_point);
+        let tamano = ventana.inner_size();
+        let pantalla = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [tamano.width, tamano.height],
+            pixels_per_point: salida.pixels_per_point,
+        };
+
+        for (id, delta) in &salida.textures_delta.set {
+            self.renderizador.update_texture(dispositivo, cola, *id, delta);
+        }
+        let mut codificador = dispositivo.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Codificador de la Interfaz"),
+        });
+        let comandos = self
+            .renderizador
+            .update_buffers(dispositivo, cola, &mut codificador, &primitivas, &pantalla);
+        {
+            let mut pase = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pase de la Interfaz"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: vista,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            self.renderizador.render(&mut pase, &primitivas, &pantalla);
+        }
+        cola.submit(comandos.into_iter().chain(std::iter::once(codificador.finish())));
+        for id in &salida.textures_delta.free {
+            self.renderizador.free_texture(id);
+        }
+    }
+}
+
+// =============================================================================
+// APLICACIÓN PRINCIPAL CON WGPU
+// =============================================================================
+
+/// Estructura de uniformes compartida con el GPU
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DatosUniformes {
+    tiempo_actual: f32,
+    tipo_render: u32,
+    dimension_pantalla: [f32; 2],
+    tinte: [f32; 3],
+    actividad: f32,
+    matriz_modelo: [[f32; 4]; 4],
+    matriz_vista: [[f32; 4]; 4],
+    matriz_proyeccion: [[f32; 4]; 4],
+    albedo: [f32; 3],
+    metalico: f32,
+    emisivo: [f32; 3],
+    rugosidad: f32,
+    /// Posición de la cámara y luz ambiente
+    ojo: [f32; 3],
+    ambiente: f32,
+    /// Estrella que ilumina la escena como luz puntual
+    luz: [f32; 3],
+    intensidad_luz: f32,
+    rayleigh: [f32; 3],
+    mie: f32,
+    espesor_atmosfera: f32,
+    anisotropia_mie: f32,
+    intensidad_atmosfera: f32,
+    radio_cuerpo: f32,
+    relieve: f32,
+    /// Bordes del anillo en radios del cuerpo (0 = sin anillo)
+    anillo_interno: f32,
+    anillo_externo: f32,
+    _relleno: [f32; 1],
+}
+
+/// Campos de `DatosUniformes` propios de un cuerpo, con el layout del `DatosCuerpo` del
+/// shader. Con constantes de empuje viajan así en cada dibujo, sin franja de uniformes
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DatosCuerpo {
+    matriz_modelo: [[f32; 4]; 4],
+    tinte: [f32; 3],
+    actividad: f32,
+    albedo: [f32; 3],
+    metalico: f32,
+    emisivo: [f32; 3],
+    rugosidad: f32,
+    rayleigh: [f32; 3],
+    mie: f32,
+    tipo_render: u32,
+    espesor_atmosfera: f32,
+    anisotropia_mie: f32,
+    intensidad_atmosfera: f32,
+    radio_cuerpo: f32,
+    relieve: f32,
+    anillo_interno: f32,
+    anillo_externo: f32,
+}
+
+impl DatosCuerpo {
+    const INICIO: &'static str = "// INICIO DE DATOS DEL CUERPO";
+    const FIN: &'static str = "// FIN DE DATOS DEL CUERPO";
+
+    fn de(uniformes: &DatosUniformes) -> Self {
+        DatosCuerpo {
+            matriz_modelo: uniformes.matriz_modelo,
+            tinte: uniformes.tinte,
+            actividad: uniformes.actividad,
+            albedo: uniformes.albedo,
+            metalico: uniformes.metalico,
+            emisivo: uniformes.emisivo,
+            rugosidad: uniformes.rugosidad,
+            rayleigh: uniformes.rayleigh,
+            mie: uniformes.mie,
+            tipo_render: uniformes.tipo_render,
+            espesor_atmosfera: uniformes.espesor_atmosfera,
+            anisotropia_mie: uniformes.anisotropia_mie,
+            intensidad_atmosfera: uniformes.intensidad_atmosfera,
+            radio_cuerpo: uniformes.radio_cuerpo,
+            relieve: uniformes.relieve,
+            anillo_interno: uniformes.anillo_interno,
+            anillo_externo: uniformes.anillo_externo,
+        }
+    }
+
+    /// El shader con `cuerpo()` leyendo las constantes de empuje en vez de la franja del cuerpo
+    fn fuente_con_empuje(fuente: &str) -> Result<String, String> {
+        let (Some(inicio), Some(fin)) = (fuente.find(Self::INICIO), fuente.find(Self::FIN)) else {
+            return Err(format!("el shader no marca `{}` y `{}`", Self::INICIO, Self::FIN));
+        };
+        let empuje = "var<push_constant> por_dibujo: DatosCuerpo;\n\nfn cuerpo() -> DatosCuerpo {\n    return por_dibujo;\n}";
+        Ok(format!("{}{}\n{}", &fuente[..inicio], empuje, &fuente[fin..]))
+    }
+
+    /// Los datos para el pipeline recién puesto: cambiar de layout borra las constantes
+    fn empujar(datos: Option<&Self>, pase: &mut wgpu::RenderPass) {
+        if let Some(datos) = datos {
+            pase.set_push_constants(wgpu::ShaderStages::VERTEX_FRAGMENT, 0, bytemuck::bytes_of(datos));
+        }
+    }
+}
+
+/// Estructura de vértice con posición y normal
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VerticeEsfera {
+    posicion: [f32; 3],
+    normal: [f32; 3],
+    /// Coordenadas equirectangulares: u = longitud, v = colatitud (0 en el polo norte)
+    uv: [f32; 2],
+}
+
+impl VerticeEsfera {
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<VerticeEsfera>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+/// Mezcla aditiva para elementos luminosos (estrellas, brillos)
+const MEZCLA_ADITIVA: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+};
+
+/// Capacidad del buffer de líneas de superposición (en vértices)
+const MAX_VERTICES_LINEA: usize = 4096;
+/// Seis vértices por tramo de estela
+const MAX_VERTICES_CINTA: usize = 6 * 4096;
+
+
+/// Vértice de línea en coordenadas de pantalla normalizadas con color
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VerticeLinea {
+    posicion: [f32; 2],
+    color: [f32; 3],
+}
+
+impl VerticeLinea {
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<VerticeLinea>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+/// Agrega un círculo (como segmentos de LineList) en coordenadas de pantalla
+/// Círculo en pantalla; `radio` va por eje porque las coordenadas normalizadas no son cuadradas
+fn agregar_circulo(lineas: &mut Vec<VerticeLinea>, centro: [f32; 2], radio: [f32; 2], color: [f32; 3]) {
+    const SEGMENTOS: usize = 48;
+    for i in 0..SEGMENTOS {
+        for paso in [i, i + 1] {
+            let angulo = paso as f32 * 2.0 * std::f32::consts::PI / SEGMENTOS as f32;
+            lineas.push(VerticeLinea {
+                posicion: [centro[0] + radio[0] * angulo.cos(), centro[1] + radio[1] * angulo.sin()],
+                color,
+            });
+        }
+    }
+}
+
+/// Galaxia de fondo: billboard orientado con forma espiral o elíptica
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanciaGalaxia {
+    posicion: [f32; 2],
+    tamano: f32,
+    rotacion: f32,
+    /// Factor de achatamiento por inclinación respecto a la línea de visión
+    aplanamiento: f32,
+    brillo: f32,
+    semilla: f32,
+    /// 0 = espiral, 1 = elíptica
+    tipo: u32,
+}
+
+impl InstanciaGalaxia {
+    const ATRIBUTOS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32,
+        2 => Float32,
+        3 => Float32,
+        4 => Float32,
+        5 => Float32,
+        6 => Uint32,
+    ];
+
+    fn descriptor_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanciaGalaxia>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATRIBUTOS,
+        }
+    }
+}
+
+fn generar_galaxias(ajustes: &AjustesGalaxias) -> Vec<InstanciaGalaxia> {
+    let mut generador = GeneradorAleatorio::nuevo(ajustes.semilla);
+    (0..ajustes.cantidad)
+        .map(|_| InstanciaGalaxia {
+            posicion: [generador.flotante() * 2.4 - 1.2, generador.flotante() * 2.4 - 1.2],
+            tamano: 0.03 + generador.flotante() * 0.06,
+            rotacion: generador.flotante() * std::f32::consts::TAU,
+            aplanamiento: 0.25 + generador.flotante() * 0.75,
+            brillo: ajustes.brillo * (0.4 + generador.flotante() * 0.6),
+            semilla: generador.flotante() * 100.0,
+            tipo: u32::from(generador.flotante() < 0.35),
+        })
+        .collect()
+}
+
+/// `VerticeEsfera` en la mitad de bytes: posición en media precisión (w = 1), normal
+/// octaédrica en dos Snorm16 y uv en dos Unorm16
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VerticeCompacto {
+    posicion: [u16; 4],
+    normal: [i16; 2],
+    uv: [u16; 2],
+}
+
+impl VerticeCompacto {
+    const ATRIBUTOS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        0 => Float16x4,
+        1 => Snorm16x2,
+        2 => Unorm16x2,
+    ];
+
+    fn desde(vertice: &VerticeEsfera) -> Self {
+        let mitad = |x: f32| half::f16::from_f32(x).to_bits();
+        let [x, y, z] = vertice.posicion;
+        let unorm = |x: f32| (x.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+        VerticeCompacto {
+            posicion: [mitad(x), mitad(y), mitad(z), mitad(1.0)],
+            normal: normal_octaedrica(vertice.normal),
+            uv: [unorm(vertice.uv[0]), unorm(vertice.uv[1])],
+        }
+    }
+}
+
+/// Proyecta la normal sobre el octaedro |x| + |y| + |z| = 1 y dobla la mitad z < 0 sobre las
+/// esquinas, para guardarla en dos componentes
+fn normal_octaedrica([x, y, z]: [f32; 3]) -> [i16; 2] {
+    let suma = (x.abs() + y.abs() + z.abs()).max(f32::EPSILON);
+    let (mut u, mut v) = (x / suma, y / suma);
+    if z < 0.0 {
+        (u, v) = ((1.0 - v.abs()) * u.signum(), (1.0 - u.abs()) * v.signum());
+    }
+    let snorm = |x: f32| (x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+    [snorm(u), snorm(v)]
+}
+
+/// Cómo llegan los vértices de las esferas al pipeline de planetas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatoVertices {
+    Completos,
+    Compactos,
+}
+
+impl FormatoVertices {
+    fn descriptor_layout(self) -> wgpu::VertexBufferLayout<'static> {
+        match self {
+            FormatoVertices::Completos => VerticeEsfera::descriptor_layout(),
+            FormatoVertices::Compactos => wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<VerticeCompacto>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &VerticeCompacto::ATRIBUTOS,
+            },
+        }
+    }
+
+    fn punto_entrada(self) -> &'static str {
+        match self {
+            FormatoVertices::Completos => "vertex_principal",
+            FormatoVertices::Compactos => "vertex_compacto",
+        }
+    }
+
+    /// Vértice que agranda la esfera hasta la cáscara de la atmósfera
+    fn punto_entrada_atmosfera(self) -> &'static str {
+        match self {
+            FormatoVertices::Completos => "vertex_atmosfera",
+            FormatoVertices::Compactos => "vertex_atmosfera_compacto",
+        }
+    }
+
+    fn tamano(self) -> usize {
+        match self {
+            FormatoVertices::Completos => std::mem::size_of::<VerticeEsfera>(),
+            FormatoVertices::Compactos => std::mem::size_of::<VerticeCompacto>(),
+        }
+    }
+
+    /// Contenido del buffer de vértices en este formato
+    fn codificar(self, vertices: &[VerticeEsfera]) -> Vec<u8> {
+        match self {
+            FormatoVertices::Completos => bytemuck::cast_slice(vertices).to_vec(),
+            FormatoVertices::Compactos => {
+                let compactos: Vec<VerticeCompacto> = vertices.iter().map(VerticeCompacto::desde).collect();
+                bytemuck::cast_slice(&compactos).to_vec()
+            }
+        }
+    }
+}
+
+/// Índices de una malla: de 16 bits mientras los vértices quepan, si no de 32
+enum IndicesMalla {
+    Cortos(Vec<u16>),
+    Largos(Vec<u32>),
+}
+
+impl IndicesMalla {
+    fn desde(indices: Vec<u32>, cantidad_vertices: usize) -> Self {
+        if cantidad_vertices <= u16::MAX as usize + 1 {
+            IndicesMalla::Cortos(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            IndicesMalla::Largos(indices)
+        }
+    }
+
+    fn formato(&self) -> wgpu::IndexFormat {
+        match self {
+            IndicesMalla::Cortos(_) => wgpu::IndexFormat::Uint16,
+            IndicesMalla::Largos(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            IndicesMalla::Cortos(indices) => indices.len(),
+            IndicesMalla::Largos(indices) => indices.len(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            IndicesMalla::Cortos(indices) => Box::new(indices.iter().map(|&i| i as u32)),
+            IndicesMalla::Largos(indices) => Box::new(indices.iter().copied()),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            IndicesMalla::Cortos(indices) => bytemuck::cast_slice(indices),
+            IndicesMalla::Largos(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+/// Las subdivisiones pedidas, o las más que caben: los vértices deben poder indexarse con
+/// `u32` y cada buffer no pasar de `limite_buffer` bytes
+fn subdivisiones_esfera(pedidas: u32, bytes_vertice: usize, limite_buffer: u64) -> u32 {
+    let caben = |s: u32| {
+        let vertices = (s as u64 + 1).pow(2);
+        let indices = 6 * (s as u64).pow(2);
+        vertices <= u32::MAX as u64 && vertices * bytes_vertice as u64 <= limite_buffer && indices * 4 <= limite_buffer
+    };
+    let minimas = 3;
+    match (minimas..=pedidas.max(minimas)).rev().find(|&s| caben(s)) {
+        Some(s) if s == pedidas => s,
+        Some(s) => {
+            eprintln!("Esfera de {} subdivisiones no válida; se usan {}", pedidas, s);
+            s
+        }
+        None => minimas,
+    }
+}
+
+fn generar_esfera(subdivisiones: u32) -> (Vec<VerticeEsfera>, IndicesMalla) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for latitud in 0..=subdivisiones {
+        let theta = latitud as f32 * std::f32::consts::PI / subdivisiones as f32;
+        let seno_theta = theta.sin();
+        let coseno_theta = theta.cos();
+
+        for longitud in 0..=subdivisiones {
+            let phi = longitud as f32 * 2.0 * std::f32::consts::PI / subdivisiones as f32;
+            let seno_phi = phi.sin();
+            let coseno_phi = phi.cos();
+
+            let coord_x = seno_theta * coseno_phi;
+            let coord_y = coseno_theta;
+            let coord_z = seno_theta * seno_phi;
+
+            // La longitud crece hacia el este visto desde afuera, de ahí el 1 - u
+            vertices.push(VerticeEsfera {
+                posicion: [coord_x, coord_y, coord_z],
+                normal: [coord_x, coord_y, coord_z],
+                uv: [
+                    1.0 - longitud as f32 / subdivisiones as f32,
+                    latitud as f32 / subdivisiones as f32,
+                ],
+            });
+        }
+    }
+
+    for lat in 0..subdivisiones {
+        for lon in 0..subdivisiones {
+            let primero = lat * (subdivisiones + 1) + lon;
+            let segundo = primero + subdivisiones + 1;
+
+            indices.push(primero);
+            indices.push(segundo);
+            indices.push(primero + 1);
+
+            indices.push(segundo);
+            indices.push(segundo + 1);
+            indices.push(primero + 1);
+        }
+    }
+
+    let indices = IndicesMalla::desde(indices, vertices.len());
+    (vertices, indices)
+}
+
+/// Corona plana en el plano XZ del modelo (el ecuador de la esfera), `segmentos` tramos angulares.
+/// `uv` = (fracción de vuelta, radio), para que el shader sepa a qué distancia está cada fragmento.
+fn generar_anillo(radio_interno: f32, radio_externo: f32, segmentos: u32) -> (Vec<VerticeEsfera>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for paso in 0..=segmentos {
+        let fraccion = paso as f32 / segmentos as f32;
+        let angulo = fraccion * 2.0 * std::f32::consts::PI;
+        let (seno, coseno) = angulo.sin_cos();
+        for radio in [radio_interno, radio_externo] {
+            vertices.push(VerticeEsfera {
+                posicion: [radio * coseno, 0.0, radio * seno],
+                normal: [0.0, 1.0, 0.0],
+                uv: [fraccion, radio],
+            });
+        }
+    }
+
+    // Seis índices por tramo, en orden angular: un rango de tramos es un rango de índices
+    for paso in 0..segmentos {
+        let interno = (paso * 2) as u16;
+        let externo = interno + 1;
+        indices.extend_from_slice(&[interno, externo, interno + 2, externo, externo + 2, interno + 2]);
+    }
+
+    (vertices, indices)
+}
+
+/// Geometría del anillo de un cuerpo
+struct MallaAnillo {
+    buffer_vertices: wgpu::Buffer,
+    buffer_indices: wgpu::Buffer,
+    segmentos: u32,
+}
+
+impl MallaAnillo {
+    const SEGMENTOS: u32 = 128;
+    /// Partículas que orbitan dentro del anillo
+    const ESCOMBROS: u32 = 6000;
+
+    fn nueva(dispositivo: &wgpu::Device, anillo: &Anillo) -> Self {
+        let (vertices, indices) = generar_anillo(anillo.radio_interno, anillo.radio_externo, Self::SEGMENTOS);
+        MallaAnillo {
+            buffer_vertices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Vértices de Anillo"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            buffer_indices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Índices de Anillo"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            segmentos: Self::SEGMENTOS,
+        }
+    }
+
+    /// Rangos de índices de los tramos más lejanos (`lejanos = true`) o más cercanos a `ojo_modelo`,
+    /// el ojo de la cámara en coordenadas del modelo. Sin buffer de profundidad, la mitad lejana
+    /// se dibuja antes que el planeta y la cercana después.
+    fn rangos_mitad(&self, ojo_modelo: &Vec3, lejanos: bool) -> Vec<std::ops::Range<u32>> {
+        let angulo_ojo = ojo_modelo.z.atan2(ojo_modelo.x);
+        let mut rangos: Vec<std::ops::Range<u32>> = Vec::new();
+        for tramo in 0..self.segmentos {
+            let centro = (tramo as f32 + 0.5) / self.segmentos as f32 * 2.0 * std::f32::consts::PI;
+            if ((centro - angulo_ojo).cos() < 0.0) != lejanos {
+                continue;
+            }
+            let indices = tramo * 6..tramo * 6 + 6;
+            match rangos.last_mut() {
+                Some(ultimo) if ultimo.end == indices.start => ultimo.end = indices.end,
+                _ => rangos.push(indices),
+            }
+        }
+        rangos
+    }
+}
+
+/// Malla OBJ de un cuerpo en los buffers de la GPU, centrada y reducida a radio 1 para que la
+/// `escala` del cuerpo sea su radio envolvente. Sin buffer de profundidad los triángulos se
+/// pintan de atrás hacia adelante, así que el orden se rehace cuando el ojo se mueve
+struct MallaModelo {
+    buffer_vertices: wgpu::Buffer,
+    buffer_indices: wgpu::Buffer,
+    cantidad_indices: u32,
+    /// Centro de cada triángulo en el espacio del modelo
+    centros: Vec<Vec3>,
+    /// Ojo (espacio del modelo) con el que se ordenó la última vez
+    ojo_ordenado: Option<Vec3>,
+}
+
+impl MallaModelo {
+    /// Fracción de la distancia que tiene que moverse el ojo para reordenar
+    const TOLERANCIA_ORDEN: f32 = 0.02;
+
+    fn nueva(dispositivo: &wgpu::Device, modelo: &ModeloOBJ, formato: FormatoVertices) -> Self {
+        let vertices = Self::vertices(modelo);
+        let centros: Vec<Vec3> = vertices
+            .chunks_exact(3)
+            .map(|triangulo| triangulo.iter().map(|v| Vec3::from(v.posicion)).sum::<Vec3>() / 3.0)
+            .collect();
+        let indices: Vec<u32> = (0..centros.len() as u32 * 3).collect();
+        MallaModelo {
+            buffer_vertices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Vértices de Modelo"),
+                contents: &formato.codificar(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            buffer_indices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Índices de Modelo"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            }),
+            cantidad_indices: indices.len() as u32,
+            centros,
+            ojo_ordenado: None,
+        }
+    }
+
+    /// Los vértices de `obtener_array_vertices` (tres por cara) en el formato de la esfera,
+    /// con la caja del modelo centrada en el origen y el vértice más lejano a radio 1. La v del
+    /// OBJ crece hacia arriba y la de la esfera desde el polo norte, así que se invierte
+    fn vertices(modelo: &ModeloOBJ) -> Vec<VerticeEsfera> {
+        let vertices = modelo.obtener_array_vertices();
+        let (minimo, maximo) = vertices.iter().fold(
+            (Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY)),
+            |(minimo, maximo), v| (minimo.inf(&v.posicion), maximo.sup(&v.posicion)),
+        );
+        let centro = (minimo + maximo) * 0.5;
+        let radio = vertices
+            .iter()
+            .map(|v| (v.posicion - centro).norm())
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        vertices
+            .iter()
+            .map(|v| VerticeEsfera {
+                posicion: ((v.posicion - centro) / radio).into(),
+                normal: v.vector_normal.into(),
+                uv: [v.coordenadas_textura.x, 1.0 - v.coordenadas_textura.y],
+            })
+            .collect()
+    }
+
+    /// Reordena los triángulos del más lejano al más cercano si el ojo se movió lo suficiente
+    fn ordenar(&mut self, cola: &wgpu::Queue, ojo_modelo: &Vec3) {
+        let tolerancia = Self::TOLERANCIA_ORDEN * ojo_modelo.norm();
+        if self.ojo_ordenado.is_some_and(|ojo| (ojo - ojo_modelo).norm() < tolerancia) {
+            return;
+        }
+        let distancias: Vec<f32> = self.centros.iter().map(|c| (c - ojo_modelo).norm_squared()).collect();
+        let mut triangulos: Vec<u32> = (0..self.centros.len() as u32).collect();
+        triangulos.sort_unstable_by(|&a, &b| distancias[b as usize].total_cmp(&distancias[a as usize]));
+        let indices: Vec<u32> = triangulos.iter().flat_map(|&t| [t * 3, t * 3 + 1, t * 3 + 2]).collect();
+        cola.write_buffer(&self.buffer_indices, 0, bytemuck::cast_slice(&indices));
+        self.ojo_ordenado = Some(*ojo_modelo);
+    }
+}
+
+/// Brazos, ejes y base de latón del estilo planetario: cada cuerpo que orbita cuelga de un
+/// brazo que sale del eje de su padre, a una altura propia para que los brazos no se crucen,
+/// y los ejes de los demás bajan hasta un disco por debajo de la eclíptica. La malla se rehace
+/// en espacio de mundo cada cuadro; sin búfer de profundidad, las piezas de cada cuerpo se
+/// pintan justo antes que él y la base antes que todo (o después, vista desde abajo)
+struct MecanismoPlanetario {
+    buffer_vertices: wgpu::Buffer,
+    capacidad: usize,
+    /// Vértices del disco, al principio del buffer
+    base: std::ops::Range<u32>,
+    /// Brazo y eje de cada cuerpo, por índice; vacío si no se dibuja
+    piezas: Vec<std::ops::Range<u32>>,
+    /// Cara de arriba del disco de la base
+    altura_base: f32,
+}
+
+impl MecanismoPlanetario {
+    const LADOS: usize = 12;
+    const LADOS_BASE: usize = 64;
+    const RADIO_EJE: f32 = 0.012;
+    const GROSOR_BASE: f32 = 0.03;
+    const CAPACIDAD_INICIAL: usize = 4096;
+
+    fn nuevo(dispositivo: &wgpu::Device, formato: FormatoVertices) -> Self {
+        MecanismoPlanetario {
+            buffer_vertices: Self::crear_buffer(dispositivo, formato, Self::CAPACIDAD_INICIAL),
+            capacidad: Self::CAPACIDAD_INICIAL,
+            base: 0..0,
+            piezas: Vec::new(),
+            altura_base: 0.0,
+        }
+    }
+
+    fn crear_buffer(dispositivo: &wgpu::Device, formato: FormatoVertices, capacidad: usize) -> wgpu::Buffer {
+        dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer del Mecanismo del Planetario"),
+            size: (capacidad * formato.tamano()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Con el ojo bajo la base el mecanismo tapa a los cuerpos en vez de quedar detrás
+    fn tapa_cuerpos(&self, ojo: &Vec3) -> bool {
+        ojo.z < self.altura_base
+    }
+
+    /// Rehace la malla con las posiciones del cuadro de los cuerpos `incluidos` (en orden de la
+    /// escena) y la sube con los triángulos de cada tramo de atrás hacia adelante para `ojo`
+    #[allow(clippy::too_many_arguments)]
+    fn preparar(
+        &mut self,
+        anillo: &mut AnilloSubidas,
+        dispositivo: &wgpu::Device,
+        codificador: &mut wgpu::CommandEncoder,
+        formato: FormatoVertices,
+        cuerpos: &[CuerpoCeleste],
+        incluidos: &[usize],
+        centro: Vec3,
+        ojo: Vec3,
+    ) {
+        let alto = incluidos.iter().map(|&i| cuerpos[i].radio_superficie()).fold(0.0, f32::max);
+        self.altura_base = centro.z - alto * 1.3 - 0.1;
+        let alcance = incluidos
+            .iter()
+            .map(|&i| (cuerpos[i].posicion_3d() - centro).norm() + cuerpos[i].radio_envolvente())
+            .fold(0.2, f32::max);
+
+        let mut base = Vec::new();
+        let arriba = Vec3::new(centro.x, centro.y, self.altura_base);
+        agregar_cilindro(&mut base, arriba - Vec3::z() * Self::GROSOR_BASE, arriba, alcance * 1.1, Self::LADOS_BASE);
+
+        // Altura de la que sale el eje de cada cuerpo: la base o su brazo
+        let mut niveles = vec![self.altura_base; cuerpos.len()];
+        let mut piezas = vec![Vec::new(); cuerpos.len()];
+        for &indice in incluidos {
+            let piezas = &mut piezas[indice];
+            let cuerpo = &cuerpos[indice];
+            let posicion = cuerpo.posicion_3d();
+            if let Some(padre) = cuerpo.padre.filter(|&p| p < indice && incluidos.contains(&p)) {
+                let hermanos: Vec<usize> = incluidos.iter().copied().filter(|&i| cuerpos[i].padre == Some(padre)).collect();
+                let orden = hermanos.iter().position(|&i| i == indice).unwrap_or(0);
+                let (desde, hasta) = (niveles[padre], -cuerpos[padre].radio_superficie());
+                let altura = desde + (hasta - desde) * (orden + 1) as f32 / (hermanos.len() + 1) as f32;
+                let eje_padre = cuerpos[padre].posicion_3d();
+                agregar_cilindro(
+                    piezas,
+                    Vec3::new(eje_padre.x, eje_padre.y, altura),
+                    Vec3::new(posicion.x, posicion.y, altura),
+                    Self::RADIO_EJE,
+                    Self::LADOS,
+                );
+                niveles[indice] = altura;
+            }
+            let abajo = posicion.z - cuerpo.radio_superficie();
+            if niveles[indice] < abajo {
+                let desde = Vec3::new(posicion.x, posicion.y, niveles[indice]);
+                agregar_cilindro(piezas, desde, Vec3::new(posicion.x, posicion.y, abajo), Self::RADIO_EJE, Self::LADOS);
+            }
+        }
+
+        let ordenar = |vertices: Vec<VerticeEsfera>| -> Vec<VerticeEsfera> {
+            let mut triangulos: Vec<(f32, [VerticeEsfera; 3])> = vertices
+                .chunks_exact(3)
+                .map(|t| {
+                    let centro = t.iter().map(|v| Vec3::from(v.posicion)).sum::<Vec3>() / 3.0;
+                    ((centro - ojo).norm_squared(), [t[0], t[1], t[2]])
+                })
+                .collect();
+            triangulos.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+            triangulos.into_iter().flat_map(|(_, t)| t).collect()
+        };
+        let mut vertices = ordenar(base);
+        self.base = 0..vertices.len() as u32;
+        self.piezas = piezas
+            .into_iter()
+            .map(|propias| {
+                let inicio = vertices.len() as u32;
+                vertices.extend(ordenar(propias));
+                inicio..vertices.len() as u32
+            })
+            .collect();
+
+        if vertices.len() > self.capacidad {
+            self.capacidad = vertices.len().next_power_of_two();
+            self.buffer_vertices = Self::crear_buffer(dispositivo, formato, self.capacidad);
+        }
+        anillo.subir(dispositivo, codificador, &self.buffer_vertices, 0, &formato.codificar(&vertices));
+    }
+}
+
+/// Cilindro macizo de `desde` a `hasta` como triángulos sueltos en sentido antihorario vistos
+/// desde afuera (el pipeline de planetas descarta las caras traseras)
+fn agregar_cilindro(vertices: &mut Vec<VerticeEsfera>, desde: Vec3, hasta: Vec3, radio: f32, lados: usize) {
+    let largo = hasta - desde;
+    if largo.norm() <= f32::EPSILON {
+        return;
+    }
+    let eje = largo.normalize();
+    let auxiliar = if eje.z.abs() < 0.9 { Vec3::z() } else { Vec3::x() };
+    let u = auxiliar.cross(&eje).normalize();
+    let v = eje.cross(&u);
+    let vertice = |posicion: Vec3, normal: Vec3| VerticeEsfera {
+        posicion: posicion.into(),
+        normal: normal.into(),
+        uv: [0.0, 0.0],
+    };
+    for k in 0..lados {
+        let angulo = |k: usize| std::f32::consts::TAU * k as f32 / lados as f32;
+        let (n0, n1) = (u * angulo(k).cos() + v * angulo(k).sin(), u * angulo(k + 1).cos() + v * angulo(k + 1).sin());
+        let (a0, a1, b0, b1) = (desde + n0 * radio, desde + n1 * radio, hasta + n0 * radio, hasta + n1 * radio);
+        vertices.extend([
+            vertice(a0, n0),
+            vertice(a1, n1),
+            vertice(b1, n1),
+            vertice(a0, n0),
+            vertice(b1, n1),
+            vertice(b0, n0),
+            vertice(hasta, eje),
+            vertice(b0, eje),
+            vertice(b1, eje),
+            vertice(desde, -eje),
+            vertice(a1, -eje),
+            vertice(a0, -eje),
+        ]);
+    }
+}
+
+/// Celda de un quadtree sobre la cuadrícula equirectangular de la esfera: en su `nivel` hay
+/// `4·2^nivel` columnas de longitud (`x`) y `2·2^nivel` filas de colatitud (`y`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Parche {
+    nivel: u8,
+    x: u32,
+    y: u32,
+}
+
+impl Parche {
+    fn columnas(nivel: u8) -> u32 {
+        4 << nivel
+    }
+
+    fn filas(nivel: u8) -> u32 {
+        2 << nivel
+    }
+
+    fn raices() -> impl Iterator<Item = Parche> {
+        (0..Self::filas(0)).flat_map(|y| (0..Self::columnas(0)).map(move |x| Parche { nivel: 0, x, y }))
+    }
+
+    fn hijos(self) -> [Parche; 4] {
+        let (x, y, nivel) = (self.x * 2, self.y * 2, self.nivel + 1);
+        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| Parche { nivel, x: x + dx, y: y + dy })
+    }
+
+    /// Celda del mismo nivel al lado dado; la longitud da la vuelta y más allá de un polo no hay
+    fn vecino(self, (dx, dy): (i32, i32)) -> Option<Parche> {
+        let y = self.y.checked_add_signed(dy).filter(|&y| y < Self::filas(self.nivel))?;
+        let x = (self.x as i64 + dx as i64).rem_euclid(Self::columnas(self.nivel) as i64) as u32;
+        Some(Parche { x, y, ..self })
+    }
+
+    /// Hoja de `hojas` que contiene a esta celda, si es ella o una más gruesa
+    fn cubierta_por(self, hojas: &std::collections::BTreeSet<Parche>) -> Option<Parche> {
+        (0..=self.nivel).rev().map(|nivel| {
+            let corrimiento = self.nivel - nivel;
+            Parche { nivel, x: self.x >> corrimiento, y: self.y >> corrimiento }
+        })
+        .find(|ancestro| hojas.contains(ancestro))
+    }
+
+    /// Punto de la esfera unitaria en la fracción (s, t) ∈ [0, 1]² de la celda, con sus uv. Las
+    /// cuentas van en f64 sobre fracciones de potencias de dos, así un borde compartido da los
+    /// mismos vértices desde los dos lados
+    fn punto(self, s: f64, t: f64) -> ([f32; 3], [f32; 2]) {
+        let u = (self.x as f64 + s) / Self::columnas(self.nivel) as f64;
+        let v = (self.y as f64 + t) / Self::filas(self.nivel) as f64;
+        let (theta, phi) = (v * std::f64::consts::PI, u * std::f64::consts::TAU);
+        let posicion = [theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()].map(|c| c as f32);
+        // Como en `generar_esfera`: la longitud crece hacia el este visto desde afuera
+        (posicion, [(1.0 - u) as f32, v as f32])
+    }
+
+    /// Centro de la celda y la mayor distancia de él a sus esquinas y bordes
+    fn esfera(self) -> (Vec3, f32) {
+        let centro = Vec3::from(self.punto(0.5, 0.5).0);
+        let radio = [(0.0, 0.0), (0.5, 0.0), (1.0, 0.0), (0.0, 0.5), (1.0, 0.5), (0.0, 1.0), (0.5, 1.0), (1.0, 1.0)]
+            .into_iter()
+            .map(|(s, t)| (Vec3::from(self.punto(s, t).0) - centro).norm())
+            .fold(0.0, f32::max);
+        (centro, radio)
+    }
+}
+
+/// Superficie de un cuerpo visto de cerca: hojas de un quadtree de `Parche`s, más finas
+/// donde están cerca del ojo, cada una con una cuadrícula de `CELDAS`² cuadros
+struct MallaLod {
+    hojas: Vec<Parche>,
+    buffer_vertices: wgpu::Buffer,
+    buffer_indices: wgpu::Buffer,
+    formato_indices: wgpu::IndexFormat,
+    cantidad_indices: u32,
+}
+
+impl MallaLod {
+    const CELDAS: u32 = 16;
+    /// Una celda se divide si su radio pasa esta fracción de su distancia al ojo
+    const UMBRAL: f32 = 0.25;
+    /// En media precisión la posición tiene unos 11 bits: más niveles apilarían los vértices
+    const NIVELES_COMPACTOS: u8 = 5;
+
+    /// Hojas para el ojo en el marco del cuerpo (esfera unitaria), con vecinas a lo sumo un
+    /// nivel distintas. Vacío si ninguna raíz necesita dividirse: alcanza con la esfera
+    fn hojas(ojo_modelo: &Vec3, niveles: u8) -> Vec<Parche> {
+        let dividir = |parche: Parche| {
+            let (centro, radio) = parche.esfera();
+            parche.nivel < niveles && radio > Self::UMBRAL * (ojo_modelo - centro).norm()
+        };
+        let mut hojas = std::collections::BTreeSet::new();
+        let mut pendientes: Vec<Parche> = Parche::raices().collect();
+        let mut dividida = false;
+        while let Some(parche) = pendientes.pop() {
+            if dividir(parche) {
+                dividida = true;
+                pendientes.extend(parche.hijos());
+            } else {
+                hojas.insert(parche);
+            }
+        }
+        if !dividida {
+            return Vec::new();
+        }
+        // Se parten las hojas gruesas junto a otras dos o más niveles más finas
+        loop {
+            let gruesas: std::collections::BTreeSet<Parche> = hojas
+                .iter()
+                .flat_map(|hoja| Self::LADOS.iter().filter_map(move |&lado| hoja.vecino(lado).map(|v| (hoja, v))))
+                .filter_map(|(hoja, vecino)| vecino.cubierta_por(&hojas).filter(|g| g.nivel + 1 < hoja.nivel))
+                .collect();
+            if gruesas.is_empty() {
+                break;
+            }
+            for gruesa in gruesas {
+                hojas.remove(&gruesa);
+                hojas.extend(gruesa.hijos());
+            }
+        }
+        hojas.into_iter().collect()
+    }
+
+    /// Izquierda, derecha, arriba (norte) y abajo, en ese orden
+    const LADOS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+    fn nueva(dispositivo: &wgpu::Device, hojas: Vec<Parche>, formato: FormatoVertices) -> Self {
+        let (vertices, indices) = Self::generar(&hojas);
+        let indices = IndicesMalla::desde(indices, vertices.len());
+        MallaLod {
+            buffer_vertices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Vértices de Parches"),
+                contents: &formato.codificar(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            buffer_indices: dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Buffer de Índices de Parches"),
+                contents: indices.bytes(),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            formato_indices: indices.formato(),
+            cantidad_indices: indices.len() as u32,
+            hojas,
+        }
+    }
+
+    /// Cuadrícula de cada hoja con la orientación de `generar_esfera`. En un borde con una
+    /// vecina más gruesa los vértices impares se pliegan sobre el par anterior: el borde queda
+    /// con los vértices de la vecina y no se abren grietas, tampoco con relieve
+    fn generar(hojas: &[Parche]) -> (Vec<VerticeEsfera>, Vec<u32>) {
+        let conjunto: std::collections::BTreeSet<Parche> = hojas.iter().copied().collect();
+        let lado = Self::CELDAS + 1;
+        let mut vertices = Vec::with_capacity(hojas.len() * (lado * lado) as usize);
+        let mut indices = Vec::with_capacity(hojas.len() * (6 * Self::CELDAS * Self::CELDAS) as usize);
+        for hoja in hojas {
+            let gruesa = Self::LADOS.map(|direccion| {
+                hoja.vecino(direccion)
+                    .and_then(|vecino| vecino.cubierta_por(&conjunto))
+                    .is_some_and(|vecina| vecina.nivel < hoja.nivel)
+            });
+            let base = vertices.len() as u32;
+            for fila in 0..lado {
+                for columna in 0..lado {
+                    let borde_vertical = (columna == 0 && gruesa[0]) || (columna == Self::CELDAS && gruesa[1]);
+                    let borde_horizontal = (fila == 0 && gruesa[2]) || (fila == Self::CELDAS && gruesa[3]);
+                    let fila = if borde_vertical { fila & !1 } else { fila };
+                    let columna = if borde_horizontal { columna & !1 } else { columna };
+                    let celdas = Self::CELDAS as f64;
+                    let (posicion, uv) = hoja.punto(columna as f64 / celdas, fila as f64 / celdas);
+                    vertices.push(VerticeEsfera {
+                        posicion,
+                        normal: posicion,
+                        uv,
+                    });
+                }
+            }
+            for fila in 0..Self::CELDAS {
+                for columna in 0..Self::CELDAS {
+                    let primero = base + fila * lado + columna;
+                    let segundo = primero + lado;
+                    indices.extend_from_slice(&[primero, segundo, primero + 1, segundo, segundo + 1, primero + 1]);
+                }
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+/// Vértices de superposición ya subidos para el cuadro
+#[derive(Debug, Clone, Copy, Default)]
+struct Superposiciones {
+    lineas: usize,
+    lineas_cielo: usize,
+    cintas: usize,
+}
+
+/// La parte de `EstadoAplicacion` que cada cuadro avanza sin tocar la GPU: el reloj, los
+/// cuerpos y la cámara que los sigue. Así `actualizar` y las pruebas corren el mismo paso
+struct Simulacion<'a> {
+    reloj: &'a mut RelojSimulacion,
+    camara: &'a mut CamaraVirtual,
+    cuerpos: &'a mut [CuerpoCeleste],
+    grafo: &'a mut GrafoEscena,
+    fisica: &'a mut SimulacionNCuerpos,
+    fila: &'a mut FilaTamanos,
+    animaciones: &'a [Animacion],
+    capas: &'a AjustesCapas,
+    foco: &'a mut Option<usize>,
+}
+
+impl Simulacion<'_> {
+    /// Avanza un cuadro de `paso` segundos de pared y deja los cuerpos y la cámara en el
+    /// instante nuevo del reloj
+    fn avanzar(&mut self, paso: f64) {
+        self.avanzar_animaciones(paso);
+        self.reloj.avanzar(paso);
+        self.posicionar(self.reloj.tiempo as f32);
+        self.seguir_foco();
+    }
+
+    /// Lo que se anima con el tiempo de pared aunque la simulación vaya a pasos fijos: el
+    /// deslizamiento de la cámara y la fila de tamaños
+    fn avanzar_animaciones(&mut self, paso: f64) {
+        self.fila.avanzar(paso as f32);
+        self.camara.avanzar_transicion(paso as f32);
+    }
+
+    /// Pone los cuerpos en el instante `tiempo` (en segundos de simulación)
+    fn posicionar(&mut self, tiempo: f32) {
+        self.fila.restaurar(self.cuerpos);
+        if self.fisica.activa {
+            self.fisica.avanzar_hasta(tiempo as f64);
+            self.fisica.aplicar(self.cuerpos);
+            GrafoEscena::localizar(self.cuerpos);
+        } else {
+            for cuerpo in self.cuerpos.iter_mut() {
+                if let Some(orbita) = cuerpo.orbita {
+                    cuerpo.posicion_local = orbita.posicion(tiempo);
+                }
+            }
+        }
+        self.grafo.actualizar(self.cuerpos);
+        for animacion in self.animaciones {
+            animacion.aplicar(self.cuerpos, tiempo);
+        }
+        if self.fila.visible() {
+            let incluidos: Vec<usize> = (0..self.cuerpos.len())
+                .filter(|&i| self.capas.muestra(self.cuerpos[i].clase) && CuerpoCeleste::visible_en(self.cuerpos, i))
+                .collect();
+            let centro = self.grafo.centro_escena();
+            self.fila.aplicar(self.grafo, self.cuerpos, &incluidos, centro);
+        }
+    }
+
+    /// Hace que la cámara siga al cuerpo dado, o vuelve al centro de la escena
+    fn enfocar(&mut self, foco: Option<usize>) {
+        *self.foco = foco;
+        let objetivo = foco.map_or(Vec3::zeros(), |i| self.cuerpos[i].posicion_3d());
+        self.camara.superficie = foco.map_or(0.0, |i| self.cuerpos[i].radio_superficie());
+        match foco {
+            Some(_) => self.camara.transicionar(objetivo),
+            None => self.camara.transicion = 0.0,
+        }
+        self.camara.seguir(objetivo);
+        // Al pasar a un cuerpo más grande el ojo no puede quedar dentro de él
+        self.camara.orientar(self.camara.guinada, self.camara.cabeceo, self.camara.radio);
+    }
+
+    /// La cámara sigue al cuerpo enfocado, corrida por lo que falta del deslizamiento
+    fn seguir_foco(&mut self) {
+        if let Some(foco) = *self.foco {
+            self.camara.superficie = self.cuerpos[foco].radio_superficie();
+            self.camara.seguir(self.cuerpos[foco].posicion_3d());
+        }
+    }
+}
+
+struct EstadoAplicacion {
+    ventana: Option<Arc<Window>>,
+    /// Para devolverle la superficie a la ventana si rehacer el dispositivo falla
+    instancia: wgpu::Instance,
+    superficie: Option<wgpu::Surface<'static>>,
+    dispositivo: Arc<wgpu::Device>,
+    cola_comandos: wgpu::Queue,
+    configuracion: wgpu::SurfaceConfiguration,
+    tamano_ventana: winit::dpi::PhysicalSize<u32>,
+    /// Pipeline de planetas de cada tipo de shader, compilados en segundo plano
+    pipelines: CompiladorPipelines,
+    shaders_propios: ShadersPropios,
+    recarga_escena: RecargaEscena,
+    pipeline_anillos: wgpu::RenderPipeline,
+    /// Escombros de los anillos de la mitad cercana y de la lejana
+    pipelines_escombros: [wgpu::RenderPipeline; 2],
+    pipeline_atmosferas: wgpu::RenderPipeline,
+    /// Malla de anillo de cada cuerpo que lo tiene
+    mallas_anillos: Vec<Option<MallaAnillo>>,
+    /// Parches de cada cuerpo visto de cerca; `None` = se dibuja la esfera
+    mallas_lod: Vec<Option<MallaLod>>,
+    niveles_lod: u8,
+    pipeline_lineas: wgpu::RenderPipeline,
+    buffer_vertices: wgpu::Buffer,
+    buffer_indices: wgpu::Buffer,
+    /// 32 bits solo si la esfera tiene más vértices de los que indexa un `u16`
+    formato_indices: wgpu::IndexFormat,
+    cantidad_indices: u32,
+    cantidad_vertices: usize,
+    formato_vertices: FormatoVertices,
+    buffer_lineas: wgpu::Buffer,
+    /// Cintas de las estelas: triángulos con mezcla aditiva, detrás de los planetas
+    pipeline_cintas: wgpu::RenderPipeline,
+    buffer_cintas: wgpu::Buffer,
+    cielo: CieloEstrellado,
+    pipeline_galaxias: wgpu::RenderPipeline,
+    buffer_galaxias: wgpu::Buffer,
+    cantidad_galaxias: u32,
+    texto: RenderizadorTexto,
+    texturas: TexturasPlanetas,
+    /// Modelos OBJ soltados sobre la ventana que aún se están leyendo
+    modelos: DecodificadorRecursos<(std::path::PathBuf, ModeloOBJ)>,
+    /// Los `modelo` de la escena, por índice de cuerpo
+    modelos_escena: DecodificadorRecursos<ModeloOBJ>,
+    /// Malla OBJ de cada cuerpo que la tiene y ya se leyó; los demás se dibujan como esfera
+    mallas_modelos: Vec<Option<MallaModelo>>,
+    /// Brazos y base del estilo planetario; solo se rehace con ese estilo
+    mecanismo: MecanismoPlanetario,
+    /// Solo con soporte de compute shaders
+    plumas: Option<PlumasImpacto>,
+    particulas: EfectosParticulas,
+    /// Solo si la escena define `[cinturon]` y hay compute y dibujo indirecto
+    cinturon: Option<CinturonAsteroides>,
+    cinturon_soportado: bool,
+    /// Se conserva para rehacer los recursos de la escena al cambiarla
+    modulo_shader: wgpu::ShaderModule,
+    /// Escena cargada; la recuperación del vigilante la vuelve a construir
+    escena: ArchivoEscena,
+    /// Error del último intento de abrir una escena desde el panel
+    aviso_escena: Option<String>,
+    modificadores: winit::keyboard::ModifiersState,
+    portapapeles: Portapapeles,
+    /// `--grabar-entrada`: cada evento de entrada y cada fin de cuadro
+    grabador_entrada: Option<GrabadorEntrada>,
+    paso_a_paso: PasoAPaso,
+    paso_fijo: PasoFijo,
+    fila: FilaTamanos,
+    /// `--replay`: mientras dura, la entrada real se ignora salvo para cerrar
+    repeticion: Option<RepeticionEntrada>,
+    /// Paso del cuadro que la repetición acaba de entregar
+    paso_repetido: Option<f64>,
+    /// Órdenes de la terminal (solo con ventana)
+    consola: Option<std::sync::mpsc::Receiver<String>>,
+    telescopio: Telescopio,
+    posproceso: Posproceso,
+    comparacion_cpu: ComparacionCpu,
+    buffer_uniformes: wgpu::Buffer,
+    grupo_bind_uniformes: wgpu::BindGroup,
+    layout_uniformes: wgpu::BindGroupLayout,
+    /// Uniformes de todos los cuerpos, una franja de `separacion_uniformes` bytes por cuerpo
+    /// ligada con desplazamiento dinámico; crece con la escena y no se recrea por cuadro
+    buffer_cuerpos: wgpu::Buffer,
+    separacion_uniformes: u32,
+    /// Va en el grupo de uniformes junto a los de cada cuerpo
+    paleta_bandas_gas: wgpu::TextureView,
+    /// Los datos de cada cuerpo van como constantes de empuje en cada dibujo: los cuerpos
+    /// usan los uniformes globales y `buffer_cuerpos` no se sube
+    constantes_empuje: bool,
+    cache_grupos: CacheBindGroups,
+    anillo: AnilloSubidas,
+    datos_uniformes: DatosUniformes,
+    camara: CamaraVirtual,
+    reloj: RelojSimulacion,
+    editor: EditorOrbitas,
+    kepler: LeyesKepler,
+    fisica: SimulacionNCuerpos,
+    /// Instante del último avance del reloj de simulación
+    ultimo_cuadro: std::time::Instant,
+    /// Frecuencia del monitor con que se cuantiza el paso de cada cuadro
+    frecuencia: FrecuenciaPantalla,
+    posicion_mouse: Option<winit::dpi::PhysicalPosition<f64>>,
+    mouse_presionado: bool,
+    posicion_presion: Option<winit::dpi::PhysicalPosition<f64>>,
+    cuerpos: Vec<CuerpoCeleste>,
+    grafo: GrafoEscena,
+    planeta_seleccionado: Option<usize>,
+    /// Cuerpo que la cámara sigue como objetivo
+    foco: Option<usize>,
+    interfaz: Option<InterfazEgui>,
+    medicion: HerramientaMedicion,
+    quiz: ModoQuiz,
+    animaciones: Vec<Animacion>,
+    constelaciones: Vec<Constelacion>,
+    mostrar_constelaciones: bool,
+    cuadricula_ecliptica: CuadriculaCeleste,
+    cuadricula_ecuatorial: CuadriculaCeleste,
+    mostrar_ecliptica: bool,
+    mostrar_ecuatorial: bool,
+    /// Sombreado de caricatura en todos los cuerpos, no solo en los que lo piden
+    caricatura: bool,
+    escala_distancias: EscalaDistancias,
+    capas: AjustesCapas,
+    estelas: EstelasOrbitales,
+    ruta_ajustes: std::path::PathBuf,
+    ajustes_grabacion: AjustesGrabacion,
+    ajustes_pantalla: AjustesPantalla,
+    grabacion: Option<Grabacion>,
+    radio_disco_ecliptica: f32,
+    pub eventos: ObservadoresEventos,
+    detector_eventos: DetectorEventos,
+    titulo_actual: String,
+    ajustes_gpu: AjustesGpu,
+    /// Adaptadores que pueden dibujar en la ventana, y el que se está usando
+    adaptadores: Vec<String>,
+    adaptador_actual: String,
+    backend: wgpu::Backend,
+    /// Adaptador elegido en el panel; se cambia al terminar el cuadro
+    pub adaptador_pedido: Option<String>,
+    /// Imagen de la superficie pedida al terminar el cuadro anterior (con `latencia_baja`)
+    cuadro_adelantado: Option<wgpu::SurfaceTexture>,
+    latencia: LatenciaEntrada,
+    /// Solo con ventana: en modo sin pantalla no hay superficie que se bloquee
+    vigilante: Option<VigilanteGpu>,
+}
+
+impl EstadoAplicacion {
+    /// Sin ventana se inicializa en modo sin pantalla (render sólo a texturas).
+    /// `forzar_software` pide el adaptador de software (recuperación del vigilante). Falla si
+    /// no hay superficie, adaptador o dispositivo
+    async fn inicializar(
+        ventana: Option<Arc<Window>>,
+        tamano_ventana: winit::dpi::PhysicalSize<u32>,
+        ajustes: &Ajustes,
+        forzar_software: bool,
+    ) -> Result<Self, String> {
+        let instancia = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let superficie = ventana
+            .as_ref()
+            .map(|ventana| instancia.create_surface(ventana.clone()))
+            .transpose()
+            .map_err(|e| format!("no se pudo crear la superficie: {}", e))?;
+
+        let opciones_adaptador = |software| wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: superficie.as_ref(),
+            force_fallback_adapter: software,
+        };
+        let adaptadores: Vec<wgpu::Adapter> = instancia
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .filter(|adaptador| superficie.as_ref().is_none_or(|s| adaptador.is_surface_supported(s)))
+            .collect();
+        let etiquetas_adaptadores = adaptadores.iter().map(|a| AjustesGpu::etiqueta(&a.get_info())).collect();
+        let elegido = match &ajustes.gpu.adaptador {
+            Some(pedido) if !forzar_software => {
+                let elegido = adaptadores.into_iter().find(|a| ajustes.gpu.elige(&a.get_info()));
+                if elegido.is_none() {
+                    eprintln!("No hay un adaptador '{}' que sirva; se usa el predeterminado", pedido);
+                }
+                elegido
+            }
+            _ => None,
+        };
+        let adaptador = match elegido {
+            Some(adaptador) => adaptador,
+            None => match instancia.request_adapter(&opciones_adaptador(forzar_software)).await {
+                Some(adaptador) => adaptador,
+                None if forzar_software => {
+                    eprintln!("No hay adaptador de software disponible; se usa el predeterminado");
+                    instancia
+                        .request_adapter(&opciones_adaptador(false))
+                        .await
+                        .ok_or("no se encontró un adaptador gráfico")?
+                }
+                None => return Err("no se encontró un adaptador gráfico".to_string()),
+            },
+        };
+
+        // GL las emula con glUniform por dibujo (no ahorra nada) y wgpu-hal 0.19 lee sus
+        // datos desalineados, lo que aborta en compilaciones de depuración
+        let tamano_empuje = std::mem::size_of::<DatosCuerpo>() as u32;
+        let constantes_empuje = ajustes.dibujo.constantes_empuje
+            && adaptador.get_info().backend != wgpu::Backend::Gl
+            && adaptador.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && adaptador.limits().max_push_constant_size >= tamano_empuje;
+        let (dispositivo, cola_comandos) = adaptador
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: if constantes_empuje {
+                        wgpu::Features::PUSH_CONSTANTS
+                    } else {
+                        wgpu::Features::empty()
+                    },
+                    required_limits: wgpu::Limits {
+                        max_push_constant_size: if constantes_empuje { tamano_empuje } else { 0 },
+                        ..wgpu::Limits::default()
+                    },
+                },
+                None,
+            )
+            .await
+            .map_err(|e| format!("{} no pudo crear el dispositivo: {}", adaptador.get_info().name, e))?;
+        // Compartido con el hilo que compila pipelines
+        let dispositivo = Arc::new(dispositivo);
+
+        let vigilante = superficie.as_ref().map(|_| VigilanteGpu::nuevo(&adaptador.get_info()));
+
+        let configuracion = match &superficie {
+            Some(superficie) => {
+                let capacidades_superficie = superficie.get_capabilities(&adaptador);
+                let formato_superficie = capacidades_superficie
+                    .formats
+                    .iter()
+                    .copied()
+                    .find(|f| f.is_srgb())
+                    .unwrap_or(capacidades_superficie.formats[0]);
+
+                let configuracion = wgpu::SurfaceConfiguration {
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    format: formato_superficie,
+                    width: tamano_ventana.width,
+                    height: tamano_ventana.height,
+                    present_mode: capacidades_superficie
+                        .present_modes
+                        .iter()
+                        .copied()
+                        .find(|m| m == &wgpu::PresentMode::Fifo)
+                        .unwrap_or(capacidades_superficie.present_modes[0]),
+                    alpha_mode: capacidades_superficie.alpha_modes[0],
+                    view_formats: vec![],
+                    desired_maximum_frame_latency: ajustes.pantalla.cuadros_en_cola(),
+                };
+                superficie.configure(&dispositivo, &configuracion);
+                configuracion
+            }
+            None => wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                width: tamano_ventana.width,
+                height: tamano_ventana.height,
+                present_mode: wgpu::PresentMode::Fifo,
+                alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+                view_formats: vec![],
+                desired_maximum_frame_latency: ajustes.pantalla.cuadros_en_cola(),
+            },
+        };
+
+        let formato_vertices = if ajustes.vertices.compactos {
+            FormatoVertices::Compactos
+        } else {
+            FormatoVertices::Completos
+        };
+        let niveles_lod = match formato_vertices {
+            FormatoVertices::Compactos => ajustes.vertices.niveles_lod.min(MallaLod::NIVELES_COMPACTOS),
+            FormatoVertices::Completos => ajustes.vertices.niveles_lod,
+        };
+        let subdivisiones = subdivisiones_esfera(
+            ajustes.vertices.subdivisiones_esfera,
+            formato_vertices.tamano(),
+            dispositivo.limits().max_buffer_size,
+        );
+        let (vertices, indices) = generar_esfera(subdivisiones);
+        let cantidad_indices = indices.len() as u32;
+        let formato_indices = indices.formato();
+        let buffer_vertices = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Vértices"),
+            contents: &formato_vertices.codificar(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let cantidad_vertices = vertices.len();
+
+        let buffer_indices = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Índices"),
+            contents: indices.bytes(),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let datos_uniformes = DatosUniformes {
+            tiempo_actual: 0.0,
+            tipo_render: 1,
+            dimension_pantalla: [tamano_ventana.width as f32, tamano_ventana.height as f32],
+            tinte: [1.0, 1.0, 1.0],
+            actividad: 1.0,
+            matriz_modelo: Mat4::identity().into(),
+            matriz_vista: Mat4::identity().into(),
+            matriz_proyeccion: Mat4::identity().into(),
+            ambiente: 0.1,
+            intensidad_luz: std::f32::consts::PI,
+            ..bytemuck::Zeroable::zeroed()
+        };
+
+        let buffer_uniformes = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Uniformes"),
+            contents: bytemuck::cast_slice(&[datos_uniformes]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let layout_bind_group_uniformes =
+            dispositivo.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            // Los uniformes globales van en 0; los de cada cuerpo, en su franja
+                            has_dynamic_offset: true,
+                            min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<DatosUniformes>() as u64),
+                        },
+                        count: None,
+                    },
+                    // Paleta de bandas de gas; el shader la lee con `textureLoad`
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("Layout de Bind Group de Uniformes"),
+            });
+
+        let paleta_bandas_gas = Paleta::bandas_gas()
+            .textura_1d(&dispositivo, &cola_comandos, "Paleta de Bandas de Gas", 256)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let grupo_bind_uniformes = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout_bind_group_uniformes,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer_uniformes.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(&paleta_bandas_gas),
+                },
+            ],
+            label: Some("Bind Group de Uniformes"),
+        });
+
+        // Shader WGSL embebido; con constantes de empuje, `cuerpo()` las lee a ellas
+        let codigo_uniformes = include_str!("shader.wgsl");
+        let codigo_shader = if constantes_empuje {
+            DatosCuerpo::fuente_con_empuje(codigo_uniformes).expect("shader embebido sin marcas de datos del cuerpo")
+        } else {
+            codigo_uniformes.to_owned()
+        };
+        
+        let modulo_shader = dispositivo.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Módulo de Shader Principal"),
+            source: wgpu::ShaderSource::Wgsl(codigo_shader.as_str().into()),
+        });
+        // El horneado es un compute de un solo cuerpo con su propio buffer de uniformes
+        let modulo_horneado = constantes_empuje.then(|| {
+            dispositivo.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Módulo de Shader de Horneado"),
+                source: wgpu::ShaderSource::Wgsl(codigo_uniformes.into()),
+            })
+        });
+        let rangos_empuje: &[wgpu::PushConstantRange] = if constantes_empuje {
+            &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                range: 0..tamano_empuje,
+            }]
+        } else {
+            &[]
+        };
+
+        let layout_pipeline_render =
+            dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Layout del Pipeline de Render"),
+                bind_group_layouts: &[&layout_bind_group_uniformes],
+                push_constant_ranges: &[],
+            });
+
+        let soporta_compute = adaptador
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS);
+        let resolucion_horneado = ajustes.texturas.resolucion_horneado;
+        let horneador = (soporta_compute && resolucion_horneado > 0).then(|| {
+            HorneadorSuperficies::nuevo(&dispositivo, modulo_horneado.as_ref().unwrap_or(&modulo_shader), resolucion_horneado)
+        });
+        let presupuesto_texturas = ajustes.memoria.presupuesto_bytes(adaptador.get_info().device_type);
+        let generador_mipmaps = ajustes.texturas.mipmaps.then(|| {
+            let formatos = [TexturasPlanetas::formato_mapas(configuracion.format), HorneadorSuperficies::FORMATO];
+            GeneradorMipmaps::nuevo(&dispositivo, &modulo_shader, &formatos)
+        });
+        let mut texturas = TexturasPlanetas::nuevas(
+            &dispositivo,
+            &cola_comandos,
+            configuracion.format,
+            horneador,
+            generador_mipmaps,
+            &ajustes.texturas,
+            presupuesto_texturas,
+        );
+        let layout_pipeline_planetas =
+            dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Layout del Pipeline de Planetas"),
+                bind_group_layouts: &[&layout_bind_group_uniformes, texturas.layout()],
+                push_constant_ranges: rangos_empuje,
+            });
+
+        // Solo el material provisional se compila aquí; el de cada tipo llega del hilo compilador
+        let provisional = crear_pipeline_planetas(
+            &dispositivo,
+            &layout_pipeline_planetas,
+            &modulo_shader,
+            "fragment_provisional",
+            configuracion.format,
+            formato_vertices,
+        );
+        let layout_compilador = dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layout del Pipeline de Planetas"),
+            bind_group_layouts: &[&layout_bind_group_uniformes, texturas.layout()],
+            push_constant_ranges: rangos_empuje,
+        });
+        let pipelines = CompiladorPipelines::nuevo(
+            dispositivo.clone(),
+            adaptador.get_info().backend,
+            codigo_shader,
+            layout_compilador,
+            configuracion.format,
+            formato_vertices,
+            provisional,
+        );
+
+        // Anillos: translúcidos y visibles por ambas caras
+        let pipeline_anillos = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Anillos"),
+            layout: Some(&layout_pipeline_planetas),
+            vertex: wgpu::VertexState {
+                module: &modulo_shader,
+                entry_point: "vertex_anillo",
+                buffers: &[VerticeEsfera::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &modulo_shader,
+                entry_point: "fragment_anillo",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: configuracion.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        // Escombros de los anillos: quads sin buffer de vértices, uno por instancia
+        let pipeline_escombros = |punto_entrada: &str| {
+            dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Pipeline de Escombros de Anillo"),
+                layout: Some(&layout_pipeline_planetas),
+                vertex: wgpu::VertexState {
+                    module: &modulo_shader,
+                    entry_point: punto_entrada,
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &modulo_shader,
+                    entry_point: "fragment_escombro",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: configuracion.format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let pipelines_escombros = [
+            pipeline_escombros("vertex_escombro_cercano"),
+            pipeline_escombros("vertex_escombro_lejano"),
+        ];
+
+        // Caras traseras: cada píxel de la cáscara recibe un fragmento también con la cámara dentro
+        let pipeline_atmosferas = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Atmósferas"),
+            layout: Some(&layout_pipeline_planetas),
+            vertex: wgpu::VertexState {
+                module: &modulo_shader,
+                entry_point: formato_vertices.punto_entrada_atmosfera(),
+                buffers: &[formato_vertices.descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &modulo_shader,
+                entry_point: "fragment_atmosfera",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: configuracion.format,
+                    blend: Some(MEZCLA_ADITIVA),
+                    write_mask: wgpu::ColorWrites::COLOR,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Front),
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let layout_pipeline_lineas =
+            dispositivo.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Layout del Pipeline de Líneas"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline_lineas = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Líneas de Superposición"),
+            layout: Some(&layout_pipeline_lineas),
+            vertex: wgpu::VertexState {
+                module: &modulo_shader,
+                entry_point: "vertex_linea",
+                buffers: &[VerticeLinea::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &modulo_shader,
+                entry_point: "fragment_linea",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: configuracion.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let pipeline_cintas = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Cintas de Estelas"),
+            layout: Some(&layout_pipeline_lineas),
+            vertex: wgpu::VertexState {
+                module: &modulo_shader,
+                entry_point: "vertex_linea",
+                buffers: &[VerticeLinea::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &modulo_shader,
+                entry_point: "fragment_linea",
+                targets: &[Some(wgpu::ColorTargetState {
                     format: configuracion.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(MEZCLA_ADITIVA),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let buffer_cintas = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Cintas"),
+            size: (MAX_VERTICES_CINTA * std::mem::size_of::<VerticeLinea>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let buffer_lineas = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Líneas"),
+            size: (MAX_VERTICES_LINEA * std::mem::size_of::<VerticeLinea>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let pipeline_galaxias = dispositivo.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipeline de Galaxias de Fondo"),
+            layout: Some(&layout_pipeline_render),
+            vertex: wgpu::VertexState {
+                module: &modulo_shader,
+                entry_point: "vertex_galaxia",
+                buffers: &[InstanciaGalaxia::descriptor_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &modulo_shader,
+                entry_point: "fragment_galaxia",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: configuracion.format,
+                    blend: Some(MEZCLA_ADITIVA),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let mut galaxias = generar_galaxias(&ajustes.galaxias);
+        let cantidad_galaxias = galaxias.len() as u32;
+        if galaxias.is_empty() {
+            galaxias.push(InstanciaGalaxia::zeroed());
+        }
+        let buffer_galaxias = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Instancias de Galaxias"),
+            contents: bytemuck::cast_slice(&galaxias),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let interfaz = ventana
+            .as_deref()
+            .map(|ventana| InterfazEgui::nueva(ventana, &dispositivo, configuracion.format));
+        // Sin ventana (time-lapse) la escena no cambia a mitad de camino
+        let recarga_escena = RecargaEscena::nueva(ventana.is_some());
+        let frecuencia = FrecuenciaPantalla {
+            hz: ventana.as_deref().and_then(FrecuenciaPantalla::del_monitor),
+            ..FrecuenciaPantalla::default()
+        };
+
+        let texto = RenderizadorTexto::nuevo(
+            &dispositivo,
+            &cola_comandos,
+            configuracion.format,
+            &modulo_shader,
+            &layout_bind_group_uniformes,
+        );
+
+        let escena = &ajustes.escena;
+        let mut cuerpos = escena.cuerpos().expect("la escena se valida al cargarla");
+        let mut grafo = GrafoEscena::default();
+        grafo.actualizar(&mut cuerpos);
+        let separacion_uniformes = (std::mem::size_of::<DatosUniformes>() as u32)
+            .next_multiple_of(dispositivo.limits().min_uniform_buffer_offset_alignment);
+        let buffer_cuerpos = Self::crear_buffer_cuerpos(&dispositivo, separacion_uniformes, cuerpos.len());
+        let mallas_anillos = cuerpos
+            .iter()
+            .map(|cuerpo| cuerpo.anillo.as_ref().map(|anillo| MallaAnillo::nueva(&dispositivo, anillo)))
+            .collect();
+        let animaciones = escena.animaciones(&cuerpos).unwrap_or_else(|error| {
+            eprintln!("Animaciones desactivadas: {}", error);
+            Vec::new()
+        });
+        texturas.cargar(&dispositivo, &cola_comandos, &cuerpos, &escena.texturas);
+        let modelos = DecodificadorRecursos::nuevo(texturas.grupo_decodificacion());
+        let mut modelos_escena = DecodificadorRecursos::nuevo(texturas.grupo_decodificacion());
+        let mallas_modelos = Self::pedir_modelos(&mut modelos_escena, &cuerpos);
+        let mecanismo = MecanismoPlanetario::nuevo(&dispositivo, formato_vertices);
+
+        let ruta_constelaciones = std::path::Path::new(&ajustes.constelaciones.archivo);
+        let constelaciones = if ruta_constelaciones.exists() {
+            cargar_constelaciones(ruta_constelaciones).unwrap_or_else(|error| {
+                eprintln!("Constelaciones desactivadas: {}", error);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
+        // Al menos una instancia para no crear un buffer vacío
+        let plumas = soporta_compute.then(|| {
+            PlumasImpacto::nuevas(&dispositivo, &modulo_shader, configuracion.format, &layout_bind_group_uniformes)
+        });
+        let particulas =
+            EfectosParticulas::nuevos(&dispositivo, &modulo_shader, configuracion.format, &layout_bind_group_uniformes);
+        let anillo = AnilloSubidas::nuevo(&dispositivo);
+        let cinturon_soportado = CinturonAsteroides::soportado(&adaptador.get_downlevel_capabilities());
+        let cinturon = escena
+            .cinturon
+            .filter(|_| cinturon_soportado)
+            .map(|definicion| {
+                CinturonAsteroides::nuevo(
+                    &dispositivo,
+                    &modulo_shader,
+                    configuracion.format,
+                    &layout_bind_group_uniformes,
+                    &definicion,
+                )
+            });
+        let telescopio = Telescopio::nuevo(&dispositivo, &modulo_shader, configuracion.format, &layout_bind_group_uniformes);
+        let posproceso = Posproceso::nuevo(
+            &dispositivo,
+            &modulo_shader,
+            configuracion.format,
+            &layout_bind_group_uniformes,
+            &ajustes.posproceso,
+        );
+        let comparacion_cpu = ComparacionCpu::nueva(&dispositivo, texturas.grupo_decodificacion());
+        let cielo = CieloEstrellado::nuevo(
+            &dispositivo,
+            &cola_comandos,
+            configuracion.format,
+            &modulo_shader,
+            &layout_bind_group_uniformes,
+            &ajustes.estrellas,
+        );
+
+        Ok(Self {
+            ventana,
+            instancia,
+            superficie,
+            dispositivo,
+            cola_comandos,
+            configuracion,
+            tamano_ventana,
+            pipelines,
+            shaders_propios: ShadersPropios::default(),
+            recarga_escena,
+            pipeline_anillos,
+            pipelines_escombros,
+            pipeline_atmosferas,
+            mallas_anillos,
+            mallas_lod: Vec::new(),
+            niveles_lod,
+            pipeline_lineas,
+            buffer_vertices,
+            buffer_indices,
+            formato_indices,
+            cantidad_indices,
+            cantidad_vertices,
+            formato_vertices,
+            buffer_lineas,
+            pipeline_cintas,
+            buffer_cintas,
+            cielo,
+            pipeline_galaxias,
+            buffer_galaxias,
+            cantidad_galaxias,
+            texto,
+            texturas,
+            modelos,
+            modelos_escena,
+            mallas_modelos,
+            mecanismo,
+            plumas,
+            particulas,
+            cinturon,
+            cinturon_soportado,
+            modulo_shader,
+            escena: escena.clone(),
+            aviso_escena: None,
+            modificadores: winit::keyboard::ModifiersState::empty(),
+            portapapeles: Portapapeles::default(),
+            grabador_entrada: None,
+            repeticion: None,
+            paso_repetido: None,
+            paso_a_paso: PasoAPaso::default(),
+            paso_fijo: PasoFijo {
+                pasos_por_segundo: ajustes.simulacion.pasos_por_segundo,
+                ..PasoFijo::default()
+            },
+            fila: FilaTamanos::default(),
+            consola: None,
+            telescopio,
+            posproceso,
+            comparacion_cpu,
+            buffer_uniformes,
+            grupo_bind_uniformes,
+            layout_uniformes: layout_bind_group_uniformes,
+            buffer_cuerpos,
+            paleta_bandas_gas,
+            separacion_uniformes,
+            constantes_empuje,
+            cache_grupos: CacheBindGroups::nueva(),
+            anillo,
+            datos_uniformes,
+            camara: CamaraVirtual::inicial(),
+            reloj: RelojSimulacion::nuevo(),
+            editor: EditorOrbitas::default(),
+            kepler: LeyesKepler::default(),
+            fisica: SimulacionNCuerpos::nueva(),
+            ultimo_cuadro: std::time::Instant::now(),
+            frecuencia,
+            posicion_mouse: None,
+            mouse_presionado: false,
+            posicion_presion: None,
+            cuerpos,
+            grafo,
+            planeta_seleccionado: None,
+            foco: None,
+            interfaz,
+            medicion: HerramientaMedicion::default(),
+            quiz: ModoQuiz::nuevo(),
+            animaciones,
+            constelaciones,
+            mostrar_constelaciones: ajustes.constelaciones.visibles,
+            cuadricula_ecliptica: CuadriculaCeleste::nueva(
+                SistemaCoordenadas::Ecliptico,
+                ajustes.cuadriculas.paso_grados,
+            ),
+            cuadricula_ecuatorial: CuadriculaCeleste::nueva(
+                SistemaCoordenadas::Ecuatorial,
+                ajustes.cuadriculas.paso_grados,
+            ),
+            mostrar_ecliptica: ajustes.cuadriculas.ecliptica,
+            caricatura: ajustes.dibujo.caricatura,
+            mostrar_ecuatorial: ajustes.cuadriculas.ecuatorial,
+            escala_distancias: EscalaDistancias::Simulacion,
+            capas: ajustes.capas.clone(),
+            estelas: EstelasOrbitales::default(),
+            ruta_ajustes: ajustes
+                .ruta
+                .clone()
+                .unwrap_or_else(|| Ajustes::RUTA_PREDETERMINADA.into()),
+            ajustes_grabacion: ajustes.grabacion.clone(),
+            ajustes_pantalla: ajustes.pantalla.clone(),
+            grabacion: None,
+            radio_disco_ecliptica: ajustes.cuadriculas.radio_disco,
+            eventos: ObservadoresEventos::default(),
+            detector_eventos: DetectorEventos::default(),
+            titulo_actual: String::new(),
+            ajustes_gpu: ajustes.gpu.clone(),
+            adaptadores: etiquetas_adaptadores,
+            adaptador_actual: AjustesGpu::etiqueta(&adaptador.get_info()),
+            backend: adaptador.get_info().backend,
+            adaptador_pedido: None,
+            cuadro_adelantado: None,
+            latencia: LatenciaEntrada::default(),
+            vigilante,
+        })
+    }
+
+    /// Vuelve a leer la frecuencia del monitor, p. ej. al mover la ventana a otro
+    pub fn revisar_monitor(&mut self) {
+        let Some(ventana) = &self.ventana else { return };
+        let hz = FrecuenciaPantalla::del_monitor(ventana);
+        if hz == self.frecuencia.hz {
+            return;
+        }
+        self.frecuencia = FrecuenciaPantalla { hz, ..FrecuenciaPantalla::default() };
+        if let Some(hz) = hz {
+            println!("Monitor a {:.0} Hz", hz);
+        }
+    }
+
+    pub fn redimensionar(&mut self, nuevo_tamano: winit::dpi::PhysicalSize<u32>) {
+        if nuevo_tamano.width > 0 && nuevo_tamano.height > 0 {
+            // La imagen adelantada es del tamaño viejo
+            self.cuadro_adelantado = None;
+            self.tamano_ventana = nuevo_tamano;
+            self.configuracion.width = nuevo_tamano.width;
+            self.configuracion.height = nuevo_tamano.height;
+            if let Some(superficie) = &self.superficie {
+                superficie.configure(&self.dispositivo, &self.configuracion);
+            }
+            self.datos_uniformes.dimension_pantalla = [
+                nuevo_tamano.width as f32, 
+                nuevo_tamano.height as f32
+            ];
+        }
+    }
+
+    fn procesar_mouse_click(&mut self, presionado: bool) {
+        self.mouse_presionado = presionado;
+        if presionado {
+            self.posicion_presion = self.posicion_mouse;
+            if self.editor.activo {
+                self.editor.arrastre = self.extremo_bajo_cursor();
+            }
+        } else if self.editor.arrastre.take().is_some() {
+            if let Some(mensaje) = self.editor.describir(self.planeta_seleccionado.map(|i| &self.cuerpos[i])) {
+                println!("{}", mensaje);
+            }
+        } else if self.es_click() {
+            self.procesar_seleccion();
+        }
+    }
+
+    /// Órbita del cuerpo seleccionado, si tiene
+    fn orbita_seleccionada(&self) -> Option<(usize, Orbita)> {
+        let indice = self.planeta_seleccionado?;
+        Some((indice, self.cuerpos[indice].orbita?))
+    }
+
+    /// Asa del editor bajo el cursor
+    fn extremo_bajo_cursor(&self) -> Option<ExtremoOrbita> {
+        let (indice, orbita) = self.orbita_seleccionada()?;
+        let cursor = self.posicion_mouse?;
+        let vista = self.matriz_vista_proyeccion();
+        let [ancho, alto] = self.datos_uniformes.dimension_pantalla;
+        let origen = self.grafo.origen(indice);
+        ExtremoOrbita::TODOS.into_iter().find(|extremo| {
+            let [x, y] = orbita.punto_en_anomalia(extremo.anomalia());
+            self.proyectar_punto_cielo(&vista, &(origen + Vec3::new(x, y, 0.0))).is_some_and(|ndc| {
+                let dx = (ndc[0] + 1.0) * 0.5 * ancho - cursor.x as f32;
+                let dy = (1.0 - ndc[1]) * 0.5 * alto - cursor.y as f32;
+                dx * dx + dy * dy <= EditorOrbitas::RADIO_ASA * EditorOrbitas::RADIO_ASA * 2.0
+            })
+        })
+    }
+
+    /// Rayo de mundo (origen en el plano cercano, dirección unitaria) que pasa por el cursor
+    fn rayo_desde_cursor(&self, cursor: winit::dpi::PhysicalPosition<f64>) -> Option<(Vec3, Vec3)> {
+        let x = (cursor.x as f32 / self.tamano_ventana.width as f32) * 2.0 - 1.0;
+        let y = 1.0 - (cursor.y as f32 / self.tamano_ventana.height as f32) * 2.0;
+        let inversa = self.matriz_vista_proyeccion().try_inverse()?;
+        let desproyectar = |z: f32| {
+            let punto = inversa * nalgebra_glm::vec4(x, y, z, 1.0);
+            punto.xyz() / punto.w
+        };
+        let (cerca, lejos) = (desproyectar(0.0), desproyectar(1.0));
+        let direccion = lejos - cerca;
+        (direccion.norm() > f32::EPSILON).then(|| (cerca, direccion.normalize()))
+    }
+
+    /// Punto del plano de la eclíptica (z = 0) bajo el cursor
+    fn punto_ecliptica_bajo_cursor(&self, cursor: winit::dpi::PhysicalPosition<f64>) -> Option<Vec3> {
+        let (origen, direccion) = self.rayo_desde_cursor(cursor)?;
+        if direccion.z.abs() < 1e-6 {
+            return None;
+        }
+        let t = -origen.z / direccion.z;
+        (t > 0.0).then(|| origen + direccion * t)
+    }
+
+    fn arrastrar_extremo(&mut self, cursor: winit::dpi::PhysicalPosition<f64>) {
+        let Some(punto) = self.punto_ecliptica_bajo_cursor(cursor) else { return };
+        let Some(indice) = self.planeta_seleccionado else { return };
+        let origen = self.grafo.origen(indice);
+        let editor = &self.editor;
+        let cuerpo = &mut self.cuerpos[indice];
+        if let Some(orbita) = &mut cuerpo.orbita {
+            editor.arrastrar(orbita, (punto - origen).xy().norm());
+            cuerpo.posicion_local = orbita.posicion(self.datos_uniformes.tiempo_actual);
+            self.grafo.actualizar(&mut self.cuerpos);
+        }
+    }
+
+    /// Un click es una pulsación soltada casi en el mismo lugar (no un arrastre de cámara)
+    fn es_click(&self) -> bool {
+        match (self.posicion_presion, self.posicion_mouse) {
+            (Some(inicio), Some(fin)) => {
+                let dx = fin.x - inicio.x;
+                let dy = fin.y - inicio.y;
+                dx * dx + dy * dy < 16.0
+            }
+            _ => false,
+        }
+    }
+
+    /// Entra en la lista de dibujo: visible en el árbol y con su capa activa
+    fn cuerpo_dibujable(&self, indice: usize) -> bool {
+        self.capas.muestra(self.cuerpos[indice].clase) && self.cuerpo_visible(indice)
+    }
+
+    /// Visible si ni el cuerpo ni ninguno de sus ancestros están ocultos
+    fn cuerpo_visible(&self, indice: usize) -> bool {
+        CuerpoCeleste::visible_en(&self.cuerpos, indice)
+    }
+
+    /// Hace que la cámara siga al cuerpo dado, o vuelve al centro de la escena
+    fn enfocar(&mut self, foco: Option<usize>) {
+        self.simulacion().enfocar(foco);
+    }
+
+    /// Presta a `Simulacion` el reloj, los cuerpos y la cámara
+    fn simulacion(&mut self) -> Simulacion<'_> {
+        Simulacion {
+            reloj: &mut self.reloj,
+            camara: &mut self.camara,
+            cuerpos: &mut self.cuerpos,
+            grafo: &mut self.grafo,
+            fisica: &mut self.fisica,
+            fila: &mut self.fila,
+            animaciones: &self.animaciones,
+            capas: &self.capas,
+            foco: &mut self.foco,
+        }
+    }
+
+    /// Deja fijo en el origen al cuerpo dado (p. ej. la Tierra para la vista geocéntrica) o
+    /// vuelve al marco de la escena. Las estelas se rehacen en el marco nuevo y una cámara sin
+    /// foco se traslada con la escena para que el cuadro no salte
+    fn cambiar_marco(&mut self, marco: Option<usize>) {
+        let antes = self.grafo.centro_escena();
+        self.grafo.marco = marco;
+        self.grafo.actualizar(&mut self.cuerpos);
+        self.estelas.vaciar();
+        if self.foco.is_none() {
+            let objetivo = self.camara.objetivo + self.grafo.centro_escena() - antes;
+            self.camara.enfocar(objetivo);
+        }
+        match marco {
+            Some(indice) => println!("Marco de referencia: centrado en {}", self.cuerpos[indice].nombre),
+            None => println!("Marco de referencia: el de la escena"),
+        }
+    }
+
+    /// Enlace que reproduce la vista y el instante actuales
+    fn enlace_vista(&self) -> EnlaceVista {
+        let objetivo = self.camara.objetivo;
+        EnlaceVista {
+            guinada: self.camara.guinada,
+            cabeceo: self.camara.cabeceo,
+            radio: self.camara.radio,
+            objetivo: [objetivo.x, objetivo.y, objetivo.z],
+            foco: self.foco.map(|i| self.cuerpos[i].nombre.clone()),
+            marco: self.grafo.marco.map(|i| self.cuerpos[i].nombre.clone()),
+            tiempo: self.reloj.tiempo,
+            escala: self.reloj.escala,
+            pausado: self.reloj.pausado,
+            invertido: self.reloj.invertido,
+        }
+    }
+
+    /// Interpreta y aplica un enlace; los campos que omita conservan la vista actual
+    fn abrir_enlace(&mut self, texto: &str) -> Result<(), String> {
+        let enlace = EnlaceVista::interpretar(texto, self.enlace_vista())?;
+        self.aplicar_enlace(&enlace)
+    }
+
+    /// Restaura la vista de un enlace; falla si nombra un cuerpo que no está en la escena
+    fn aplicar_enlace(&mut self, enlace: &EnlaceVista) -> Result<(), String> {
+        let buscar = |nombre: &Option<String>, uso: &str| -> Result<Option<usize>, String> {
+            match nombre {
+                Some(nombre) => Some(
+                    self.cuerpos
+                        .iter()
+                        .position(|c| c.nombre.eq_ignore_ascii_case(nombre))
+                        .ok_or_else(|| format!("el enlace {} '{}', que no está en la escena", uso, nombre)),
+                )
+                .transpose(),
+                None => Ok(None),
+            }
+        };
+        let foco = buscar(&enlace.foco, "enfoca")?;
+        let marco = buscar(&enlace.marco, "centra el marco en")?;
+        if marco != self.grafo.marco {
+            self.grafo.marco = marco;
+            self.estelas.vaciar();
+        }
+        self.reloj.tiempo = enlace.tiempo;
+        self.reloj.escala = enlace.escala;
+        self.reloj.pausado = enlace.pausado;
+        self.reloj.invertido = enlace.invertido;
+        // El radio del enlace puede estar cerca de la superficie del foco
+        self.camara.superficie = foco.map_or(0.0, |i| self.cuerpos[i].radio_superficie());
+        self.camara.orientar(enlace.guinada, enlace.cabeceo, enlace.radio);
+        self.actualizar_a(self.reloj.tiempo as f32);
+        match foco {
+            // El enlace reproduce la vista tal cual, sin deslizarse hasta ella
+            Some(_) => {
+                self.enfocar(foco);
+                self.camara.terminar_transicion();
+            }
+            None => {
+                self.foco = None;
+                self.camara.transicion = 0.0;
+                self.camara.superficie = 0.0;
+                let [x, y, z] = enlace.objetivo;
+                self.camara.enfocar(Vec3::new(x, y, z));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cambia de escena sin reiniciar: se rehacen los cuerpos y lo que depende de ellos
+    /// (anillos, mapas, cinturón, física, selección) y se conservan el dispositivo, la
+    /// superficie, los pipelines, la cámara y el reloj
+    fn cambiar_escena(&mut self, escena: ArchivoEscena) -> Result<(), String> {
+        let mut cuerpos = escena.cuerpos()?;
+        self.grafo = GrafoEscena::default();
+        self.grafo.actualizar(&mut cuerpos);
+        self.animaciones = escena.animaciones(&cuerpos).unwrap_or_else(|error| {
+            eprintln!("Animaciones desactivadas: {}", error);
+            Vec::new()
+        });
+        self.mallas_anillos = cuerpos
+            .iter()
+            .map(|cuerpo| cuerpo.anillo.as_ref().map(|anillo| MallaAnillo::nueva(&self.dispositivo, anillo)))
+            .collect();
+        self.mallas_modelos = Self::pedir_modelos(&mut self.modelos_escena, &cuerpos);
+        self.texturas.cargar(&self.dispositivo, &self.cola_comandos, &cuerpos, &escena.texturas);
+        self.cinturon = escena.cinturon.filter(|_| self.cinturon_soportado).map(|definicion| {
+            CinturonAsteroides::nuevo(
+                &self.dispositivo,
+                &self.modulo_shader,
+                self.configuracion.format,
+                &self.layout_uniformes,
+                &definicion,
+            )
+        });
+        self.cuerpos = cuerpos;
+        self.escena = escena;
+
+        // Todo lo que guarda índices de cuerpos de la escena anterior
+        self.planeta_seleccionado = None;
+        self.foco = None;
+        self.telescopio.objetivo = None;
+        self.editor.arrastre = None;
+        self.medicion = HerramientaMedicion::default();
+        self.quiz = ModoQuiz::nuevo();
+        self.fisica = SimulacionNCuerpos::nueva();
+        self.estelas = EstelasOrbitales::default();
+        self.fila.descartar();
+        self.detector_eventos = DetectorEventos::default();
+        self.particulas.vaciar();
+        self.actualizar_a(self.reloj.tiempo as f32);
+        Ok(())
+    }
+
+    /// Carga y aplica una escena; si falla se conserva la actual
+    fn cargar_escena(&mut self, ruta: &std::path::Path) -> Result<(), String> {
+        ArchivoEscena::cargar(ruta).and_then(|escena| self.cambiar_escena(escena))?;
+        println!("Escena cargada: {} ({} cuerpos)", ruta.display(), self.cuerpos.len());
+        Ok(())
+    }
+
+    /// Aplica lo que cambió en disco de la escena y de sus recursos
+    fn revisar_escena(&mut self) {
+        let cambios = self.recarga_escena.revisar(&self.escena);
+        if let (true, Some(ruta)) = (cambios.escena, self.escena.ruta.clone()) {
+            self.aviso_escena = ArchivoEscena::cargar(&ruta).and_then(|escena| self.parchear_escena(escena)).err();
+            match &self.aviso_escena {
+                None => println!("Escena recargada: {}", ruta.display()),
+                Some(error) => eprintln!("Escena sin recargar, se conserva la anterior: {}", error),
+            }
+        }
+        for ruta in cambios.recursos {
+            self.recargar_recurso(&ruta);
+        }
+    }
+
+    /// Versión nueva de la escena actual. Con los mismos cuerpos (por nombre y en orden) se
+    /// reemplazan sus parámetros y se conservan la selección, el foco, el marco, las estelas
+    /// y los cuerpos ocultos; solo se vuelven a leer los mapas, modelos y el cinturón que
+    /// cambiaron. Si los cuerpos son otros se cambia de escena
+    fn parchear_escena(&mut self, escena: ArchivoEscena) -> Result<(), String> {
+        let nombres = |escena: &ArchivoEscena| escena.cuerpo.iter().map(|c| c.nombre.clone()).collect::<Vec<_>>();
+        if nombres(&escena) != nombres(&self.escena) || self.cuerpos.len() != self.escena.cuerpo.len() {
+            return self.cambiar_escena(escena);
+        }
+        let mut cuerpos = escena.cuerpos()?;
+        for (nuevo, viejo) in cuerpos.iter_mut().zip(&self.cuerpos) {
+            nuevo.visible = viejo.visible;
+        }
+        self.fila.descartar();
+        self.grafo.actualizar(&mut cuerpos);
+        self.animaciones = escena.animaciones(&cuerpos).unwrap_or_else(|error| {
+            eprintln!("Animaciones desactivadas: {}", error);
+            Vec::new()
+        });
+        self.mallas_anillos = cuerpos
+            .iter()
+            .map(|cuerpo| cuerpo.anillo.as_ref().map(|anillo| MallaAnillo::nueva(&self.dispositivo, anillo)))
+            .collect();
+        for (indice, (nuevo, viejo)) in cuerpos.iter().zip(&self.cuerpos).enumerate() {
+            if nuevo.modelo != viejo.modelo {
+                self.mallas_modelos[indice] = None;
+                if let Some(ruta) = nuevo.modelo.clone() {
+                    self.modelos_escena.pedir(indice, move || ModeloOBJ::abrir(&ruta));
+                }
+            }
+        }
+        let superficies = |cuerpos: &[CuerpoCeleste]| -> Vec<(u32, Option<Relieve>)> {
+            cuerpos.iter().map(|c| (c.tipo_shader, c.relieve.clone())).collect()
+        };
+        if escena.texturas != self.escena.texturas || superficies(&cuerpos) != superficies(&self.cuerpos) {
+            self.texturas.cargar(&self.dispositivo, &self.cola_comandos, &cuerpos, &escena.texturas);
+        }
+        if escena.cinturon != self.escena.cinturon {
+            self.cinturon = escena.cinturon.filter(|_| self.cinturon_soportado).map(|definicion| {
+                CinturonAsteroides::nuevo(
+                    &self.dispositivo,
+                    &self.modulo_shader,
+                    self.configuracion.format,
+                    &self.layout_uniformes,
+                    &definicion,
+                )
+            });
+        }
+        self.cuerpos = cuerpos;
+        self.escena = escena;
+        self.actualizar_a(self.reloj.tiempo as f32);
+        Ok(())
+    }
+
+    /// Vuelve a leer un mapa, relieve o modelo de la escena que cambió en disco
+    fn recargar_recurso(&mut self, ruta: &std::path::Path) {
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            if let Some(mapa) = self.escena.texturas.get(&cuerpo.nombre).filter(|m| m.rutas().iter().any(|r| r == ruta)) {
+                self.texturas.asignar_mapa(&self.dispositivo, indice, mapa.clone());
+            }
+            if let Some(relieve) = cuerpo.relieve.as_ref().filter(|r| r.mapa == ruta) {
+                self.texturas.asignar_relieve(&self.dispositivo, indice, relieve);
+            }
+            if cuerpo.modelo.as_deref() == Some(ruta) {
+                let ruta = ruta.to_path_buf();
+                self.modelos_escena.pedir(indice, move || ModeloOBJ::abrir(&ruta));
+            }
+        }
+        println!("Recargado: {}", ruta.display());
+    }
+
+    /// Archivo soltado sobre la ventana: una escena reemplaza a la actual, una imagen pasa a
+    /// ser el mapa del cuerpo seleccionado y un modelo (OBJ o glTF) se agrega como cuerpo en el foco
+    fn soltar_archivo(&mut self, ruta: &std::path::Path) {
+        let extension = ruta
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let resultado = match extension.as_str() {
+            "toml" => self.cargar_escena(ruta),
+            "png" | "jpg" | "jpeg" | "hdr" => self.asignar_mapa(ruta),
+            "obj" | "gltf" | "glb" => {
+                let ruta = ruta.to_path_buf();
+                self.modelos.pedir(0, move || ModeloOBJ::abrir(&ruta).map(|modelo| (ruta, modelo)));
+                Ok(())
+            }
+            _ => Err("solo se aceptan escenas .toml, imágenes (png, jpg, hdr) y modelos .obj, .gltf o .glb".to_string()),
+        };
+        if let Err(error) = resultado {
+            eprintln!("{} ignorado: {}", ruta.display(), error);
+        }
+    }
+
+    /// Usa la imagen como mapa del cuerpo seleccionado y la anota en la escena
+    fn asignar_mapa(&mut self, ruta: &std::path::Path) -> Result<(), String> {
+        let indice = self
+            .planeta_seleccionado
+            .ok_or("selecciona primero el cuerpo que recibe la imagen")?;
+        self.texturas.asignar_mapa(&self.dispositivo, indice, MapaSuperficie::Imagen(ruta.to_path_buf()));
+        let nombre = self.cuerpos[indice].nombre.clone();
+        println!("Textura de {}: {}", nombre, ruta.display());
+        self.escena.texturas.insert(nombre, MapaSuperficie::Imagen(ruta.to_path_buf()));
+        Ok(())
+    }
+
+    /// Pide en segundo plano la malla de cada cuerpo con `modelo`; hasta que llega se ve la esfera
+    fn pedir_modelos(
+        decodificador: &mut DecodificadorRecursos<ModeloOBJ>,
+        cuerpos: &[CuerpoCeleste],
+    ) -> Vec<Option<MallaModelo>> {
+        decodificador.reiniciar();
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            if let Some(ruta) = cuerpo.modelo.clone() {
+                decodificador.pedir(indice, move || ModeloOBJ::abrir(&ruta));
+            }
+        }
+        cuerpos.iter().map(|_| None).collect()
+    }
+
+    /// Sube las mallas de la escena ya leídas; con `esperar`, bloquea hasta tenerlas todas
+    fn recibir_mallas_modelos(&mut self, esperar: bool) {
+        let listos = if esperar { self.modelos_escena.esperar() } else { self.modelos_escena.recibir() };
+        for (indice, resultado) in listos {
+            let Some(cuerpo) = self.cuerpos.get(indice) else { continue };
+            match resultado {
+                Ok(modelo) => {
+                    self.mallas_modelos[indice] =
+                        Some(MallaModelo::nueva(&self.dispositivo, &modelo, self.formato_vertices));
+                }
+                Err(error) => eprintln!("Modelo de {} ignorado, se dibuja la esfera: {}", cuerpo.nombre, error),
+            }
+        }
+    }
+
+    /// Agrega como cuerpos los modelos que terminaron de leerse
+    fn recibir_modelos(&mut self) {
+        for (_, resultado) in self.modelos.recibir() {
+            match resultado.and_then(|(ruta, modelo)| self.agregar_modelo(&ruta, &modelo)) {
+                Ok(indice) => self.planeta_seleccionado = Some(indice),
+                Err(error) => eprintln!("Modelo ignorado: {}", error),
+            }
+        }
+    }
+
+    /// Cuerpo fijo a un lado del foco de la cámara, con el nombre del archivo
+    fn agregar_modelo(&mut self, ruta: &std::path::Path, modelo: &ModeloOBJ) -> Result<usize, String> {
+        const ESCALA: f32 = 0.05;
+        let base = ruta.file_stem().map_or("Modelo".into(), |n| n.to_string_lossy());
+        let mut nombre = base.to_string();
+        let mut copia = 1;
+        while self.cuerpos.iter().any(|c| c.nombre == nombre) {
+            copia += 1;
+            nombre = format!("{} {}", base, copia);
+        }
+        let posicion = match self.foco {
+            Some(foco) => {
+                let cuerpo = &self.cuerpos[foco];
+                [cuerpo.posicion[0] + cuerpo.escala * 1.5 + ESCALA, cuerpo.posicion[1]]
+            }
+            None => [self.camara.objetivo.x, self.camara.objetivo.y],
+        };
+        let definicion = DefinicionCuerpo {
+            nombre,
+            posicion,
+            escala: ESCALA,
+            shader: "rocoso".to_string(),
+            radio_km: 10.0,
+            magnitud_absoluta: 12.0,
+            periodo: None,
+            padre: None,
+            clase: Some(ClaseCuerpo::Asteroide),
+            color: None,
+            inclinacion_grados: 0.0,
+            anillo: None,
+            material: None,
+            atmosfera: None,
+            modelo: Some(ruta.to_path_buf()),
+            efecto: None,
+            fragmento: None,
+            relieve: None,
+            magnetosfera: None,
+            caricatura: false,
+        };
+        let indice = self.agregar_cuerpo(definicion)?;
+        self.mallas_modelos[indice] = Some(MallaModelo::nueva(&self.dispositivo, modelo, self.formato_vertices));
+        println!(
+            "Modelo {} ({} caras, {} grupos de material) agregado como '{}'",
+            ruta.display(),
+            modelo.cantidad_caras(),
+            modelo.grupos().len(),
+            self.cuerpos[indice].nombre
+        );
+        Ok(indice)
+    }
+
+    /// Agrega un cuerpo a la escena cargada sin rehacer los demás
+    fn agregar_cuerpo(&mut self, definicion: DefinicionCuerpo) -> Result<usize, String> {
+        let cuerpo = definicion.resolver(&self.cuerpos)?;
+        self.texturas.agregar_cuerpo(&self.dispositivo, &cuerpo);
+        self.mallas_anillos
+            .push(cuerpo.anillo.as_ref().map(|anillo| MallaAnillo::nueva(&self.dispositivo, anillo)));
+        self.mallas_modelos.push(None);
+        self.cuerpos.push(cuerpo);
+        self.escena.cuerpo.push(definicion);
+        self.grafo.actualizar(&mut self.cuerpos);
+        if self.fisica.activa {
+            self.fisica.iniciar(&self.cuerpos, self.reloj.tiempo);
+        }
+        Ok(self.cuerpos.len() - 1)
+    }
+
+    /// Atiende las órdenes escritas en la terminal desde el último cuadro
+    fn atender_consola(&mut self) {
+        let Some(consola) = &self.consola else { return };
+        let lineas: Vec<String> = consola.try_iter().collect();
+        for linea in lineas {
+            let (orden, argumento) = linea.trim().split_once(' ').unwrap_or((linea.trim(), ""));
+            match (orden, argumento.trim()) {
+                ("", _) => {}
+                ("escena", ruta) if !ruta.is_empty() => {
+                    if let Err(error) = self.cargar_escena(std::path::Path::new(ruta)) {
+                        eprintln!("Error en la escena: {}", error);
+                    }
+                }
+                ("escena", _) => self.abrir_escena(),
+                _ => eprintln!("Orden desconocida '{}'. Órdenes: escena [archivo.toml]", linea.trim()),
+            }
+        }
+    }
+
+    /// Entrega un evento de ventana a la interfaz; `true` si no debe llegar a la escena
+    fn procesar_evento_interfaz(&mut self, evento: &WindowEvent) -> bool {
+        match (&mut self.interfaz, &self.ventana) {
+            (Some(interfaz), Some(ventana)) => interfaz.procesar_evento(ventana, evento),
+            _ => false,
+        }
+    }
+
+    fn construir_interfaz(&mut self, contexto: &egui::Context) {
+        egui::Window::new("Escena")
+            .default_pos([10.0, 10.0])
+            .resizable(false)
+            .show(contexto, |ui| {
+                let raices: Vec<usize> = (0..self.cuerpos.len())
+                    .filter(|&i| self.cuerpos[i].padre.is_none())
+                    .collect();
+                for raiz in raices {
+                    self.nodo_arbol_escena(ui, raiz);
+                }
+                if self.foco.is_some() && ui.button("Volver al centro").clicked() {
+                    self.enfocar(None);
+                }
+                let mut marco = self.grafo.marco;
+                egui::ComboBox::from_label("Marco (H)")
+                    .selected_text(marco.map_or("De la escena", |i| self.cuerpos[i].nombre.as_str()).to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut marco, None, "De la escena");
+                        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+                            ui.selectable_value(&mut marco, Some(indice), &cuerpo.nombre);
+                        }
+                    });
+                if marco != self.grafo.marco {
+                    self.cambiar_marco(marco);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Abrir escena…").clicked() {
+                        self.abrir_escena();
+                    }
+                    if ui.button("Guardar captura…").clicked() {
+                        self.guardar_captura_como();
+                    }
+                    ui.checkbox(&mut self.recarga_escena.activa, "Recargar al guardar")
+                        .on_hover_text("La escena, sus mapas, relieves y modelos");
+                });
+                ui.label(match &self.escena.ruta {
+                    Some(ruta) => format!("Escena: {}", ruta.display()),
+                    None => "Escena: incluida".to_string(),
+                });
+                if let Some(aviso) = &self.aviso_escena {
+                    ui.colored_label(egui::Color32::LIGHT_RED, aviso);
+                }
+
+                ui.separator();
+                ui.label(self.texturas.describir_memoria());
+                ui.label(self.cache_grupos.describir());
+                ui.label(self.anillo.describir());
+                ui.label(format!(
+                    "Vértices de esfera: {} × {} B ({:?})",
+                    self.cantidad_vertices,
+                    self.formato_vertices.tamano(),
+                    self.formato_vertices
+                ));
+                ui.label(self.pipelines.describir());
+                let mut cambiadas = false;
+                egui::CollapsingHeader::new("Capas").default_open(true).show(ui, |ui| {
+                    for (nombre, activa) in self.capas.campos_mut() {
+                        cambiadas |= ui.checkbox(activa, nombre).changed();
+                    }
+                });
+                let filtro = &mut self.posproceso.filtro;
+                egui::ComboBox::from_label("Filtro")
+                    .selected_text(filtro.nombre())
+                    .show_ui(ui, |ui| {
+                        for opcion in FiltroPosproceso::TODOS {
+                            ui.selectable_value(filtro, opcion, opcion.nombre());
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut self.posproceso.escala, 1..=Posproceso::ESCALA_MAXIMA).text("Pixelado"));
+                if self.posproceso.escala > 1 {
+                    ui.checkbox(&mut self.posproceso.paleta, "Paleta de 16 colores");
+                }
+                if cambiadas {
+                    if let Err(error) = self.capas.guardar(&self.ruta_ajustes) {
+                        eprintln!("No se guardaron las capas: {}", error);
+                    }
+                }
+            });
+
+        egui::Window::new("Física")
+            .default_pos([contexto.screen_rect().right() - 230.0, 10.0])
+            .resizable(false)
+            .show(contexto, |ui| self.panel_fisica(ui));
+
+        egui::Window::new("Depuración")
+            .default_pos([10.0, contexto.screen_rect().bottom() - 190.0])
+            .resizable(false)
+            .show(contexto, |ui| self.panel_depuracion(ui));
+
+        if self.kepler.activo {
+            let pantalla = contexto.screen_rect();
+            egui::Window::new("Tercera ley de Kepler")
+                .default_pos([pantalla.right() - 270.0, pantalla.bottom() - 250.0])
+                .resizable(false)
+                .show(contexto, |ui| self.grafica_kepler(ui));
+        }
+    }
+
+    /// T² contra a³ de cada órbita, agrupadas por el cuerpo central: las de un mismo centro
+    /// caen sobre una recta por el origen, la ajustada por mínimos cuadrados
+    fn grafica_kepler(&self, ui: &mut egui::Ui) {
+        let mut grupos: Vec<(Option<usize>, Vec<[f64; 2]>)> = Vec::new();
+        let mut seleccion = None;
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            let Some(orbita) = cuerpo.orbita else { continue };
+            let punto = [(orbita.semieje_mayor as f64).powi(3), (orbita.periodo as f64).powi(2)];
+            if self.planeta_seleccionado == Some(indice) {
+                seleccion = Some((cuerpo.nombre.as_str(), punto));
+            }
+            match grupos.iter_mut().find(|(padre, _)| *padre == cuerpo.padre) {
+                Some((_, puntos)) => puntos.push(punto),
+                None => grupos.push((cuerpo.padre, vec![punto])),
+            }
+        }
+        if grupos.is_empty() {
+            ui.label("La escena no tiene órbitas");
+            return;
+        }
+
+        egui_plot::Plot::new("kepler")
+            .height(160.0)
+            .width(240.0)
+            .x_axis_label("a³ (u³)")
+            .y_axis_label("T² (s²)")
+            .legend(egui_plot::Legend::default())
+            .show(ui, |grafica| {
+                for (padre, puntos) in grupos {
+                    let centro = padre.map_or("el origen", |i| self.cuerpos[i].nombre.as_str());
+                    let suma_xy: f64 = puntos.iter().map(|[x, y]| x * y).sum();
+                    let suma_xx: f64 = puntos.iter().map(|[x, _]| x * x).sum();
+                    let maximo = puntos.iter().map(|[x, _]| *x).fold(0.0, f64::max) * 1.1;
+                    if suma_xx > 0.0 {
+                        let pendiente = suma_xy / suma_xx;
+                        let recta = vec![[0.0, 0.0], [maximo, maximo * pendiente]];
+                        grafica.line(egui_plot::Line::new(recta).name(format!("T²/a³ = {:.1}", pendiente)));
+                    }
+                    grafica.points(
+                        egui_plot::Points::new(puntos)
+                            .radius(3.0)
+                            .name(format!("Alrededor de {}", centro)),
+                    );
+                }
+                if let Some((nombre, punto)) = seleccion {
+                    grafica.points(egui_plot::Points::new(vec![punto]).radius(6.0).name(nombre));
+                }
+            });
+        ui.label("Shift+K cierra el modo; los sectores son del cuerpo seleccionado");
+    }
+
+    /// Ajustes en vivo del cuerpo seleccionado (los mismos que define `escena.toml`) y de la cámara
+    fn panel_depuracion(&mut self, ui: &mut egui::Ui) {
+        let nombre_seleccion = self
+            .planeta_seleccionado
+            .map_or("(ninguno)", |i| self.cuerpos[i].nombre.as_str())
+            .to_string();
+        egui::ComboBox::from_label("Cuerpo")
+            .selected_text(nombre_seleccion)
+            .show_ui(ui, |ui| {
+                for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+                    ui.selectable_value(&mut self.planeta_seleccionado, Some(indice), &cuerpo.nombre);
+                }
+            });
+
+        if let Some(indice) = self.planeta_seleccionado {
+            let tiempo = self.datos_uniformes.tiempo_actual;
+            let cuerpo = &mut self.cuerpos[indice];
+            ui.add(
+                egui::Slider::new(&mut cuerpo.escala, 0.01..=1.0)
+                    .logarithmic(true)
+                    .text("Escala"),
+            );
+            if let Some(orbita) = &mut cuerpo.orbita {
+                let mut periodo = orbita.periodo;
+                let cambio = ui
+                    .add(
+                        egui::Slider::new(&mut periodo, 1.0..=600.0)
+                            .logarithmic(true)
+                            .text("Periodo (s)"),
+                    )
+                    .changed();
+                if cambio {
+                    orbita.cambiar_periodo(periodo, tiempo);
+                }
+            }
+            egui::ComboBox::from_label("Shader")
+                .selected_text(nombre_tipo_shader(cuerpo.tipo_shader))
+                .show_ui(ui, |ui| {
+                    for (tipo, nombre) in TIPOS_SHADER {
+                        ui.selectable_value(&mut cuerpo.tipo_shader, tipo, nombre);
+                    }
+                });
+            ui.checkbox(&mut cuerpo.caricatura, "Caricatura (B: todos)");
+        }
+
+        ui.separator();
+        let frustum = Frustum::de_camara(&self.camara.matriz_vista(), &self.camara.matriz_proyeccion(self.aspecto()));
+        let dibujables: Vec<&CuerpoCeleste> = (0..self.cuerpos.len())
+            .filter(|&indice| self.cuerpo_dibujable(indice))
+            .map(|indice| &self.cuerpos[indice])
+            .collect();
+        let fuera = dibujables
+            .iter()
+            .filter(|cuerpo| !frustum.contiene_esfera(&cuerpo.posicion_3d(), cuerpo.radio_envolvente()))
+            .count();
+        ui.label(format!("Fuera de cuadro: {} de {} cuerpos", fuera, dibujables.len()));
+        if self.adaptadores.len() > 1 && self.backend != wgpu::Backend::Gl {
+            let mut elegido = self.adaptador_actual.clone();
+            egui::ComboBox::from_label("GPU")
+                .selected_text(elegido.as_str())
+                .show_ui(ui, |ui| {
+                    for etiqueta in &self.adaptadores {
+                        ui.selectable_value(&mut elegido, etiqueta.clone(), etiqueta);
+                    }
+                });
+            if elegido != self.adaptador_actual {
+                self.adaptador_pedido = Some(elegido);
+            }
+        } else if self.adaptadores.len() > 1 {
+            ui.label(format!("GPU: {}", self.adaptador_actual))
+                .on_hover_text("Con OpenGL se elige en [gpu] de los ajustes, al reiniciar");
+        } else {
+            ui.label(format!("GPU: {}", self.adaptador_actual));
+        }
+        ui.checkbox(&mut self.ajustes_pantalla.latencia_baja, "Latencia baja")
+            .on_hover_text("Un cuadro en cola; la espera del refresco pasa a antes de leer la entrada");
+        ui.label(match self.latencia.promedio {
+            Some(promedio) => format!("Entrada → presentación: {:.1} ms", promedio * 1000.0),
+            None => String::from("Entrada → presentación: arrastra o usa la rueda"),
+        });
+        if ui.button("Reiniciar cámara").clicked() {
+            self.camara = CamaraVirtual::inicial();
+            self.enfocar(None);
+        }
+    }
+
+    /// Parámetros del modo N cuerpos; cambiar G o el integrador reinicia la referencia de energía
+    fn panel_fisica(&mut self, ui: &mut egui::Ui) {
+        let tiempo = self.datos_uniformes.tiempo_actual as f64;
+        let fisica = &mut self.fisica;
+        if ui.checkbox(&mut fisica.activa, "Modo N cuerpos").changed() && fisica.activa {
+            fisica.iniciar(&self.cuerpos, tiempo);
+        }
+
+        let cambio_g = ui
+            .add(
+                egui::Slider::new(&mut fisica.constante_gravitacional, 0.001..=0.1)
+                    .logarithmic(true)
+                    .text("G"),
+            )
+            .changed();
+        let integrador_anterior = fisica.integrador;
+        egui::ComboBox::from_label("Integrador")
+            .selected_text(fisica.integrador.nombre())
+            .show_ui(ui, |ui| {
+                for integrador in Integrador::TODOS {
+                    ui.selectable_value(&mut fisica.integrador, integrador, integrador.nombre());
+                }
+            });
+        ui.add(
+            egui::Slider::new(&mut fisica.paso, 0.001..=1.0)
+                .logarithmic(true)
+                .text("Paso (s)"),
+        );
+        ui.add(egui::Slider::new(&mut fisica.apertura, 0.0..=1.0).text("θ Barnes–Hut"));
+        ui.label(if fisica.cantidad_cuerpos() > SimulacionNCuerpos::UMBRAL_BARNES_HUT {
+            format!("Gravedad: Barnes–Hut ({} cuerpos)", fisica.cantidad_cuerpos())
+        } else {
+            format!(
+                "Gravedad: suma directa (Barnes–Hut desde {} cuerpos)",
+                SimulacionNCuerpos::UMBRAL_BARNES_HUT + 1
+            )
+        });
+        if cambio_g || fisica.integrador != integrador_anterior {
+            fisica.reiniciar_energia();
+        }
+        ui.add(
+            egui::Slider::new(&mut self.paso_fijo.pasos_por_segundo, 0..=120)
+                .text("Pasos/s (0 = por cuadro)"),
+        );
+
+        if fisica.activa {
+            ui.label(format!("Deriva de energía: {:+.3e}", fisica.deriva_energia()));
+            egui::CollapsingHeader::new("Conservación").show(ui, |ui| {
+                let serie = |valor: fn(&MuestraConservacion) -> f64| -> egui_plot::PlotPoints {
+                    fisica.historial.iter().map(|m| [m.tiempo, valor(m)]).collect()
+                };
+                egui_plot::Plot::new("energia")
+                    .height(120.0)
+                    .width(200.0)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |grafica| {
+                        grafica.line(egui_plot::Line::new(serie(|m| m.cinetica)).name("Cinética"));
+                        grafica.line(egui_plot::Line::new(serie(|m| m.potencial)).name("Potencial"));
+                        grafica.line(egui_plot::Line::new(serie(MuestraConservacion::energia)).name("Total"));
+                    });
+                egui_plot::Plot::new("momento_angular")
+                    .height(80.0)
+                    .width(200.0)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |grafica| {
+                        grafica.line(egui_plot::Line::new(serie(|m| m.momento_angular)).name("|L|"));
+                    });
+            });
+            if ui.button("Reiniciar desde las órbitas").clicked() {
+                for cuerpo in &mut self.cuerpos {
+                    if let Some(orbita) = cuerpo.orbita {
+                        cuerpo.posicion_local = orbita.posicion(tiempo as f32);
+                    }
+                }
+                self.grafo.actualizar(&mut self.cuerpos);
+                self.fisica.iniciar(&self.cuerpos, tiempo);
+            }
+        }
+    }
+
+    /// Fila del árbol de la escena: casilla de visibilidad, nombre (click = enfocar) e hijos
+    fn nodo_arbol_escena(&mut self, ui: &mut egui::Ui, indice: usize) {
+        let hijos: Vec<usize> = (0..self.cuerpos.len())
+            .filter(|&i| self.cuerpos[i].padre == Some(indice))
+            .collect();
+        let fila = |estado: &mut Self, ui: &mut egui::Ui| {
+            ui.checkbox(&mut estado.cuerpos[indice].visible, "");
+            let enfocado = estado.foco == Some(indice);
+            if ui.selectable_label(enfocado, &estado.cuerpos[indice].nombre).clicked() {
+                estado.enfocar((!enfocado).then_some(indice));
+            }
+        };
+
+        if hijos.is_empty() && self.cuerpos[indice].anillo.is_none() {
+            ui.horizontal(|ui| fila(self, ui));
+        } else {
+            let id = ui.make_persistent_id(("nodo_escena", indice));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| fila(self, ui))
+                .body(|ui| {
+                    if let Some(anillo) = &mut self.cuerpos[indice].anillo {
+                        ui.checkbox(&mut anillo.visible, "Anillos");
+                    }
+                    for hijo in hijos {
+                        self.nodo_arbol_escena(ui, hijo);
+                    }
+                });
+        }
+    }
+
+    fn procesar_seleccion(&mut self) {
+        let cuerpo = self.cuerpo_bajo_cursor();
+        if self.quiz.activo && self.quiz.responder_click(cuerpo, self.cuerpos.len()) {
+            return;
+        }
+        if self.medicion.activa {
+            if let Some(indice) = cuerpo {
+                self.medicion.registrar(indice);
+            }
+        } else if cuerpo != self.planeta_seleccionado {
+            self.planeta_seleccionado = cuerpo;
+            self.eventos.emitir_seleccion(cuerpo.map(|i| &self.cuerpos[i]));
+        }
+    }
+
+    /// El observador de las mediciones es la propia cámara
+    fn posicion_observador(&self) -> Vec3 {
+        self.camara.ojo
+    }
+
+    fn aspecto(&self) -> f32 {
+        self.tamano_ventana.width as f32 / self.tamano_ventana.height.max(1) as f32
+    }
+
+    fn matriz_vista_proyeccion(&self) -> Mat4 {
+        self.camara.matriz_proyeccion(self.aspecto()) * self.camara.matriz_vista()
+    }
+
+    /// Proyección gnomónica de una dirección del cielo; `None` si queda detrás del observador
+    fn proyectar_direccion_cielo(&self, vista_proyeccion: &Mat4, direccion: &Vec3) -> Option<[f32; 2]> {
+        self.proyectar_cielo(vista_proyeccion, nalgebra_glm::vec4(direccion.x, direccion.y, direccion.z, 0.0))
+    }
+
+    /// Proyección en perspectiva de un punto de la escena, con la misma cámara que el cielo
+    fn proyectar_punto_cielo(&self, vista_proyeccion: &Mat4, punto: &Vec3) -> Option<[f32; 2]> {
+        self.proyectar_cielo(vista_proyeccion, nalgebra_glm::vec4(punto.x, punto.y, punto.z, 1.0))
+    }
+
+    fn proyectar_cielo(&self, vista_proyeccion: &Mat4, homogeneo: nalgebra_glm::Vec4) -> Option<[f32; 2]> {
+        let recorte = vista_proyeccion * homogeneo;
+        if recorte.w < 0.05 {
+            return None;
+        }
+        Some([recorte.x / recorte.w, recorte.y / recorte.w])
+    }
+
+    fn agregar_constelaciones(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        let vista = self.matriz_vista_proyeccion();
+        let color = [0.35, 0.5, 0.8];
+        for constelacion in &self.constelaciones {
+            let proyectadas: Vec<Option<[f32; 2]>> = constelacion
+                .direcciones
+                .iter()
+                .map(|d| self.proyectar_direccion_cielo(&vista, d))
+                .collect();
+            for &(a, b) in &constelacion.segmentos {
+                if let (Some(inicio), Some(fin)) = (proyectadas[a], proyectadas[b]) {
+                    lineas.push(VerticeLinea { posicion: inicio, color });
+                    lineas.push(VerticeLinea { posicion: fin, color });
+                }
+            }
+
+            // Etiqueta en el centroide de las estrellas visibles
+            let visibles: Vec<[f32; 2]> = proyectadas.iter().flatten().copied().collect();
+            if !visibles.is_empty() {
+                let n = visibles.len() as f32;
+                let centro = [
+                    visibles.iter().map(|p| p[0]).sum::<f32>() / n,
+                    visibles.iter().map(|p| p[1]).sum::<f32>() / n,
+                ];
+                if centro[0].abs() < 1.2 && centro[1].abs() < 1.2 {
+                    lote.agregar(
+                        &constelacion.nombre,
+                        centro,
+                        2.0,
+                        [0.5, 0.65, 0.95, 0.9],
+                        AlineacionTexto::Centro,
+                    );
+                }
+            }
+        }
+    }
+
+    fn agregar_cuadricula(
+        &self,
+        cuadricula: &CuadriculaCeleste,
+        color: [f32; 3],
+        lineas: &mut Vec<VerticeLinea>,
+        lote: &mut LoteTexto,
+    ) {
+        let vista = self.matriz_vista_proyeccion();
+        let tenue = [color[0] * 0.5, color[1] * 0.5, color[2] * 0.5];
+        let polilineas = cuadricula
+            .polilineas
+            .iter()
+            .map(|p| (p, tenue))
+            .chain(std::iter::once((&cuadricula.circulo_fundamental, color)));
+        for (polilinea, color) in polilineas {
+            let proyectada: Vec<Option<[f32; 2]>> = polilinea
+                .iter()
+                .map(|d| self.proyectar_direccion_cielo(&vista, d))
+                .collect();
+            for par in proyectada.windows(2) {
+                if let [Some(inicio), Some(fin)] = *par {
+                    lineas.push(VerticeLinea { posicion: inicio, color });
+                    lineas.push(VerticeLinea { posicion: fin, color });
+                }
+            }
+        }
+
+        let color_texto = [color[0], color[1], color[2], 0.8];
+        for (texto, direccion) in &cuadricula.etiquetas {
+            if let Some(posicion) = self.proyectar_direccion_cielo(&vista, direccion) {
+                if posicion[0].abs() < 1.0 && posicion[1].abs() < 1.0 {
+                    lote.agregar(texto, posicion, 1.0, color_texto, AlineacionTexto::Izquierda);
+                }
+            }
+        }
+    }
+
+    /// Trayectoria de cada cuerpo en órbita, muestreada a lo largo de un periodo
+    fn agregar_orbitas(&self, lineas: &mut Vec<VerticeLinea>) {
+        let vista = self.matriz_vista_proyeccion();
+        let color = [0.3, 0.3, 0.4];
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            let Some(orbita) = cuerpo.orbita else { continue };
+            if !self.cuerpo_dibujable(indice) {
+                continue;
+            }
+            self.agregar_orbita(lineas, &vista, indice, &orbita, color);
+        }
+    }
+
+    /// Cinta de cada estela, más ancha y brillante hacia el cuerpo; el color compara la rapidez
+    /// de cada punto con la media de la estela, así se ve la segunda ley de Kepler. Debajo van
+    /// los sectores del modo Kepler, que usan el mismo pipeline aditivo
+    fn generar_cintas(&self) -> Vec<VerticeLinea> {
+        const ANCHO_PIXELES: f32 = 4.0;
+        let mut vertices = Vec::new();
+        if self.kepler.activo {
+            self.agregar_sectores_kepler(&mut vertices);
+        }
+        if !self.capas.estelas {
+            return vertices;
+        }
+        let vista = self.matriz_vista_proyeccion();
+        let [ancho, alto] = self.datos_uniformes.dimension_pantalla;
+        let gradiente = Paleta::velocidad();
+        for indice in 0..self.cuerpos.len() {
+            if !self.cuerpo_dibujable(indice) {
+                continue;
+            }
+            let muestras: Vec<&MuestraEstela> = self.estelas.estela(indice).collect();
+            if muestras.len() < 2 {
+                continue;
+            }
+            let media = muestras.iter().map(|m| m.rapidez).sum::<f32>() / muestras.len() as f32;
+            let origen = if self.grafo.marco.is_some() { Vec3::zeros() } else { self.grafo.origen(indice) };
+            let puntos: Vec<_> = muestras
+                .iter()
+                .enumerate()
+                .map(|(k, muestra)| {
+                    let [x, y] = muestra.posicion;
+                    let frescura = (k + 1) as f32 / muestras.len() as f32;
+                    let relativa = if media > f32::EPSILON { muestra.rapidez / media - 1.0 } else { 0.0 };
+                    let color = gradiente.muestrear(0.5 + relativa).a_flotante().map(|c| c * frescura);
+                    let ndc = self.proyectar_punto_cielo(&vista, &(origen + Vec3::new(x, y, 0.0)));
+                    (ndc, color, ANCHO_PIXELES * (0.25 + 0.75 * frescura))
+                })
+                .collect();
+            for par in puntos.windows(2) {
+                let ((Some(a), color_a, ancho_a), (Some(b), color_b, ancho_b)) = (par[0], par[1]) else { continue };
+                // Normal del tramo en píxeles, devuelta a NDC
+                let (dx, dy) = ((b[0] - a[0]) * ancho, (b[1] - a[1]) * alto);
+                let largo = (dx * dx + dy * dy).sqrt();
+                if largo <= f32::EPSILON {
+                    continue;
+                }
+                let normal = |grosor: f32| [-dy / largo * grosor / ancho, dx / largo * grosor / alto];
+                let (na, nb) = (normal(ancho_a), normal(ancho_b));
+                let esquinas = [
+                    ([a[0] + na[0], a[1] + na[1]], color_a),
+                    ([a[0] - na[0], a[1] - na[1]], color_a),
+                    ([b[0] + nb[0], b[1] + nb[1]], color_b),
+                    ([b[0] - nb[0], b[1] - nb[1]], color_b),
+                ];
+                for k in [0, 1, 2, 2, 1, 3] {
+                    let (posicion, color) = esquinas[k];
+                    vertices.push(VerticeLinea { posicion, color });
+                }
+            }
+        }
+        vertices.truncate(MAX_VERTICES_CINTA);
+        vertices
+    }
+
+    /// Sectores que barre el radio vector del cuerpo seleccionado en tiempos iguales, desde el
+    /// periapsis: anchos y cortos cerca del padre, estrechos y largos lejos, con la misma área.
+    /// El sector en curso se rellena además hasta el cuerpo
+    fn agregar_sectores_kepler(&self, vertices: &mut Vec<VerticeLinea>) {
+        const PASOS_SECTOR: usize = 12;
+        const TONOS: [[f32; 3]; 2] = [[0.12, 0.18, 0.32], [0.05, 0.08, 0.15]];
+        const TONO_ACTUAL: [f32; 3] = [0.40, 0.28, 0.05];
+        let Some((indice, orbita)) = self.orbita_seleccionada() else { return };
+        if !self.cuerpo_dibujable(indice) {
+            return;
+        }
+        let vista = self.matriz_vista_proyeccion();
+        let origen = self.grafo.origen(indice);
+        let Some(foco) = self.proyectar_punto_cielo(&vista, &origen) else { return };
+        let proyectar = |tiempo: f32| {
+            let [x, y] = orbita.posicion(tiempo);
+            self.proyectar_punto_cielo(&vista, &(origen + Vec3::new(x, y, 0.0)))
+        };
+        let mut abanico = |inicio: f32, fin: f32, color: [f32; 3]| {
+            let puntos: Vec<_> = (0..=PASOS_SECTOR)
+                .map(|k| proyectar(inicio + (fin - inicio) * k as f32 / PASOS_SECTOR as f32))
+                .collect();
+            for par in puntos.windows(2) {
+                let (Some(a), Some(b)) = (par[0], par[1]) else { continue };
+                for posicion in [foco, a, b] {
+                    vertices.push(VerticeLinea { posicion, color });
+                }
+            }
+        };
+
+        let duracion = orbita.periodo / LeyesKepler::SECTORES as f32;
+        let tiempo = self.datos_uniformes.tiempo_actual;
+        let transcurrido = (tiempo - orbita.paso_por_periapsis()).rem_euclid(orbita.periodo);
+        let inicio_vuelta = tiempo - transcurrido;
+        for sector in 0..LeyesKepler::SECTORES {
+            let inicio = inicio_vuelta + sector as f32 * duracion;
+            abanico(inicio, inicio + duracion, TONOS[sector % 2]);
+        }
+        let inicio_actual = inicio_vuelta + (transcurrido / duracion).floor() * duracion;
+        abanico(inicio_actual, tiempo, TONO_ACTUAL);
+    }
+
+    /// Líneas de campo de cada magnetosfera, por delante de los cuerpos salvo donde las tapa el
+    /// propio cuerpo. El color sigue |B| en escala logarítmica: tres décadas bajo la superficie
+    fn agregar_magnetosferas(&self, lineas: &mut Vec<VerticeLinea>) {
+        let vista = self.matriz_vista_proyeccion();
+        let gradiente = Paleta::campo_magnetico();
+        let ojo = self.camara.ojo;
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            let Some(campo) = &cuerpo.campo_magnetico else { continue };
+            if !self.cuerpo_dibujable(indice) {
+                continue;
+            }
+            let modelo = self.grafo.matriz_modelo(indice, cuerpo, self.datos_uniformes.tiempo_actual);
+            let centro = cuerpo.posicion_3d();
+            for linea in &campo.lineas {
+                let puntos: Vec<_> = linea
+                    .iter()
+                    .map(|punto| {
+                        let p = &punto.posicion;
+                        let mundo = (modelo * nalgebra_glm::vec4(p.x, p.y, p.z, 1.0)).xyz();
+                        let hacia = mundo - ojo;
+                        let distancia = hacia.norm();
+                        let tapado = interseccion_rayo_esfera(&ojo, &(hacia / distancia), &centro, cuerpo.escala)
+                            .is_some_and(|t| t < distancia * 0.999);
+                        let color = gradiente.muestrear(1.0 + punto.intensidad.log10() / 3.0).a_flotante();
+                        ((!tapado).then(|| self.proyectar_punto_cielo(&vista, &mundo)).flatten(), color)
+                    })
+                    .collect();
+                for par in puntos.windows(2) {
+                    if let ((Some(inicio), color_inicio), (Some(fin), color_fin)) = (par[0], par[1]) {
+                        lineas.push(VerticeLinea { posicion: inicio, color: color_inicio });
+                        lineas.push(VerticeLinea { posicion: fin, color: color_fin });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ejes del cuerpo seleccionado en su propio marco: X (rojo) y Z (azul) giran con él, Y
+    /// (verde) es el eje de giro de polo a polo y el anillo marca el ecuador. Como las líneas
+    /// de campo, el cuerpo tapa lo que queda detrás de él
+    fn agregar_ejes(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        const LARGO: f32 = 1.6;
+        const PUNTA: f32 = 0.12;
+        const RADIO_ECUADOR: f32 = 1.15;
+        const SEGMENTOS: usize = 64;
+        let Some(indice) = self.planeta_seleccionado.filter(|&i| self.cuerpo_dibujable(i)) else { return };
+        let cuerpo = &self.cuerpos[indice];
+        let vista = self.matriz_vista_proyeccion();
+        let tiempo = self.datos_uniformes.tiempo_actual;
+        let modelo = self.grafo.matriz_modelo(indice, cuerpo, tiempo);
+        let ojo = self.camara.ojo;
+        let centro = cuerpo.posicion_3d();
+        let proyectar = |local: Vec3| {
+            let mundo = (modelo * nalgebra_glm::vec4(local.x, local.y, local.z, 1.0)).xyz();
+            let hacia = mundo - ojo;
+            let distancia = hacia.norm();
+            let tapado = interseccion_rayo_esfera(&ojo, &(hacia / distancia), &centro, cuerpo.escala)
+                .is_some_and(|t| t < distancia * 0.999);
+            (!tapado).then(|| self.proyectar_punto_cielo(&vista, &mundo)).flatten()
+        };
+        let mut segmento = |a: Vec3, b: Vec3, color: [f32; 3]| {
+            if let (Some(inicio), Some(fin)) = (proyectar(a), proyectar(b)) {
+                lineas.push(VerticeLinea { posicion: inicio, color });
+                lineas.push(VerticeLinea { posicion: fin, color });
+            }
+        };
+
+        let ejes = [
+            (Vec3::x(), Vec3::y(), [1.0, 0.3, 0.3]),
+            (Vec3::y(), Vec3::z(), [0.4, 1.0, 0.4]),
+            (Vec3::z(), Vec3::x(), [0.4, 0.6, 1.0]),
+        ];
+        for (eje, lateral, color) in ejes {
+            let punta = eje * LARGO;
+            segmento(eje * 1.02, punta, color);
+            for lado in [-1.0, 1.0] {
+                segmento(punta, eje * (LARGO - PUNTA) + lateral * (lado * PUNTA * 0.5), color);
+            }
+        }
+        segmento(-Vec3::y() * 1.02, -Vec3::y() * LARGO, [0.2, 0.6, 0.2]);
+        let ecuador = |k: usize| {
+            let angulo = std::f32::consts::TAU * k as f32 / SEGMENTOS as f32;
+            Vec3::new(angulo.cos(), 0.0, angulo.sin()) * RADIO_ECUADOR
+        };
+        for k in 0..SEGMENTOS {
+            segmento(ecuador(k), ecuador(k + 1), [0.9, 0.8, 0.3]);
+        }
+
+        // Ángulo de `matriz_orientacion`; abs() quita el -0 del instante inicial
+        let giro = (-tiempo * 0.3).rem_euclid(std::f32::consts::TAU).abs().to_degrees();
+        let etiquetas = [
+            (Vec3::x(), "X".to_string(), [1.0, 0.3, 0.3, 1.0]),
+            (Vec3::z(), "Z".to_string(), [0.4, 0.6, 1.0, 1.0]),
+            (
+                Vec3::y(),
+                format!("inclinación {:.1}° · giro {:.0}°", cuerpo.inclinacion_axial.to_degrees(), giro),
+                [0.4, 1.0, 0.4, 1.0],
+            ),
+        ];
+        for (eje, texto, color) in etiquetas {
+            if let Some(posicion) = proyectar(eje * (LARGO + PUNTA)) {
+                lote.agregar(&texto, posicion, 1.0, color, AlineacionTexto::Izquierda);
+            }
+        }
+    }
+
+    /// Elipse de una órbita centrada en el padre del cuerpo
+    fn agregar_orbita(&self, lineas: &mut Vec<VerticeLinea>, vista: &Mat4, indice: usize, orbita: &Orbita, color: [f32; 3]) {
+        const SEGMENTOS: usize = 96;
+        let origen = self.grafo.origen(indice);
+        let punto = |k: usize| {
+            let [x, y] = orbita.punto_en_anomalia(k as f32 * 2.0 * std::f32::consts::PI / SEGMENTOS as f32);
+            self.proyectar_punto_cielo(vista, &(origen + Vec3::new(x, y, 0.0)))
+        };
+        for k in 0..SEGMENTOS {
+            if let (Some(inicio), Some(fin)) = (punto(k), punto(k + 1)) {
+                lineas.push(VerticeLinea { posicion: inicio, color });
+                lineas.push(VerticeLinea { posicion: fin, color });
+            }
+        }
+    }
+
+    /// Órbita seleccionada resaltada, con asas en sus extremos
+    fn agregar_editor(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        let Some((indice, orbita)) = self.orbita_seleccionada() else { return };
+        if !self.cuerpo_dibujable(indice) {
+            return;
+        }
+        let vista = self.matriz_vista_proyeccion();
+        self.agregar_orbita(lineas, &vista, indice, &orbita, [0.3, 0.9, 1.0]);
+        let [ancho, alto] = self.datos_uniformes.dimension_pantalla;
+        let radio = [EditorOrbitas::RADIO_ASA * 2.0 / ancho, EditorOrbitas::RADIO_ASA * 2.0 / alto];
+        let origen = self.grafo.origen(indice);
+        for extremo in ExtremoOrbita::TODOS {
+            let [x, y] = orbita.punto_en_anomalia(extremo.anomalia());
+            let Some(centro) = self.proyectar_punto_cielo(&vista, &(origen + Vec3::new(x, y, 0.0))) else { continue };
+            let color = if self.editor.arrastre == Some(extremo) { [1.0, 0.9, 0.2] } else { [1.0, 0.5, 0.2] };
+            agregar_circulo(lineas, centro, radio, color);
+            let posicion = [centro[0] + radio[0] * 1.5, centro[1] + radio[1] * 1.5];
+            lote.agregar(extremo.etiqueta(), posicion, 1.0, [color[0], color[1], color[2], 1.0], AlineacionTexto::Izquierda);
+        }
+    }
+
+    /// Anillos y radios sobre el plano XY de la escena (el plano de la eclíptica)
+    fn agregar_disco_ecliptica(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        const ANILLOS: usize = 4;
+        const RADIOS: usize = 12;
+        const SEGMENTOS: usize = 64;
+        let vista = self.matriz_vista_proyeccion();
+        let color = [0.45, 0.35, 0.15];
+        let radio_maximo = self.radio_disco_ecliptica;
+        let mut segmento = |a: Vec3, b: Vec3| {
+            if let (Some(inicio), Some(fin)) =
+                (self.proyectar_punto_cielo(&vista, &a), self.proyectar_punto_cielo(&vista, &b))
+            {
+                lineas.push(VerticeLinea { posicion: inicio, color });
+                lineas.push(VerticeLinea { posicion: fin, color });
+            }
+        };
+        let centro = self.grafo.centro_escena();
+        let punto = |radio: f32, angulo: f32| centro + Vec3::new(radio * angulo.cos(), radio * angulo.sin(), 0.0);
+
+        for anillo in 1..=ANILLOS {
+            let radio = radio_maximo * anillo as f32 / ANILLOS as f32;
+            for k in 0..SEGMENTOS {
+                let a0 = std::f32::consts::TAU * k as f32 / SEGMENTOS as f32;
+                let a1 = std::f32::consts::TAU * (k + 1) as f32 / SEGMENTOS as f32;
+                segmento(punto(radio, a0), punto(radio, a1));
+            }
+        }
+        for k in 0..RADIOS {
+            let angulo = std::f32::consts::TAU * k as f32 / RADIOS as f32;
+            segmento(punto(radio_maximo * 0.1, angulo), punto(radio_maximo, angulo));
+        }
+
+        let etiqueta = format!("{:.2} UA", radio_maximo);
+        if let Some(posicion) = self.proyectar_punto_cielo(&vista, &punto(radio_maximo, 0.0)) {
+            lote.agregar(&etiqueta, posicion, 1.0, [0.7, 0.55, 0.25, 0.8], AlineacionTexto::Izquierda);
+        }
+    }
+
+    /// Barra de escala en la esquina inferior izquierda, medida a la distancia del foco de la cámara
+    fn agregar_barra_escala(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        const LONGITUD_OBJETIVO: f32 = 150.0;
+        const MARGEN: f32 = 20.0;
+        let [ancho, alto] = self.datos_uniformes.dimension_pantalla;
+        if ancho <= 2.0 * MARGEN + LONGITUD_OBJETIVO || alto <= 2.0 * MARGEN {
+            return;
+        }
+
+        // Unidades de escena por píxel en el plano del objetivo
+        let altura_visible = 2.0 * self.camara.radio * (self.camara.campo_vision * 0.5).tan();
+        let unidades_por_pixel = (altura_visible / alto) as f64;
+        let escala = self.escala_distancias;
+        let valor = valor_redondo(escala.convertir(unidades_por_pixel * LONGITUD_OBJETIVO as f64));
+        let longitud = (valor / escala.convertir(unidades_por_pixel)) as f32;
+
+        let a_ndc = |x: f32, y: f32| [x / ancho * 2.0 - 1.0, 1.0 - y / alto * 2.0];
+        let (x0, x1, y) = (MARGEN, MARGEN + longitud, alto - MARGEN);
+        let color = [0.85, 0.85, 0.85];
+        for (inicio, fin) in [
+            (a_ndc(x0, y), a_ndc(x1, y)),
+            (a_ndc(x0, y - 5.0), a_ndc(x0, y + 5.0)),
+            (a_ndc(x1, y - 5.0), a_ndc(x1, y + 5.0)),
+        ] {
+            lineas.push(VerticeLinea { posicion: inicio, color });
+            lineas.push(VerticeLinea { posicion: fin, color });
+        }
+
+        let decimales = (-valor.log10().floor()).max(0.0) as usize;
+        let etiqueta = format!("{:.*} {}", decimales, valor, escala.sufijo());
+        lote.agregar(&etiqueta, a_ndc(x0, y - 24.0), 2.0, [0.85, 0.85, 0.85, 1.0], AlineacionTexto::Izquierda);
+    }
+
+    fn describir_seleccion(&self) -> Option<String> {
+        let cuerpo = &self.cuerpos[self.planeta_seleccionado?];
+        let observador = self.posicion_observador();
+        let estrella = self.cuerpos.iter().find(|c| c.es_estrella());
+        Some(format!(
+            "{}: diámetro angular {} · magnitud {:.2}",
+            cuerpo.nombre,
+            formatear_angulo(cuerpo.diametro_angular(&observador)),
+            cuerpo.magnitud_aparente(&observador, estrella)
+        ))
+    }
+
+    /// Ficha de texto del cuerpo seleccionado, para copiarla
+    fn ficha_seleccion(&self) -> Option<String> {
+        let cuerpo = &self.cuerpos[self.planeta_seleccionado?];
+        let observador = self.posicion_observador();
+        let estrella = self.cuerpos.iter().find(|c| c.es_estrella());
+        let [x, y] = cuerpo.posicion;
+        let mut ficha = format!(
+            "{} ({})\nRadio: {} km\nPosición: ({:.3}, {:.3}) u\n",
+            cuerpo.nombre,
+            format!("{:?}", cuerpo.clase).to_lowercase(),
+            cuerpo.radio_km,
+            x,
+            y
+        );
+        if let Some(estrella) = estrella.filter(|e| e.nombre != cuerpo.nombre) {
+            ficha += &format!("Distancia a {}: {:.3} u\n", estrella.nombre, cuerpo.distancia_a(estrella));
+        }
+        if let Some(orbita) = cuerpo.orbita {
+            ficha += &format!(
+                "Órbita: semieje {:.3} u, excentricidad {:.3}, periodo {} s\n",
+                orbita.semieje_mayor, orbita.excentricidad, orbita.periodo
+            );
+        }
+        ficha += &format!(
+            "Diámetro angular: {}\nMagnitud aparente: {:.2}",
+            formatear_angulo(cuerpo.diametro_angular(&observador)),
+            cuerpo.magnitud_aparente(&observador, estrella)
+        );
+        Some(ficha)
+    }
+
+    /// Ctrl+C copia la ficha del cuerpo seleccionado, o el enlace de la vista si no hay
+    /// selección; con Shift siempre copia el enlace
+    fn copiar(&mut self) {
+        let texto = match self.ficha_seleccion().filter(|_| !self.modificadores.shift_key()) {
+            Some(ficha) => ficha,
+            None => self.enlace_vista().to_string(),
+        };
+        match self.portapapeles.copiar(&texto) {
+            Ok(()) => println!("Copiado: {}", texto.lines().next().unwrap_or_default()),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    /// Ctrl+V salta a la vista del enlace que haya en el portapapeles
+    fn pegar(&mut self) {
+        let resultado = self.portapapeles.pegar().and_then(|texto| self.abrir_enlace(&texto));
+        if let Err(error) = resultado {
+            eprintln!("No se pegó el enlace: {}", error);
+        }
+    }
+
+    fn procesar_tecla(&mut self, tecla: KeyCode) {
+        match tecla {
+            KeyCode::KeyM => self.medicion.alternar(),
+            KeyCode::KeyO if self.modificadores.control_key() => self.abrir_escena(),
+            KeyCode::KeyC if self.modificadores.control_key() => self.copiar(),
+            KeyCode::KeyV if self.modificadores.control_key() => self.pegar(),
+            KeyCode::KeyO => self.editor.alternar(),
+            KeyCode::KeyV => self.telescopio.alternar(),
+            KeyCode::KeyK if self.modificadores.shift_key() => self.kepler.alternar(),
+            KeyCode::KeyK => println!("Enlace de la vista: {}", self.enlace_vista()),
+            KeyCode::KeyQ => self.quiz.alternar(self.cuerpos.len()),
+            KeyCode::Space => self.reloj.pausado = !self.reloj.pausado,
+            KeyCode::KeyP => self.paso_a_paso.alternar(),
+            KeyCode::KeyN => self.paso_a_paso.pedir(),
+            KeyCode::Equal | KeyCode::NumpadAdd => self.reloj.ajustar_escala(2.0),
+            KeyCode::Minus | KeyCode::NumpadSubtract => self.reloj.ajustar_escala(0.5),
+            KeyCode::KeyR => self.reloj.invertido = !self.reloj.invertido,
+            KeyCode::F1 => {
+                if let Some(interfaz) = &mut self.interfaz {
+                    interfaz.visible = !interfaz.visible;
+                }
+            }
+            KeyCode::F9 => match self.grabacion.take() {
+                Some(grabacion) => self.detener_grabacion(grabacion),
+                None => {
+                    let base = format!("grabacion_{}", marca_de_tiempo_actual());
+                    let nombre = if self.ajustes_grabacion.ffmpeg { format!("{}.mp4", base) } else { base };
+                    let sugerido = self.ajustes_grabacion.carpeta.join(nombre);
+                    let destino = if self.modificadores.shift_key() {
+                        dialogos::destino_grabacion(&sugerido, self.ajustes_grabacion.ffmpeg)
+                    } else {
+                        Some(sugerido)
+                    };
+                    if let Some(Err(error)) = destino.map(|destino| self.iniciar_grabacion(&destino)) {
+                        eprintln!("No se inició la grabación: {}", error);
+                    }
+                }
+            },
+            KeyCode::F11 => self.alternar_pantalla_completa(),
+            KeyCode::F12 if self.modificadores.shift_key() => self.guardar_captura_como(),
+            KeyCode::F12 => match self.guardar_captura() {
+                Ok(ruta) => println!("Captura guardada en {}", ruta.display()),
+                Err(error) => eprintln!("No se guardó la captura: {}", error),
+            },
+            KeyCode::KeyG => self.mostrar_ecliptica = !self.mostrar_ecliptica,
+            KeyCode::KeyF => self.fila.alternar(&mut self.reloj),
+            KeyCode::KeyB => self.caricatura = !self.caricatura,
+            KeyCode::Tab if self.modificadores.shift_key() => {
+                self.comparacion_cpu.alambre = !self.comparacion_cpu.alambre;
+            }
+            KeyCode::Tab => self.comparacion_cpu.alternar(),
+            KeyCode::KeyU => self.escala_distancias = self.escala_distancias.alternar(),
+            KeyCode::KeyH => {
+                let marco = match self.grafo.marco {
+                    Some(_) => None,
+                    None => self.planeta_seleccionado.or(self.foco),
+                };
+                self.cambiar_marco(marco);
+            }
+            KeyCode::KeyT => {
+                self.capas.estelas = !self.capas.estelas;
+                if !self.capas.estelas {
+                    self.estelas.vaciar();
+                }
+                if let Err(error) = self.capas.guardar(&self.ruta_ajustes) {
+                    eprintln!("No se guardaron las capas: {}", error);
+                }
+            }
+            KeyCode::KeyL => {
+                self.capas.nombres = !self.capas.nombres;
+                if let Err(error) = self.capas.guardar(&self.ruta_ajustes) {
+                    eprintln!("No se guardaron las capas: {}", error);
+                }
+            }
+            KeyCode::KeyE => self.mostrar_ecuatorial = !self.mostrar_ecuatorial,
+            KeyCode::KeyC => {
+                self.mostrar_constelaciones = !self.mostrar_constelaciones;
+                if self.mostrar_constelaciones && self.constelaciones.is_empty() {
+                    println!("No hay catálogo de constelaciones cargado");
+                }
+            }
+            _ => {
+                if let Some(opcion) = opcion_de_tecla(tecla) {
+                    if self.quiz.activo {
+                        self.quiz.responder_nombre(opcion, self.cuerpos.len());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Centro en pantalla y radio aparente (por eje) de un cuerpo; `None` si está detrás de la cámara
+    fn transformacion_pantalla(&self, cuerpo: &CuerpoCeleste) -> Option<([f32; 2], [f32; 2])> {
+        let proyeccion = self.camara.matriz_proyeccion(self.aspecto());
+        let vista_proyeccion = proyeccion * self.camara.matriz_vista();
+        let centro = self.proyectar_punto_cielo(&vista_proyeccion, &cuerpo.posicion_3d())?;
+        let w = (vista_proyeccion * cuerpo.posicion_3d().push(1.0)).w;
+        Some((
+            centro,
+            [cuerpo.escala * proyeccion[(0, 0)] / w, cuerpo.escala * proyeccion[(1, 1)] / w],
+        ))
+    }
+
+    /// Diámetro en píxeles con que se ve cada cuerpo (0 si no se dibuja); el objetivo del
+    /// telescopio cuenta con el tamaño que tiene en su recuadro
+    fn diametros_en_pantalla(&self) -> Vec<f32> {
+        let alto = self.datos_uniformes.dimension_pantalla[1];
+        let mut diametros: Vec<f32> = (0..self.cuerpos.len())
+            .map(|indice| {
+                if !self.cuerpo_dibujable(indice) {
+                    return 0.0;
+                }
+                self.transformacion_pantalla(&self.cuerpos[indice])
+                    .map_or(0.0, |(_, radio)| radio[1] * alto)
+            })
+            .collect();
+        if let (true, Some(objetivo)) = (self.telescopio.activo, self.telescopio.objetivo) {
+            let ampliado = Telescopio::RESOLUCION as f32 * Telescopio::OCUPACION;
+            diametros[objetivo] = diametros[objetivo].max(ampliado);
+        }
+        diametros
+    }
+
+    /// Cuerpo más cercano cuya esfera corta el rayo que sale de la cámara por el cursor
+    fn cuerpo_bajo_cursor(&self) -> Option<usize> {
+        let (origen, direccion) = self.rayo_desde_cursor(self.posicion_mouse?)?;
+        self.cuerpos
+            .iter()
+            .enumerate()
+            .filter(|&(indice, _)| self.cuerpo_dibujable(indice))
+            .filter_map(|(indice, cuerpo)| {
+                interseccion_rayo_esfera(&origen, &direccion, &cuerpo.posicion_3d(), cuerpo.escala)
+                    .map(|distancia| (indice, distancia))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(indice, _)| indice)
+    }
+
+    /// Superposiciones del cuadro: líneas y texto en coordenadas de pantalla.
+    /// Las primeras `lineas_cielo` líneas pertenecen a la esfera celeste y van detrás de los planetas.
+    fn generar_superposiciones(&self) -> (Vec<VerticeLinea>, usize, LoteTexto) {
+        let mut lineas = Vec::new();
+        let mut lote = self.texto.nuevo_lote(self.datos_uniformes.dimension_pantalla);
+        if self.capas.orbitas {
+            self.agregar_orbitas(&mut lineas);
+        }
+        if self.capas.cuadriculas && self.mostrar_ecliptica {
+            self.agregar_disco_ecliptica(&mut lineas, &mut lote);
+            self.agregar_cuadricula(&self.cuadricula_ecliptica, [0.9, 0.7, 0.3], &mut lineas, &mut lote);
+        }
+        if self.capas.cuadriculas && self.mostrar_ecuatorial {
+            self.agregar_cuadricula(&self.cuadricula_ecuatorial, [0.35, 0.8, 0.55], &mut lineas, &mut lote);
+        }
+        if self.mostrar_constelaciones {
+            self.agregar_constelaciones(&mut lineas, &mut lote);
+        }
+        lineas.truncate(MAX_VERTICES_LINEA);
+        let lineas_cielo = lineas.len();
+
+        if self.capas.magnetosferas {
+            self.agregar_magnetosferas(&mut lineas);
+        }
+        let escalar = |radio: [f32; 2], factor: f32| [radio[0] * factor, radio[1] * factor];
+        let seleccion = self
+            .planeta_seleccionado
+            .filter(|&i| self.cuerpo_dibujable(i))
+            .and_then(|i| self.transformacion_pantalla(&self.cuerpos[i]));
+        if let Some((centro, radio)) = seleccion {
+            agregar_circulo(&mut lineas, centro, escalar(radio, 1.1), [0.3, 0.9, 1.0]);
+        }
+        let resaltado = self.quiz.cuerpo_resaltado().and_then(|i| self.transformacion_pantalla(&self.cuerpos[i]));
+        if let Some((centro, radio)) = resaltado {
+            agregar_circulo(&mut lineas, centro, escalar(radio, 1.15), [1.0, 0.3, 0.9]);
+            agregar_circulo(&mut lineas, centro, escalar(radio, 1.2), [1.0, 0.3, 0.9]);
+        }
+        if let Some((a, b)) = self.medicion.par_seleccionado() {
+            let color = [1.0, 0.9, 0.2];
+            if let (Some((inicio, _)), Some((fin, _))) = (
+                self.transformacion_pantalla(&self.cuerpos[a]),
+                self.transformacion_pantalla(&self.cuerpos[b]),
+            ) {
+                lineas.push(VerticeLinea { posicion: inicio, color });
+                lineas.push(VerticeLinea { posicion: fin, color });
+            }
+        }
+        // Hasta aquí el lote solo tiene etiquetas del cielo; la barra de escala siempre se muestra
+        if !self.capas.etiquetas {
+            lote.vaciar();
+        }
+        self.agregar_impostores(&mut lote);
+        if self.capas.nombres {
+            self.agregar_nombres(&mut lote);
+        }
+        if self.editor.activo {
+            self.agregar_editor(&mut lineas, &mut lote);
+        }
+        if self.capas.ejes {
+            self.agregar_ejes(&mut lineas, &mut lote);
+        }
+        if self.fila.visible() {
+            self.agregar_fila(&mut lineas, &mut lote);
+        }
+        self.agregar_barra_escala(&mut lineas, &mut lote);
+        self.agregar_etiqueta_telescopio(&mut lote);
+        if let Some(texto) = self.paso_a_paso.describir() {
+            let [ancho, alto] = self.datos_uniformes.dimension_pantalla;
+            let posicion = [-1.0 + 24.0 / ancho.max(1.0), 1.0 - 24.0 / alto.max(1.0)];
+            lote.agregar(&texto, posicion, 2.0, [1.0, 0.85, 0.3, 1.0], AlineacionTexto::Izquierda);
+        }
+        lineas.truncate(MAX_VERTICES_LINEA);
+        (lineas, lineas_cielo, lote)
+    }
+
+    /// Nombre y radio real de cada cuerpo de la fila de tamaños, alternando arriba y abajo y en
+    /// dos alturas para que no se pisen los de cuerpos pequeños vecinos, y la barra de escala
+    /// común de la fila
+    fn agregar_fila(&self, lineas: &mut Vec<VerticeLinea>, lote: &mut LoteTexto) {
+        const ESCALA: f32 = 1.0;
+        let pixel_y = 2.0 / self.datos_uniformes.dimension_pantalla[1].max(1.0);
+        let renglon = (GLIFO_ALTO as f32 * ESCALA + 4.0) * pixel_y;
+        let color = [0.75, 0.8, 0.85, self.fila.mezcla()];
+        for (k, &indice) in self.fila.orden().iter().enumerate() {
+            let cuerpo = &self.cuerpos[indice];
+            let Some((centro, radio)) = self.transformacion_pantalla(cuerpo) else { continue };
+            let altura = radio[1] + renglon * (1 + k / 2 % 2) as f32;
+            // El lote ancla el texto por arriba: encima se sube además un renglón
+            let posicion = if k % 2 == 1 {
+                [centro[0], centro[1] + altura + renglon]
+            } else {
+                [centro[0], centro[1] - altura]
+            };
+            let etiqueta = format!("{} {:.0} km", cuerpo.nombre, cuerpo.radio_km);
+            lote.agregar(&etiqueta, posicion, ESCALA, color, AlineacionTexto::Centro);
+        }
+
+        let Some((inicio, largo, kilometros)) = self.fila.barra(&self.cuerpos) else { return };
+        let vista = self.matriz_vista_proyeccion();
+        let fin = inicio + Vec3::new(largo, 0.0, 0.0);
+        let [Some(a), Some(b)] = [inicio, fin].map(|p| self.proyectar_punto_cielo(&vista, &p)) else { return };
+        let tono = [color[0] * color[3], color[1] * color[3], color[2] * color[3]];
+        let marca = 5.0 * pixel_y;
+        for (desde, hasta) in [
+            (a, b),
+            ([a[0], a[1] - marca], [a[0], a[1] + marca]),
+            ([b[0], b[1] - marca], [b[0], b[1] + marca]),
+        ] {
+            lineas.push(VerticeLinea { posicion: desde, color: tono });
+            lineas.push(VerticeLinea { posicion: hasta, color: tono });
+        }
+        let etiqueta = format!("{:.0} km", kilometros);
+        let medio = [(a[0] + b[0]) * 0.5, a[1] - marca * 2.0];
+        lote.agregar(&etiqueta, medio, ESCALA, color, AlineacionTexto::Centro);
+    }
+
+    /// Nombre de cada cuerpo dibujable justo encima de su disco; se desvanece entre
+    /// `NOMBRES_NITIDOS_HASTA` y `NOMBRES_OCULTOS_DESDE` de distancia a la cámara
+    fn agregar_nombres(&self, lote: &mut LoteTexto) {
+        const NOMBRES_NITIDOS_HASTA: f32 = 2.0;
+        const NOMBRES_OCULTOS_DESDE: f32 = 6.0;
+        const ESCALA: f32 = 2.0;
+        // El lote ancla el texto por su borde superior: se sube su alto más un margen de 6 px
+        let pixel_y = 2.0 / self.datos_uniformes.dimension_pantalla[1].max(1.0);
+        let elevacion = (GLIFO_ALTO as f32 * ESCALA + 6.0) * pixel_y;
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            if !self.cuerpo_dibujable(indice) {
+                continue;
+            }
+            let distancia = (cuerpo.posicion_3d() - self.camara.ojo).norm();
+            let avance = ((distancia - NOMBRES_NITIDOS_HASTA) / (NOMBRES_OCULTOS_DESDE - NOMBRES_NITIDOS_HASTA))
+                .clamp(0.0, 1.0);
+            let mut opacidad = 1.0 - avance * avance * (3.0 - 2.0 * avance);
+            // En la fila de tamaños el nombre pasa a la etiqueta de debajo
+            if self.fila.orden().contains(&indice) {
+                opacidad *= 1.0 - self.fila.mezcla();
+            }
+            if opacidad <= 0.01 {
+                continue;
+            }
+            let Some((centro, radio)) = self.transformacion_pantalla(cuerpo) else { continue };
+            let posicion = [centro[0], centro[1] + radio[1] + elevacion];
+            let color = [0.9, 0.92, 0.95, opacidad];
+            lote.agregar(&cuerpo.nombre, posicion, ESCALA, color, AlineacionTexto::Centro);
+            // Icono de la clase a la izquierda del nombre, con un glifo de separación
+            let pixel_x = 2.0 / self.datos_uniformes.dimension_pantalla[0].max(1.0);
+            let centro_icono = [
+                posicion[0] - lote.ancho_texto(&cuerpo.nombre, ESCALA) * 0.5 - 5.5 * ESCALA * pixel_x,
+                posicion[1] - GLIFO_ALTO as f32 * ESCALA * 0.5 * pixel_y,
+            ];
+            lote.agregar_sprite(Sprite::icono(cuerpo.clase), centro_icono, GLIFO_ALTO as f32 * ESCALA, color);
+        }
+    }
+
+    /// Halo difuso sobre los cuerpos que ocupan menos de `DIAMETRO_MINIMO` píxeles,
+    /// para que no desaparezcan a lo lejos
+    fn agregar_impostores(&self, lote: &mut LoteTexto) {
+        const DIAMETRO_MINIMO: f32 = 4.0;
+        const ALTO_HALO: f32 = 10.0;
+        let diametros = self.diametros_en_pantalla();
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            if !self.cuerpo_dibujable(indice) || diametros[indice] >= DIAMETRO_MINIMO {
+                continue;
+            }
+            let Some((centro, _)) = self.transformacion_pantalla(cuerpo) else { continue };
+            let [r, g, b] = cuerpo.tinte.map(|c| (c * 0.9).min(1.0));
+            lote.agregar_sprite(Sprite::Halo, centro, ALTO_HALO, [r, g, b, 0.9]);
+        }
+    }
+
+    /// Nombre del cuerpo observado y aumento, sobre el recuadro del telescopio
+    fn agregar_etiqueta_telescopio(&self, lote: &mut LoteTexto) {
+        if self.uniformes_telescopio().is_none() {
+            return;
+        }
+        let Some(indice) = self.telescopio.objetivo else { return };
+        let cuerpo = &self.cuerpos[indice];
+        let (centro, radio) = Telescopio::recuadro(self.datos_uniformes.dimension_pantalla);
+        let aumento = Telescopio::aumento(&self.camara, cuerpo);
+        let texto = format!("{} x{:.0}", cuerpo.nombre, aumento);
+        let posicion = [centro[0], centro[1] + radio[1] * 1.08];
+        lote.agregar(&texto, posicion, 2.0, [0.85, 0.9, 0.95, 1.0], AlineacionTexto::Centro);
+    }
+
+    fn actualizar_titulo(&mut self) {
+        let mut titulo = String::from(TITULO_VENTANA);
+        let lecturas = [
+            self.reloj.describir(),
+            self.paso_a_paso.describir(),
+            self.fila.describir(),
+            self.editor.describir(self.planeta_seleccionado.map(|i| &self.cuerpos[i])),
+            self.kepler.describir(self.planeta_seleccionado.map(|i| &self.cuerpos[i])),
+            self.quiz.describir(&self.cuerpos),
+            self.describir_seleccion(),
+            self.medicion.describir(&self.cuerpos),
+            self.comparacion_cpu.activa.then(|| String::from("CPU | GPU")),
+        ];
+        for texto in lecturas.into_iter().flatten() {
+            titulo.push_str(" | ");
+            titulo.push_str(&texto);
+        }
+        if let Some(grabacion) = &self.grabacion {
+            titulo.push_str(&format!(" | ● GRABANDO ({} cuadros)", grabacion.cuadros));
+        }
+        if titulo != self.titulo_actual {
+            if let Some(ventana) = &self.ventana {
+                ventana.set_title(&titulo);
+            }
+            self.titulo_actual = titulo;
+        }
+    }
+
+    fn procesar_movimiento_mouse(&mut self, posicion: winit::dpi::PhysicalPosition<f64>) {
+        if self.editor.arrastre.is_some() {
+            self.arrastrar_extremo(posicion);
+        } else if self.mouse_presionado {
+            if let Some(pos_anterior) = self.posicion_mouse {
+                let delta_x = (posicion.x - pos_anterior.x) as f32;
+                let delta_y = (posicion.y - pos_anterior.y) as f32;
+                
+                // Sensibilidad del mouse
+                self.camara.orbitar(delta_x * 0.005, -delta_y * 0.005);
+            }
+        }
+        self.posicion_mouse = Some(posicion);
+    }
+
+    /// Aplica un evento de entrada de la ventana o de una repetición, y lo graba si hay
+    /// grabación. Devuelve `true` si pide cerrar
+    fn procesar_entrada(&mut self, entrada: EventoEntrada) -> bool {
+        self.anotar_entrada(&entrada);
+        match entrada {
+            EventoEntrada::Tecla { tecla } => self.procesar_tecla(tecla),
+            EventoEntrada::Modificadores { control, mayus, alt } => {
+                use winit::keyboard::ModifiersState;
+                let mut modificadores = ModifiersState::empty();
+                modificadores.set(ModifiersState::CONTROL, control);
+                modificadores.set(ModifiersState::SHIFT, mayus);
+                modificadores.set(ModifiersState::ALT, alt);
+                self.modificadores = modificadores;
+            }
+            EventoEntrada::Cursor { x, y } => {
+                if self.mouse_presionado {
+                    self.latencia.entrada();
+                }
+                self.procesar_movimiento_mouse(winit::dpi::PhysicalPosition::new(x, y));
+            }
+            EventoEntrada::Click { presionado } => self.procesar_mouse_click(presionado),
+            EventoEntrada::Rueda { pasos } => {
+                self.latencia.entrada();
+                self.camara.acercar(pasos);
+            }
+            EventoEntrada::Soltar { ruta } => self.soltar_archivo(&ruta),
+            EventoEntrada::Cuadro { .. } => {}
+            EventoEntrada::Cerrar => return true,
+        }
+        false
+    }
+
+    fn anotar_entrada(&mut self, entrada: &EventoEntrada) {
+        let Some(grabador) = &mut self.grabador_entrada else { return };
+        if let Err(error) = grabador.anotar(entrada) {
+            eprintln!("Grabación de entrada detenida: {}", error);
+            self.grabador_entrada = None;
+        }
+    }
+
+    /// Entrega los eventos grabados del próximo cuadro; `true` si la sesión grabada cerró
+    /// la aplicación
+    fn repetir_cuadro(&mut self) -> bool {
+        let Some(repeticion) = &mut self.repeticion else { return false };
+        let Some((eventos, paso)) = repeticion.siguiente_cuadro() else {
+            println!("Repetición terminada");
+            self.repeticion = None;
+            return false;
+        };
+        self.paso_repetido = paso;
+        eventos.into_iter().any(|evento| self.procesar_entrada(evento))
+    }
+
+    fn actualizar(&mut self) {
+        self.atender_consola();
+        self.revisar_escena();
+        self.recibir_modelos();
+        self.recibir_mallas_modelos(false);
+        let ahora = std::time::Instant::now();
+        // La interfaz anima sus transiciones con el paso de pared cuantizado, simule o no
+        let pared = self.frecuencia.cuantizar((ahora - self.ultimo_cuadro).as_secs_f64());
+        if let Some(interfaz) = &mut self.interfaz {
+            interfaz.paso_cuadro = pared as f32;
+        }
+        // En pausa de paso a paso no se simula ni se graba el cuadro
+        if !self.paso_a_paso.avanzar() {
+            self.ultimo_cuadro = ahora;
+            self.paso_repetido = None;
+            self.actualizar_titulo();
+            return;
+        }
+        let transcurrido = match (&self.grabacion, self.paso_repetido.take()) {
+            (Some(grabacion), _) => grabacion.paso(),
+            (None, Some(paso)) => paso,
+            (None, None) if self.paso_a_paso.activo => PasoAPaso::PASO,
+            (None, None) => pared,
+        };
+        self.anotar_entrada(&EventoEntrada::Cuadro { paso: transcurrido });
+        let antes = self.reloj.tiempo;
+        self.ultimo_cuadro = ahora;
+        // La grabación y el paso a paso ya avanzan un paso fijo por cuadro
+        if self.paso_fijo.activo() && self.grabacion.is_none() && !self.paso_a_paso.activo {
+            self.simulacion().avanzar_animaciones(transcurrido);
+            self.avanzar_paso_fijo(transcurrido);
+        } else {
+            self.paso_fijo.reiniciar();
+            self.simulacion().avanzar(transcurrido);
+            self.actualizar_dependientes(self.reloj.tiempo as f32);
+        }
+        self.paso_a_paso.ultimo_paso = self.reloj.tiempo - antes;
+    }
+
+    /// Simula los pasos fijos que completan `transcurrido` y deja los cuerpos interpolados
+    /// entre los dos últimos para este cuadro
+    fn avanzar_paso_fijo(&mut self, transcurrido: f64) {
+        match &self.paso_fijo.actual {
+            // El cuadro anterior quedó interpolado: los pasos siguen desde el último simulado
+            Some(actual) => self.datos_uniformes.tiempo_actual = actual.tiempo,
+            None => {
+                self.actualizar_a(self.reloj.tiempo as f32);
+                let instantanea = InstantaneaCuerpos::tomar(self.reloj.tiempo as f32, &self.cuerpos, &self.grafo);
+                self.paso_fijo.registrar(instantanea);
+            }
+        }
+        for _ in 0..self.paso_fijo.acumular(transcurrido) {
+            self.reloj.avanzar(self.paso_fijo.paso());
+            self.actualizar_a(self.reloj.tiempo as f32);
+            let instantanea = InstantaneaCuerpos::tomar(self.reloj.tiempo as f32, &self.cuerpos, &self.grafo);
+            self.paso_fijo.registrar(instantanea);
+        }
+        if let Some(tiempo) = self.paso_fijo.interpolar(&mut self.cuerpos, &mut self.grafo) {
+            self.datos_uniformes.tiempo_actual = tiempo;
+        }
+        // La cámara responde a la entrada en cada cuadro, no solo en cada paso
+        self.actualizar_camara();
+    }
+
+    /// Sigue al cuerpo enfocado y pasa la cámara y la luz a los uniformes
+    fn actualizar_camara(&mut self) {
+        self.simulacion().seguir_foco();
+        self.datos_uniformes.matriz_vista = self.camara.matriz_vista().into();
+        self.datos_uniformes.matriz_proyeccion = self.camara.matriz_proyeccion(self.aspecto()).into();
+        self.datos_uniformes.ojo = self.camara.ojo.into();
+        let estrella = self.cuerpos.iter().find(|c| c.es_estrella());
+        self.datos_uniformes.luz = estrella.map_or(Vec3::zeros(), |c| c.posicion_3d()).into();
+    }
+
+    /// Avanza la simulación a un instante dado (en segundos de simulación)
+    fn actualizar_a(&mut self, tiempo: f32) {
+        self.simulacion().posicionar(tiempo);
+        self.actualizar_dependientes(tiempo);
+    }
+
+    /// Lo que sigue a los cuerpos ya puestos en `tiempo`: estelas, pipelines, texturas,
+    /// eventos, partículas, cámara y título
+    fn actualizar_dependientes(&mut self, tiempo: f32) {
+        // Las partículas avanzan también con el tiempo en reversa
+        let paso = (tiempo - self.datos_uniformes.tiempo_actual).abs();
+        self.datos_uniformes.tiempo_actual = tiempo;
+        if self.capas.estelas {
+            let en_marco = self.grafo.marco.is_some();
+            self.estelas.registrar(&self.cuerpos, tiempo, !self.fisica.activa && !en_marco, en_marco);
+        }
+        self.pipelines.preparar(self.tipos_en_uso());
+        self.shaders_propios.actualizar(&self.cuerpos, &mut self.pipelines);
+        self.texturas.recibir_mapas(&self.dispositivo, &self.cola_comandos, &self.cuerpos);
+        self.texturas.hornear_pendientes(&self.dispositivo, &self.cola_comandos, &self.cuerpos);
+        let diametros = self.diametros_en_pantalla();
+        self.texturas.transmitir(&self.dispositivo, &self.cola_comandos, &diametros);
+        if self.telescopio.activo {
+            if let Some(indice) = self.cuerpo_bajo_cursor() {
+                self.telescopio.objetivo = Some(indice);
+            }
+        }
+        let impactos = self.detector_eventos.revisar(&self.cuerpos, tiempo, &mut self.eventos);
+        if let Some(plumas) = &mut self.plumas {
+            for impacto in &impactos {
+                plumas.emitir(&self.cola_comandos, impacto, self.cuerpos[impacto.mayor].escala);
+            }
+            plumas.simular(&self.dispositivo, &self.cola_comandos, paso);
+        }
+        self.particulas.simular(&self.cuerpos, &impactos, self.plumas.is_none(), paso);
+        self.actualizar_camara();
+        self.actualizar_titulo();
+    }
+
+    fn renderizar(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let (Some(superficie), Some(vigilante)) = (&self.superficie, self.vigilante.clone()) else {
+            return Ok(());
+        };
+        // Dispara los avisos de trabajo terminado que espera el vigilante
+        self.dispositivo.poll(wgpu::Maintain::Poll);
+        let salida = match self.cuadro_adelantado.take() {
+            Some(salida) => salida,
+            None => vigilante
+                .vigilar("get_current_texture", || superficie.get_current_texture())
+                .inspect_err(|error| {
+                    if matches!(error, wgpu::SurfaceError::Timeout) {
+                        vigilante.registrar_bloqueo();
+                    }
+                })?,
+        };
+        let vista = salida
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        vigilante.vigilar("envío del cuadro", || {
+            self.dibujar_escena(&vista);
+            if let (Some(mut interfaz), Some(ventana)) = (self.interfaz.take(), self.ventana.clone()) {
+                if interfaz.visible {
+                    let salida_interfaz = interfaz.ejecutar(&ventana, |contexto| self.construir_interfaz(contexto));
+                    interfaz.dibujar(&ventana, &self.dispositivo, &self.cola_comandos, &vista, salida_interfaz);
+                }
+                self.interfaz = Some(interfaz);
+            }
+            salida.present();
+        });
+        vigilante.trabajo_enviado(&self.cola_comandos);
+        self.latencia.presentado();
+
+        let en_cola = self.ajustes_pantalla.cuadros_en_cola();
+        if self.configuracion.desired_maximum_frame_latency != en_cola {
+            self.configuracion.desired_maximum_frame_latency = en_cola;
+            self.latencia.reiniciar();
+            self.redimensionar(self.tamano_ventana);
+        }
+        if self.ajustes_pantalla.latencia_baja {
+            self.adelantar_cuadro(&vigilante);
+        }
+        Ok(())
+    }
+
+    /// Con `latencia_baja`, al terminar un cuadro se espera aquí a la GPU y a la próxima
+    /// imagen de la superficie, que es donde Fifo bloquea hasta el refresco. Así la espera
+    /// queda antes de leer la entrada y no entre la entrada y el dibujo: los eventos que
+    /// llegan mientras tanto los ve el cuadro siguiente, que ya tiene la imagen en mano
+    fn adelantar_cuadro(&mut self, vigilante: &VigilanteGpu) {
+        let Some(superficie) = &self.superficie else { return };
+        vigilante.vigilar("espera de la GPU", || self.dispositivo.poll(wgpu::Maintain::Wait));
+        // Si falla, `renderizar` la vuelve a pedir y se ocupa del error
+        self.cuadro_adelantado = vigilante
+            .vigilar("get_current_texture", || superficie.get_current_texture())
+            .ok();
+    }
+
+    /// Pasa al adaptador de etiqueta `etiqueta` rehaciendo el dispositivo; la simulación y la
+    /// vista se conservan, y si el adaptador no da un dispositivo se sigue con el actual
+    pub fn cambiar_adaptador(&mut self, etiqueta: String, ajustes: &Ajustes) {
+        if etiqueta == self.adaptador_actual {
+            return;
+        }
+        // Los dispositivos GL comparten el display EGL: soltar el viejo invalida el nuevo, y
+        // conservarlo dejaría toda su memoria sin liberar
+        if self.backend == wgpu::Backend::Gl {
+            eprintln!(
+                "Con OpenGL el adaptador no se cambia en marcha: indica \"{}\" en [gpu] de los ajustes y reinicia",
+                etiqueta
+            );
+            return;
+        }
+        println!("Cambiando al adaptador {}", etiqueta);
+        let anterior = self.ajustes_gpu.adaptador.replace(etiqueta);
+        if let Err(error) = self.rehacer_dispositivo(ajustes, false, false) {
+            eprintln!("No se cambió de adaptador, se sigue con {}: {}", self.adaptador_actual, error);
+            self.ajustes_gpu.adaptador = anterior;
+        }
+    }
+
+    /// Aplica un paso de recuperación del vigilante; si falla, el siguiente paso lo intenta
+    fn recuperar(&mut self, recuperacion: Recuperacion, ajustes: &Ajustes) {
+        if recuperacion == Recuperacion::Superficie {
+            self.redimensionar(self.tamano_ventana);
+            return;
+        }
+        if let Err(error) = self.rehacer_dispositivo(ajustes, recuperacion == Recuperacion::Software, true) {
+            eprintln!("No se pudo recrear el dispositivo: {}", error);
+        }
+    }
+
+    /// Cambia el estado por uno con dispositivo nuevo que conserva la simulación (cuerpos,
+    /// reloj, cámara, capas) y solo rehace los recursos GPU. Si no se puede crear, el estado
+    /// actual sigue con una superficie nueva. `colgado` es el caso del vigilante: el
+    /// dispositivo viejo no responde y no se espera su trabajo
+    fn rehacer_dispositivo(&mut self, ajustes: &Ajustes, software: bool, colgado: bool) -> Result<(), String> {
+        // La ventana admite una sola superficie: se suelta antes de crear la nueva
+        self.cuadro_adelantado = None;
+        self.superficie = None;
+        // La escena y el adaptador pueden haber cambiado desde el arranque
+        let ajustes = Ajustes {
+            escena: self.escena.clone(),
+            gpu: self.ajustes_gpu.clone(),
+            ..ajustes.clone()
+        };
+        let resultado = pollster::block_on(EstadoAplicacion::inicializar(
+            self.ventana.clone(),
+            self.tamano_ventana,
+            &ajustes,
+            software,
+        ));
+        let mut nuevo = match resultado {
+            Ok(nuevo) => nuevo,
+            Err(error) => {
+                self.restaurar_superficie();
+                return Err(error);
+            }
+        };
+        self.conservar_en(&mut nuevo);
+        let viejo = std::mem::replace(self, nuevo);
+        if colgado {
+            // El dispositivo viejo no se suelta: liberarlo puede quedarse esperando al trabajo
+            // colgado
+            std::mem::forget(viejo);
+        } else {
+            // Responde: se termina su trabajo y se suelta con sus buffers y texturas, y así
+            // también se libera la GPU que se deja
+            viejo.dispositivo.poll(wgpu::Maintain::Wait);
+            drop(viejo);
+        }
+        Ok(())
+    }
+
+    /// Pasa a `nuevo`, recién creado con otro dispositivo, lo que no depende de la GPU: la
+    /// simulación, la vista, la entrada y la interfaz. Sin `..` en el patrón, un campo
+    /// agregado a `EstadoAplicacion` no compila hasta decidir aquí si se conserva o se rehace
+    fn conservar_en(&mut self, nuevo: &mut EstadoAplicacion) {
+        let EstadoAplicacion {
+            // Se conservan tal cual
+            recarga_escena, escena, aviso_escena, modificadores, portapapeles, grabador_entrada,
+            paso_a_paso, paso_fijo, fila, repeticion, paso_repetido, consola, datos_uniformes,
+            camara, reloj, editor, kepler, fisica, ultimo_cuadro, frecuencia, posicion_mouse,
+            mouse_presionado, posicion_presion, cuerpos, grafo, planeta_seleccionado, foco,
+            medicion, quiz, animaciones, constelaciones, mostrar_constelaciones,
+            cuadricula_ecliptica, cuadricula_ecuatorial, mostrar_ecliptica, mostrar_ecuatorial,
+            caricatura, escala_distancias, capas, estelas, ruta_ajustes, ajustes_grabacion,
+            ajustes_pantalla, grabacion, radio_disco_ecliptica, eventos, detector_eventos,
+            titulo_actual, ajustes_gpu, adaptador_pedido, latencia,
+            // Se rehacen, con lo elegido en los viejos
+            telescopio, posproceso, comparacion_cpu, interfaz, vigilante,
+            // Recursos del dispositivo, o datos que dependen de él
+            ventana: _, instancia: _, superficie: _, dispositivo: _, cola_comandos: _,
+            configuracion: _, tamano_ventana: _, pipelines: _, shaders_propios: _,
+            pipeline_anillos: _, pipelines_escombros: _, pipeline_atmosferas: _, mallas_anillos: _,
+            mallas_lod: _, niveles_lod: _, pipeline_lineas: _, buffer_vertices: _,
+            buffer_indices: _, formato_indices: _, cantidad_indices: _, cantidad_vertices: _,
+            formato_vertices: _, buffer_lineas: _, pipeline_cintas: _, buffer_cintas: _, cielo: _,
+            pipeline_galaxias: _, buffer_galaxias: _, cantidad_galaxias: _, texto: _, texturas: _,
+            modelos: _, modelos_escena: _, mallas_modelos: _, mecanismo: _, plumas: _,
+            particulas: _, cinturon: _, cinturon_soportado: _, modulo_shader: _,
+            buffer_uniformes: _, grupo_bind_uniformes: _, layout_uniformes: _, buffer_cuerpos: _,
+            separacion_uniformes: _, paleta_bandas_gas: _, constantes_empuje: _, cache_grupos: _,
+            anillo: _, adaptadores: _, adaptador_actual: _, backend: _, cuadro_adelantado: _,
+        } = self;
+        std::mem::swap(recarga_escena, &mut nuevo.recarga_escena);
+        std::mem::swap(escena, &mut nuevo.escena);
+        std::mem::swap(aviso_escena, &mut nuevo.aviso_escena);
+        std::mem::swap(modificadores, &mut nuevo.modificadores);
+        std::mem::swap(portapapeles, &mut nuevo.portapapeles);
+        std::mem::swap(grabador_entrada, &mut nuevo.grabador_entrada);
+        std::mem::swap(paso_a_paso, &mut nuevo.paso_a_paso);
+        std::mem::swap(paso_fijo, &mut nuevo.paso_fijo);
+        std::mem::swap(fila, &mut nuevo.fila);
+        std::mem::swap(repeticion, &mut nuevo.repeticion);
+        std::mem::swap(paso_repetido, &mut nuevo.paso_repetido);
+        std::mem::swap(consola, &mut nuevo.consola);
+        std::mem::swap(datos_uniformes, &mut nuevo.datos_uniformes);
+        std::mem::swap(camara, &mut nuevo.camara);
+        std::mem::swap(reloj, &mut nuevo.reloj);
+        std::mem::swap(editor, &mut nuevo.editor);
+        std::mem::swap(kepler, &mut nuevo.kepler);
+        std::mem::swap(fisica, &mut nuevo.fisica);
+        std::mem::swap(ultimo_cuadro, &mut nuevo.ultimo_cuadro);
+        std::mem::swap(frecuencia, &mut nuevo.frecuencia);
+        std::mem::swap(posicion_mouse, &mut nuevo.posicion_mouse);
+        std::mem::swap(mouse_presionado, &mut nuevo.mouse_presionado);
+        std::mem::swap(posicion_presion, &mut nuevo.posicion_presion);
+        std::mem::swap(cuerpos, &mut nuevo.cuerpos);
+        std::mem::swap(grafo, &mut nuevo.grafo);
+        std::mem::swap(planeta_seleccionado, &mut nuevo.planeta_seleccionado);
+        std::mem::swap(foco, &mut nuevo.foco);
+        std::mem::swap(medicion, &mut nuevo.medicion);
+        std::mem::swap(quiz, &mut nuevo.quiz);
+        std::mem::swap(animaciones, &mut nuevo.animaciones);
+        std::mem::swap(constelaciones, &mut nuevo.constelaciones);
+        std::mem::swap(mostrar_constelaciones, &mut nuevo.mostrar_constelaciones);
+        std::mem::swap(cuadricula_ecliptica, &mut nuevo.cuadricula_ecliptica);
+        std::mem::swap(cuadricula_ecuatorial, &mut nuevo.cuadricula_ecuatorial);
+        std::mem::swap(mostrar_ecliptica, &mut nuevo.mostrar_ecliptica);
+        std::mem::swap(mostrar_ecuatorial, &mut nuevo.mostrar_ecuatorial);
+        std::mem::swap(caricatura, &mut nuevo.caricatura);
+        std::mem::swap(escala_distancias, &mut nuevo.escala_distancias);
+        std::mem::swap(capas, &mut nuevo.capas);
+        std::mem::swap(estelas, &mut nuevo.estelas);
+        std::mem::swap(ruta_ajustes, &mut nuevo.ruta_ajustes);
+        std::mem::swap(ajustes_grabacion, &mut nuevo.ajustes_grabacion);
+        std::mem::swap(ajustes_pantalla, &mut nuevo.ajustes_pantalla);
+        std::mem::swap(grabacion, &mut nuevo.grabacion);
+        std::mem::swap(radio_disco_ecliptica, &mut nuevo.radio_disco_ecliptica);
+        std::mem::swap(eventos, &mut nuevo.eventos);
+        std::mem::swap(detector_eventos, &mut nuevo.detector_eventos);
+        std::mem::swap(titulo_actual, &mut nuevo.titulo_actual);
+        std::mem::swap(ajustes_gpu, &mut nuevo.ajustes_gpu);
+        std::mem::swap(adaptador_pedido, &mut nuevo.adaptador_pedido);
+        std::mem::swap(latencia, &mut nuevo.latencia);
+        nuevo.telescopio.heredar(telescopio);
+        nuevo.posproceso.heredar(posproceso);
+        nuevo.comparacion_cpu.heredar(comparacion_cpu);
+        if let (Some(nueva), Some(vieja)) = (&mut nuevo.interfaz, interfaz) {
+            nueva.heredar(vieja);
+        }
+        // El vigilante viejo se suelta aquí para que su hilo termine
+        if let (Some(nuevo), Some(viejo)) = (&nuevo.vigilante, vigilante.take()) {
+            nuevo.heredar(&viejo);
+        }
+    }
+
+    /// Después de un intento fallido de rehacer el dispositivo, vuelve a crear la superficie
+    /// de la ventana para el dispositivo actual
+    fn restaurar_superficie(&mut self) {
+        let Some(ventana) = self.ventana.clone() else { return };
+        match self.instancia.create_surface(ventana) {
+            Ok(superficie) => {
+                superficie.configure(&self.dispositivo, &self.configuracion);
+                self.superficie = Some(superficie);
+            }
+            Err(error) => eprintln!("No se pudo recrear la superficie: {}", error),
+        }
+    }
+
+    /// Renderiza un cuadro en una textura propia y lo devuelve como imagen RGBA
+    fn capturar_cuadro(&mut self) -> image::RgbaImage {
+        let textura = self.dispositivo.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Textura de Captura"),
+            size: wgpu::Extent3d {
+                width: self.configuracion.width,
+                height: self.configuracion.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.configuracion.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let vista = textura.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.dibujar_escena(&vista);
+        leer_textura(&self.dispositivo, &self.cola_comandos, &textura)
+    }
+
+    pub fn iniciar_grabacion(&mut self, destino: &std::path::Path) -> Result<(), String> {
+        let tamano = (self.configuracion.width, self.configuracion.height);
+        self.grabacion = Some(Grabacion::iniciar(destino, self.ajustes_grabacion.cuadros_por_segundo, tamano)?);
+        println!("Grabando en {} (F9 para detener)", destino.display());
+        Ok(())
+    }
+
+    fn detener_grabacion(&mut self, grabacion: Grabacion) {
+        match grabacion.terminar() {
+            Ok(resumen) => println!("Grabación terminada: {}", resumen),
+            Err(error) => eprintln!("La grabación terminó con errores: {}", error),
+        }
+    }
+
+    /// Termina la grabación en curso, si la hay (al salir)
+    pub fn cerrar(&mut self) {
+        if let Some(grabacion) = self.grabacion.take() {
+            self.detener_grabacion(grabacion);
+        }
+    }
+
+    /// Entre la ventana y la pantalla completa de `[pantalla]`; el nuevo tamaño llega como
+    /// `Resized` y pasa por `redimensionar`
+    pub fn alternar_pantalla_completa(&mut self) {
+        let Some(ventana) = &self.ventana else { return };
+        if ventana.fullscreen().is_some() {
+            ventana.set_fullscreen(None);
+            return;
+        }
+        match self.ajustes_pantalla.modo(ventana) {
+            Ok(modo) => ventana.set_fullscreen(Some(modo)),
+            Err(error) => eprintln!("Pantalla completa no disponible: {}", error),
+        }
+    }
+
+    /// Agrega el cuadro recién dibujado a la grabación en curso; un error la detiene
+    pub fn grabar_cuadro(&mut self) {
+        if self.grabacion.is_none() || self.paso_a_paso.congelado {
+            return;
+        }
+        let imagen = self.capturar_cuadro();
+        let Some(grabacion) = &mut self.grabacion else { return };
+        if let Err(error) = grabacion.agregar(&imagen) {
+            eprintln!("Grabación detenida: {}", error);
+            if let Some(grabacion) = self.grabacion.take() {
+                self.detener_grabacion(grabacion);
+            }
+        }
+    }
+
+    /// Guarda el cuadro actual (sin la interfaz) en `capturas/` con la fecha y hora en el nombre
+    fn guardar_captura(&mut self) -> Result<std::path::PathBuf, String> {
+        let carpeta = std::path::Path::new(CARPETA_CAPTURAS);
+        std::fs::create_dir_all(carpeta).map_err(|e| format!("{}: {}", carpeta.display(), e))?;
+        let ruta = ruta_captura_libre(carpeta);
+        self.guardar_captura_en(&ruta)?;
+        Ok(ruta)
+    }
+
+    fn guardar_captura_en(&mut self, ruta: &std::path::Path) -> Result<(), String> {
+        self.capturar_cuadro()
+            .save(ruta)
+            .map_err(|e| format!("{}: {}", ruta.display(), e))
+    }
+
+    /// Como `guardar_captura`, pero el destino se elige en un diálogo del sistema
+    fn guardar_captura_como(&mut self) {
+        let sugerida = ruta_captura_libre(std::path::Path::new(CARPETA_CAPTURAS));
+        let Some(ruta) = dialogos::destino_captura(&sugerida) else { return };
+        match self.guardar_captura_en(&ruta) {
+            Ok(()) => println!("Captura guardada en {}", ruta.display()),
+            Err(error) => eprintln!("No se guardó la captura: {}", error),
+        }
+    }
+
+    /// Elige una escena en un diálogo del sistema y la carga; el error queda en el panel
+    fn abrir_escena(&mut self) {
+        let actual = self.escena.ruta.clone().unwrap_or_else(|| ArchivoEscena::RUTA_PREDETERMINADA.into());
+        let Some(ruta) = dialogos::abrir_escena(&actual) else { return };
+        self.aviso_escena = self.cargar_escena(&ruta).err();
+        if let Some(error) = &self.aviso_escena {
+            eprintln!("Error en la escena: {}", error);
+        }
+    }
+
+    /// Uniformes globales del cuadro con los datos propios de un cuerpo
+    fn uniformes_cuerpo(&self, base: &DatosUniformes, indice: usize, cuerpo: &CuerpoCeleste) -> DatosUniformes {
+        let mut uniformes = *base;
+        uniformes.matriz_modelo = self.grafo.matriz_modelo(indice, cuerpo, uniformes.tiempo_actual).into();
+        uniformes.tipo_render = cuerpo.tipo_shader | self.bandera_caricatura(cuerpo.caricatura);
+        uniformes.tinte = cuerpo.tinte;
+        uniformes.actividad = cuerpo.actividad;
+        let MaterialPbr { albedo, metalico, rugosidad, emisivo } = cuerpo.material;
+        uniformes.albedo = albedo;
+        uniformes.metalico = metalico;
+        uniformes.rugosidad = rugosidad;
+        uniformes.emisivo = emisivo;
+        uniformes.radio_cuerpo = cuerpo.escala;
+        uniformes.relieve = cuerpo.relieve.as_ref().map_or(0.0, |r| r.altura);
+        if let Some(anillo) = cuerpo.anillo {
+            uniformes.anillo_interno = anillo.radio_interno;
+            uniformes.anillo_externo = anillo.radio_externo;
+        }
+        if let Some(atmosfera) = cuerpo.atmosfera {
+            uniformes.rayleigh = atmosfera.rayleigh;
+            uniformes.mie = atmosfera.mie;
+            uniformes.espesor_atmosfera = atmosfera.espesor;
+            uniformes.anisotropia_mie = atmosfera.anisotropia_mie;
+            uniformes.intensidad_atmosfera = atmosfera.intensidad;
+        }
+        uniformes
+    }
+
+    /// Shaders que dibujan la escena: los de sus cuerpos y el latón del estilo planetario
+    fn tipos_en_uso(&self) -> Vec<u32> {
+        let laton = (self.escena.estilo == EstiloEscena::Planetario).then_some(TIPO_LATON);
+        self.cuerpos.iter().map(|c| c.tipo_shader).chain(laton).collect()
+    }
+
+    /// Bit de caricatura de un cuerpo: la tecla B lo pone en todos
+    fn bandera_caricatura(&self, propia: bool) -> u32 {
+        if self.caricatura || propia {
+            BANDERA_CARICATURA
+        } else {
+            0
+        }
+    }
+
+    /// Uniformes de las piezas de latón: su malla ya está en espacio de mundo
+    fn uniformes_mecanismo(&self, base: &DatosUniformes) -> DatosUniformes {
+        let mut uniformes = *base;
+        uniformes.matriz_modelo = Mat4::identity().into();
+        uniformes.tipo_render = TIPO_LATON | self.bandera_caricatura(false);
+        uniformes.tinte = [1.0; 3];
+        uniformes.actividad = 1.0;
+        let MaterialPbr { albedo, metalico, rugosidad, emisivo } = MaterialPbr::por_tipo(TIPO_LATON);
+        uniformes.albedo = albedo;
+        uniformes.metalico = metalico;
+        uniformes.rugosidad = rugosidad;
+        uniformes.emisivo = emisivo;
+        uniformes
+    }
+
+    /// Rehace el mecanismo del estilo planetario para la cámara principal
+    fn preparar_mecanismo(&mut self, codificador: &mut wgpu::CommandEncoder) {
+        if self.escena.estilo != EstiloEscena::Planetario {
+            return;
+        }
+        let incluidos: Vec<usize> = (0..self.cuerpos.len()).filter(|&i| self.cuerpo_dibujable(i)).collect();
+        self.mecanismo.preparar(
+            &mut self.anillo,
+            &self.dispositivo,
+            codificador,
+            self.formato_vertices,
+            &self.cuerpos,
+            &incluidos,
+            self.grafo.centro_escena(),
+            self.camara.ojo,
+        );
+    }
+
+    /// Un tramo de la malla del mecanismo; deja listos los buffers de la esfera
+    fn dibujar_mecanismo<'a>(
+        &'a self,
+        pase: &mut wgpu::RenderPass<'a>,
+        base: &DatosUniformes,
+        grupo_cuerpos: &'a wgpu::BindGroup,
+        tramo: std::ops::Range<u32>,
+    ) {
+        if self.escena.estilo != EstiloEscena::Planetario || tramo.is_empty() {
+            return;
+        }
+        let datos = self.constantes_empuje.then(|| DatosCuerpo::de(&self.uniformes_mecanismo(base)));
+        match datos {
+            Some(_) => pase.set_bind_group(0, &self.grupo_bind_uniformes, &[0]),
+            None => pase.set_bind_group(0, grupo_cuerpos, &[self.cuerpos.len() as u32 * self.separacion_uniformes]),
+        }
+        pase.set_bind_group(1, self.texturas.predeterminado(), &[]);
+        pase.set_pipeline(self.pipelines.para(TIPO_LATON));
+        DatosCuerpo::empujar(datos.as_ref(), pase);
+        pase.set_vertex_buffer(0, self.mecanismo.buffer_vertices.slice(..));
+        pase.draw(tramo, 0..1);
+        pase.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+    }
+
+    /// Dibuja la mitad lejana o cercana de un anillo y deja listos los buffers de la esfera
+    fn dibujar_mitad_anillo<'a>(
+        &'a self,
+        pase: &mut wgpu::RenderPass<'a>,
+        malla: &'a MallaAnillo,
+        ojo_modelo: &Vec3,
+        lejanos: bool,
+        datos: Option<&DatosCuerpo>,
+    ) {
+        pase.set_pipeline(&self.pipeline_anillos);
+        DatosCuerpo::empujar(datos, pase);
+        pase.set_vertex_buffer(0, malla.buffer_vertices.slice(..));
+        pase.set_index_buffer(malla.buffer_indices.slice(..), wgpu::IndexFormat::Uint16);
+        for rango in malla.rangos_mitad(ojo_modelo, lejanos) {
+            pase.draw_indexed(rango, 0, 0..1);
+        }
+        if self.capas.particulas {
+            pase.set_pipeline(&self.pipelines_escombros[lejanos as usize]);
+            DatosCuerpo::empujar(datos, pase);
+            pase.draw(0..6, 0..MallaAnillo::ESCOMBROS);
+        }
+        pase.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+        pase.set_index_buffer(self.buffer_indices.slice(..), self.formato_indices);
+    }
+
+    fn dibujar_mitad_particulas<'a>(&'a self, pase: &mut wgpu::RenderPass<'a>, indice: usize, lejanas: bool) {
+        if self.capas.particulas && self.particulas.dibujar_mitad(pase, indice, lejanas) {
+            pase.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+        }
+    }
+
+    /// Un solo envío por cuadro: las subidas del anillo quedan en orden con los pases que
+    /// las leen (el telescopio y la vista principal usan los mismos buffers de uniformes)
+    fn dibujar_escena(&mut self, vista: &wgpu::TextureView) {
+        let (lineas, lineas_cielo, lote_texto) = self.generar_superposiciones();
+        let cintas = self.generar_cintas();
+        let grupo_cuerpos = self.grupo_cuerpos();
+
+        self.anillo.empezar(&self.dispositivo);
+        let mut codificador = self
+            .dispositivo
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Codificador de Comandos de Render"),
+            });
+        let dispositivo = &self.dispositivo;
+        self.anillo.subir(dispositivo, &mut codificador, &self.buffer_lineas, 0, bytemuck::cast_slice(&lineas));
+        self.anillo.subir(dispositivo, &mut codificador, &self.buffer_cintas, 0, bytemuck::cast_slice(&cintas));
+        self.texto.preparar(&mut self.anillo, dispositivo, &mut codificador, &lote_texto);
+        self.recortar_cinturon(&mut codificador);
+        self.preparar_mecanismo(&mut codificador);
+        let adelante = (self.camara.objetivo - self.camara.ojo).normalize();
+        if let Some(plumas) = &self.plumas {
+            plumas.ordenar(&mut self.anillo, &self.dispositivo, &mut codificador, self.camara.ojo, adelante);
+        }
+        self.particulas.preparar(
+            &mut self.anillo,
+            &self.dispositivo,
+            &mut codificador,
+            &self.cuerpos,
+            self.camara.ojo,
+            adelante,
+        );
+
+        if let Some(uniformes) = self.uniformes_telescopio() {
+            let resolucion = self.datos_uniformes.dimension_pantalla;
+            self.telescopio.preparar(&mut self.anillo, &self.dispositivo, &mut codificador, resolucion);
+            self.subir_uniformes(&mut codificador, &uniformes);
+            self.codificar_escena(&mut codificador, &self.telescopio.vista, &uniformes, &grupo_cuerpos, None);
+        }
+
+        let base = self.datos_uniformes;
+        self.actualizar_lod(base.tiempo_actual);
+        self.ordenar_modelos(base.tiempo_actual);
+        self.subir_uniformes(&mut codificador, &base);
+        let superposiciones = Superposiciones {
+            lineas: lineas.len(),
+            lineas_cielo,
+            cintas: cintas.len(),
+        };
+        let tamano = [self.configuracion.width, self.configuracion.height];
+        self.posproceso.preparar(&self.dispositivo, &self.cola_comandos, tamano);
+        let destino = self.posproceso.intermedia().unwrap_or(vista);
+        self.codificar_escena(&mut codificador, destino, &base, &grupo_cuerpos, Some(superposiciones));
+        self.posproceso.aplicar(&mut codificador, vista, &self.grupo_bind_uniformes);
+        let dibujables: Vec<bool> = (0..self.cuerpos.len()).map(|i| self.cuerpo_dibujable(i)).collect();
+        self.comparacion_cpu.preparar(
+            &self.dispositivo,
+            &self.cola_comandos,
+            &self.posproceso,
+            tamano,
+            &self.camara,
+            &self.cuerpos,
+            &self.grafo,
+            self.capas.orbitas,
+            base.tiempo_actual,
+            |indice| dibujables[indice],
+        );
+        self.comparacion_cpu.dibujar(&mut codificador, vista, tamano, &self.posproceso, &self.grupo_bind_uniformes);
+        self.anillo.enviar(&self.cola_comandos, codificador);
+        self.cache_grupos.terminar_cuadro();
+    }
+
+    /// Orden de pintor de las mallas OBJ para el ojo de la cámara principal
+    fn ordenar_modelos(&mut self, tiempo: f32) {
+        let ojo = nalgebra_glm::vec4(self.camara.ojo.x, self.camara.ojo.y, self.camara.ojo.z, 1.0);
+        for (indice, malla) in self.mallas_modelos.iter_mut().enumerate() {
+            let Some(malla) = malla else { continue };
+            let cuerpo = &self.cuerpos[indice];
+            if let Some(inversa) = self.grafo.matriz_modelo(indice, cuerpo, tiempo).try_inverse() {
+                malla.ordenar(&self.cola_comandos, &(inversa * ojo).xyz());
+            }
+        }
+    }
+
+    /// Rehace los parches de los cuerpos dibujables cuyas hojas cambiaron al moverse el ojo
+    fn actualizar_lod(&mut self, tiempo: f32) {
+        self.mallas_lod.resize_with(self.cuerpos.len(), || None);
+        if self.niveles_lod == 0 {
+            return;
+        }
+        let ojo = nalgebra_glm::vec4(self.camara.ojo.x, self.camara.ojo.y, self.camara.ojo.z, 1.0);
+        for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+            let inversa = self.grafo.matriz_modelo(indice, cuerpo, tiempo).try_inverse();
+            let con_modelo = self.mallas_modelos.get(indice).is_some_and(Option::is_some);
+            let hojas = match inversa {
+                Some(inversa) if self.cuerpo_dibujable(indice) && !con_modelo => MallaLod::hojas(&(inversa * ojo).xyz(), self.niveles_lod),
+                _ => Vec::new(),
+            };
+            let malla = &mut self.mallas_lod[indice];
+            if hojas.is_empty() {
+                *malla = None;
+            } else if malla.as_ref().is_none_or(|m| m.hojas != hojas) {
+                *malla = Some(MallaLod::nueva(&self.dispositivo, hojas, self.formato_vertices));
+            }
+        }
+    }
+
+    /// Uniformes globales y de cada cuerpo dibujable para la cámara de `base`, los de los
+    /// cuerpos en una sola copia
+    fn subir_uniformes(&mut self, codificador: &mut wgpu::CommandEncoder, base: &DatosUniformes) {
+        self.anillo.subir(&self.dispositivo, codificador, &self.buffer_uniformes, 0, bytemuck::cast_slice(&[*base]));
+        if self.constantes_empuje {
+            return;
+        }
+        let separacion = self.separacion_uniformes as usize;
+        // La franja que sigue a la del último cuerpo es la del mecanismo del planetario
+        let mut franjas = vec![0u8; (self.cuerpos.len() + 1) * separacion];
+        for indice in 0..self.cuerpos.len() {
+            if !self.cuerpo_dibujable(indice) {
+                continue;
+            }
+            let uniformes = self.uniformes_cuerpo(base, indice, &self.cuerpos[indice]);
+            let bytes = bytemuck::bytes_of(&uniformes);
+            franjas[indice * separacion..][..bytes.len()].copy_from_slice(bytes);
+        }
+        let mecanismo = self.uniformes_mecanismo(base);
+        let bytes = bytemuck::bytes_of(&mecanismo);
+        franjas[self.cuerpos.len() * separacion..][..bytes.len()].copy_from_slice(bytes);
+        self.anillo.subir(&self.dispositivo, codificador, &self.buffer_cuerpos, 0, &franjas);
+    }
+
+    /// Culling del cinturón para la cámara principal, con los cuerpos más grandes en
+    /// pantalla como oclusores
+    fn recortar_cinturon(&mut self, codificador: &mut wgpu::CommandEncoder) {
+        if !self.capas.asteroides {
+            return;
+        }
+        let Some(cinturon) = &self.cinturon else {
+            return;
+        };
+        let ojo = self.camara.ojo;
+        let mut oclusores: Vec<(f32, [f32; 4])> = (0..self.cuerpos.len())
+            .filter(|&indice| self.cuerpo_dibujable(indice))
+            .map(|indice| {
+                let cuerpo = &self.cuerpos[indice];
+                let centro = cuerpo.posicion_3d();
+                let aparente = cuerpo.escala / (centro - ojo).norm().max(1e-4);
+                (aparente, [centro.x, centro.y, centro.z, cuerpo.escala])
+            })
+            .collect();
+        oclusores.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let oclusores: Vec<[f32; 4]> = oclusores
+            .into_iter()
+            .take(ParametrosCulling::MAX_OCLUSORES)
+            .map(|(_, esfera)| esfera)
+            .collect();
+        let centro = self.grafo.centro_escena();
+        cinturon.recortar(&mut self.anillo, &self.dispositivo, codificador, &self.datos_uniformes, centro, &oclusores);
+    }
+
+    fn crear_buffer_cuerpos(dispositivo: &wgpu::Device, separacion: u32, capacidad: usize) -> wgpu::Buffer {
+        dispositivo.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer de Uniformes de Cuerpos"),
+            size: separacion as wgpu::BufferAddress * capacidad.max(1) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Bind group de los uniformes de los cuerpos, tomado de la caché mientras su buffer no
+    /// cambie. Si la escena ya no cabe, el buffer se rehace con el doble de franjas
+    fn grupo_cuerpos(&mut self) -> Arc<wgpu::BindGroup> {
+        let capacidad = self.buffer_cuerpos.size() / self.separacion_uniformes as wgpu::BufferAddress;
+        if (capacidad as usize) < self.cuerpos.len() + 1 {
+            self.buffer_cuerpos = Self::crear_buffer_cuerpos(
+                &self.dispositivo,
+                self.separacion_uniformes,
+                (self.cuerpos.len() + 1).next_power_of_two(),
+            );
+        }
+        self.cache_grupos.obtener(
+            &self.dispositivo,
+            "Bind Group de Cuerpos",
+            &self.layout_uniformes,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &self.buffer_cuerpos,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(std::mem::size_of::<DatosUniformes>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(&self.paleta_bandas_gas),
+                },
+            ],
+        )
+    }
+
+    /// Uniformes del telescopio: misma escena vista desde la cámara con un campo estrecho hacia su objetivo
+    fn uniformes_telescopio(&self) -> Option<DatosUniformes> {
+        if !self.telescopio.activo {
+            return None;
+        }
+        let indice = self.telescopio.objetivo.filter(|&i| self.cuerpo_dibujable(i))?;
+        let (vista, proyeccion) = Telescopio::encuadre(&self.camara, &self.cuerpos[indice]);
+        let mut uniformes = self.datos_uniformes;
+        uniformes.matriz_vista = vista.into();
+        uniformes.matriz_proyeccion = proyeccion.into();
+        uniformes.dimension_pantalla = [Telescopio::RESOLUCION as f32; 2];
+        Some(uniformes)
+    }
+
+    /// Pase de la escena hacia `destino` con la cámara de `base`; las superposiciones ya
+    /// subidas solo se dibujan en la vista principal
+    fn codificar_escena(
+        &self,
+        codificador: &mut wgpu::CommandEncoder,
+        destino: &wgpu::TextureView,
+        base: &DatosUniformes,
+        grupo_cuerpos: &wgpu::BindGroup,
+        superposiciones: Option<Superposiciones>,
+    ) {
+        let Superposiciones { lineas, lineas_cielo, cintas } = superposiciones.unwrap_or_default();
+
+        {
+            let mut pase_render = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pase de Renderizado Principal"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: destino,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.05,
+                            g: 0.08,
+                            b: 0.15,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.cielo.dibujar(&mut pase_render, &self.grupo_bind_uniformes);
+
+            if self.cantidad_galaxias > 0 {
+                pase_render.set_pipeline(&self.pipeline_galaxias);
+                pase_render.set_bind_group(0, &self.grupo_bind_uniformes, &[0]);
+                pase_render.set_vertex_buffer(0, self.buffer_galaxias.slice(..));
+                pase_render.draw(0..6, 0..self.cantidad_galaxias);
+            }
+
+            if lineas_cielo > 0 {
+                pase_render.set_pipeline(&self.pipeline_lineas);
+                pase_render.set_vertex_buffer(0, self.buffer_lineas.slice(..));
+                pase_render.draw(0..lineas_cielo as u32, 0..1);
+            }
+
+            // Vista desde arriba, la base queda detrás de las órbitas, del cinturón y de los cuerpos
+            let base_delante = self.mecanismo.tapa_cuerpos(&self.camara.ojo);
+            if !base_delante {
+                self.dibujar_mecanismo(&mut pase_render, base, grupo_cuerpos, self.mecanismo.base.clone());
+            }
+
+            if cintas > 0 {
+                pase_render.set_pipeline(&self.pipeline_cintas);
+                pase_render.set_vertex_buffer(0, self.buffer_cintas.slice(..));
+                pase_render.draw(0..cintas as u32, 0..1);
+            }
+
+            // Recortado para la cámara principal: el telescopio no lo dibuja
+            if let Some(cinturon) = self.cinturon.as_ref().filter(|_| superposiciones.is_some() && self.capas.asteroides) {
+                cinturon.dibujar(&mut pase_render, &self.grupo_bind_uniformes);
+            }
+
+            pase_render.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+            pase_render.set_index_buffer(self.buffer_indices.slice(..), self.formato_indices);
+
+            let frustum = Frustum::de_camara(&base.matriz_vista.into(), &base.matriz_proyeccion.into());
+            for (indice, cuerpo) in self.cuerpos.iter().enumerate() {
+                if !self.cuerpo_dibujable(indice) {
+                    continue;
+                }
+                // Su brazo y su eje pueden entrar en cuadro sin él
+                let piezas = self.mecanismo.piezas.get(indice).cloned().unwrap_or_default();
+                self.dibujar_mecanismo(&mut pase_render, base, grupo_cuerpos, piezas);
+                // Las partículas del cuerpo (la cola de un cometa) pueden entrar en cuadro sin él
+                if !frustum.contiene_esfera(&cuerpo.posicion_3d(), cuerpo.radio_envolvente()) {
+                    self.dibujar_mitad_particulas(&mut pase_render, indice, true);
+                    self.dibujar_mitad_particulas(&mut pase_render, indice, false);
+                    continue;
+                }
+                let datos = self
+                    .constantes_empuje
+                    .then(|| DatosCuerpo::de(&self.uniformes_cuerpo(base, indice, cuerpo)));
+                match datos {
+                    Some(_) => pase_render.set_bind_group(0, &self.grupo_bind_uniformes, &[0]),
+                    None => pase_render.set_bind_group(0, grupo_cuerpos, &[indice as u32 * self.separacion_uniformes]),
+                }
+                pase_render.set_bind_group(1, self.texturas.grupo(indice), &[]);
+
+                let anillo = self.mallas_anillos[indice]
+                    .as_ref()
+                    .filter(|_| cuerpo.anillo.is_some_and(|a| a.visible))
+                    .and_then(|malla| {
+                        let inversa = self
+                            .grafo
+                            .matriz_modelo(indice, cuerpo, base.tiempo_actual)
+                            .try_inverse()?;
+                        let ojo = inversa * nalgebra_glm::vec4(self.camara.ojo.x, self.camara.ojo.y, self.camara.ojo.z, 1.0);
+                        Some((malla, ojo.xyz()))
+                    });
+                if let Some((malla, ojo)) = &anillo {
+                    self.dibujar_mitad_anillo(&mut pase_render, malla, ojo, true, datos.as_ref());
+                }
+                self.dibujar_mitad_particulas(&mut pase_render, indice, true);
+                let pipeline = cuerpo
+                    .fragmento
+                    .as_deref()
+                    .and_then(|ruta| self.shaders_propios.pipeline(ruta, &self.pipelines))
+                    .unwrap_or_else(|| self.pipelines.para(cuerpo.tipo_shader));
+                pase_render.set_pipeline(pipeline);
+                DatosCuerpo::empujar(datos.as_ref(), &mut pase_render);
+                // La malla OBJ del cuerpo, sus parches de cerca o la esfera compartida
+                let propia = match self.mallas_modelos.get(indice).and_then(Option::as_ref) {
+                    Some(modelo) => Some((
+                        &modelo.buffer_vertices,
+                        &modelo.buffer_indices,
+                        wgpu::IndexFormat::Uint32,
+                        modelo.cantidad_indices,
+                    )),
+                    None => self.mallas_lod.get(indice).and_then(Option::as_ref).map(|malla| {
+                        (&malla.buffer_vertices, &malla.buffer_indices, malla.formato_indices, malla.cantidad_indices)
+                    }),
+                };
+                match propia {
+                    Some((vertices, indices, formato, cantidad)) => {
+                        pase_render.set_vertex_buffer(0, vertices.slice(..));
+                        pase_render.set_index_buffer(indices.slice(..), formato);
+                        pase_render.draw_indexed(0..cantidad, 0, 0..1);
+                        pase_render.set_vertex_buffer(0, self.buffer_vertices.slice(..));
+                        pase_render.set_index_buffer(self.buffer_indices.slice(..), self.formato_indices);
+                    }
+                    None => pase_render.draw_indexed(0..self.cantidad_indices, 0, 0..1),
+                }
+                if cuerpo.atmosfera.is_some() && self.capas.atmosferas {
+                    pase_render.set_pipeline(&self.pipeline_atmosferas);
+                    DatosCuerpo::empujar(datos.as_ref(), &mut pase_render);
+                    pase_render.draw_indexed(0..self.cantidad_indices, 0, 0..1);
+                }
+                if let Some((malla, ojo)) = &anillo {
+                    self.dibujar_mitad_anillo(&mut pase_render, malla, ojo, false, datos.as_ref());
+                }
+                self.dibujar_mitad_particulas(&mut pase_render, indice, false);
+            }
+
+            if base_delante {
+                self.dibujar_mecanismo(&mut pase_render, base, grupo_cuerpos, self.mecanismo.base.clone());
+            }
+
+            if let Some(plumas) = &self.plumas {
+                plumas.dibujar(&mut pase_render, &self.grupo_bind_uniformes);
+            }
+
+            if superposiciones.is_none() {
+                return;
+            }
+
+            if lineas > lineas_cielo {
+                pase_render.set_pipeline(&self.pipeline_lineas);
+                pase_render.set_vertex_buffer(0, self.buffer_lineas.slice(..));
+                pase_render.draw(lineas_cielo as u32..lineas as u32, 0..1);
+            }
+
+            if self.uniformes_telescopio().is_some() {
+                self.telescopio.dibujar(&mut pase_render, &self.grupo_bind_uniformes);
+            }
+
+            self.texto.dibujar(&mut pase_render, &self.grupo_bind_uniformes);
+        }
+    }
+}
+
+const CARPETA_CAPTURAS: &str = "capturas";
+
+/// Nombre con la fecha y hora en `carpeta`; dos capturas en el mismo segundo no se pisan
+fn ruta_captura_libre(carpeta: &std::path::Path) -> std::path::PathBuf {
+    let base = format!("captura_{}", marca_de_tiempo_actual());
+    (0..)
+        .map(|n| match n {
+            0 => carpeta.join(format!("{}.png", base)),
+            n => carpeta.join(format!("{}_{}.png", base, n)),
+        })
+        .find(|ruta| !ruta.exists())
+        .expect("rango infinito")
+}
+
+fn marca_de_tiempo_actual() -> String {
+    let segundos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    marca_de_tiempo(segundos)
+}
+
+/// `AAAAMMDD_HHMMSS` en UTC para segundos desde la época Unix
+fn marca_de_tiempo(segundos: u64) -> String {
+    let dias = (segundos / 86_400) as i64;
+    let resto = segundos % 86_400;
+    // Días a fecha civil (algoritmo de Howard Hinnant)
+    let z = dias + 719_468;
+    let era = z.div_euclid(146_097);
+    let dia_era = z.rem_euclid(146_097);
+    let anio_era = (dia_era - dia_era / 1460 + dia_era / 36_524 - dia_era / 146_096) / 365;
+    let dia_anio = dia_era - (365 * anio_era + anio_era / 4 - anio_era / 100);
+    let mes_marzo = (5 * dia_anio + 2) / 153;
+    let dia = dia_anio - (153 * mes_marzo + 2) / 5 + 1;
+    let mes = if mes_marzo < 10 { mes_marzo + 3 } else { mes_marzo - 9 };
+    let anio = anio_era + era * 400 + i64::from(mes <= 2);
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}",
+        anio,
+        mes,
+        dia,
+        resto / 3600,
+        resto / 60 % 60,
+        resto % 60
+    )
+}
+
+/// Copia una textura 2D de 8 bits por canal a memoria, quitando el relleno de filas
+fn leer_textura(dispositivo: &wgpu::Device, cola: &wgpu::Queue, textura: &wgpu::Texture) -> image::RgbaImage {
+    let ancho = textura.width();
+    let alto = textura.height();
+    let bytes_por_fila = ancho * 4;
+    let alineacion = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let bytes_por_fila_alineados = bytes_por_fila.div_ceil(alineacion) * alineacion;
+
+    let buffer_lectura = dispositivo.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Buffer de Lectura de Textura"),
+        size: (bytes_por_fila_alineados * alto) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut codificador = dispositivo.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Codificador de Copia de Textura"),
+    });
+    codificador.copy_texture_to_buffer(
+        textura.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer_lectura,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_por_fila_alineados),
+                rows_per_image: Some(alto),
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+        },
+        textura.size(),
+    );
+    cola.submit(std::iter::once(codificador.finish()));
+
+    let porcion = buffer_lectura.slice(..);
+    let (emisor, receptor) = std::sync::mpsc::channel();
+    porcion.map_async(wgpu::MapMode::Read, move |resultado| {
+        let _ = emisor.send(resultado);
+    });
+    dispositivo.poll(wgpu::Maintain::Wait);
+    receptor
+        .recv()
+        .expect("el mapeo del buffer de lectura no respondió")
+        .expect("no se pudo mapear el buffer de lectura");
+
+    let mut pixeles = Vec::with_capacity((bytes_por_fila * alto) as usize);
+    {
+        let datos = porcion.get_mapped_range();
+        for fila in datos.chunks(bytes_por_fila_alineados as usize) {
+            pixeles.extend_from_slice(&fila[..bytes_por_fila as usize]);
+        }
+    }
+    buffer_lectura.unmap();
+
+    let es_bgra = matches!(
+        textura.format(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+    );
+    if es_bgra {
+        for pixel in pixeles.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    image::RgbaImage::from_raw(ancho, alto, pixeles).expect("tamaño de imagen inconsistente")
+}
+
+// =============================================================================
+// EXPORTADOR DE TIME-LAPSE
+// =============================================================================
+
+/// Opciones del modo por lotes `--timelapse <carpeta> [--pasos N] [--dt S] [--tamano AxH]`
+struct OpcionesTimelapse {
+    carpeta: std::path::PathBuf,
+    pasos: u32,
+    incremento: f32,
+    tamano: winit::dpi::PhysicalSize<u32>,
+}
+
+impl OpcionesTimelapse {
+    /// `None` si no se pidió el modo time-lapse
+    fn desde_argumentos(argumentos: &[String]) -> Option<Result<Self, String>> {
+        let carpeta = valor_argumento(argumentos, "--timelapse")?;
+        Some(Self::interpretar(carpeta, argumentos))
+    }
+
+    fn interpretar(carpeta: &str, argumentos: &[String]) -> Result<Self, String> {
+        let pasos = match valor_argumento(argumentos, "--pasos") {
+            Some(texto) => texto
+                .parse()
+                .map_err(|_| format!("--pasos espera un entero, se recibió '{}'", texto))?,
+            None => 240,
+        };
+        let incremento = match valor_argumento(argumentos, "--dt") {
+            Some(texto) => texto
+                .parse()
+                .map_err(|_| format!("--dt espera segundos de simulación, se recibió '{}'", texto))?,
+            None => 0.5,
+        };
+        let tamano = tamano_argumento(argumentos)?;
+        Ok(OpcionesTimelapse {
+            carpeta: carpeta.into(),
+            pasos,
+            incremento,
+            tamano,
+        })
+    }
+}
+
+/// `--tamano ANCHOxALTO`, o 1000x800 sin la bandera
+fn tamano_argumento(argumentos: &[String]) -> Result<winit::dpi::PhysicalSize<u32>, String> {
+    let Some(texto) = valor_argumento(argumentos, "--tamano") else {
+        return Ok(winit::dpi::PhysicalSize::new(1000, 800));
+    };
+    let (ancho, alto) = texto
+        .split_once('x')
+        .and_then(|(a, h)| Some((a.parse().ok()?, h.parse().ok()?)))
+        .filter(|&(a, h): &(u32, u32)| a > 0 && h > 0)
+        .ok_or_else(|| format!("--tamano espera ANCHOxALTO, se recibió '{}'", texto))?;
+    Ok(winit::dpi::PhysicalSize::new(ancho, alto))
+}
+
+/// Valor que sigue a una bandera en la línea de comandos (`--bandera valor`)
+fn valor_argumento<'a>(argumentos: &'a [String], bandera: &str) -> Option<&'a str> {
+    argumentos
+        .iter()
+        .position(|a| a == bandera)
+        .and_then(|i| argumentos.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Avanza la simulación en incrementos fijos y guarda un PNG numerado por paso
+fn exportar_timelapse(
+    opciones: OpcionesTimelapse,
+    ajustes: &Ajustes,
+    enlace: Option<&str>,
+) -> Result<(), String> {
+    std::fs::create_dir_all(&opciones.carpeta)
+        .map_err(|e| format!("no se pudo crear {}: {}", opciones.carpeta.display(), e))?;
+
+    let mut estado = pollster::block_on(EstadoAplicacion::inicializar(None, opciones.tamano, ajustes, false))?;
+    if let Some(enlace) = enlace {
+        estado.abrir_enlace(enlace)?;
+    }
+    // Los cuadros exportados no deben salir con el material provisional
+    estado.pipelines.preparar(estado.tipos_en_uso());
+    estado.shaders_propios.actualizar(&estado.cuerpos, &mut estado.pipelines);
+    estado.pipelines.esperar();
+    estado.texturas.esperar_mapas(&estado.dispositivo, &estado.cola_comandos, &estado.cuerpos);
+    estado.recibir_mallas_modelos(true);
+    let inicio = estado.reloj.tiempo as f32;
+    for paso in 0..opciones.pasos {
+        estado.actualizar_a(inicio + paso as f32 * opciones.incremento);
+        let ruta = opciones.carpeta.join(format!("cuadro_{:05}.png", paso));
+        estado
+            .capturar_cuadro()
+            .save(&ruta)
+            .map_err(|e| format!("no se pudo escribir {}: {}", ruta.display(), e))?;
+        println!("Cuadro {}/{}: {}", paso + 1, opciones.pasos, ruta.display());
+    }
+    Ok(())
+}
+
+// =============================================================================
+// SIMULACIÓN SIN VENTANA
+// =============================================================================
+
+/// Opciones del subcomando `simulate [--pasos N] [--dt S] [--ncuerpos] [--integrador I]
+/// [--cada K] [--salida tabla.csv]`
+struct OpcionesSimulacion {
+    pasos: u32,
+    /// Segundos de simulación por paso; en modo N cuerpos es también el paso de integración
+    incremento: f64,
+    ncuerpos: bool,
+    integrador: Integrador,
+    /// Cada cuántos pasos se escribe una fila por cuerpo en la tabla
+    cada: u32,
+    salida: Option<std::path::PathBuf>,
+}
+
+impl OpcionesSimulacion {
+    /// `None` si el primer argumento no es `simulate`
+    fn desde_argumentos(argumentos: &[String]) -> Option<Result<Self, String>> {
+        (argumentos.first()?.as_str() == "simulate").then(|| Self::interpretar(argumentos))
+    }
+
+    fn interpretar(argumentos: &[String]) -> Result<Self, String> {
+        let entero = |bandera: &str, omision: u32| match valor_argumento(argumentos, bandera) {
+            Some(texto) => texto
+                .parse()
+                .map_err(|_| format!("{} espera un entero, se recibió '{}'", bandera, texto)),
+            None => Ok(omision),
+        };
+        let incremento = match valor_argumento(argumentos, "--dt") {
+            Some(texto) => texto
+                .parse()
+                .ok()
+                .filter(|&dt: &f64| dt > 0.0 && dt.is_finite())
+                .ok_or_else(|| format!("--dt espera segundos de simulación positivos, se recibió '{}'", texto))?,
+            None => 0.01,
+        };
+        let integrador = match valor_argumento(argumentos, "--integrador") {
+            Some(texto) => *Integrador::TODOS
+                .iter()
+                .find(|i| i.nombre().eq_ignore_ascii_case(texto))
+                .ok_or_else(|| format!("--integrador espera euler, verlet o rk4, se recibió '{}'", texto))?,
+            None => Integrador::Verlet,
+        };
+        Ok(OpcionesSimulacion {
+            pasos: entero("--pasos", 1000)?,
+            incremento,
+            ncuerpos: argumentos.iter().any(|a| a == "--ncuerpos"),
+            integrador,
+            cada: entero("--cada", 1)?.max(1),
+            salida: valor_argumento(argumentos, "--salida").map(Into::into),
+        })
+    }
+}
+
+/// Estado final de `simular` y la tabla CSV de los pasos anotados
+struct ResultadoSimulacion {
+    cuerpos: Vec<CuerpoCeleste>,
+    fisica: SimulacionNCuerpos,
+    tabla: String,
+}
+
+/// Avanza la escena `pasos` veces sin GPU ni ventana. Con la misma escena y opciones el
+/// resultado es idéntico en cada ejecución
+fn simular(opciones: &OpcionesSimulacion, escena: &ArchivoEscena) -> Result<ResultadoSimulacion, String> {
+    let mut cuerpos = escena.cuerpos()?;
+    let mut grafo = GrafoEscena::default();
+    let posicionar = |cuerpos: &mut [CuerpoCeleste], grafo: &mut GrafoEscena, tiempo: f64| {
+        for cuerpo in cuerpos.iter_mut() {
+            if let Some(orbita) = cuerpo.orbita {
+                cuerpo.posicion_local = orbita.posicion(tiempo as f32);
+            }
+        }
+        grafo.actualizar(cuerpos);
+    };
+    posicionar(&mut cuerpos, &mut grafo, 0.0);
+
+    let mut fisica = SimulacionNCuerpos::nueva();
+    if opciones.ncuerpos {
+        fisica.activa = true;
+        fisica.integrador = opciones.integrador;
+        fisica.paso = opciones.incremento;
+        fisica.iniciar(&cuerpos, 0.0);
+    }
+
+    // distancia_padre: al padre, o al origen para los cuerpos sin padre
+    let mut tabla = String::from("paso,tiempo,cuerpo,x,y,distancia_padre");
+    if opciones.ncuerpos {
+        tabla.push_str(",energia,deriva_energia,momento_angular");
+    }
+    tabla.push('\n');
+    let anotar = |tabla: &mut String, paso: u32, tiempo: f64, cuerpos: &[CuerpoCeleste], fisica: &SimulacionNCuerpos| {
+        let muestra = fisica.activa.then(|| fisica.muestra());
+        for cuerpo in cuerpos {
+            let [x, y] = cuerpo.posicion;
+            let [lx, ly] = cuerpo.posicion_local;
+            tabla.push_str(&format!("{},{:.6},{},{:.6},{:.6},{:.6}", paso, tiempo, cuerpo.nombre, x, y, lx.hypot(ly)));
+            if let Some(muestra) = muestra {
+                tabla.push_str(&format!(
+                    ",{:.9},{:.3e},{:.9}",
+                    muestra.energia(),
+                    fisica.deriva_energia(),
+                    muestra.momento_angular
+                ));
+            }
+            tabla.push('\n');
+        }
+    };
+    anotar(&mut tabla, 0, 0.0, &cuerpos, &fisica);
+
+    for paso in 1..=opciones.pasos {
+        let tiempo = paso as f64 * opciones.incremento;
+        if fisica.activa {
+            fisica.avanzar_hasta(tiempo);
+            fisica.aplicar(&mut cuerpos);
+            GrafoEscena::localizar(&mut cuerpos);
+            grafo.actualizar(&mut cuerpos);
+        } else {
+            posicionar(&mut cuerpos, &mut grafo, tiempo);
+        }
+        if paso % opciones.cada == 0 || paso == opciones.pasos {
+            anotar(&mut tabla, paso, tiempo, &cuerpos, &fisica);
+        }
+    }
+    Ok(ResultadoSimulacion { cuerpos, fisica, tabla })
+}
+
+/// Corre `simular` e imprime el estado final; con `--salida` escribe también la tabla
+fn simular_sin_ventana(opciones: OpcionesSimulacion, escena: &ArchivoEscena) -> Result<(), String> {
+    let ResultadoSimulacion { cuerpos, fisica, tabla } = simular(&opciones, escena)?;
+    let tiempo = opciones.pasos as f64 * opciones.incremento;
+    let modo = if fisica.activa {
+        format!("N cuerpos ({}, paso {} s)", fisica.integrador.nombre(), fisica.paso)
+    } else {
+        "órbitas".to_string()
+    };
+    println!("Simulación: {} pasos de {} s ({}), t = {:.3} s", opciones.pasos, opciones.incremento, modo, tiempo);
+    println!("{:<16} {:>12} {:>12} {:>12}", "cuerpo", "x", "y", "dist. padre");
+    for cuerpo in &cuerpos {
+        let [x, y] = cuerpo.posicion;
+        let [lx, ly] = cuerpo.posicion_local;
+        println!("{:<16} {:>12.6} {:>12.6} {:>12.6}", cuerpo.nombre, x, y, lx.hypot(ly));
+    }
+    if fisica.activa {
+        let muestra = fisica.muestra();
+        println!(
+            "Energía {:.9} (deriva {:+.3e}), momento angular {:.9}",
+            muestra.energia(),
+            fisica.deriva_energia(),
+            muestra.momento_angular
+        );
+    }
+
+    if let Some(ruta) = &opciones.salida {
+        std::fs::write(ruta, tabla).map_err(|e| format!("no se pudo escribir {}: {}", ruta.display(), e))?;
+        println!("Tabla: {}", ruta.display());
+    }
+    Ok(())
+}
+
+// =============================================================================
+// RENDER POR CPU
+// =============================================================================
+
+/// Opciones del subcomando
+/// `render-cpu <salida.png> [--tamano AxH] [--tiempo S] [--orbitas] [--alambre]`
+struct OpcionesRenderCpu {
+    salida: std::path::PathBuf,
+    tamano: winit::dpi::PhysicalSize<u32>,
+    tiempo: f32,
+    trazos: TrazosCpu,
+}
+
+impl OpcionesRenderCpu {
+    const SUBDIVISIONES: u32 = 48;
+
+    /// `None` si el primer argumento no es `render-cpu`
+    fn desde_argumentos(argumentos: &[String]) -> Option<Result<Self, String>> {
+        (argumentos.first()?.as_str() == "render-cpu").then(|| Self::interpretar(argumentos))
+    }
+
+    fn interpretar(argumentos: &[String]) -> Result<Self, String> {
+        let salida = argumentos
+            .get(1)
+            .filter(|a| !a.starts_with("--"))
+            .ok_or("render-cpu espera la ruta del PNG de salida")?;
+        let tiempo = match valor_argumento(argumentos, "--tiempo") {
+            Some(texto) => texto
+                .parse()
+                .map_err(|_| format!("--tiempo espera segundos de simulación, se recibió '{}'", texto))?,
+            None => 0.0,
+        };
+        Ok(OpcionesRenderCpu {
+            salida: salida.into(),
+            tamano: tamano_argumento(argumentos)?,
+            tiempo,
+            trazos: TrazosCpu {
+                orbitas: argumentos.iter().any(|a| a == "--orbitas"),
+                alambre: argumentos.iter().any(|a| a == "--alambre"),
             },
-            multiview: None,
+        })
+    }
+}
+
+/// Triángulos sueltos de una malla indexada en el formato del pipeline de CPU
+fn triangulos_cpu(vertices: &[VerticeEsfera], indices: impl IntoIterator<Item = u32>) -> Vec<Vertice> {
+    indices
+        .into_iter()
+        .map(|indice| {
+            let v = &vertices[indice as usize];
+            Vertice::nuevo(v.posicion.into(), v.normal.into(), Vec3::new(v.uv[0], v.uv[1], 0.0))
+        })
+        .collect()
+}
+
+/// Cuerpos de una escena listos para el pipeline de CPU: las mallas se arman una vez y cada
+/// cuadro solo mueve las órbitas. Cada cuerpo es su esfera (o su modelo OBJ) con el
+/// `ShaderFragmento` de su tipo y la luz de la estrella
+struct EscenaCpu {
+    cuerpos: Vec<CuerpoCeleste>,
+    grafo: GrafoEscena,
+    mallas: Vec<Vec<Vertice>>,
+    trazos: TrazosCpu,
+}
+
+/// Lo que el pipeline de CPU dibuja con líneas además de (o en vez de) las caras
+#[derive(Debug, Clone, Copy, Default)]
+struct TrazosCpu {
+    /// Elipse de la órbita de cada cuerpo, como la capa de órbitas de la GPU
+    orbitas: bool,
+    /// Solo las aristas de las mallas
+    alambre: bool,
+}
+
+impl EscenaCpu {
+    /// Lee los modelos en el grupo de decodificación y espera a tenerlos todos
+    fn nueva(escena: &ArchivoEscena, subdivisiones: u32) -> Result<Self, String> {
+        let cuerpos = escena.cuerpos()?;
+        let mut modelos = DecodificadorRecursos::nuevo(grupo_decodificacion(0));
+        let mut mallas = Self::mallas(&cuerpos, subdivisiones, &mut modelos);
+        Self::recibir_modelos(&mut mallas, &cuerpos, modelos.esperar());
+        Ok(EscenaCpu {
+            cuerpos,
+            grafo: GrafoEscena::default(),
+            mallas,
+            trazos: TrazosCpu::default(),
+        })
+    }
+
+    /// La esfera como malla de cada cuerpo, y en `modelos` el pedido de los triángulos de los
+    /// que tienen modelo OBJ; `recibir_modelos` los pone en su lugar cuando llegan
+    fn mallas(
+        cuerpos: &[CuerpoCeleste],
+        subdivisiones: u32,
+        modelos: &mut DecodificadorRecursos<Vec<Vertice>>,
+    ) -> Vec<Vec<Vertice>> {
+        modelos.reiniciar();
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            if let Some(ruta) = cuerpo.modelo.clone() {
+                modelos.pedir(indice, move || {
+                    let vertices = MallaModelo::vertices(&ModeloOBJ::abrir(&ruta)?);
+                    Ok(triangulos_cpu(&vertices, 0..vertices.len() as u32))
+                });
+            }
+        }
+        let (vertices, indices) = generar_esfera(subdivisiones);
+        vec![triangulos_cpu(&vertices, indices.iter()); cuerpos.len()]
+    }
+
+    /// Reemplaza la esfera de cada cuerpo cuyo modelo ya se leyó
+    fn recibir_modelos(
+        mallas: &mut [Vec<Vertice>],
+        cuerpos: &[CuerpoCeleste],
+        listos: Vec<(usize, Result<Vec<Vertice>, String>)>,
+    ) {
+        for (indice, resultado) in listos {
+            match (resultado, mallas.get_mut(indice)) {
+                (Ok(triangulos), Some(malla)) => *malla = triangulos,
+                (Err(error), _) => eprintln!("Modelo de {} ignorado, se dibuja la esfera: {}", cuerpos[indice].nombre, error),
+                (Ok(_), None) => {}
+            }
+        }
+    }
+
+    /// Limpia `buffer` y dibuja la escena en el instante `tiempo` vista desde `camara`
+    fn renderizar(&mut self, buffer: &mut BufferDePantalla, camara: &CamaraVirtual, tiempo: f32) {
+        for cuerpo in self.cuerpos.iter_mut() {
+            if let Some(orbita) = cuerpo.orbita {
+                cuerpo.posicion_local = orbita.posicion(tiempo);
+            }
+        }
+        self.grafo.actualizar(&mut self.cuerpos);
+        Self::dibujar(buffer, camara, &self.cuerpos, &self.grafo, &self.mallas, self.trazos, tiempo, |_| true);
+    }
+
+    /// Limpia `buffer` y dibuja los cuerpos ya ubicados por `grafo` para los que `incluido`
+    /// es verdadero, cada uno con su malla de `mallas`, y después los `trazos`
+    #[allow(clippy::too_many_arguments)]
+    fn dibujar(
+        buffer: &mut BufferDePantalla,
+        camara: &CamaraVirtual,
+        cuerpos: &[CuerpoCeleste],
+        grafo: &GrafoEscena,
+        mallas: &[Vec<Vertice>],
+        trazos: TrazosCpu,
+        tiempo: f32,
+        incluido: impl Fn(usize) -> bool,
+    ) {
+        let luz = cuerpos.iter().find(|c| c.es_estrella()).map_or(Vec3::zeros(), |c| c.posicion_3d());
+        let mut uniformes = UniformesCPU::nuevos(camara, buffer.ancho, buffer.alto, luz, tiempo);
+        buffer.establecer_color_fondo(ColorLineal::nuevo(0.05, 0.08, 0.15).a_srgb().a_hexadecimal());
+        buffer.limpiar();
+
+        for (indice, (cuerpo, malla)) in cuerpos.iter().zip(mallas).enumerate() {
+            if !incluido(indice) {
+                continue;
+            }
+            uniformes.model_matrix = grafo.matriz_modelo(indice, cuerpo, tiempo);
+            uniformes.tinte = cuerpo.tinte.into();
+            uniformes.actividad = cuerpo.actividad;
+            if trazos.alambre {
+                buffer.establecer_color_actual(ColorLineal::nuevo(0.3, 0.9, 0.5).a_srgb().a_hexadecimal());
+                renderizar_alambre_cpu(buffer, malla, &uniformes);
+            } else {
+                renderizar_triangulos_cpu(buffer, malla, &uniformes, shader_cpu(cuerpo.tipo_shader));
+            }
+        }
+        if trazos.orbitas {
+            Self::dibujar_orbitas(buffer, &mut uniformes, cuerpos, grafo, incluido);
+        }
+    }
+
+    /// Elipse de cada órbita centrada en el padre del cuerpo, en el color de la capa de la GPU
+    fn dibujar_orbitas(
+        buffer: &mut BufferDePantalla,
+        uniformes: &mut UniformesCPU,
+        cuerpos: &[CuerpoCeleste],
+        grafo: &GrafoEscena,
+        incluido: impl Fn(usize) -> bool,
+    ) {
+        const SEGMENTOS: usize = 96;
+        uniformes.model_matrix = Mat4::identity();
+        buffer.establecer_color_actual(ColorLineal::nuevo(0.3, 0.3, 0.4).a_srgb().a_hexadecimal());
+        for (indice, cuerpo) in cuerpos.iter().enumerate() {
+            let Some(orbita) = cuerpo.orbita.filter(|_| incluido(indice)) else { continue };
+            let origen = grafo.origen(indice);
+            let punto = |k: usize| {
+                let [x, y] = orbita.punto_en_anomalia(k as f32 * 2.0 * std::f32::consts::PI / SEGMENTOS as f32);
+                origen + Vec3::new(x, y, 0.0)
+            };
+            for k in 0..SEGMENTOS {
+                renderizar_segmento_cpu(buffer, &punto(k), &punto(k + 1), uniformes);
+            }
+        }
+    }
+}
+
+/// Modo híbrido (Tab): la mitad izquierda de la ventana muestra los mismos cuerpos, desde la
+/// misma cámara, rasterizados por el pipeline de CPU; el `BufferDePantalla` se sube a una
+/// textura y se copia con el pase de posproceso. La derecha sigue siendo la GPU
+pub struct ComparacionCpu {
+    pub activa: bool,
+    /// Shift+Tab: la mitad de CPU muestra solo las aristas de las mallas
+    pub alambre: bool,
+    buffer: BufferDePantalla,
+    /// Mallas de CPU de cada cuerpo; se rehacen si cambia la cantidad de cuerpos
+    mallas: Vec<Vec<Vertice>>,
+    /// Modelos OBJ leyéndose en el grupo de decodificación; mientras, el cuerpo es la esfera
+    modelos: DecodificadorRecursos<Vec<Vertice>>,
+    /// Parámetros de posproceso en cero: la imagen de CPU se copia tal cual
+    buffer_parametros: wgpu::Buffer,
+    textura: Option<(wgpu::Texture, wgpu::BindGroup)>,
+}
+
+impl ComparacionCpu {
+    const SUBDIVISIONES: u32 = 32;
+    /// Píxeles de la ventana por píxel de CPU, para que el rasterizado no frene el cuadro
+    const REDUCCION: u32 = 2;
+
+    fn nueva(dispositivo: &wgpu::Device, grupo: Arc<rayon::ThreadPool>) -> Self {
+        let buffer_parametros = dispositivo.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Buffer de Parámetros de la Comparación CPU"),
+            contents: bytemuck::bytes_of(&ParametrosPosproceso::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM,
         });
+        ComparacionCpu {
+            activa: false,
+            alambre: false,
+            buffer: BufferDePantalla::nuevo(1, 1),
+            mallas: Vec::new(),
+            modelos: DecodificadorRecursos::nuevo(grupo),
+            buffer_parametros,
+            textura: None,
+        }
+    }
 
-        Self {
-            superficie,
-            dispositivo,
-            cola_comandos,
-            configuracion,
-            tamano_ventana,
-            pipeline_render,
-            buffer_vertices,
-            buffer_indices,
-            cantidad_indices,
-            buffer_uniformes,
-            grupo_bind_uniformes,
-            datos_uniformes,
-            rotacion_camara: [0.0, 0.0],
-            tiempo_inicio: std::time::Instant::now(),
-            posicion_mouse: None,
-            mouse_presionado: false,
+    /// Toma lo elegido en la de un dispositivo anterior; las mallas se rehacen solas
+    pub fn heredar(&mut self, vieja: &ComparacionCpu) {
+        self.activa = vieja.activa;
+        self.alambre = vieja.alambre;
+    }
+
+    pub fn alternar(&mut self) {
+        self.activa = !self.activa;
+        if !self.activa {
+            // Libera la memoria de las mallas y de la textura
+            self.mallas = Vec::new();
+            self.modelos.reiniciar();
+            self.textura = None;
+        }
+    }
+
+    /// Rasteriza el cuadro en la CPU y lo sube a la textura (rehecha al cambiar el tamaño)
+    #[allow(clippy::too_many_arguments)]
+    fn preparar(
+        &mut self,
+        dispositivo: &wgpu::Device,
+        cola: &wgpu::Queue,
+        posproceso: &Posproceso,
+        [ancho, alto]: [u32; 2],
+        camara: &CamaraVirtual,
+        cuerpos: &[CuerpoCeleste],
+        grafo: &GrafoEscena,
+        orbitas: bool,
+        tiempo: f32,
+        incluido: impl Fn(usize) -> bool,
+    ) {
+        if !self.activa {
+            return;
+        }
+        let (ancho, alto) = (ancho.div_ceil(Self::REDUCCION).max(1), alto.div_ceil(Self::REDUCCION).max(1));
+        if self.mallas.len() != cuerpos.len() {
+            self.mallas = EscenaCpu::mallas(cuerpos, Self::SUBDIVISIONES, &mut self.modelos);
+        }
+        EscenaCpu::recibir_modelos(&mut self.mallas, cuerpos, self.modelos.recibir());
+        if (self.buffer.ancho, self.buffer.alto) != (ancho as usize, alto as usize) {
+            self.buffer = BufferDePantalla::nuevo(ancho as usize, alto as usize);
+            self.textura = None;
+        }
+        let trazos = TrazosCpu { orbitas, alambre: self.alambre };
+        EscenaCpu::dibujar(&mut self.buffer, camara, cuerpos, grafo, &self.mallas, trazos, tiempo, incluido);
+
+        let tamano = wgpu::Extent3d { width: ancho, height: alto, depth_or_array_layers: 1 };
+        let (textura, _) = self.textura.get_or_insert_with(|| {
+            let textura = dispositivo.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Textura de la Comparación CPU"),
+                size: tamano,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // Los colores del buffer están en sRGB, como la superficie cuando es sRGB
+                format: TexturasPlanetas::formato_mapas(posproceso.formato),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let vista = textura.create_view(&wgpu::TextureViewDescriptor::default());
+            let grupo = dispositivo.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group de la Comparación CPU"),
+                layout: &posproceso.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&vista),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&posproceso.muestreador_cercano),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.buffer_parametros.as_entire_binding(),
+                    },
+                ],
+            });
+            (textura, grupo)
+        });
+        let pixeles: Vec<u8> = self
+            .buffer
+            .buffer_colores
+            .iter()
+            .flat_map(|&color| [(color >> 16) as u8, (color >> 8) as u8, color as u8, 255])
+            .collect();
+        cola.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: textura,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixeles,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * ancho),
+                rows_per_image: Some(alto),
+            },
+            tamano,
+        );
+    }
+
+    /// Copia la imagen de CPU sobre la mitad izquierda de `destino`, con una línea divisoria
+    fn dibujar(
+        &self,
+        codificador: &mut wgpu::CommandEncoder,
+        destino: &wgpu::TextureView,
+        [ancho, alto]: [u32; 2],
+        posproceso: &Posproceso,
+        grupo_uniformes: &wgpu::BindGroup,
+    ) {
+        let Some((_, grupo)) = self.textura.as_ref().filter(|_| self.activa) else { return };
+        let mut pase = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pase de la Comparación CPU"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: destino,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        // El triángulo cubre toda la ventana: la imagen queda alineada píxel a píxel con la
+        // de la GPU y el recorte deja ver solo su mitad
+        pase.set_scissor_rect(0, 0, (ancho / 2).max(1), alto);
+        pase.set_pipeline(&posproceso.pipeline_copia);
+        pase.set_bind_group(0, grupo_uniformes, &[0]);
+        pase.set_bind_group(1, grupo, &[]);
+        pase.draw(0..3, 0..1);
+    }
+}
+
+/// La escena en el instante pedido, vista desde la cámara inicial, sin GPU
+fn renderizar_en_cpu(opciones: OpcionesRenderCpu, escena: &ArchivoEscena) -> Result<(), String> {
+    let mut escena_cpu = EscenaCpu::nueva(escena, OpcionesRenderCpu::SUBDIVISIONES)?;
+    escena_cpu.trazos = opciones.trazos;
+    let mut buffer = BufferDePantalla::nuevo(opciones.tamano.width as usize, opciones.tamano.height as usize);
+    escena_cpu.renderizar(&mut buffer, &CamaraVirtual::inicial(), opciones.tiempo);
+
+    buffer
+        .a_imagen()
+        .save(&opciones.salida)
+        .map_err(|e| format!("no se pudo escribir {}: {}", opciones.salida.display(), e))?;
+    println!("Render por CPU: {}", opciones.salida.display());
+    Ok(())
+}
+
+// =============================================================================
+// RENDER EN LA TERMINAL
+// =============================================================================
+
+/// Opciones del subcomando `terminal [--tamano COLUMNASxFILAS] [--ascii] [--velocidad X]
+/// [--segundos S]`: la escena animada con el pipeline de CPU, sin ventana (sirve por SSH)
+struct OpcionesTerminal {
+    columnas: usize,
+    filas: usize,
+    /// Caracteres de una rampa de brillo en vez de medios bloques de dos colores
+    ascii: bool,
+    /// Segundos de simulación por segundo real
+    velocidad: f32,
+    /// Segundos reales que dura; sin ellos, hasta Ctrl+C
+    segundos: Option<f32>,
+}
+
+impl OpcionesTerminal {
+    const SUBDIVISIONES: u32 = 24;
+    const CUADROS_POR_SEGUNDO: f32 = 20.0;
+    /// Píxeles del framebuffer por lado de cada píxel de la terminal (se promedian)
+    const SUPERMUESTREO: usize = 2;
+    /// Del menos al más brillante
+    const RAMPA: &'static [u8] = b" .:-=+*#%@";
+
+    /// `None` si el primer argumento no es `terminal`
+    fn desde_argumentos(argumentos: &[String]) -> Option<Result<Self, String>> {
+        (argumentos.first()?.as_str() == "terminal").then(|| Self::interpretar(argumentos))
+    }
+
+    fn interpretar(argumentos: &[String]) -> Result<Self, String> {
+        let numero = |bandera: &str| -> Result<Option<f32>, String> {
+            valor_argumento(argumentos, bandera)
+                .map(|texto| {
+                    texto
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|valor| *valor > 0.0)
+                        .ok_or_else(|| format!("{} espera un número positivo, se recibió '{}'", bandera, texto))
+                })
+                .transpose()
+        };
+        // Sin --tamano, el de la terminal (80 × 24 si no es una); la última fila queda para el estado
+        let (columnas, filas) = match valor_argumento(argumentos, "--tamano") {
+            Some(_) => {
+                let tamano = tamano_argumento(argumentos)?;
+                (tamano.width as usize, tamano.height as usize)
+            }
+            None => {
+                let (columnas, filas) = crossterm::terminal::size().ok().filter(|&(c, f)| c > 0 && f > 1).unwrap_or((80, 24));
+                (columnas as usize, filas as usize - 1)
+            }
+        };
+        Ok(OpcionesTerminal {
+            columnas,
+            filas,
+            ascii: argumentos.iter().any(|a| a == "--ascii"),
+            velocidad: numero("--velocidad")?.unwrap_or(1.0),
+            segundos: numero("--segundos")?,
+        })
+    }
+
+    /// Píxeles de la terminal: con medios bloques cada celda lleva dos, uno sobre otro, y
+    /// sale cuadrada; con caracteres, uno solo
+    fn pixeles(&self) -> (usize, usize) {
+        (self.columnas, if self.ascii { self.filas } else { self.filas * 2 })
+    }
+}
+
+/// Promedio de un bloque de `lado_x` × `lado_y` píxeles del framebuffer desde `(x, y)`
+fn promedio_bloque(buffer: &BufferDePantalla, x: usize, y: usize, lado_x: usize, lado_y: usize) -> ColorLineal {
+    let mut suma = ColorLineal::nuevo(0.0, 0.0, 0.0);
+    for fila in y..y + lado_y {
+        for columna in x..x + lado_x {
+            let color = ColorRGB::desde_hexadecimal(buffer.buffer_colores[fila * buffer.ancho + columna]);
+            suma = suma.sumar(&color.a_lineal());
+        }
+    }
+    suma.multiplicar(1.0 / (lado_x * lado_y) as f32)
+}
+
+/// Un cuadro como texto con colores de 24 bits (comandos de crossterm), empezando en la
+/// esquina superior izquierda; solo repite un color cuando cambia
+fn cuadro_terminal(buffer: &BufferDePantalla, opciones: &OpcionesTerminal) -> String {
+    use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+    use std::fmt::Write;
+    let rgb = |[r, g, b]: [u8; 3]| Color::Rgb { r, g, b };
+    let lado = OpcionesTerminal::SUPERMUESTREO;
+    // Cada celda ocupa el doble de alto que de ancho en pantalla
+    let (lado_x, lado_y) = if opciones.ascii { (lado, lado * 2) } else { (lado, lado) };
+    let mut texto = crossterm::cursor::MoveTo(0, 0).to_string();
+    let (mut frente, mut fondo) = (None, None);
+    for fila in 0..opciones.filas {
+        for columna in 0..opciones.columnas {
+            if opciones.ascii {
+                let promedio = promedio_bloque(buffer, columna * lado_x, fila * lado_y, lado_x, lado_y);
+                // La rampa va por brillo percibido: indexarla con la luminancia lineal
+                // oscurecería los medios tonos
+                let brillo = lineal_a_srgb(promedio.luminancia().clamp(0.0, 1.0));
+                let color = promedio.a_srgb();
+                let color = [color.rojo, color.verde, color.azul];
+                let rampa = OpcionesTerminal::RAMPA;
+                let caracter = rampa[((brillo * rampa.len() as f32) as usize).min(rampa.len() - 1)] as char;
+                if frente != Some(color) {
+                    let _ = write!(texto, "{}", SetForegroundColor(rgb(color)));
+                    frente = Some(color);
+                }
+                texto.push(caracter);
+            } else {
+                let [arriba, abajo] = [fila * 2, fila * 2 + 1].map(|media_fila| {
+                    let color = promedio_bloque(buffer, columna * lado_x, media_fila * lado_y, lado_x, lado_y).a_srgb();
+                    [color.rojo, color.verde, color.azul]
+                });
+                if frente != Some(arriba) {
+                    let _ = write!(texto, "{}", SetForegroundColor(rgb(arriba)));
+                    frente = Some(arriba);
+                }
+                if fondo != Some(abajo) {
+                    let _ = write!(texto, "{}", SetBackgroundColor(rgb(abajo)));
+                    fondo = Some(abajo);
+                }
+                texto.push('▀');
+            }
+        }
+        let _ = write!(texto, "{}\r\n", ResetColor);
+        (frente, fondo) = (None, None);
+    }
+    texto
+}
+
+/// Pantalla alternativa con el cursor oculto y, si la entrada es una terminal, modo crudo
+/// (así Ctrl+C llega como tecla y no corta el programa). Al soltarse, también por un error
+/// o un pánico, deja la terminal como estaba
+struct TerminalAnimada {
+    cruda: bool,
+}
+
+impl TerminalAnimada {
+    fn abrir(salida: &mut impl std::io::Write) -> Result<Self, String> {
+        use crossterm::{cursor, terminal};
+        let cruda = terminal::enable_raw_mode().is_ok();
+        let terminal = TerminalAnimada { cruda };
+        crossterm::execute!(salida, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))
+            .map_err(|e| format!("no se pudo preparar la terminal: {}", e))?;
+        Ok(terminal)
+    }
+
+    /// Si se pulsó Ctrl+C, Esc o q desde el último cuadro; sin modo crudo no hay teclas y
+    /// Ctrl+C llega como señal
+    fn salir_pedido(&self) -> bool {
+        use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+        while self.cruda && event::poll(std::time::Duration::ZERO).unwrap_or(false) {
+            let Ok(Event::Key(tecla)) = event::read() else { continue };
+            let ctrl_c = tecla.code == KeyCode::Char('c') && tecla.modifiers.contains(KeyModifiers::CONTROL);
+            if tecla.kind == KeyEventKind::Press && (ctrl_c || matches!(tecla.code, KeyCode::Esc | KeyCode::Char('q'))) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Drop for TerminalAnimada {
+    fn drop(&mut self) {
+        use crossterm::{cursor, style, terminal};
+        let _ = crossterm::execute!(std::io::stdout(), style::ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        if self.cruda {
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+}
+
+/// Anima la escena en la terminal hasta Ctrl+C, Esc o q (o `--segundos`)
+fn renderizar_en_terminal(opciones: OpcionesTerminal, escena: &ArchivoEscena) -> Result<(), String> {
+    use std::io::Write;
+    let mut escena_cpu = EscenaCpu::nueva(escena, OpcionesTerminal::SUBDIVISIONES)?;
+    let (ancho, alto) = opciones.pixeles();
+    let lado = OpcionesTerminal::SUPERMUESTREO;
+    let alto_celda = if opciones.ascii { 2 } else { 1 };
+    let mut buffer = BufferDePantalla::nuevo(ancho * lado, alto * lado * alto_celda);
+    let camara = CamaraVirtual::inicial();
+    let periodo = std::time::Duration::from_secs_f32(1.0 / OpcionesTerminal::CUADROS_POR_SEGUNDO);
+
+    let mut salida = std::io::stdout().lock();
+    let terminal = TerminalAnimada::abrir(&mut salida)?;
+    let inicio = std::time::Instant::now();
+    loop {
+        let cuadro_inicio = std::time::Instant::now();
+        let transcurrido = inicio.elapsed().as_secs_f32();
+        if terminal.salir_pedido() || opciones.segundos.is_some_and(|segundos| transcurrido >= segundos) {
+            break;
+        }
+        let tiempo = transcurrido * opciones.velocidad;
+        escena_cpu.renderizar(&mut buffer, &camara, tiempo);
+        let mut texto = cuadro_terminal(&buffer, &opciones);
+        let ocupado = cuadro_inicio.elapsed();
+        texto.push_str(&format!(
+            "{} t = {:.1} s · {:.0} ms por cuadro · Ctrl+C para salir",
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine),
+            tiempo,
+            ocupado.as_secs_f32() * 1000.0
+        ));
+        salida
+            .write_all(texto.as_bytes())
+            .and_then(|_| salida.flush())
+            .map_err(|e| format!("no se pudo escribir en la terminal: {}", e))?;
+        std::thread::sleep(periodo.saturating_sub(cuadro_inicio.elapsed()));
+    }
+    Ok(())
+}
+
+// =============================================================================
+// COMPARACIÓN DE ESCENAS
+// =============================================================================
+
+/// Un cambio entre dos archivos TOML, con la ruta del valor (`cuerpo[Marte].escala`)
+#[derive(Debug, Clone, PartialEq)]
+enum Diferencia {
+    Agregado(String, toml::Value),
+    Quitado(String, toml::Value),
+    Cambiado(String, toml::Value, toml::Value),
+}
+
+impl fmt::Display for Diferencia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mostrar = |valor: &toml::Value| match valor {
+            toml::Value::Table(_) => "{…}".to_string(),
+            otro => otro.to_string(),
+        };
+        match self {
+            Diferencia::Agregado(ruta, valor) => write!(f, "+ {} = {}", ruta, mostrar(valor)),
+            Diferencia::Quitado(ruta, valor) => write!(f, "- {} = {}", ruta, mostrar(valor)),
+            Diferencia::Cambiado(ruta, antes, despues) => {
+                write!(f, "~ {}: {} → {}", ruta, mostrar(antes), mostrar(despues))
+            }
+        }
+    }
+}
+
+/// Compara dos valores TOML en profundidad. Las listas de tablas con `nombre` (los
+/// `[[cuerpo]]`) se emparejan por nombre, así reordenar cuerpos no cuenta como cambio;
+/// las demás listas, por posición. Un entero y un flotante iguales no difieren
+fn comparar_toml(ruta: &str, antes: &toml::Value, despues: &toml::Value, diferencias: &mut Vec<Diferencia>) {
+    use toml::Value;
+    let unir = |clave: &str| if ruta.is_empty() { clave.to_string() } else { format!("{}.{}", ruta, clave) };
+    let nombre = |valor: &Value| valor.get("nombre").and_then(Value::as_str).map(str::to_string);
+    match (antes, despues) {
+        (Value::Table(a), Value::Table(b)) => {
+            for (clave, valor) in a {
+                match b.get(clave) {
+                    Some(otro) => comparar_toml(&unir(clave), valor, otro, diferencias),
+                    None => diferencias.push(Diferencia::Quitado(unir(clave), valor.clone())),
+                }
+            }
+            for (clave, valor) in b.iter().filter(|(clave, _)| !a.contains_key(*clave)) {
+                diferencias.push(Diferencia::Agregado(unir(clave), valor.clone()));
+            }
+        }
+        (Value::Array(a), Value::Array(b))
+            if a.iter().chain(b).all(|v| nombre(v).is_some()) && !(a.is_empty() && b.is_empty()) =>
+        {
+            let por_nombre = |lista: &[Value], buscado: &str| lista.iter().find(|v| nombre(v).as_deref() == Some(buscado)).cloned();
+            for valor in a {
+                let clave = nombre(valor).expect("filtrado arriba");
+                let ruta_elemento = format!("{}[{}]", ruta, clave);
+                match por_nombre(b, &clave) {
+                    Some(otro) => comparar_toml(&ruta_elemento, valor, &otro, diferencias),
+                    None => diferencias.push(Diferencia::Quitado(ruta_elemento, valor.clone())),
+                }
+            }
+            for valor in b {
+                let clave = nombre(valor).expect("filtrado arriba");
+                if por_nombre(a, &clave).is_none() {
+                    diferencias.push(Diferencia::Agregado(format!("{}[{}]", ruta, clave), valor.clone()));
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.iter().chain(b).any(Value::is_table) => {
+            for (indice, par) in a.iter().zip(b).enumerate() {
+                comparar_toml(&format!("{}[{}]", ruta, indice), par.0, par.1, diferencias);
+            }
+            for (indice, valor) in a.iter().enumerate().skip(b.len()) {
+                diferencias.push(Diferencia::Quitado(format!("{}[{}]", ruta, indice), valor.clone()));
+            }
+            for (indice, valor) in b.iter().enumerate().skip(a.len()) {
+                diferencias.push(Diferencia::Agregado(format!("{}[{}]", ruta, indice), valor.clone()));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            let iguales = a.len() == b.len()
+                && a.iter().zip(b).all(|(x, y)| {
+                    let mut internas = Vec::new();
+                    comparar_toml("", x, y, &mut internas);
+                    internas.is_empty()
+                });
+            if !iguales {
+                diferencias.push(Diferencia::Cambiado(ruta.to_string(), antes.clone(), despues.clone()));
+            }
+        }
+        (Value::Integer(a), Value::Float(b)) | (Value::Float(b), Value::Integer(a)) if *a as f64 == *b => {}
+        (a, b) if a == b => {}
+        (a, b) => diferencias.push(Diferencia::Cambiado(ruta.to_string(), a.clone(), b.clone())),
+    }
+}
+
+/// Lee un TOML cualquiera (escena, ajustes o sesión); si parece una escena y no es válida,
+/// lo avisa pero la compara igual
+fn leer_para_comparar(ruta: &std::path::Path) -> Result<toml::Value, String> {
+    let texto = std::fs::read_to_string(ruta).map_err(|e| format!("no se pudo leer {}: {}", ruta.display(), e))?;
+    let valor: toml::Value = toml::from_str(&texto).map_err(|e| format!("{} no es TOML válido: {}", ruta.display(), e))?;
+    if valor.get("cuerpo").is_some() {
+        if let Err(error) = ArchivoEscena::interpretar(&texto, &ruta.display().to_string()) {
+            eprintln!("Aviso: {}", error);
         }
     }
+    Ok(valor)
+}
 
-    pub fn redimensionar(&mut self, nuevo_tamano: winit::dpi::PhysicalSize<u32>) {
-        if nuevo_tamano.width > 0 && nuevo_tamano.height > 0 {
-            self.tamano_ventana = nuevo_tamano;
-            self.configuracion.width = nuevo_tamano.width;
-            self.configuracion.height = nuevo_tamano.height;
-            self.superficie.configure(&self.dispositivo, &self.configuracion);
-            self.datos_uniformes.dimension_pantalla = [
-                nuevo_tamano.width as f32, 
-                nuevo_tamano.height as f32
-            ];
-        }
+/// Subcomando `diff-scenes <a.toml> <b.toml>`: imprime lo que cambia de `a` a `b`.
+/// Devuelve si hubo diferencias
+fn comparar_escenas(argumentos: &[String]) -> Result<bool, String> {
+    let [antes, despues] = argumentos else {
+        return Err("uso: diff-scenes <antes.toml> <despues.toml>".to_string());
+    };
+    let (antes, despues) = (std::path::Path::new(antes), std::path::Path::new(despues));
+    let mut diferencias = Vec::new();
+    comparar_toml("", &leer_para_comparar(antes)?, &leer_para_comparar(despues)?, &mut diferencias);
+    if diferencias.is_empty() {
+        println!("{} y {} son equivalentes", antes.display(), despues.display());
+        return Ok(false);
+    }
+    println!("--- {}", antes.display());
+    println!("+++ {}", despues.display());
+    for diferencia in &diferencias {
+        println!("{}", diferencia);
     }
+    let cuerpos: std::collections::BTreeSet<&str> = diferencias
+        .iter()
+        .filter_map(|d| {
+            let (Diferencia::Agregado(ruta, _) | Diferencia::Quitado(ruta, _) | Diferencia::Cambiado(ruta, _, _)) = d;
+            ruta.strip_prefix("cuerpo[")?.split_once(']').map(|(nombre, _)| nombre)
+        })
+        .collect();
+    let plural = if diferencias.len() == 1 { "" } else { "s" };
+    print!("{} diferencia{}", diferencias.len(), plural);
+    if !cuerpos.is_empty() {
+        print!(" en {} cuerpos ({})", cuerpos.len(), cuerpos.into_iter().collect::<Vec<_>>().join(", "));
+    }
+    println!();
+    Ok(true)
+}
 
-    fn procesar_mouse_click(&mut self, presionado: bool) {
-        self.mouse_presionado = presionado;
+/// Código de salida de `diff-scenes`, como diff: 0 sin diferencias, 1 con diferencias, 2 si
+/// no se pudo comparar
+fn codigo_comparacion(argumentos: &[String]) -> i32 {
+    match comparar_escenas(argumentos) {
+        Ok(hubo) => hubo as i32,
+        Err(error) => {
+            eprintln!("Error al comparar: {}", error);
+            2
+        }
     }
+}
 
-    fn procesar_movimiento_mouse(&mut self, posicion: winit::dpi::PhysicalPosition<f64>) {
-        if self.mouse_presionado {
-            if let Some(pos_anterior) = self.posicion_mouse {
-                let delta_x = (posicion.x - pos_anterior.x) as f32;
-                let delta_y = (posicion.y - pos_anterior.y) as f32;
-                
-                // Sensibilidad del mouse
-                self.rotacion_camara[0] += delta_x * 0.005;
-                self.rotacion_camara[1] = (self.rotacion_camara[1] - delta_y * 0.005)
-                    .clamp(-1.5, 1.5);
+// =============================================================================
+// INTERFAZ DEL SHADER
+// =============================================================================
+
+/// Estructuras que el shader lee de buffers y su contraparte en Rust, para revisar que los
+/// tamaños coincidan
+const ESTRUCTURAS_COMPARTIDAS: [(&str, &str, usize); 6] = [
+    ("UniformesPlaneta", "DatosUniformes", std::mem::size_of::<DatosUniformes>()),
+    ("Particula", "ParticulaGpu", std::mem::size_of::<ParticulaGpu>()),
+    ("ParametrosParticulas", "ParametrosParticulas", std::mem::size_of::<ParametrosParticulas>()),
+    ("ParametrosOrden", "ParametrosOrden", std::mem::size_of::<ParametrosOrden>()),
+    ("Cinturon", "CinturonGpu", std::mem::size_of::<CinturonGpu>()),
+    ("ParametrosCulling", "ParametrosCulling", std::mem::size_of::<ParametrosCulling>()),
+];
+
+/// Nombre WGSL de un tipo: el declarado si lo tiene, si no su forma (`vec3<f32>`)
+fn nombre_tipo_wgsl(modulo: &naga::Module, tipo: naga::Handle<naga::Type>) -> String {
+    use naga::{ImageClass, ImageDimension, ScalarKind, TypeInner};
+    let escalar = |escalar: naga::Scalar| {
+        match escalar.kind {
+            ScalarKind::Float => "f",
+            ScalarKind::Sint => "i",
+            ScalarKind::Uint => "u",
+            ScalarKind::Bool => return "bool".to_string(),
+            ScalarKind::AbstractInt | ScalarKind::AbstractFloat => return "abstracto".to_string(),
+        }
+        .to_string()
+            + &(escalar.width as u32 * 8).to_string()
+    };
+    let definicion = &modulo.types[tipo];
+    if let Some(nombre) = &definicion.name {
+        return nombre.clone();
+    }
+    match &definicion.inner {
+        TypeInner::Scalar(s) => escalar(*s),
+        TypeInner::Vector { size, scalar } => format!("vec{}<{}>", *size as u8, escalar(*scalar)),
+        TypeInner::Matrix { columns, rows, scalar } => {
+            format!("mat{}x{}<{}>", *columns as u8, *rows as u8, escalar(*scalar))
+        }
+        TypeInner::Atomic(s) => format!("atomic<{}>", escalar(*s)),
+        TypeInner::Array { base, size, .. } => match size {
+            naga::ArraySize::Constant(n) => format!("array<{}, {}>", nombre_tipo_wgsl(modulo, *base), n),
+            naga::ArraySize::Dynamic => format!("array<{}>", nombre_tipo_wgsl(modulo, *base)),
+        },
+        TypeInner::Image { dim, arrayed, class } => {
+            let dimension = match dim {
+                ImageDimension::D1 => "1d",
+                ImageDimension::D2 => "2d",
+                ImageDimension::D3 => "3d",
+                ImageDimension::Cube => "cube",
+            };
+            let arreglo = if *arrayed { "_array" } else { "" };
+            match class {
+                ImageClass::Sampled { kind, .. } => {
+                    let kind = escalar(naga::Scalar { kind: *kind, width: 4 });
+                    format!("texture_{}{}<{}>", dimension, arreglo, kind)
+                }
+                ImageClass::Depth { .. } => format!("texture_depth_{}{}", dimension, arreglo),
+                ImageClass::Storage { format, .. } => {
+                    format!("texture_storage_{}{}<{:?}>", dimension, arreglo, format).to_lowercase()
+                }
             }
         }
-        self.posicion_mouse = Some(posicion);
+        TypeInner::Sampler { comparison: true } => "sampler_comparison".to_string(),
+        TypeInner::Sampler { comparison: false } => "sampler".to_string(),
+        otro => format!("{:?}", otro),
     }
+}
 
-    fn actualizar(&mut self) {
-        self.datos_uniformes.tiempo_actual = self.tiempo_inicio.elapsed().as_secs_f32();
-        self.cola_comandos.write_buffer(
-            &self.buffer_uniformes,
-            0,
-            bytemuck::cast_slice(&[self.datos_uniformes]),
-        );
+/// Entradas o salidas de una etapa: `@location(n) nombre: tipo`, abriendo las estructuras
+fn enlaces_wgsl(modulo: &naga::Module, nombre: Option<&str>, tipo: naga::Handle<naga::Type>, enlace: Option<&naga::Binding>) -> Vec<String> {
+    let nombre = nombre.unwrap_or("_");
+    match (enlace, &modulo.types[tipo].inner) {
+        (Some(naga::Binding::Location { location, .. }), _) => {
+            vec![format!("@location({}) {}: {}", location, nombre, nombre_tipo_wgsl(modulo, tipo))]
+        }
+        (Some(naga::Binding::BuiltIn(integrado)), _) => {
+            use naga::BuiltIn;
+            let integrado = match integrado {
+                BuiltIn::Position { .. } => "position".to_string(),
+                BuiltIn::VertexIndex => "vertex_index".to_string(),
+                BuiltIn::InstanceIndex => "instance_index".to_string(),
+                BuiltIn::FrontFacing => "front_facing".to_string(),
+                BuiltIn::FragDepth => "frag_depth".to_string(),
+                BuiltIn::SampleIndex => "sample_index".to_string(),
+                BuiltIn::SampleMask => "sample_mask".to_string(),
+                BuiltIn::GlobalInvocationId => "global_invocation_id".to_string(),
+                BuiltIn::LocalInvocationId => "local_invocation_id".to_string(),
+                BuiltIn::LocalInvocationIndex => "local_invocation_index".to_string(),
+                BuiltIn::WorkGroupId => "workgroup_id".to_string(),
+                BuiltIn::NumWorkGroups => "num_workgroups".to_string(),
+                otro => format!("{:?}", otro).to_lowercase(),
+            };
+            vec![format!("@builtin({}) {}: {}", integrado, nombre, nombre_tipo_wgsl(modulo, tipo))]
+        }
+        (None, naga::TypeInner::Struct { members, .. }) => members
+            .iter()
+            .flat_map(|miembro| enlaces_wgsl(modulo, miembro.name.as_deref(), miembro.ty, miembro.binding.as_ref()))
+            .collect(),
+        (None, _) => Vec::new(),
     }
+}
 
-    fn renderizar(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let salida = self.superficie.get_current_texture()?;
-        let vista = salida
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut codificador = self
-            .dispositivo
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Codificador de Comandos de Render"),
-            });
+/// Bind groups, estructuras de los buffers (con desplazamientos) y puntos de entrada de un
+/// módulo WGSL, tal como los ve naga tras validarlo
+fn describir_interfaz_shader(fuente: &str, origen: &str) -> Result<String, String> {
+    use std::fmt::Write;
+    let modulo = naga::front::wgsl::parse_str(fuente).map_err(|e| e.emit_to_string_with_path(fuente, origen))?;
+    let informacion = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&modulo)
+        .map_err(|e| e.emit_to_string_with_path(fuente, origen))?;
+    let mut disposicion = naga::proc::Layouter::default();
+    disposicion.update(modulo.to_ctx()).map_err(|e| format!("{}: {}", origen, e))?;
 
-        // Configuración: [posición_x, posición_y, escala, tipo_shader]
-        // Tipos: 1=Sol, 2=Rocoso(Marte), 3=Gaseoso(Júpiter), 4=Anillos(Saturno), 5=Volcánico, 6=Luna(Hielo)
-        let configuracion_planetas = [
-            [0.0, 0.0, 0.55, 1.0],      // Centro: Sol (amarillo-naranja brillante)
-            [-0.6, 0.35, 0.12, 2.0],    // Izq arriba: Marte (pequeño, rojo)
-            [0.65, -0.25, 0.38, 4.0],   // Der abajo: Saturno (grande con anillos)
-            [-0.3, -0.5, 0.18, 6.0],    // Izq abajo: Luna helada (azul-blanco)
-        ];
+    let mut texto = String::new();
+    let _ = writeln!(texto, "Interfaz de {}", origen);
 
-        let datos_planetas: Vec<_> = configuracion_planetas
+    // Los recursos ligados, en orden de grupo y binding, con los puntos de entrada que los usan
+    let mut recursos: Vec<_> = modulo
+        .global_variables
+        .iter()
+        .filter_map(|(variable, global)| global.binding.as_ref().map(|enlace| (enlace.group, enlace.binding, variable, global)))
+        .collect();
+    recursos.sort_by_key(|&(grupo, binding, _, _)| (grupo, binding));
+    let _ = writeln!(texto, "\nBind groups:");
+    let mut estructuras = Vec::new();
+    for (grupo, binding, variable, global) in &recursos {
+        let espacio = match global.space {
+            naga::AddressSpace::Uniform => "<uniform>".to_string(),
+            naga::AddressSpace::Storage { access } if access.contains(naga::StorageAccess::STORE) => {
+                "<storage, read_write>".to_string()
+            }
+            naga::AddressSpace::Storage { .. } => "<storage, read>".to_string(),
+            _ => String::new(),
+        };
+        let tamano = match (global.space, &modulo.types[global.ty].inner) {
+            (naga::AddressSpace::Storage { .. }, naga::TypeInner::Array { size: naga::ArraySize::Dynamic, stride, .. }) => {
+                format!(" ({} B por elemento)", stride)
+            }
+            (naga::AddressSpace::Uniform | naga::AddressSpace::Storage { .. }, _) => {
+                format!(" ({} B)", disposicion[global.ty].size)
+            }
+            _ => String::new(),
+        };
+        let usuarios: Vec<&str> = modulo
+            .entry_points
             .iter()
-            .map(|config_planeta| {
-                let mut uniformes_planeta = self.datos_uniformes;
-                uniformes_planeta.pos_planeta = [config_planeta[0], config_planeta[1]];
-                uniformes_planeta.factor_escala = config_planeta[2];
-                uniformes_planeta.tipo_render = config_planeta[3] as u32;
-
-                let buffer_uniforme_planeta = self.dispositivo.create_buffer_init(
-                    &wgpu::util::BufferInitDescriptor {
-                        label: Some("Buffer de Uniformes de Planeta"),
-                        contents: bytemuck::cast_slice(&[uniformes_planeta]),
-                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            .enumerate()
+            .filter(|(indice, _)| !informacion.get_entry_point(*indice)[*variable].is_empty())
+            .map(|(_, entrada)| entrada.name.as_str())
+            .collect();
+        let _ = writeln!(
+            texto,
+            "  @group({}) @binding({}) var{} {}: {}{}",
+            grupo,
+            binding,
+            espacio,
+            global.name.as_deref().unwrap_or("_"),
+            nombre_tipo_wgsl(&modulo, global.ty),
+            tamano
+        );
+        let _ = writeln!(texto, "      usado por: {}", if usuarios.is_empty() { "(nadie)".to_string() } else { usuarios.join(", ") });
+        if !espacio.is_empty() {
+            // El tipo del buffer y los de sus arreglos y miembros
+            let mut pendientes = vec![global.ty];
+            while let Some(tipo) = pendientes.pop() {
+                match &modulo.types[tipo].inner {
+                    naga::TypeInner::Struct { members, .. } if !estructuras.contains(&tipo) => {
+                        estructuras.push(tipo);
+                        pendientes.extend(members.iter().map(|m| m.ty));
                     }
-                );
+                    naga::TypeInner::Array { base, .. } => pendientes.push(*base),
+                    _ => {}
+                }
+            }
+        }
+    }
 
-                let bind_group_planeta = self.dispositivo.create_bind_group(
-                    &wgpu::BindGroupDescriptor {
-                        layout: &self.pipeline_render.get_bind_group_layout(0),
-                        entries: &[wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: buffer_uniforme_planeta.as_entire_binding(),
-                        }],
-                        label: Some("Bind Group de Planeta"),
-                    }
-                );
+    let _ = writeln!(texto, "\nEstructuras de buffers:");
+    for tipo in estructuras {
+        let naga::TypeInner::Struct { members, span } = &modulo.types[tipo].inner else {
+            continue;
+        };
+        let nombre = nombre_tipo_wgsl(&modulo, tipo);
+        let rust = ESTRUCTURAS_COMPARTIDAS.iter().find(|(wgsl, _, _)| *wgsl == nombre).map_or(String::new(), |(_, rust, tamano)| {
+            // Rust rellena los uniformes pequeños hasta 16 B, lo que piden algunos backends
+            let marca = match *tamano {
+                igual if igual == *span as usize => "coincide",
+                relleno if relleno == (*span as usize).next_multiple_of(16) => "coincide, con relleno",
+                _ => "NO COINCIDE",
+            };
+            format!("  ↔ Rust {} ({} B, {})", rust, tamano, marca)
+        });
+        let _ = writeln!(texto, "  struct {} ({} B, alineación {}){}", nombre, span, disposicion[tipo].alignment, rust);
+        for miembro in members {
+            let _ = writeln!(
+                texto,
+                "    @offset({:>3}) {}: {}",
+                miembro.offset,
+                miembro.name.as_deref().unwrap_or("_"),
+                nombre_tipo_wgsl(&modulo, miembro.ty)
+            );
+        }
+    }
 
-                (buffer_uniforme_planeta, bind_group_planeta)
-            })
+    let _ = writeln!(texto, "\nPuntos de entrada:");
+    for entrada in &modulo.entry_points {
+        let etapa = match entrada.stage {
+            naga::ShaderStage::Vertex => "@vertex".to_string(),
+            naga::ShaderStage::Fragment => "@fragment".to_string(),
+            naga::ShaderStage::Compute => {
+                let [x, y, z] = entrada.workgroup_size;
+                format!("@compute @workgroup_size({}, {}, {})", x, y, z)
+            }
+        };
+        let _ = writeln!(texto, "  {} {}", etapa, entrada.name);
+        let funcion = &entrada.function;
+        let entradas: Vec<String> = funcion
+            .arguments
+            .iter()
+            .flat_map(|argumento| enlaces_wgsl(&modulo, argumento.name.as_deref(), argumento.ty, argumento.binding.as_ref()))
             .collect();
+        if !entradas.is_empty() {
+            let _ = writeln!(texto, "      entrada: {}", entradas.join(", "));
+        }
+        if let Some(resultado) = &funcion.result {
+            let salidas = enlaces_wgsl(&modulo, Some("salida"), resultado.ty, resultado.binding.as_ref());
+            let _ = writeln!(texto, "      salida: {}", salidas.join(", "));
+        }
+    }
+    Ok(texto)
+}
 
-        {
-            let mut pase_render = codificador.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Pase de Renderizado Principal"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &vista,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.05,
-                            g: 0.08,
-                            b: 0.15,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+/// Líneas de la entrada estándar, leídas en un hilo para no bloquear el bucle de eventos
+fn leer_consola() -> std::sync::mpsc::Receiver<String> {
+    let (enviar, recibir) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("consola".into())
+        .spawn(move || {
+            for linea in std::io::stdin().lines() {
+                let Ok(linea) = linea else { break };
+                if enviar.send(linea).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("no se pudo crear el hilo de la consola");
+    recibir
+}
 
-            pase_render.set_pipeline(&self.pipeline_render);
-            pase_render.set_vertex_buffer(0, self.buffer_vertices.slice(..));
-            pase_render.set_index_buffer(self.buffer_indices.slice(..), wgpu::IndexFormat::Uint16);
+const TITULO_VENTANA: &str = "Sistema Solar - Pablo Cabrera 231156";
 
-            for i in 0..200 {
-                let posicion_x = (i as f32 * 567.123).sin() * 2.0;
-                let posicion_y = (i as f32 * 432.567).cos() * 2.0;
-                let tamano_estrella = ((i as f32 * 789.345).sin() * 0.5 + 0.5) * 0.003;
-                
-                let mut uniformes_estrella = self.datos_uniformes;
-                uniformes_estrella.pos_planeta = [posicion_x, posicion_y];
-                uniformes_estrella.factor_escala = tamano_estrella;
-                uniformes_estrella.tipo_render = 7;
-
-                self.cola_comandos.write_buffer(
-                    &self.buffer_uniformes, 
-                    0, 
-                    bytemuck::cast_slice(&[uniformes_estrella])
-                );
-                pase_render.set_bind_group(0, &self.grupo_bind_uniformes, &[]);
-                pase_render.draw_indexed(0..self.cantidad_indices, 0, 0..1);
+fn main() {
+    env_logger::init();
+
+    let argumentos: Vec<String> = std::env::args().skip(1).collect();
+    if argumentos.first().map(String::as_str) == Some("diff-scenes") {
+        std::process::exit(codigo_comparacion(&argumentos[1..]));
+    }
+    if let Some(posicion) = argumentos.iter().position(|a| a == "--dump-shader-interface") {
+        // Sin archivo (o seguido de otra bandera) describe el shader incluido
+        let resultado = match argumentos.get(posicion + 1).filter(|a| !a.starts_with("--")) {
+            Some(ruta) => std::fs::read_to_string(ruta)
+                .map_err(|e| format!("no se pudo leer {}: {}", ruta, e))
+                .and_then(|fuente| describir_interfaz_shader(&fuente, ruta)),
+            None => describir_interfaz_shader(include_str!("shader.wgsl"), "shader.wgsl (incluido)"),
+        };
+        match resultado {
+            Ok(texto) => print!("{}", texto),
+            Err(error) => {
+                eprintln!("Error en el shader: {}", error);
+                std::process::exit(1);
             }
+        }
+        return;
+    }
+    let mut ajustes = match Ajustes::desde_argumentos(&argumentos) {
+        Ok(ajustes) => ajustes,
+        Err(error) => {
+            eprintln!("Error en ajustes: {}", error);
+            std::process::exit(1);
+        }
+    };
+    ajustes.escena = match ArchivoEscena::desde_argumentos(&argumentos) {
+        Ok(escena) => escena,
+        Err(error) => {
+            eprintln!("Error en la escena: {}", error);
+            std::process::exit(1);
+        }
+    };
 
-            for (indice, (buffer_planeta, bind_group_planeta)) in datos_planetas.iter().enumerate() {
-                let planeta = configuracion_planetas[indice];
+    let enlace = match enlace_desde_argumentos(&argumentos) {
+        Ok(texto) => texto,
+        Err(error) => {
+            eprintln!("Error en el enlace: {}", error);
+            std::process::exit(1);
+        }
+    };
 
-                let mut uniformes_planeta = self.datos_uniformes;
-                uniformes_planeta.pos_planeta = [
-                    planeta[0] * self.rotacion_camara[0].cos() 
-                        - planeta[2] * self.rotacion_camara[0].sin(),
-                    planeta[1] * self.rotacion_camara[1].cos()
-                ];
-                uniformes_planeta.factor_escala = planeta[2] * 
-                    (0.8 + 0.2 * (self.rotacion_camara[0].cos() 
-                               * self.rotacion_camara[1].cos()));
-                uniformes_planeta.tipo_render = planeta[3] as u32;
-
-                self.cola_comandos.write_buffer(
-                    buffer_planeta, 
-                    0, 
-                    bytemuck::cast_slice(&[uniformes_planeta])
-                );
-                pase_render.set_bind_group(0, bind_group_planeta, &[]);
-                pase_render.draw_indexed(0..self.cantidad_indices, 0, 0..1);
-            }
+    if let Some(opciones) = OpcionesSimulacion::desde_argumentos(&argumentos) {
+        let resultado = opciones.and_then(|opciones| simular_sin_ventana(opciones, &ajustes.escena));
+        if let Err(error) = resultado {
+            eprintln!("Error en la simulación: {}", error);
+            std::process::exit(1);
         }
+        return;
+    }
 
-        self.cola_comandos.submit(std::iter::once(codificador.finish()));
-        salida.present();
+    if let Some(opciones) = OpcionesRenderCpu::desde_argumentos(&argumentos) {
+        let resultado = opciones.and_then(|opciones| renderizar_en_cpu(opciones, &ajustes.escena));
+        if let Err(error) = resultado {
+            eprintln!("Error en el render por CPU: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(opciones) = OpcionesTerminal::desde_argumentos(&argumentos) {
+        let resultado = opciones.and_then(|opciones| renderizar_en_terminal(opciones, &ajustes.escena));
+        if let Err(error) = resultado {
+            eprintln!("Error en la terminal: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        Ok(())
+    if let Some(opciones) = OpcionesTimelapse::desde_argumentos(&argumentos) {
+        let resultado = opciones.and_then(|opciones| exportar_timelapse(opciones, &ajustes, enlace.as_deref()));
+        if let Err(error) = resultado {
+            eprintln!("Error en time-lapse: {}", error);
+            std::process::exit(1);
+        }
+        return;
     }
-}
 
-fn main() {
-    env_logger::init();
-    
     let loop_eventos = EventLoop::new().unwrap();
     let ventana = Arc::new(
         winit::window::WindowBuilder::new()
-            .with_title("Sistema Solar - Pablo Cabrera 231156")
+            .with_title(TITULO_VENTANA)
             .with_inner_size(winit::dpi::LogicalSize::new(1000, 800))
             .build(&loop_eventos)
             .unwrap(),
     );
 
-    let mut estado = pollster::block_on(EstadoAplicacion::inicializar(ventana.clone()));
+    let mut estado = match pollster::block_on(EstadoAplicacion::inicializar(
+        Some(ventana.clone()),
+        ventana.inner_size(),
+        &ajustes,
+        false,
+    )) {
+        Ok(estado) => estado,
+        Err(error) => {
+            eprintln!("Error al iniciar la GPU: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(enlace) = &enlace {
+        if let Err(error) = estado.abrir_enlace(enlace) {
+            eprintln!("Error en el enlace: {}", error);
+            std::process::exit(1);
+        }
+    }
+    if ajustes.pantalla.completa {
+        estado.alternar_pantalla_completa();
+    }
+    if let Some(ruta) = valor_argumento(&argumentos, "--replay") {
+        let repeticion = RepeticionEntrada::cargar(std::path::Path::new(ruta)).and_then(|repeticion| {
+            estado.abrir_enlace(&repeticion.vista)?;
+            let [ancho, alto] = repeticion.tamano;
+            let _ = ventana.request_inner_size(winit::dpi::PhysicalSize::new(ancho, alto));
+            Ok(repeticion)
+        });
+        match repeticion {
+            Ok(repeticion) => estado.repeticion = Some(repeticion),
+            Err(error) => {
+                eprintln!("Error en la repetición: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(ruta) = valor_argumento(&argumentos, "--grabar-entrada") {
+        let tamano = [estado.tamano_ventana.width, estado.tamano_ventana.height];
+        match GrabadorEntrada::crear(std::path::Path::new(ruta), &estado.enlace_vista(), tamano) {
+            Ok(grabador) => estado.grabador_entrada = Some(grabador),
+            Err(error) => {
+                eprintln!("Error en la grabación de entrada: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(destino) = valor_argumento(&argumentos, "--grabar") {
+        if let Err(error) = estado.iniciar_grabacion(std::path::Path::new(destino)) {
+            eprintln!("Error en la grabación: {}", error);
+            std::process::exit(1);
+        }
+    }
+
+    estado.consola = Some(leer_consola());
+    estado.eventos.on_seleccion(|cuerpo| {
+        if let Some(cuerpo) = cuerpo {
+            println!("Seleccionado: {}", cuerpo.nombre);
+        }
+    });
+    estado.eventos.on_eclipse(|ocultante, eclipsado| {
+        println!("Eclipse: {} proyecta sombra sobre {}", ocultante.nombre, eclipsado.nombre);
+    });
+    estado.eventos.on_orbita_completada(|cuerpo, vueltas| {
+        println!("{} completó su órbita #{}", cuerpo.nombre, vueltas);
+    });
+    estado.eventos.on_impacto(|mayor, menor, rapidez| {
+        println!("Impacto: {} contra {} a {:.3} u/s", menor.nombre, mayor.nombre, rapidez);
+    });
 
     println!("===========================================");
     println!("Sistema Solar Interactivo - TODO EN UNO");
@@ -803,7 +16441,39 @@ fn main() {
     println!("===========================================");
     println!("Controles:");
     println!("  Click y arrastra: Rotar cámara");
+    println!("  Rueda del mouse: Acercar/alejar");
+    println!("  Click: Seleccionar cuerpo (diámetro angular y magnitud)");
+    println!("  Espacio: Pausar/reanudar el tiempo");
+    println!("  + / -: Acelerar/frenar el tiempo (0.1x a 1000x)");
+    println!("  R: Invertir el tiempo");
+    println!("  P / N: Modo paso a paso (la simulación se congela) / avanzar un cuadro de 1/60 s");
+    println!("  O: Editor de órbitas (arrastra periapsis/apoapsis del cuerpo seleccionado)");
+    println!("  M: Modo medición (click en dos cuerpos)");
+    println!("  V: Telescopio (vista aumentada del cuerpo bajo el cursor)");
+    println!("  Q: Modo quiz (click o teclas 1-9 para responder)");
+    println!("  C: Mostrar/ocultar constelaciones");
+    println!("  G / E: Cuadrícula eclíptica (con disco) / ecuatorial");
+    println!("  F1: Mostrar/ocultar paneles (escena, física y depuración)");
+    println!("  U: Unidades de la barra de escala (simulación / millones de km)");
+    println!("  T: Estelas coloreadas por rapidez (azul lento, rojo rápido)");
+    println!("  L: Nombres de los cuerpos");
+    println!("  K: Imprimir el enlace de la vista actual (ábrelo con --vista <enlace>, o --vista - desde la entrada)");
+    println!("  F9: Grabar / detener (cuadros a paso fijo en grabaciones/; ver [grabacion] en ajustes.toml)");
+    println!("  Mayús+F9: Grabar eligiendo el destino");
+    println!("  F11: Pantalla completa (sin bordes, o exclusiva según [pantalla] en ajustes.toml)");
+    println!("  F12: Captura PNG en capturas/ (Mayús+F12: elegir dónde)");
+    println!("  Ctrl+O: Abrir otra escena sin reiniciar (o 'escena [archivo.toml]' en esta terminal)");
+    println!("  Ctrl+C / Ctrl+V: Copiar la ficha del cuerpo seleccionado (Shift: el enlace de la vista) / pegar un enlace");
+    println!("  Soltar un archivo: .toml cambia la escena, una imagen texturiza el cuerpo seleccionado, un .obj o .gltf agrega un cuerpo");
     println!("  ESC: Salir");
+    println!("Otra escena: --escena <archivo.toml> (cuerpos, animaciones y texturas)");
+    println!("Time-lapse sin ventana: --timelapse <carpeta> [--pasos N] [--dt S] [--tamano AxH]");
+    println!("Simular sin ventana: simulate [--pasos N] [--dt S] [--ncuerpos] [--integrador euler|verlet|rk4] [--cada K] [--salida tabla.csv]");
+    println!("  (la distancia de la tabla es al padre, o al origen para los cuerpos sin padre)");
+    println!("Interfaz del shader: --dump-shader-interface [archivo.wgsl] (bind groups, estructuras y puntos de entrada)");
+    println!("Comparar escenas: diff-scenes <antes.toml> <despues.toml> (cuerpos por nombre)");
+    println!("Grabar desde el inicio: --grabar <carpeta> (PNG) o --grabar <video.mp4> (requiere ffmpeg)");
+    println!("Grabar la entrada: --grabar-entrada <sesion.toml>; repetirla: --replay <sesion.toml> (misma escena y ajustes)");
     println!("===========================================");
 
     loop_eventos
@@ -812,37 +16482,48 @@ fn main() {
                 Event::WindowEvent {
                     ref event,
                     window_id,
-                } if window_id == ventana.id() => match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                state: ElementState::Pressed,
-                                physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => control_flujo.exit(),
-                    WindowEvent::Resized(tamano_fisico) => {
-                        estado.redimensionar(*tamano_fisico);
+                } if window_id == ventana.id() => {
+                    if estado.procesar_evento_interfaz(event) {
+                        return;
                     }
-                    WindowEvent::CursorMoved { position, .. } => {
-                        estado.procesar_movimiento_mouse(*position);
+                    if let Some(entrada) = EventoEntrada::desde_ventana(event) {
+                        let cerrar = match estado.repeticion {
+                            Some(_) => entrada == EventoEntrada::Cerrar,
+                            None => estado.procesar_entrada(entrada),
+                        };
+                        if cerrar {
+                            estado.cerrar();
+                            control_flujo.exit();
+                        }
+                        return;
                     }
-                    WindowEvent::MouseInput { state: mouse_state, button: winit::event::MouseButton::Left, .. } => {
-                        estado.procesar_mouse_click(*mouse_state == ElementState::Pressed);
+                    match event {
+                    WindowEvent::Resized(tamano_fisico) => {
+                        estado.redimensionar(*tamano_fisico);
                     }
+                    WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => estado.revisar_monitor(),
                     WindowEvent::RedrawRequested => {
+                        if estado.repetir_cuadro() {
+                            estado.cerrar();
+                            control_flujo.exit();
+                            return;
+                        }
                         estado.actualizar();
                         match estado.renderizar() {
-                            Ok(_) => {}
+                            Ok(_) => estado.grabar_cuadro(),
                             Err(wgpu::SurfaceError::Lost) => estado.redimensionar(estado.tamano_ventana),
                             Err(wgpu::SurfaceError::OutOfMemory) => control_flujo.exit(),
                             Err(e) => eprintln!("Error de renderizado: {:?}", e),
                         }
+                        if let Some(recuperacion) = estado.vigilante.as_ref().and_then(VigilanteGpu::revisar_cuadro) {
+                            estado.recuperar(recuperacion, &ajustes);
+                        } else if let Some(etiqueta) = estado.adaptador_pedido.take() {
+                            estado.cambiar_adaptador(etiqueta, &ajustes);
+                        }
                     }
                     _ => {}
-                },
+                    }
+                }
                 Event::AboutToWait => {
                     ventana.request_redraw();
                 }
@@ -850,4 +16531,489 @@ fn main() {
             }
         })
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Todos los colores con canales múltiplos de 5 (52³)
+    fn colores_de_prueba() -> impl Iterator<Item = ColorRGB> {
+        (0..=255u8).step_by(5).flat_map(|r| {
+            (0..=255u8).step_by(5).flat_map(move |g| (0..=255u8).step_by(5).map(move |b| ColorRGB::nuevo(r, g, b)))
+        })
+    }
+
+    #[test]
+    fn hsv_ida_y_vuelta() {
+        for color in colores_de_prueba() {
+            let (h, s, v) = color.a_hsv();
+            assert_eq!(ColorRGB::desde_hsv(h, s, v).a_hexadecimal(), color.a_hexadecimal(), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn lineal_ida_y_vuelta() {
+        for color in colores_de_prueba() {
+            assert_eq!(color.a_lineal().a_srgb().a_hexadecimal(), color.a_hexadecimal(), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn hsl_ida_y_vuelta() {
+        for color in colores_de_prueba() {
+            let (h, s, l) = color.a_hsl();
+            assert_eq!(ColorRGB::desde_hsl(h, s, l).a_hexadecimal(), color.a_hexadecimal(), "{:?}", color);
+        }
+    }
+
+    /// Píxeles pintados de un buffer que empezó en negro, con su profundidad
+    fn pintados(buffer: &BufferDePantalla) -> Vec<(i32, i32, f32)> {
+        (0..buffer.ancho * buffer.alto)
+            .filter(|&i| buffer.buffer_colores[i] != 0)
+            .map(|i| ((i % buffer.ancho) as i32, (i / buffer.ancho) as i32, buffer.buffer_profundidad[i]))
+            .collect()
+    }
+
+    #[test]
+    fn linea_cubre_los_ocho_octantes() {
+        let extremos: [(i32, i32); 8] = [(7, 3), (3, 7), (-3, 7), (-7, 3), (-7, -3), (-3, -7), (3, -7), (7, -3)];
+        for (dx, dy) in extremos {
+            let mut buffer = BufferDePantalla::nuevo(32, 32);
+            buffer.dibujar_linea(16, 16, 16 + dx, 16 + dy, (0.0, 1.0));
+            let puntos = pintados(&buffer);
+            // Un píxel por paso del eje mayor, a no más de medio píxel de la recta ideal
+            let mayor = dx.abs().max(dy.abs());
+            assert_eq!(puntos.len() as i32, mayor + 1, "({}, {})", dx, dy);
+            for &(x, y, prof) in &puntos {
+                let (u, v) = ((x - 16) as f32, (y - 16) as f32);
+                let (avance, desvio) = if dx.abs() > dy.abs() {
+                    (u / dx as f32, v - u * dy as f32 / dx as f32)
+                } else {
+                    (v / dy as f32, u - v * dx as f32 / dy as f32)
+                };
+                assert!(desvio.abs() <= 0.5, "({}, {}): ({}, {})", dx, dy, x, y);
+                assert!((prof - avance).abs() < 1e-6, "({}, {}): ({}, {}) a {}", dx, dy, x, y, prof);
+            }
+            assert!(puntos.iter().any(|&(x, y, _)| (x, y) == (16 + dx, 16 + dy)), "({}, {})", dx, dy);
+        }
+    }
+
+    #[test]
+    fn linea_vertical_horizontal_y_de_un_punto() {
+        let mut buffer = BufferDePantalla::nuevo(8, 8);
+        buffer.dibujar_linea(2, 1, 2, 5, (0.5, 0.5));
+        let esperados: Vec<(i32, i32, f32)> = (1..=5).map(|y| (2, y, 0.5)).collect();
+        assert_eq!(pintados(&buffer), esperados);
+
+        let mut buffer = BufferDePantalla::nuevo(8, 8);
+        buffer.dibujar_linea(6, 3, 1, 3, (0.5, 0.5));
+        let esperados: Vec<(i32, i32, f32)> = (1..=6).map(|x| (x, 3, 0.5)).collect();
+        assert_eq!(pintados(&buffer), esperados);
+
+        let mut buffer = BufferDePantalla::nuevo(8, 8);
+        buffer.dibujar_linea(4, 4, 4, 4, (0.25, 0.75));
+        assert_eq!(pintados(&buffer), [(4, 4, 0.25)]);
+    }
+
+    fn triangulo_en_pantalla(esquinas: [[f32; 3]; 3]) -> [Vertice; 3] {
+        esquinas.map(|[x, y, z]| Vertice::nuevo(Vec3::new(x, y, z), Vec3::z(), Vec3::zeros()))
+    }
+
+    #[test]
+    fn triangulos_vecinos_no_repiten_pixeles() {
+        let buffer = BufferDePantalla::nuevo(16, 16);
+        // Un cuadrado de 12 × 12 partido en abanico desde el centro, en los dos sentidos de
+        // giro, y una tira con lados inclinados que pasan por centros de píxel
+        let centro = [8.0, 8.0, 0.0];
+        let esquinas = [[2.0, 2.0, 0.0], [14.0, 2.0, 0.0], [14.0, 14.0, 0.0], [2.0, 14.0, 0.0]];
+        let mut horario = Vec::new();
+        let mut antihorario = Vec::new();
+        for i in 0..4 {
+            horario.push([centro, esquinas[i], esquinas[(i + 1) % 4]]);
+            antihorario.push([centro, esquinas[(i + 1) % 4], esquinas[i]]);
+        }
+        let tira: Vec<[[f32; 3]; 3]> = (0..4)
+            .map(|i| {
+                let x = 1.5 + i as f32 * 3.0;
+                [[x, 1.5, 0.0], [x + 3.0, 1.5, 0.0], [x + 1.5, 13.5, 0.0]]
+            })
+            .chain((0..3).map(|i| {
+                let x = 3.0 + i as f32 * 3.0;
+                [[x + 3.0, 13.5, 0.0], [x, 13.5, 0.0], [x + 1.5, 1.5, 0.0]]
+            }))
+            .collect();
+        // Sin huecos ni repeticiones, cada conjunto pinta tantos píxeles como su área
+        let conjuntos = [("horario", horario, 144), ("antihorario", antihorario, 144), ("tira", tira, 126)];
+        for (nombre, triangulos, area) in conjuntos {
+            let mut veces = vec![0; 16 * 16];
+            for triangulo in &triangulos {
+                for fragmento in buffer.dibujar_triangulo(&triangulo_en_pantalla(*triangulo)) {
+                    veces[fragmento.posicion.y as usize * 16 + fragmento.posicion.x as usize] += 1;
+                }
+            }
+            assert!(veces.iter().all(|&v| v <= 1), "{}: {:?}", nombre, veces);
+            assert_eq!(veces.iter().sum::<i32>(), area, "{}", nombre);
+        }
+    }
+
+    #[test]
+    fn triangulo_interpola_la_profundidad() {
+        let buffer = BufferDePantalla::nuevo(16, 16);
+        let fragmentos = buffer.dibujar_triangulo(&triangulo_en_pantalla([
+            [0.0, 0.0, 0.2],
+            [16.0, 0.0, 1.0],
+            [0.0, 16.0, 0.6],
+        ]));
+        // Los 16 centros sobre la hipotenusa son del triángulo vecino: es un lado inferior derecho
+        assert_eq!(fragmentos.len(), 120);
+        for fragmento in &fragmentos {
+            let (x, y) = (fragmento.posicion.x, fragmento.posicion.y);
+            let esperada = 0.2 + 0.8 * x / 16.0 + 0.4 * y / 16.0;
+            assert!((fragmento.profundidad - esperada).abs() < 1e-6, "({}, {}): {}", x, y, fragmento.profundidad);
+            assert_eq!(fragmento.posicion.z, fragmento.profundidad);
+        }
+    }
+
+    fn obj(texto: &str) -> Result<ModeloOBJ, std::io::Error> {
+        ModeloOBJ::leer(texto.as_bytes(), "prueba.obj")
+    }
+
+    #[test]
+    fn obj_rechaza_indices_fuera_de_rango() {
+        let triangulo = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        for cara in ["f 1 2 4", "f -1 -2 -4", "f 0 1 2", "f 1/2 2/2 3/2", "f 1//1 2//1 3//1"] {
+            let error = obj(&format!("{}{}\n", triangulo, cara)).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", cara);
+            assert!(error.to_string().starts_with("línea 4:"), "{}: {}", cara, error);
+        }
+        let modelo = obj(&format!("{}f 1 2 3\nf -3 -2 -1\n", triangulo)).unwrap();
+        assert_eq!(modelo.caras[0], modelo.caras[1]);
+    }
+
+    #[test]
+    fn obj_agrupa_caras_por_material() {
+        let carpeta = std::env::temp_dir().join(format!("materiales-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&carpeta).unwrap();
+        let mtl = "Kd 9 9 9\nnewmtl rojo\nKd 1 0 0.25\nmap_Kd -s 1 1 1 texturas/roca.png\nnewmtl gris\nKs 0.5 0.5 0.5\n";
+        std::fs::write(carpeta.join("piezas.mtl"), mtl).unwrap();
+        let texto = "mtllib piezas.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\n\
+                     usemtl rojo\nf 1 2 3 4\nusemtl gris\nusemtl falta\nf 1 3 4\n";
+        let modelo = ModeloOBJ::leer(texto.as_bytes(), &carpeta.join("piezas.obj").to_string_lossy()).unwrap();
+        std::fs::remove_dir_all(&carpeta).unwrap();
+
+        // La cara previa al primer usemtl queda sin grupo, y `gris` sin caras desaparece
+        let grupos = modelo.grupos();
+        let nombres: Vec<&str> = grupos.iter().map(|g| g.material.nombre.as_str()).collect();
+        assert_eq!(nombres, ["rojo", "falta"]);
+        assert_eq!(grupos[0].caras, 1..3);
+        assert_eq!(grupos[1].caras, 3..4);
+        assert_eq!(grupos[0].material.difuso, [1.0, 0.0, 0.25]);
+        assert_eq!(grupos[0].material.mapa_difuso, Some(carpeta.join("texturas/roca.png")));
+        // Un material que el .mtl no define usa los valores por defecto
+        assert_eq!(grupos[1].material, MaterialMtl::nuevo("falta"));
+    }
+
+    #[test]
+    fn normales_suaves_pesan_por_area() {
+        // Comparten el origen: uno en z = 0 de área 2 (normal +z) y otro en x = 0 de área 1/2 (+x)
+        let modelo = obj("v 0 0 0\nv 2 0 0\nv 0 2 0\nv 0 1 0\nv 0 0 1\nf 1 2 3\nf 1 4 5\n").unwrap();
+        let normal = |cara: usize, esquina: usize| modelo.normales[modelo.caras[cara][esquina * 3 + 2]];
+        let compartida = Vec3::new(1.0, 0.0, 4.0).normalize();
+        for cara in 0..2 {
+            assert!((normal(cara, 0) - compartida).norm() < 1e-6, "{:?}", normal(cara, 0));
+        }
+        // Las esquinas de un solo triángulo conservan la normal de su cara
+        for esquina in 1..3 {
+            assert!((normal(0, esquina) - Vec3::z()).norm() < 1e-6);
+            assert!((normal(1, esquina) - Vec3::x()).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn obj_reparte_poligonos_en_abanico() {
+        // Un cuadrado y un pentágono convexos en el plano z = 0, antihorarios vistos desde +z
+        let cuadrado = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+        let pentagono = "v 0 0 0\nv 2 0 0\nv 3 1 0\nv 1 2 0\nv -1 1 0\nf 1 2 3 4 5\n";
+        let casos = [
+            (cuadrado, vec![[0, 1, 2], [0, 2, 3]]),
+            (pentagono, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]),
+        ];
+        for (texto, esperadas) in casos {
+            let modelo = obj(texto).unwrap();
+            let posiciones: Vec<[usize; 3]> = modelo.caras.iter().map(|cara| [cara[0], cara[3], cara[6]]).collect();
+            assert_eq!(posiciones, esperadas);
+            for [a, b, c] in posiciones {
+                let [a, b, c] = [a, b, c].map(|i| modelo.vertices[i]);
+                // Mismo sentido de giro que el polígono: la normal apunta a +z
+                assert!((b - a).cross(&(c - a)).z > 0.0, "{:?} {:?} {:?}", a, b, c);
+            }
+        }
+    }
+
+    #[test]
+    fn enlace_ida_y_vuelta() {
+        let enfocado = EnlaceVista {
+            guinada: 0.1 + 0.2,
+            cabeceo: -1.25e-3,
+            radio: 3.5,
+            objetivo: [0.0; 3],
+            foco: Some("67P/Churyumov_Gerasimenko 100% Ñ".to_string()),
+            marco: Some("Tierra Luna".to_string()),
+            tiempo: 1.0 / 3.0,
+            escala: 2.0,
+            pausado: true,
+            invertido: false,
+        };
+        let libre = EnlaceVista {
+            objetivo: [1.5, -0.25, 1e-7],
+            foco: None,
+            marco: None,
+            pausado: false,
+            invertido: true,
+            ..enfocado.clone()
+        };
+        for enlace in [enfocado, libre] {
+            let texto = format!("{}", enlace);
+            assert_eq!(EnlaceVista::interpretar(&texto, enlace.clone()), Ok(enlace), "{}", texto);
+        }
+    }
+
+    #[test]
+    fn enlace_rechaza_valores_no_finitos_y_nombres_mal_codificados() {
+        let base = EnlaceVista {
+            guinada: 0.0,
+            cabeceo: 0.0,
+            radio: 1.0,
+            objetivo: [0.0; 3],
+            foco: None,
+            marco: None,
+            tiempo: 0.0,
+            escala: 1.0,
+            pausado: false,
+            invertido: false,
+        };
+        for texto in ["v1/gNaN", "v1/cinf", "v1/r-inf", "v1/o0,NaN,0", "v1/tinfinity", "v1/fMar%2", "v1/f%+1", "v1/f%FF"] {
+            assert!(EnlaceVista::interpretar(texto, base.clone()).is_err(), "{}", texto);
+        }
+    }
+
+    #[test]
+    fn paleta_interpola_entre_paradas_y_satura() {
+        let negro = ColorRGB::nuevo(0, 0, 0);
+        let naranja = ColorRGB::nuevo(200, 100, 0);
+        let rosa = ColorRGB::nuevo(200, 100, 250);
+        let paleta = Paleta::nuevo(vec![(0.0, negro), (0.5, naranja), (1.0, rosa)]);
+        for (t, esperado) in [
+            (0.25, ColorRGB::nuevo(100, 50, 0)),
+            (0.5, naranja),
+            (0.75, ColorRGB::nuevo(200, 100, 125)),
+            (-1.0, negro),
+            (2.0, rosa),
+        ] {
+            assert_eq!(paleta.muestrear(t).a_hexadecimal(), esperado.a_hexadecimal(), "t = {}", t);
+        }
+        let unica = Paleta::nuevo(vec![(0.3, naranja)]);
+        for t in [-1.0, 0.0, 0.3, 0.9, 2.0] {
+            assert_eq!(unica.muestrear(t).a_hexadecimal(), naranja.a_hexadecimal(), "t = {}", t);
+        }
+    }
+
+    #[test]
+    fn recarga_entrega_lo_guardado_al_calmarse() {
+        let mut escena = ArchivoEscena::interpretar(ArchivoEscena::INCLUIDA, "escena incluida").unwrap();
+        let archivo = std::path::PathBuf::from("/escenas/sistema.toml");
+        let modelo = std::path::PathBuf::from("/escenas/modelos/nave.obj");
+        escena.ruta = Some(archivo.clone());
+        escena.cuerpo[0].modelo = Some(modelo.clone());
+        let mut recarga = RecargaEscena::nueva(true);
+        recarga.vigilar(&escena);
+        let evento = |tipo, ruta: &std::path::Path| Ok(notify::Event::new(tipo).add_path(RecargaEscena::clave(ruta)));
+        let escritura = notify::EventKind::Modify(notify::event::ModifyKind::Data(notify::event::DataChange::Content));
+        let renombre = notify::EventKind::Modify(notify::event::ModifyKind::Name(notify::event::RenameMode::To));
+        let lectura = notify::EventKind::Access(notify::event::AccessKind::Read);
+        let creacion = notify::EventKind::Create(notify::event::CreateKind::File);
+        let inicio = std::time::Instant::now();
+        let despues = |milisegundos| inicio + std::time::Duration::from_millis(milisegundos);
+
+        recarga.anotar(evento(escritura, &archivo), despues(0));
+        recarga.anotar(evento(lectura, &modelo), despues(0));
+        recarga.anotar(evento(creacion, "/escenas/otra.toml".as_ref()), despues(0));
+        assert_eq!(recarga.entregar(&escena, despues(100)), CambiosEscena::default());
+        // Como un editor: termina de guardar renombrando, y la espera vuelve a empezar
+        recarga.anotar(evento(renombre, &archivo), despues(100));
+        recarga.anotar(evento(escritura, &modelo), despues(100));
+        assert_eq!(recarga.entregar(&escena, despues(200)), CambiosEscena::default());
+        let cambios = recarga.entregar(&escena, despues(300));
+        assert_eq!(cambios, CambiosEscena { escena: true, recursos: vec![modelo] });
+        // Ya se entregó, y la lectura y el otro archivo no dejan nada pendiente
+        assert_eq!(recarga.entregar(&escena, despues(1000)), CambiosEscena::default());
+        assert!(recarga.pendientes.is_empty());
+    }
+
+    /// Con el `notify` real; depende de cuánto tarda el sistema en avisar
+    #[test]
+    #[ignore]
+    fn recarga_avisa_al_guardar_la_escena() {
+        let carpeta = std::env::temp_dir().join(format!("recarga-escena-{}", std::process::id()));
+        std::fs::create_dir_all(&carpeta).unwrap();
+        let ruta = carpeta.join("escena.toml");
+        std::fs::write(&ruta, ArchivoEscena::INCLUIDA).unwrap();
+        let escena = ArchivoEscena::cargar(&ruta).unwrap();
+
+        let mut recarga = RecargaEscena::nueva(true);
+        assert!(!recarga.revisar(&escena).escena);
+        // Como un editor: escribe otro archivo y lo renombra encima
+        let temporal = carpeta.join("escena.toml~");
+        std::fs::write(&temporal, ArchivoEscena::INCLUIDA).unwrap();
+        std::fs::rename(&temporal, &ruta).unwrap();
+        let limite = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut avisada = false;
+        while !avisada && std::time::Instant::now() < limite {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            avisada = recarga.revisar(&escena).escena;
+        }
+        std::fs::remove_dir_all(&carpeta).unwrap();
+        assert!(avisada);
+    }
+
+    /// Cuadros con vsync a `hz` que suman dos segundos de pared: pares con ±15% de ruido del
+    /// período y, cada tanto, un refresco perdido
+    fn cuadros_medidos(hz: f64) -> Vec<f64> {
+        let periodo = 1.0 / hz;
+        let mut restantes = (2.0 * hz).round() as usize;
+        let mut cuadros = Vec::new();
+        while restantes > 0 {
+            if restantes == 1 {
+                cuadros.push(periodo);
+                break;
+            }
+            let refrescos = if cuadros.len() % 10 == 4 && restantes >= 3 { 2 } else { 1 };
+            cuadros.push(refrescos as f64 * periodo + 0.15 * periodo);
+            cuadros.push(periodo - 0.15 * periodo);
+            restantes -= refrescos + 1;
+        }
+        cuadros
+    }
+
+    /// Estado de la simulación al terminar `cuadros_medidos(hz)`
+    struct FinSimulacion {
+        tiempo: f64,
+        posiciones: Vec<Vec3>,
+        foco: Option<usize>,
+        ojo: Vec3,
+        objetivo: Vec3,
+        transicion: f32,
+    }
+
+    /// Corre la escena incluida con el paso de `actualizar` a `hz`, con la cámara recién
+    /// enfocada en el último cuerpo
+    fn simular_a(hz: f64) -> FinSimulacion {
+        let escena = ArchivoEscena::interpretar(ArchivoEscena::INCLUIDA, "escena incluida").unwrap();
+        let mut cuerpos = escena.cuerpos().unwrap();
+        let animaciones = escena.animaciones(&cuerpos).unwrap();
+        let mut grafo = GrafoEscena::default();
+        grafo.actualizar(&mut cuerpos);
+        let ultimo = cuerpos.len() - 1;
+        let mut reloj = RelojSimulacion::nuevo();
+        reloj.escala = 100.0;
+        let mut camara = CamaraVirtual::inicial();
+        let mut foco = None;
+        let mut simulacion = Simulacion {
+            reloj: &mut reloj,
+            camara: &mut camara,
+            cuerpos: &mut cuerpos,
+            grafo: &mut grafo,
+            fisica: &mut SimulacionNCuerpos::nueva(),
+            fila: &mut FilaTamanos::default(),
+            animaciones: &animaciones,
+            capas: &AjustesCapas::default(),
+            foco: &mut foco,
+        };
+        simulacion.enfocar(Some(ultimo));
+        let mut frecuencia = FrecuenciaPantalla { hz: Some(hz), ..FrecuenciaPantalla::default() };
+        for medido in cuadros_medidos(hz) {
+            simulacion.avanzar(frecuencia.cuantizar(medido));
+        }
+        FinSimulacion {
+            tiempo: reloj.tiempo,
+            posiciones: cuerpos.iter().map(CuerpoCeleste::posicion_3d).collect(),
+            foco,
+            ojo: camara.ojo,
+            objetivo: camara.objetivo,
+            transicion: camara.transicion,
+        }
+    }
+
+    #[test]
+    fn frecuencias_llegan_al_mismo_estado() {
+        let fin = simular_a(60.0);
+        assert!((fin.tiempo - 200.0).abs() < 1e-9, "{}", fin.tiempo);
+        let ultimo = fin.posiciones.len() - 1;
+        assert_eq!(fin.foco, Some(ultimo));
+        // El deslizamiento no terminó: la comparación también cubre su caída por cuadro
+        assert!(fin.transicion > 0.0);
+        assert!((fin.objetivo - fin.posiciones[ultimo]).norm() > 0.0);
+        for hz in [30.0, 144.0] {
+            let otro = simular_a(hz);
+            assert!((otro.tiempo - fin.tiempo).abs() < 1e-9, "{} Hz: {} s", hz, otro.tiempo);
+            for (a, b) in fin.posiciones.iter().zip(&otro.posiciones) {
+                assert!((a - b).norm() < 1e-5, "{} Hz: {:?} y {:?}", hz, a, b);
+            }
+            assert_eq!(otro.foco, fin.foco);
+            assert!((otro.transicion - fin.transicion).abs() < 1e-6, "{} Hz", hz);
+            assert!((otro.objetivo - fin.objetivo).norm() < 1e-5, "{} Hz: {:?} y {:?}", hz, otro.objetivo, fin.objetivo);
+            assert!((otro.ojo - fin.ojo).norm() < 1e-5, "{} Hz: {:?} y {:?}", hz, otro.ojo, fin.ojo);
+        }
+    }
+
+    #[test]
+    fn comparar_escenas_sale_como_diff() {
+        let carpeta = std::env::temp_dir().join(format!("comparar-escenas-{}", std::process::id()));
+        std::fs::create_dir_all(&carpeta).unwrap();
+        let ruta = |nombre: &str| carpeta.join(nombre).to_string_lossy().into_owned();
+        std::fs::write(ruta("a.toml"), ArchivoEscena::INCLUIDA).unwrap();
+        std::fs::write(ruta("b.toml"), ArchivoEscena::INCLUIDA).unwrap();
+        // Mismo contenido con otro formato: la comparación es de valores, no de texto
+        let valor: toml::Value = toml::from_str(ArchivoEscena::INCLUIDA).unwrap();
+        std::fs::write(ruta("c.toml"), toml::to_string(&valor).unwrap()).unwrap();
+        let mut cambiado = valor.clone();
+        cambiado["cuerpo"][0]["nombre"] = toml::Value::String("Otro".to_string());
+        std::fs::write(ruta("d.toml"), toml::to_string(&cambiado).unwrap()).unwrap();
+        std::fs::write(ruta("roto.toml"), "cuerpo = [").unwrap();
+
+        let codigo = |a: &str, b: &str| codigo_comparacion(&[ruta(a), ruta(b)]);
+        let codigos = [
+            codigo("a.toml", "b.toml"),
+            codigo("a.toml", "c.toml"),
+            codigo("a.toml", "d.toml"),
+            codigo("a.toml", "falta.toml"),
+            codigo("roto.toml", "a.toml"),
+            codigo_comparacion(&[ruta("a.toml")]),
+        ];
+        std::fs::remove_dir_all(&carpeta).unwrap();
+        assert_eq!(codigos, [0, 0, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn simular_repite_el_mismo_resultado() {
+        let escena = ArchivoEscena::interpretar(ArchivoEscena::INCLUIDA, "escena incluida").unwrap();
+        for extra in [&[][..], &["--ncuerpos", "--integrador", "rk4"][..]] {
+            let argumentos: Vec<String> = ["simulate", "--pasos", "240", "--dt", "0.5", "--cada", "60"]
+                .iter()
+                .chain(extra)
+                .map(|a| a.to_string())
+                .collect();
+            let opciones = OpcionesSimulacion::interpretar(&argumentos).unwrap();
+            let primera = simular(&opciones, &escena).unwrap();
+            let segunda = simular(&opciones, &escena).unwrap();
+            assert_eq!(primera.tabla, segunda.tabla, "{:?}", extra);
+            // Encabezado, el paso 0 y cuatro pasos anotados, una fila por cuerpo
+            assert_eq!(primera.tabla.lines().count(), 1 + 5 * primera.cuerpos.len(), "{:?}", extra);
+            for (a, b) in primera.cuerpos.iter().zip(&segunda.cuerpos) {
+                assert_eq!(a.posicion, b.posicion, "{}", a.nombre);
+            }
+            assert_eq!(primera.fisica.activa, opciones.ncuerpos);
+        }
+    }
+}
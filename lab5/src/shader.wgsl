@@ -10,25 +10,148 @@ struct UniformesPlaneta {
     tiempo: f32,
     tipo_shader: u32,
     resolucion: vec2<f32>,
-    posicion_planeta: vec2<f32>,
-    escala_planeta: f32,
-    _relleno: f32,
+    tinte: vec3<f32>,
+    actividad: f32,
+    matriz_modelo: mat4x4<f32>,
+    matriz_vista: mat4x4<f32>,
+    matriz_proyeccion: mat4x4<f32>,
+    // Material PBR del cuerpo
+    albedo: vec3<f32>,
+    metalico: f32,
+    emisivo: vec3<f32>,
+    rugosidad: f32,
+    // Globales del cuadro: cámara, luz ambiente y la estrella como luz puntual
+    ojo: vec3<f32>,
+    ambiente: f32,
+    luz: vec3<f32>,
+    intensidad_luz: f32,
+    // Atmósfera del cuerpo: coeficientes de dispersión por radio del cuerpo
+    rayleigh: vec3<f32>,
+    mie: f32,
+    espesor_atmosfera: f32,
+    anisotropia_mie: f32,
+    intensidad_atmosfera: f32,
+    radio_cuerpo: f32,
+    // Altura del mapa de relieve en blanco, en radios del cuerpo (0 = sin relieve)
+    relieve: f32,
+    // Bordes del anillo en radios del cuerpo (0 = sin anillo)
+    anillo_interno: f32,
+    anillo_externo: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> uniformes: UniformesPlaneta;
 
+// Paleta de las bandas de los gigantes gaseosos (`Paleta::bandas_gas`), de 0 a 1 a lo
+// ancho de una sola fila: GLES no tiene texturas 1D
+@group(0) @binding(7)
+var paleta_bandas_gas: texture_2d<f32>;
+
+// Paleta en `t` (recortado a [0, 1]) interpolando entre texeles vecinos; la textura es
+// sRGB, así que la mezcla es como luz. Con `textureLoad` vale fuera del control uniforme
+fn muestrear_paleta(paleta: texture_2d<f32>, t: f32) -> vec3<f32> {
+    let ultimo = textureDimensions(paleta).x - 1u;
+    let x = clamp(t, 0.0, 1.0) * f32(ultimo);
+    let i = min(u32(x), ultimo);
+    let desde = textureLoad(paleta, vec2<u32>(i, 0u), 0).rgb;
+    let hasta = textureLoad(paleta, vec2<u32>(min(i + 1u, ultimo), 0u), 0).rgb;
+    return mix(desde, hasta, x - f32(i));
+}
+
+// Los campos propios del cuerpo que se dibuja (160 B)
+struct DatosCuerpo {
+    matriz_modelo: mat4x4<f32>,
+    tinte: vec3<f32>,
+    actividad: f32,
+    albedo: vec3<f32>,
+    metalico: f32,
+    emisivo: vec3<f32>,
+    rugosidad: f32,
+    rayleigh: vec3<f32>,
+    mie: f32,
+    tipo_shader: u32,
+    espesor_atmosfera: f32,
+    anisotropia_mie: f32,
+    intensidad_atmosfera: f32,
+    radio_cuerpo: f32,
+    relieve: f32,
+    anillo_interno: f32,
+    anillo_externo: f32,
+}
+
+// INICIO DE DATOS DEL CUERPO
+// Salen de la franja de uniformes del cuerpo. Si el adaptador tiene constantes de empuje,
+// el programa reemplaza esta función por una que los lee de `var<push_constant>`
+fn cuerpo() -> DatosCuerpo {
+    return DatosCuerpo(
+        uniformes.matriz_modelo,
+        uniformes.tinte,
+        uniformes.actividad,
+        uniformes.albedo,
+        uniformes.metalico,
+        uniformes.emisivo,
+        uniformes.rugosidad,
+        uniformes.rayleigh,
+        uniformes.mie,
+        uniformes.tipo_shader,
+        uniformes.espesor_atmosfera,
+        uniformes.anisotropia_mie,
+        uniformes.intensidad_atmosfera,
+        uniformes.radio_cuerpo,
+        uniformes.relieve,
+        uniformes.anillo_interno,
+        uniformes.anillo_externo,
+    );
+}
+// FIN DE DATOS DEL CUERPO
+
+// 0 = el tipo de shader sale del uniforme. Las variantes especializadas del pipeline de
+// planetas reescriben esta línea con un tipo fijo para que el compilador descarte las demás ramas
+const TIPO_ESPECIALIZADO: u32 = 0u;
+
+// Las variantes con un fragmento propio de la escena ponen esta constante en true y
+// reemplazan la función entre las marcas por la del archivo
+const SUPERFICIE_PROPIA: bool = false;
+
+// INICIO DE SUPERFICIE PROPIA
+// Color base de la superficie en `direccion` (unitaria, espacio del cuerpo) a los `t`
+// segundos; el tinte, el mapa y la iluminación se aplican después
+fn superficie_propia(direccion: vec3<f32>, uv: vec2<f32>, t: f32) -> vec3<f32> {
+    return vec3<f32>(1.0, 0.0, 1.0);
+}
+// FIN DE SUPERFICIE PROPIA
+
+// Mapa de superficie equirectangular; alfa 0 = sin mapa (color procedural)
+@group(1) @binding(0)
+var textura_superficie: texture_2d<f32>;
+@group(1) @binding(1)
+var muestreador_superficie: sampler;
+// Parte estática del color procedural horneada por `hornear_superficie`, con la rugosidad
+// relativa en alfa como 0.5 + rugosidad / 2; alfa 0 = sin hornear
+@group(1) @binding(2)
+var superficie_horneada: texture_2d<f32>;
+// Mapa de superficie en cubo, indexado con la dirección en el marco del cuerpo (sin el
+// pellizco de los polos del equirectangular); alfa 0 = sin mapa
+@group(1) @binding(4)
+var mapa_cubico: texture_cube<f32>;
+// Mapa de alturas equirectangular en escala de grises (negro = radio 1); lo lee el vertex shader
+@group(1) @binding(5)
+var mapa_relieve: texture_2d<f32>;
+
 // Estructura de entrada del vertex shader
 struct EntradaVertice {
     @location(0) posicion: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
 }
 
 // Estructura de salida del vertex shader
 struct SalidaVertice {
     @builtin(position) posicion_clip: vec4<f32>,
-    @location(0) pos_mundo: vec3<f32>,
+    @location(0) pos_cuerpo: vec3<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) pos_mundo: vec3<f32>,
 }
 
 // =============================================================================
@@ -37,38 +160,106 @@ struct SalidaVertice {
 
 @vertex
 fn vertex_principal(entrada: EntradaVertice) -> SalidaVertice {
-    var salida: SalidaVertice;
-    
-    // Aplicar rotación automática con el tiempo
-    let angulo = uniformes.tiempo * 0.3;
-    let cos_angulo = cos(angulo);
-    let sin_angulo = sin(angulo);
-    
-    // Matriz de rotación en eje Y
-    let rotacion_y = mat3x3<f32>(
-        vec3<f32>(cos_angulo, 0.0, sin_angulo),
-        vec3<f32>(0.0, 1.0, 0.0),
-        vec3<f32>(-sin_angulo, 0.0, cos_angulo)
+    return transformar_vertice(con_relieve(entrada));
+}
+
+// Los anillos comparten el formato de vértice pero no el relieve de la esfera
+@vertex
+fn vertex_anillo(entrada: EntradaVertice) -> SalidaVertice {
+    return transformar_vertice(entrada);
+}
+
+// Bilineal a mano con textureLoad: un muestreador anisótropo en el vertex shader no da el
+// mismo valor en todas las invocaciones de un vértice y abre grietas. La longitud da la vuelta
+fn altura_relieve(uv: vec2<f32>) -> f32 {
+    let tamano = vec2<i32>(textureDimensions(mapa_relieve, 0));
+    let texel = uv * vec2<f32>(tamano) - 0.5;
+    let base = vec2<i32>(floor(texel));
+    let f = texel - floor(texel);
+    let x0 = ((base.x % tamano.x) + tamano.x) % tamano.x;
+    let x1 = (x0 + 1) % tamano.x;
+    let y0 = clamp(base.y, 0, tamano.y - 1);
+    let y1 = clamp(base.y + 1, 0, tamano.y - 1);
+    let arriba = mix(textureLoad(mapa_relieve, vec2<i32>(x0, y0), 0).r, textureLoad(mapa_relieve, vec2<i32>(x1, y0), 0).r, f.x);
+    let abajo = mix(textureLoad(mapa_relieve, vec2<i32>(x0, y1), 0).r, textureLoad(mapa_relieve, vec2<i32>(x1, y1), 0).r, f.x);
+    return mix(arriba, abajo, f.y);
+}
+
+// Desplaza el vértice de la esfera unitaria a radio 1 + relieve · altura y rehace la normal
+// con las derivadas de la altura en colatitud (v) y longitud (u) por diferencias centrales
+fn con_relieve(entrada: EntradaVertice) -> EntradaVertice {
+    let k = cuerpo().relieve;
+    if k <= 0.0 {
+        return entrada;
+    }
+    let paso = 1.0 / vec2<f32>(textureDimensions(mapa_relieve, 0));
+    let uv = entrada.uv;
+    let radio = 1.0 + k * altura_relieve(uv);
+    let du = (altura_relieve(uv + vec2<f32>(paso.x, 0.0)) - altura_relieve(uv - vec2<f32>(paso.x, 0.0))) / (2.0 * paso.x);
+    let dv = (altura_relieve(uv + vec2<f32>(0.0, paso.y)) - altura_relieve(uv - vec2<f32>(0.0, paso.y))) / (2.0 * paso.y);
+
+    // θ = colatitud, φ = longitud; así los genera `generar_esfera` (u = 1 - φ / 2π)
+    let theta = uv.y * PI;
+    let phi = (1.0 - uv.x) * 2.0 * PI;
+    let e_theta = vec3<f32>(cos(theta) * cos(phi), -sin(theta), cos(theta) * sin(phi));
+    let e_phi = vec3<f32>(-sin(phi), 0.0, cos(phi));
+    let dr_theta = k * dv / PI;
+    let dr_phi = -k * du / (2.0 * PI);
+
+    var desplazada = entrada;
+    desplazada.posicion = entrada.posicion * radio;
+    desplazada.normal = normalize(
+        radio * entrada.normal - dr_theta * e_theta - dr_phi / max(sin(theta), 1e-3) * e_phi,
     );
+    return desplazada;
+}
+
+fn transformar_vertice(entrada: EntradaVertice) -> SalidaVertice {
+    var salida: SalidaVertice;
     
-    // Escalar y rotar la posición
-    let pos_escalada = entrada.posicion * uniformes.escala_planeta;
-    let pos_rotada = rotacion_y * pos_escalada;
-    let normal_rotada = rotacion_y * entrada.normal;
-    
-    // Proyección simple con offset de posición del planeta
-    let posicion_final = pos_rotada * vec3<f32>(1.0, 1.0, 0.5);
-    salida.posicion_clip = vec4<f32>(
-        posicion_final.xy + uniformes.posicion_planeta, 
-        0.5, 
-        1.0
-    );
-    salida.pos_mundo = pos_rotada;
-    salida.normal = normalize(normal_rotada);
+    // Modelo (escala, giro y posición del cuerpo), luego vista y proyección de la cámara
+    let pos_escena = cuerpo().matriz_modelo * vec4<f32>(entrada.posicion, 1.0);
+    salida.posicion_clip = uniformes.matriz_proyeccion * uniformes.matriz_vista * pos_escena;
+    salida.pos_mundo = pos_escena.xyz;
+
+    // Los patrones procedurales usan la posición en el marco del cuerpo: giran y se inclinan con él
+    salida.pos_cuerpo = entrada.posicion;
+    salida.normal = normalize((cuerpo().matriz_modelo * vec4<f32>(entrada.normal, 0.0)).xyz);
+    salida.uv = entrada.uv;
     
     return salida;
 }
 
+// Vértice de 16 bytes: posición en media precisión (w sin usar), normal octaédrica en
+// Snorm16x2 y uv en Unorm16x2; el hardware ya los entrega como flotantes
+struct EntradaVerticeCompacta {
+    @location(0) posicion: vec4<f32>,
+    @location(1) normal_octaedrica: vec2<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+// Despliega el octaedro: la mitad inferior (z < 0) viene doblada sobre las esquinas
+fn normal_octaedrica(e: vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e, 1.0 - abs(e.x) - abs(e.y));
+    let doblez = max(-n.z, 0.0);
+    n.x += select(doblez, -doblez, n.x >= 0.0);
+    n.y += select(doblez, -doblez, n.y >= 0.0);
+    return normalize(n);
+}
+
+fn descomprimir_vertice(entrada: EntradaVerticeCompacta) -> EntradaVertice {
+    var completa: EntradaVertice;
+    completa.posicion = entrada.posicion.xyz;
+    completa.normal = normal_octaedrica(entrada.normal_octaedrica);
+    completa.uv = entrada.uv;
+    return completa;
+}
+
+@vertex
+fn vertex_compacto(entrada: EntradaVerticeCompacta) -> SalidaVertice {
+    return transformar_vertice(con_relieve(descomprimir_vertice(entrada)));
+}
+
 // =============================================================================
 // FUNCIONES AUXILIARES DE RUIDO Y PATRONES
 // =============================================================================
@@ -168,10 +359,10 @@ fn shader_sol(pos: vec3<f32>, t: f32) -> vec3<f32> {
     
     // Corona pulsante
     let distancia = length(p);
-    let corona = pow(1.0 - distancia * 0.4, 3.0) * 1.5;
-    let pulso = sin(t * 2.0) * 0.2 + 1.2;
+    let corona = pow(max(1.0 - distancia * 0.4, 0.0), 3.0) * 1.5;
+    let pulso = (sin(t * 2.0) * 0.2 + 1.2) * cuerpo().actividad;
     
-    // Gradiente de temperatura (paleta naranja-amarillo brillante)
+    // Paleta de temperatura (paleta naranja-amarillo brillante)
     let temperatura = combinado * espiral * 1.2;
     var color_base: vec3<f32>;
     
@@ -188,15 +379,24 @@ fn shader_sol(pos: vec3<f32>, t: f32) -> vec3<f32> {
     return color_base * manchas * (1.0 + corona * pulso * 0.8);
 }
 
-/// Shader 2: Planeta rocoso tipo Marte
-fn shader_rocoso(pos: vec3<f32>, t: f32) -> vec3<f32> {
-    let p = pos * 5.0;
-    
+/// Shader 2: altura del terreno rocoso en `p` (posición del cuerpo por 5)
+fn altura_rocosa(p: vec3<f32>) -> f32 {
     // Generación de terreno más compleja con Voronoi
     let continentes = patron_voronoi(p * 0.8);
     let montanas = fbm_ruido(p * 3.0, 3) * 0.3;
     let detalles_finos = fbm_ruido(p * 8.0, 2) * 0.15;
-    let altura_terreno = continentes * 0.55 + montanas * 0.3 + detalles_finos * 0.15;
+    return continentes * 0.55 + montanas * 0.3 + detalles_finos * 0.15;
+}
+
+/// Altura a partir de la cual hay casquetes de hielo (crecen con la actividad)
+fn limite_hielo_rocoso() -> f32 {
+    return 0.65 - 0.1 * (cuerpo().actividad - 1.0);
+}
+
+/// Shader 2: Planeta rocoso tipo Marte (terreno estático, horneable)
+fn terreno_rocoso(pos: vec3<f32>) -> vec3<f32> {
+    let p = pos * 5.0;
+    let altura_terreno = altura_rocosa(p);
     
     var color_terreno: vec3<f32>;
     
@@ -209,8 +409,8 @@ fn shader_rocoso(pos: vec3<f32>, t: f32) -> vec3<f32> {
         } else {
             color_terreno = vec3<f32>(0.7, 0.25, 0.1);   // Rojo óxido oscuro
         }
-    } else if (altura_terreno >= 0.65) {
-        // Casquetes polares de hielo
+    } else if (altura_terreno >= limite_hielo_rocoso()) {
+        // Casquetes polares de hielo (crecen con la actividad)
         color_terreno = vec3<f32>(0.95, 0.95, 1.0);
     } else if (altura_terreno >= 0.55) {
         // Montañas con más detalle
@@ -231,6 +431,23 @@ fn shader_rocoso(pos: vec3<f32>, t: f32) -> vec3<f32> {
             color_terreno = vec3<f32>(0.45, 0.16, 0.09); // Rojo muy oscuro
         }
     }
+    
+    return color_terreno;
+}
+
+/// Shader 2: rugosidad relativa a la del material; el hielo de los casquetes es más liso
+fn rugosidad_rocosa(pos: vec3<f32>) -> f32 {
+    let altura = altura_rocosa(pos * 5.0);
+    if (altura >= 0.35 && altura >= limite_hielo_rocoso()) {
+        return 0.35;
+    }
+    return select(0.85, 1.0, altura >= 0.55);
+}
+
+/// Shader 2: tormentas de polvo animadas sobre el terreno rocoso
+fn tormentas_polvo(terreno: vec3<f32>, pos: vec3<f32>, t: f32) -> vec3<f32> {
+    let p = pos * 5.0;
+    var color_terreno = terreno;
 
     // Tormentas de polvo animadas
     let tormenta1 = fbm_ruido(p * 2.0 + vec3<f32>(t * 15.0, 0.0, t * 8.0), 3);
@@ -261,23 +478,8 @@ fn shader_gaseoso(pos: vec3<f32>, t: f32) -> vec3<f32> {
     let caos_atmosferico = fbm_ruido(p * 3.0 + vec3<f32>(t, 0.0, 0.0), 3);
     let valor_banda = clamp(bandas * 0.6 + caos_atmosferico * 0.4, 0.0, 1.0);
     
-    // Paleta de colores joviana vibrante
-    let tono1 = vec3<f32>(0.95, 0.85, 0.65);  // Crema claro
-    let tono2 = vec3<f32>(0.85, 0.5, 0.3);    // Naranja terracota
-    let tono3 = vec3<f32>(0.9, 0.7, 0.5);     // Beige dorado
-    let tono4 = vec3<f32>(1.0, 0.9, 0.7);     // Blanco cremoso
-    
-    var color_final: vec3<f32>;
-    
-    if (valor_banda < 0.25) {
-        color_final = mix(tono1, tono2, valor_banda * 4.0);
-    } else if (valor_banda < 0.5) {
-        color_final = mix(tono2, tono3, (valor_banda - 0.25) * 4.0);
-    } else if (valor_banda < 0.75) {
-        color_final = mix(tono3, tono4, (valor_banda - 0.5) * 4.0);
-    } else {
-        color_final = mix(tono4, tono1, (valor_banda - 0.75) * 4.0);
-    }
+    // Paleta joviana: crema, terracota, beige dorado y blanco cremoso, y de vuelta a crema
+    var color_final = muestrear_paleta(paleta_bandas_gas, valor_banda);
     
     // Gran Mancha Roja (en lugar de blanca)
     let centro_mancha = vec3<f32>(0.6, -0.3, 0.0);
@@ -314,44 +516,10 @@ fn shader_anillos(pos: vec3<f32>, t: f32) -> vec3<f32> {
     let tono2 = vec3<f32>(0.7, 0.5, 0.9);  // Púrpura claro
     var color_planeta = mix(tono1, tono2, bandas);
 
-    // Sistema de anillos espectaculares
+    // Los anillos son geometría aparte (fragment_anillo); aquí solo cae su sombra
     let dist_anillo = length(p.xz);
     let altura_y = abs(p.y);
     
-    if (altura_y < 0.18 && dist_anillo > 0.75 && dist_anillo < 2.0) {
-        let freq_anillos = dist_anillo * 50.0;
-        let bandas_anillo = sin(freq_anillos) * 0.5 + 0.5;
-        let var_brillo = sin(dist_anillo * 30.0 + t * 3.0) * 0.5 + 0.5;
-        
-        // Textura adicional en los anillos
-        let textura_anillos = fbm_ruido(vec3<f32>(dist_anillo * 20.0, p.y * 50.0, t * 0.5), 3);
-        let bandas_ajustadas = bandas_anillo * 0.7 + textura_anillos * 0.3;
-        
-        // Gaps de Cassini (divisiones oscuras)
-        let es_gap = (dist_anillo > 1.0 && dist_anillo < 1.15) ||
-                     (dist_anillo > 1.5 && dist_anillo < 1.55) ||
-                     (dist_anillo > 1.75 && dist_anillo < 1.78);
-        
-        if (es_gap) {
-            color_planeta *= 0.4;
-        } else {
-            var color_anillo: vec3<f32>;
-            if (bandas_ajustadas > 0.7) {
-                color_anillo = vec3<f32>(1.0, 0.9, 0.6);  // Dorado claro
-            } else if (bandas_ajustadas > 0.4) {
-                color_anillo = vec3<f32>(0.95, 0.7, 0.8); // Rosa dorado
-            } else {
-                color_anillo = vec3<f32>(0.8, 0.5, 0.6);  // Rosa oscuro
-            }
-            
-            let alpha_anillo = (1.0 - pow(altura_y / 0.18, 1.2)) * 0.95;
-            let brillo_anillo = 0.9 + var_brillo * 0.2;
-            
-            color_planeta = mix(color_planeta, color_anillo, alpha_anillo);
-            color_planeta *= brillo_anillo;
-        }
-    }
-    
     // Sombra de anillos sobre el planeta
     if (altura_y < 0.2 && dist_anillo < 0.9) {
         let bandas_sombra = sin(dist_anillo * 50.0) * 0.5 + 0.5;
@@ -401,6 +569,13 @@ fn shader_volcanico(pos: vec3<f32>, t: f32) -> vec3<f32> {
     return color_superficie;
 }
 
+/// Shader 7: latón cepillado de las piezas del planetario; `pos` es de mundo, las vetas
+/// corren a lo largo de los brazos y de los ejes
+fn shader_laton(pos: vec3<f32>) -> vec3<f32> {
+    let vetas = ruido_suave(pos * vec3<f32>(4.0, 4.0, 90.0)) * 0.6 + ruido_suave(pos * vec3<f32>(90.0, 90.0, 4.0)) * 0.4;
+    return mix(vec3<f32>(0.60, 0.42, 0.16), vec3<f32>(0.86, 0.68, 0.34), vetas);
+}
+
 /// Shader 6: Luna con cráteres de hielo
 fn shader_luna(pos: vec3<f32>) -> vec3<f32> {
     let p = pos * 5.0;
@@ -445,12 +620,63 @@ fn shader_luna(pos: vec3<f32>) -> vec3<f32> {
     return color_superficie;
 }
 
-/// Shader 7: Estrellas de fondo
-fn shader_estrella(pos: vec3<f32>, t: f32) -> vec3<f32> {
-    let dist = length(pos);
-    let brillo = pow(1.0 - dist, 8.0);
-    let parpadeo = sin(t * 3.0 + pos.x * 10.0 + pos.y * 8.0) * 0.5 + 0.5;
-    return vec3<f32>(1.0, 1.0, 1.0) * brillo * (0.7 + parpadeo * 0.3);
+/// Shader 6: rugosidad relativa a la del material; el hielo de los mares es el más liso
+fn rugosidad_luna(pos: vec3<f32>) -> f32 {
+    let p = pos * 5.0;
+    if (patron_voronoi(p * 1.2) < 0.25) {
+        return 1.0;
+    }
+    if (fbm_ruido(p * 0.8, 3) < 0.3) {
+        return 0.5;
+    }
+    return select(0.8, 0.7, fbm_ruido(p * 2.0, 2) > 0.7);
+}
+
+// =============================================================================
+// HORNEADO - Parte estática de las superficies procedurales en un mapa equirectangular
+// =============================================================================
+
+@group(1) @binding(3)
+var superficie_horneada_salida: texture_storage_2d<rgba8unorm, write>;
+
+/// Dirección sobre la esfera para unas coordenadas equirectangulares
+fn direccion_equirectangular(uv: vec2<f32>) -> vec3<f32> {
+    let phi = uv.x * 6.28318530718;
+    let theta = uv.y * 3.14159265359;
+    return vec3<f32>(sin(theta) * cos(phi), cos(theta), sin(theta) * sin(phi));
+}
+
+/// Inversa de `direccion_equirectangular`
+fn uv_equirectangular(direccion: vec3<f32>) -> vec2<f32> {
+    let u = atan2(direccion.z, direccion.x) / 6.28318530718;
+    let v = acos(clamp(direccion.y, -1.0, 1.0)) / 3.14159265359;
+    return vec2<f32>(u, v);
+}
+
+@compute @workgroup_size(8, 8)
+fn hornear_superficie(@builtin(global_invocation_id) id: vec3<u32>) {
+    let tamano = textureDimensions(superficie_horneada_salida);
+    if (id.x >= tamano.x || id.y >= tamano.y) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(tamano);
+    let direccion = direccion_equirectangular(uv);
+
+    var color: vec3<f32>;
+    var rugosidad = 1.0;
+    switch cuerpo().tipo_shader {
+        case 2u: {
+            color = terreno_rocoso(direccion);
+            rugosidad = rugosidad_rocosa(direccion);
+        }
+        case 6u: {
+            color = shader_luna(direccion);
+            rugosidad = rugosidad_luna(direccion);
+        }
+        default: { color = vec3<f32>(1.0, 0.0, 1.0); }
+    }
+    let alfa = 0.5 + 0.5 * clamp(rugosidad, 0.0, 1.0);
+    textureStore(superficie_horneada_salida, vec2<i32>(id.xy), vec4<f32>(color, alfa));
 }
 
 // =============================================================================
@@ -459,30 +685,1043 @@ fn shader_estrella(pos: vec3<f32>, t: f32) -> vec3<f32> {
 
 @fragment
 fn fragment_principal(entrada: SalidaVertice) -> @location(0) vec4<f32> {
-    let pos_normalizada = normalize(entrada.pos_mundo);
-    let normal_normalizada = normalize(entrada.normal);
+    let pos_normalizada = normalize(entrada.pos_cuerpo);
     let t = uniformes.tiempo;
+    let mapa = textureSample(textura_superficie, muestreador_superficie, entrada.uv);
+    let cubico = textureSample(mapa_cubico, muestreador_superficie, pos_normalizada);
+    // Se indexa con la misma dirección que usa el cálculo procedural
+    let horneado = textureSample(superficie_horneada, muestreador_superficie, uv_equirectangular(pos_normalizada));
     
     var color_final: vec3<f32>;
+    // Relativa a la del material; la horneada vuelve con el color
+    var rugosidad = 1.0;
+    let horneado_listo = horneado.a >= 0.5;
     
     // Seleccionar shader según tipo
-    switch uniformes.tipo_shader {
-        case 1u: { color_final = shader_sol(pos_normalizada, t); }
-        case 2u: { color_final = shader_rocoso(pos_normalizada, t); }
-        case 3u: { color_final = shader_gaseoso(pos_normalizada, t); }
-        case 4u: { color_final = shader_anillos(pos_normalizada, t); }
-        case 5u: { color_final = shader_volcanico(pos_normalizada, t); }
-        case 6u: { color_final = shader_luna(pos_normalizada); }
-        case 7u: { color_final = shader_estrella(pos_normalizada, t); }
-        default: { color_final = vec3<f32>(1.0, 0.0, 1.0); }
-    }
-    
-    // Iluminación básica direccional
+    let tipo = select(cuerpo().tipo_shader & MASCARA_TIPO, TIPO_ESPECIALIZADO, TIPO_ESPECIALIZADO != 0u);
+    if (SUPERFICIE_PROPIA) {
+        color_final = superficie_propia(pos_normalizada, entrada.uv, t);
+    } else {
+        switch tipo {
+            case 1u: { color_final = shader_sol(pos_normalizada, t); }
+            case 2u: {
+                var terreno = horneado.rgb;
+                rugosidad = horneado.a * 2.0 - 1.0;
+                if (!horneado_listo) {
+                    terreno = terreno_rocoso(pos_normalizada);
+                    rugosidad = rugosidad_rocosa(pos_normalizada);
+                }
+                color_final = tormentas_polvo(terreno, pos_normalizada, t);
+            }
+            case 3u: { color_final = shader_gaseoso(pos_normalizada, t); }
+            case 4u: { color_final = shader_anillos(pos_normalizada, t); }
+            case 5u: { color_final = shader_volcanico(pos_normalizada, t); }
+            case 6u: {
+                color_final = horneado.rgb;
+                rugosidad = horneado.a * 2.0 - 1.0;
+                if (!horneado_listo) {
+                    color_final = shader_luna(pos_normalizada);
+                    rugosidad = rugosidad_luna(pos_normalizada);
+                }
+            }
+            case 7u: { color_final = shader_laton(entrada.pos_cuerpo); }
+            default: { color_final = vec3<f32>(1.0, 0.0, 1.0); }
+        }
+    }
+    // Un cuerpo tiene a lo sumo uno de los dos mapas; el otro es transparente
+    color_final = mix(mix(color_final, mapa.rgb, mapa.a), cubico.rgb, cubico.a) * cuerpo().tinte;
+
+    let normal = normalize(entrada.normal);
+    let hacia_ojo = normalize(uniformes.ojo - entrada.pos_mundo);
+    let hacia_luz = normalize(uniformes.luz - entrada.pos_mundo);
+    let albedo = color_final * cuerpo().albedo;
+    // Las derivadas van antes de cualquier rama. El valor absoluto no depende de qué cara
+    // de la malla llega al fragmento: en el borde de la silueta vale 0 igual
+    let n_v = abs(dot(normal, hacia_ojo));
+    let pixeles_borde = n_v / max(fwidth(n_v), 1e-5);
+    if ((cuerpo().tipo_shader & BANDERA_CARICATURA) != 0u) {
+        let color = sombreado_caricatura(normal, hacia_ojo, hacia_luz, albedo, cuerpo().rugosidad * rugosidad)
+            + color_final * cuerpo().emisivo;
+        return vec4<f32>(color * smoothstep(ANCHO_CONTORNO, ANCHO_CONTORNO + 1.0, pixeles_borde), 1.0);
+    }
+    let directa = brdf_cook_torrance(normal, hacia_ojo, hacia_luz, albedo, cuerpo().metalico, cuerpo().rugosidad * rugosidad)
+        * uniformes.intensidad_luz * max(dot(normal, hacia_luz), 0.0);
+    let ambiente = albedo * uniformes.ambiente;
+
+    return vec4<f32>(directa + ambiente + color_final * cuerpo().emisivo, 1.0);
+}
+
+// =============================================================================
+// BRDF DE COOK-TORRANCE - GGX, Smith-Schlick y Fresnel de Schlick
+// =============================================================================
+
+const PI: f32 = 3.14159265;
+
+fn distribucion_ggx(n_h: f32, rugosidad: f32) -> f32 {
+    let a2 = pow(rugosidad * rugosidad, 2.0);
+    let d = n_h * n_h * (a2 - 1.0) + 1.0;
+    return a2 / max(PI * d * d, 1e-6);
+}
+
+fn geometria_schlick(n_x: f32, k: f32) -> f32 {
+    return n_x / (n_x * (1.0 - k) + k);
+}
+
+fn fresnel_schlick(coseno: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(1.0 - coseno, 5.0);
+}
+
+// Radiancia reflejada por unidad de irradiancia; los dieléctricos reflejan un 4 % en incidencia normal
+fn brdf_cook_torrance(n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, albedo: vec3<f32>, metalico: f32, rugosidad: f32) -> vec3<f32> {
+    let h = normalize(v + l);
+    let n_v = max(dot(n, v), 1e-4);
+    let n_l = max(dot(n, l), 1e-4);
+    let n_h = max(dot(n, h), 0.0);
+    let r = max(rugosidad, 0.04);
+    let k = (r + 1.0) * (r + 1.0) / 8.0;
+
+    let f0 = mix(vec3<f32>(0.04), albedo, metalico);
+    let f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    let especular = distribucion_ggx(n_h, r) * geometria_schlick(n_v, k) * geometria_schlick(n_l, k) * f
+        / (4.0 * n_v * n_l);
+    let difusa = (1.0 - f) * (1.0 - metalico) * albedo / PI;
+    return difusa + especular;
+}
+
+// =============================================================================
+// CARICATURA - Luz en bandas, brillo de borde duro y contorno de ancho fijo en pantalla
+// =============================================================================
+
+// `tipo_shader` lleva el tipo en los bits bajos y la bandera en el 8
+const MASCARA_TIPO: u32 = 0xffu;
+const BANDERA_CARICATURA: u32 = 256u;
+const BANDAS_CARICATURA: f32 = 4.0;
+// Contorno en píxeles: donde la normal se aleja del ojo más rápido que eso por píxel
+const ANCHO_CONTORNO: f32 = 1.5;
+
+fn sombreado_caricatura(n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, albedo: vec3<f32>, rugosidad: f32) -> vec3<f32> {
+    let n_l = max(dot(n, l), 0.0);
+    // Escalones con un borde de un píxel para que no dienten
+    let ancho = max(fwidth(n_l * BANDAS_CARICATURA), 1e-4);
+    let escalon = floor(n_l * BANDAS_CARICATURA);
+    let fraccion = smoothstep(1.0 - ancho, 1.0, fract(n_l * BANDAS_CARICATURA));
+    let banda = (escalon + fraccion) / BANDAS_CARICATURA;
+    let brillo = step(0.985 - 0.1 * rugosidad, max(dot(n, normalize(v + l)), 0.0)) * (1.0 - rugosidad) * step(1e-4, n_l);
+    // Misma difusa media que la BRDF: albedo / π por la irradiancia
+    return albedo * (banda * uniformes.intensidad_luz / PI + uniformes.ambiente) + vec3<f32>(brillo * uniformes.intensidad_luz * 0.1);
+}
+
+// =============================================================================
+// ATMÓSFERA - Dispersión simple de Rayleigh y Mie en una cáscara algo mayor que el cuerpo
+// =============================================================================
+
+// Alturas de escala como fracción del espesor: el aire llega más alto que la bruma
+const ALTURA_RAYLEIGH: f32 = 0.25;
+const ALTURA_MIE: f32 = 0.1;
+const MUESTRAS_VISTA: i32 = 12;
+const MUESTRAS_LUZ: i32 = 4;
+
+fn transformar_cascara(entrada: EntradaVertice) -> SalidaVertice {
+    var cascara = entrada;
+    cascara.posicion *= 1.0 + cuerpo().espesor_atmosfera;
+    return transformar_vertice(cascara);
+}
+
+@vertex
+fn vertex_atmosfera(entrada: EntradaVertice) -> SalidaVertice {
+    return transformar_cascara(entrada);
+}
+
+@vertex
+fn vertex_atmosfera_compacto(entrada: EntradaVerticeCompacta) -> SalidaVertice {
+    return transformar_cascara(descomprimir_vertice(entrada));
+}
+
+// Distancias de entrada y salida de un rayo de dirección unitaria; entrada > salida si no corta
+fn cortar_esfera(origen: vec3<f32>, direccion: vec3<f32>, centro: vec3<f32>, radio: f32) -> vec2<f32> {
+    let oc = origen - centro;
+    let b = dot(oc, direccion);
+    let h = b * b - dot(oc, oc) + radio * radio;
+    if (h < 0.0) {
+        return vec2<f32>(1.0, -1.0);
+    }
+    let raiz = sqrt(h);
+    return vec2<f32>(-b - raiz, -b + raiz);
+}
+
+fn tapado_por_cuerpo(origen: vec3<f32>, direccion: vec3<f32>, centro: vec3<f32>) -> bool {
+    let corte = cortar_esfera(origen, direccion, centro, cuerpo().radio_cuerpo);
+    return corte.x < corte.y && corte.x > 0.0;
+}
+
+// Densidad relativa de Rayleigh (x) y de Mie (y)
+fn densidad_atmosfera(p: vec3<f32>, centro: vec3<f32>) -> vec2<f32> {
+    let altura = max(length(p - centro) - cuerpo().radio_cuerpo, 0.0)
+        / (cuerpo().radio_cuerpo * cuerpo().espesor_atmosfera);
+    return exp(-altura / vec2<f32>(ALTURA_RAYLEIGH, ALTURA_MIE));
+}
+
+fn profundidad_hacia_luz(p: vec3<f32>, hacia_luz: vec3<f32>, centro: vec3<f32>, radio: f32) -> vec2<f32> {
+    let paso = cortar_esfera(p, hacia_luz, centro, radio).y / f32(MUESTRAS_LUZ);
+    var profundidad = vec2<f32>(0.0);
+    for (var i = 0; i < MUESTRAS_LUZ; i++) {
+        profundidad += densidad_atmosfera(p + hacia_luz * (f32(i) + 0.5) * paso, centro) * paso;
+    }
+    return profundidad;
+}
+
+// Se dibujan las caras traseras de la cáscara para que cada píxel cubierto reciba un solo
+// fragmento aunque la cámara esté dentro de la atmósfera; el rayo se integra analíticamente
+@fragment
+fn fragment_atmosfera(entrada: SalidaVertice) -> @location(0) vec4<f32> {
+    let centro = cuerpo().matriz_modelo[3].xyz;
+    let radio = cuerpo().radio_cuerpo * (1.0 + cuerpo().espesor_atmosfera);
+    let ojo = uniformes.ojo;
+    let direccion = normalize(entrada.pos_mundo - ojo);
+
+    let cascara = cortar_esfera(ojo, direccion, centro, radio);
+    let inicio = max(cascara.x, 0.0);
+    var fin = cascara.y;
+    if (tapado_por_cuerpo(ojo, direccion, centro)) {
+        fin = cortar_esfera(ojo, direccion, centro, cuerpo().radio_cuerpo).x;
+    }
+    if (fin <= inicio) {
+        return vec4<f32>(0.0);
+    }
+
+    // Los coeficientes vienen por radio del cuerpo; las distancias están en unidades de escena
+    let beta_rayleigh = cuerpo().rayleigh / cuerpo().radio_cuerpo;
+    let beta_mie = cuerpo().mie / cuerpo().radio_cuerpo;
+    let paso = (fin - inicio) / f32(MUESTRAS_VISTA);
+    var vista = vec2<f32>(0.0);
+    var suma_rayleigh = vec3<f32>(0.0);
+    var suma_mie = vec3<f32>(0.0);
+    for (var i = 0; i < MUESTRAS_VISTA; i++) {
+        let p = ojo + direccion * (inicio + (f32(i) + 0.5) * paso);
+        let densidad = densidad_atmosfera(p, centro) * paso;
+        vista += densidad;
+        let hacia_luz = normalize(uniformes.luz - p);
+        // En la sombra del cuerpo no llega luz directa
+        if (tapado_por_cuerpo(p, hacia_luz, centro)) {
+            continue;
+        }
+        let total = vista + profundidad_hacia_luz(p, hacia_luz, centro, radio);
+        let atenuacion = exp(-(beta_rayleigh * total.x + beta_mie * 1.1 * total.y));
+        suma_rayleigh += densidad.x * atenuacion;
+        suma_mie += densidad.y * atenuacion;
+    }
+
+    let coseno = dot(direccion, normalize(uniformes.luz - centro));
+    let fase_rayleigh = 3.0 / (16.0 * PI) * (1.0 + coseno * coseno);
+    let g = cuerpo().anisotropia_mie;
+    let fase_mie = (1.0 - g * g) / (4.0 * PI * pow(1.0 + g * g - 2.0 * g * coseno, 1.5));
+    let color = suma_rayleigh * beta_rayleigh * fase_rayleigh + suma_mie * beta_mie * fase_mie;
+    return vec4<f32>(color * uniformes.intensidad_luz * cuerpo().intensidad_atmosfera, 0.0);
+}
+
+// Material provisional mientras se compila el pipeline del cuerpo: gris con la misma luz
+@fragment
+fn fragment_provisional(entrada: SalidaVertice) -> @location(0) vec4<f32> {
     let direccion_luz = normalize(vec3<f32>(1.0, 0.5, 0.8));
-    let difusa = max(dot(normal_normalizada, direccion_luz), 0.15);
+    let difusa = max(dot(normalize(entrada.normal), direccion_luz), 0.15);
+    return vec4<f32>(vec3<f32>(0.55) * cuerpo().tinte * mix(1.0, difusa, 0.7), 1.0);
+}
+
+// =============================================================================
+// ANILLOS - Corona plana translúcida en el ecuador del cuerpo (uv.y = radio)
+// =============================================================================
+
+// Densidad de cada anillo, en radios del planeta: C, B, división de Cassini y A con la de Encke
+fn opacidad_anillo(radio: f32) -> f32 {
+    if (radio < 1.53) {
+        return 0.25;
+    } else if (radio < 1.95) {
+        return 0.9;
+    } else if (radio < 2.03) {
+        return 0.08;
+    } else if (radio > 2.2 && radio < 2.22) {
+        return 0.05;
+    }
+    return 0.65;
+}
+
+@fragment
+fn fragment_anillo(entrada: SalidaVertice) -> @location(0) vec4<f32> {
+    let radio = entrada.uv.y;
+    let t = uniformes.tiempo;
+
+    var opacidad = opacidad_anillo(radio);
+
+    let bandas_anillo = sin(radio * 150.0) * 0.5 + 0.5;
+    let var_brillo = sin(radio * 30.0 + t * 3.0) * 0.5 + 0.5;
+    let textura_anillos = fbm_ruido(vec3<f32>(radio * 20.0, 0.0, t * 0.5), 3);
+    let bandas_ajustadas = bandas_anillo * 0.7 + textura_anillos * 0.3;
+
+    var color_anillo: vec3<f32>;
+    if (bandas_ajustadas > 0.7) {
+        color_anillo = vec3<f32>(1.0, 0.9, 0.6);  // Dorado claro
+    } else if (bandas_ajustadas > 0.4) {
+        color_anillo = vec3<f32>(0.95, 0.7, 0.8); // Rosa dorado
+    } else {
+        color_anillo = vec3<f32>(0.8, 0.5, 0.6);  // Rosa oscuro
+    }
+    color_anillo *= 0.9 + var_brillo * 0.2;
+    opacidad *= 0.8 + bandas_anillo * 0.2;
+
+    // Iluminado por ambas caras
+    let direccion_luz = normalize(vec3<f32>(1.0, 0.5, 0.8));
+    let difusa = max(abs(dot(normalize(entrada.normal), direccion_luz)), 0.3);
+
+    return vec4<f32>(color_anillo * cuerpo().tinte * mix(1.0, difusa, 0.5), opacidad);
+}
+
+// Escombros del anillo: cada instancia sale de su índice (hash) y da la vuelta con la velocidad
+// de Kepler de su radio (ω ∝ r^-1.5), así que los de adentro adelantan a los de afuera
+const PERIODO_ESCOMBROS: f32 = 20.0;  // Segundos por vuelta en el borde interno
+
+struct SalidaEscombro {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) luz: vec3<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+fn azar_escombro(indice: u32, canal: u32) -> f32 {
+    return f32(mezclar_bits(indice * 8u + canal)) / 4294967296.0;
+}
+
+// Como el anillo, cada mitad se dibuja de su lado del cuerpo: la otra sale del volumen de recorte
+fn escombro_anillo(indice_vertice: u32, indice: u32, lejanos: bool) -> SalidaEscombro {
+    var salida: SalidaEscombro;
+    let datos = cuerpo();
+    let interno = datos.anillo_interno;
+    let r2 = mix(interno * interno, datos.anillo_externo * datos.anillo_externo, azar_escombro(indice, 0u));
+    let radio = sqrt(r2);
+    let periodo = PERIODO_ESCOMBROS * pow(radio / interno, 1.5);
+    let angulo = (azar_escombro(indice, 1u) + uniformes.tiempo / periodo) * 6.2831853;
+    let local = vec3<f32>(radio * cos(angulo), (azar_escombro(indice, 2u) - 0.5) * 0.01, radio * sin(angulo));
+    let mundo = (datos.matriz_modelo * vec4<f32>(local, 1.0)).xyz;
+    let centro = datos.matriz_modelo[3].xyz;
+    if ((dot(mundo - centro, uniformes.ojo - centro) < 0.0) != lejanos) {
+        salida.posicion_clip = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+        return salida;
+    }
+
+    let esquina = esquina_quad(indice_vertice);
+    var en_vista = uniformes.matriz_vista * vec4<f32>(mundo, 1.0);
+    salida.luz = normalize((uniformes.matriz_vista * vec4<f32>(uniformes.luz, 1.0)).xyz - en_vista.xyz);
+    let variacion = azar_escombro(indice, 3u);
+    let tamano = datos.radio_cuerpo * 0.008 * mix(0.4, 1.0, variacion * variacion);
+    en_vista = vec4<f32>(en_vista.xy + esquina * tamano, en_vista.zw);
+    salida.posicion_clip = uniformes.matriz_proyeccion * en_vista;
+    salida.local = esquina;
+    // Tono del anillo; en las divisiones casi no quedan escombros
+    let tono = mix(vec3<f32>(0.95, 0.7, 0.8), vec3<f32>(1.0, 0.9, 0.6), azar_escombro(indice, 4u));
+    salida.color = vec4<f32>(tono * datos.tinte, min(opacidad_anillo(radio) * 1.1, 1.0));
+    return salida;
+}
+
+@vertex
+fn vertex_escombro_lejano(@builtin(vertex_index) indice_vertice: u32, @builtin(instance_index) indice: u32) -> SalidaEscombro {
+    return escombro_anillo(indice_vertice, indice, true);
+}
+
+@vertex
+fn vertex_escombro_cercano(@builtin(vertex_index) indice_vertice: u32, @builtin(instance_index) indice: u32) -> SalidaEscombro {
+    return escombro_anillo(indice_vertice, indice, false);
+}
+
+@fragment
+fn fragment_escombro(entrada: SalidaEscombro) -> @location(0) vec4<f32> {
+    let r2 = dot(entrada.local, entrada.local);
+    if (r2 > 1.0) {
+        discard;
+    }
+    let normal = vec3<f32>(entrada.local, sqrt(1.0 - r2));
+    let difusa = max(dot(normal, normalize(entrada.luz)), 0.0);
+    return vec4<f32>(entrada.color.rgb * (0.15 + 0.85 * difusa), entrada.color.a);
+}
+
+// =============================================================================
+// SHADERS DE LÍNEAS - Superposiciones en coordenadas de pantalla
+// =============================================================================
+
+struct EntradaLinea {
+    @location(0) posicion: vec2<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+struct SalidaLinea {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vertex_linea(entrada: EntradaLinea) -> SalidaLinea {
+    var salida: SalidaLinea;
+    salida.posicion_clip = vec4<f32>(entrada.posicion, 0.0, 1.0);
+    salida.color = entrada.color;
+    return salida;
+}
+
+@fragment
+fn fragment_linea(entrada: SalidaLinea) -> @location(0) vec4<f32> {
+    return vec4<f32>(entrada.color, 1.0);
+}
+
+// =============================================================================
+// CIELO - Cubemap de estrellas en un triángulo de pantalla completa
+// =============================================================================
+
+@group(1) @binding(0)
+var cielo_estrellado: texture_cube<f32>;
+@group(1) @binding(1)
+var muestreador_cielo: sampler;
+
+struct SalidaCielo {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vertex_cielo(@builtin(vertex_index) indice: u32) -> SalidaCielo {
+    var salida: SalidaCielo;
+    // Triángulo que cubre la pantalla: (-1,-1), (3,-1), (-1,3)
+    let ndc = vec2<f32>(f32((indice << 1u) & 2u), f32(indice & 2u)) * 2.0 - 1.0;
+    salida.posicion_clip = vec4<f32>(ndc, 0.0, 1.0);
+    salida.ndc = ndc;
+    return salida;
+}
+
+@fragment
+fn fragment_cielo(entrada: SalidaCielo) -> @location(0) vec4<f32> {
+    // Dirección del píxel en el espacio de la vista, rotada al mundo (sin la traslación
+    // de la cámara, para que las estrellas queden en el infinito)
+    let proyeccion = uniformes.matriz_proyeccion;
+    let en_vista = vec3<f32>(entrada.ndc.x / proyeccion[0][0], entrada.ndc.y / proyeccion[1][1], -1.0);
+    let vista = uniformes.matriz_vista;
+    let rotacion = mat3x3<f32>(vista[0].xyz, vista[1].xyz, vista[2].xyz);
+    let direccion = normalize(transpose(rotacion) * en_vista);
+
+    let estrellas = textureSample(cielo_estrellado, muestreador_cielo, direccion).rgb;
+    let parpadeo = ruido_suave(direccion * 300.0 + vec3<f32>(uniformes.tiempo * 3.0));
+    let fondo = vec3<f32>(0.05, 0.08, 0.15);
+    return vec4<f32>(fondo + estrellas * (0.7 + parpadeo * 0.3), 1.0);
+}
+
+/// Esquinas de un quad formado por dos triángulos
+fn esquina_quad(indice: u32) -> vec2<f32> {
+    var esquinas = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0)
+    );
+    return esquinas[indice];
+}
+
+// =============================================================================
+// GALAXIAS DE FONDO - Billboards procedurales (espirales y elípticas)
+// =============================================================================
+
+struct EntradaGalaxia {
+    @builtin(vertex_index) indice_vertice: u32,
+    @location(0) posicion: vec2<f32>,
+    @location(1) tamano: f32,
+    @location(2) rotacion: f32,
+    @location(3) aplanamiento: f32,
+    @location(4) brillo: f32,
+    @location(5) semilla: f32,
+    @location(6) tipo: u32,
+}
+
+struct SalidaGalaxia {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) brillo: f32,
+    @location(2) semilla: f32,
+    @location(3) @interpolate(flat) tipo: u32,
+}
+
+@vertex
+fn vertex_galaxia(entrada: EntradaGalaxia) -> SalidaGalaxia {
+    var salida: SalidaGalaxia;
+    let esquina = esquina_quad(entrada.indice_vertice);
+    
+    // Inclinar (achatar) y rotar el plano de la galaxia
+    let achatada = vec2<f32>(esquina.x, esquina.y * entrada.aplanamiento);
+    let c = cos(entrada.rotacion);
+    let s = sin(entrada.rotacion);
+    let rotada = vec2<f32>(achatada.x * c - achatada.y * s, achatada.x * s + achatada.y * c);
     
-    // Auto-emisión para sol y lava
-    let emision = select(1.0, difusa, uniformes.tipo_shader != 1u && uniformes.tipo_shader != 5u);
+    let aspecto = uniformes.resolucion.y / max(uniformes.resolucion.x, 1.0);
+    let desplazamiento = rotada * entrada.tamano * vec2<f32>(aspecto, 1.0);
+    salida.posicion_clip = vec4<f32>(entrada.posicion + desplazamiento, 0.0, 1.0);
+    salida.local = esquina;
+    salida.brillo = entrada.brillo;
+    salida.semilla = entrada.semilla;
+    salida.tipo = entrada.tipo;
+    return salida;
+}
+
+@fragment
+fn fragment_galaxia(entrada: SalidaGalaxia) -> @location(0) vec4<f32> {
+    let radio = length(entrada.local);
+    let borde = 1.0 - smoothstep(0.7, 1.0, radio);
+    let nucleo = exp(-radio * radio * 40.0);
+    
+    var intensidad: f32;
+    var color: vec3<f32>;
+    if (entrada.tipo == 0u) {
+        // Espiral logarítmica de dos brazos con grumos de ruido
+        let angulo = atan2(entrada.local.y, entrada.local.x);
+        let brazos = cos(2.0 * angulo - log(max(radio, 0.02)) * 4.0 + entrada.semilla) * 0.5 + 0.5;
+        let grumos = ruido_suave(vec3<f32>(entrada.local * 6.0, entrada.semilla));
+        let disco = exp(-radio * 3.5) * pow(brazos, 2.0) * (0.6 + grumos * 0.4);
+        intensidad = nucleo + disco * 0.8;
+        color = mix(vec3<f32>(0.6, 0.7, 1.0), vec3<f32>(1.0, 0.9, 0.7), nucleo);
+    } else {
+        // Perfil elíptico suave y cálido
+        intensidad = exp(-radio * 4.5) + nucleo * 0.5;
+        color = vec3<f32>(1.0, 0.85, 0.65);
+    }
     
-    return vec4<f32>(color_final * mix(1.0, difusa, 0.7), 1.0);
+    return vec4<f32>(color * intensidad * borde * entrada.brillo, 1.0);
+}
+
+// =============================================================================
+// PARTÍCULAS DE IMPACTO - Simuladas en compute y dibujadas como billboards
+// =============================================================================
+
+struct Particula {
+    posicion: vec3<f32>,
+    vida: f32,
+    velocidad: vec3<f32>,
+    duracion: f32,
+    atractor: vec3<f32>,
+    gravedad: f32,
+    color: vec3<f32>,
+    tamano: f32,
+}
+
+struct ParametrosParticulas {
+    paso: f32,
+    cantidad: u32,
+}
+
+@group(0) @binding(1)
+var<storage, read_write> particulas: array<Particula>;
+@group(0) @binding(2)
+var<uniform> parametros_particulas: ParametrosParticulas;
+
+@compute @workgroup_size(64)
+fn simular_particulas(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= parametros_particulas.cantidad) {
+        return;
+    }
+    var particula = particulas[id.x];
+    if (particula.vida <= 0.0) {
+        return;
+    }
+    let paso = parametros_particulas.paso;
+    // Gravedad constante hacia el centro del cuerpo impactado
+    let hacia_centro = particula.atractor - particula.posicion;
+    let distancia = max(length(hacia_centro), 1e-4);
+    particula.velocidad += hacia_centro / distancia * particula.gravedad * paso;
+    particula.posicion += particula.velocidad * paso;
+    particula.vida -= paso;
+    particulas[id.x] = particula;
+}
+
+// Orden bitónico en la GPU: cada despacho compara pares a distancia `j` dentro de
+// secuencias de largo `k`; la cantidad de claves es una potencia de dos
+struct ClaveOrden {
+    clave: f32,
+    indice: u32,
+}
+
+struct PasoBitonico {
+    j: u32,
+    k: u32,
+}
+
+@group(0) @binding(3)
+var<storage, read_write> claves_orden: array<ClaveOrden>;
+@group(0) @binding(6)
+var<uniform> paso_bitonico: PasoBitonico;
+
+@compute @workgroup_size(64)
+fn ordenar_bitonico(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    let companero = i ^ paso_bitonico.j;
+    if (companero <= i || companero >= arrayLength(&claves_orden)) {
+        return;
+    }
+    let a = claves_orden[i];
+    let b = claves_orden[companero];
+    let ascendente = (i & paso_bitonico.k) == 0u;
+    if ((a.clave > b.clave) == ascendente) {
+        claves_orden[i] = b;
+        claves_orden[companero] = a;
+    }
+}
+
+struct ParametrosOrden {
+    ojo: vec3<f32>,
+    cantidad: u32,
+    adelante: vec3<f32>,
+}
+
+@group(0) @binding(4)
+var<storage, read_write> particulas_ordenadas: array<Particula>;
+@group(0) @binding(5)
+var<uniform> parametros_orden: ParametrosOrden;
+
+// Profundidad en la vista negada, para que el orden ascendente deje las lejanas primero;
+// las ranuras libres van al final
+@compute @workgroup_size(64)
+fn claves_particulas(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= parametros_orden.cantidad) {
+        return;
+    }
+    let particula = particulas[id.x];
+    var clave = -dot(particula.posicion - parametros_orden.ojo, parametros_orden.adelante);
+    if (particula.vida <= 0.0) {
+        clave = 3.4e38;
+    }
+    claves_orden[id.x] = ClaveOrden(clave, id.x);
+}
+
+@compute @workgroup_size(64)
+fn reunir_particulas(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= parametros_orden.cantidad) {
+        return;
+    }
+    particulas_ordenadas[id.x] = particulas[claves_orden[id.x].indice];
+}
+
+struct EntradaParticula {
+    @builtin(vertex_index) indice_vertice: u32,
+    @location(0) posicion: vec3<f32>,
+    @location(1) vida: f32,
+    @location(2) velocidad_duracion: vec4<f32>,
+    @location(3) atractor_gravedad: vec4<f32>,
+    @location(4) color_tamano: vec4<f32>,
+}
+
+struct SalidaParticula {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) opacidad: f32,
+}
+
+@vertex
+fn vertex_particula(entrada: EntradaParticula) -> SalidaParticula {
+    var salida: SalidaParticula;
+    if (entrada.vida <= 0.0) {
+        // Ranura libre: triángulo degenerado fuera del volumen visible
+        salida.posicion_clip = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        return salida;
+    }
+    let esquina = esquina_quad(entrada.indice_vertice);
+    // Billboard: el quad se abre en el espacio de la vista
+    var en_vista = uniformes.matriz_vista * vec4<f32>(entrada.posicion, 1.0);
+    en_vista = vec4<f32>(en_vista.xy + esquina * entrada.color_tamano.w, en_vista.zw);
+    salida.posicion_clip = uniformes.matriz_proyeccion * en_vista;
+    salida.local = esquina;
+
+    // Caliente brilla sin tapar (alfa 0, suma); al enfriarse queda polvo gris que tapa lo
+    // de atrás, por eso se dibujan ordenadas de atrás hacia adelante
+    let vida = clamp(entrada.vida / max(entrada.velocidad_duracion.w, 1e-4), 0.0, 1.0);
+    let calor = smoothstep(0.35, 0.8, vida);
+    let desvanecer = smoothstep(0.0, 0.2, vida);
+    salida.opacidad = (1.0 - calor) * 0.7 * desvanecer;
+    let polvo = vec3<f32>(0.36, 0.33, 0.3) * salida.opacidad;
+    salida.color = mix(polvo, entrada.color_tamano.xyz, calor) * desvanecer;
+    return salida;
+}
+
+// Alfa premultiplicado: rgb ya viene multiplicado por su cobertura
+@fragment
+fn fragment_particula(entrada: SalidaParticula) -> @location(0) vec4<f32> {
+    let intensidad = pow(max(1.0 - length(entrada.local), 0.0), 2.0);
+    return vec4<f32>(entrada.color, entrada.opacidad) * intensidad;
+}
+
+// Partículas simuladas en la CPU (`SistemaParticulas`): el color ya llega con alfa
+// premultiplicado y hecho según la edad
+struct EntradaBillboard {
+    @builtin(vertex_index) indice_vertice: u32,
+    @location(0) posicion: vec3<f32>,
+    @location(1) tamano: f32,
+    @location(2) color: vec4<f32>,
+}
+
+@vertex
+fn vertex_billboard(entrada: EntradaBillboard) -> SalidaParticula {
+    var salida: SalidaParticula;
+    let esquina = esquina_quad(entrada.indice_vertice);
+    var en_vista = uniformes.matriz_vista * vec4<f32>(entrada.posicion, 1.0);
+    en_vista = vec4<f32>(en_vista.xy + esquina * entrada.tamano, en_vista.zw);
+    salida.posicion_clip = uniformes.matriz_proyeccion * en_vista;
+    salida.local = esquina;
+    salida.color = entrada.color.rgb;
+    salida.opacidad = entrada.color.a;
+    return salida;
+}
+
+// =============================================================================
+// TEXTO - Quads con glifos muestreados de un atlas de un canal
+// =============================================================================
+
+@group(1) @binding(0)
+var atlas_glifos: texture_2d<f32>;
+@group(1) @binding(1)
+var muestreador_glifos: sampler;
+
+struct EntradaTexto {
+    @location(0) posicion: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct SalidaTexto {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vertex_texto(entrada: EntradaTexto) -> SalidaTexto {
+    var salida: SalidaTexto;
+    salida.posicion_clip = vec4<f32>(entrada.posicion, 0.0, 1.0);
+    salida.uv = entrada.uv;
+    salida.color = entrada.color;
+    return salida;
+}
+
+@fragment
+fn fragment_texto(entrada: SalidaTexto) -> @location(0) vec4<f32> {
+    let cobertura = textureSample(atlas_glifos, muestreador_glifos, entrada.uv).r;
+    return vec4<f32>(entrada.color.rgb, entrada.color.a * cobertura);
+}
+
+// =============================================================================
+// TELESCOPIO - Recuadro circular con la vista aumentada (reutiliza vertex_texto)
+// =============================================================================
+
+@group(1) @binding(0)
+var imagen_telescopio: texture_2d<f32>;
+@group(1) @binding(1)
+var muestreador_telescopio: sampler;
+
+@fragment
+fn fragment_telescopio(entrada: SalidaTexto) -> @location(0) vec4<f32> {
+    let imagen = textureSample(imagen_telescopio, muestreador_telescopio, entrada.uv).rgb;
+    let radio = length(entrada.uv * 2.0 - 1.0);
+    // Borde del ocular con un leve viñeteado hacia adentro
+    let borde = smoothstep(0.94, 0.96, radio) * (1.0 - smoothstep(0.99, 1.0, radio));
+    let vineteado = 1.0 - smoothstep(0.6, 0.95, radio) * 0.4;
+    let color = mix(imagen * vineteado, vec3<f32>(0.75, 0.8, 0.85), borde);
+    let alfa = 1.0 - smoothstep(0.99, 1.0, radio);
+    return vec4<f32>(color, alfa);
+}
+
+// =============================================================================
+// POSPROCESO - La escena se dibuja en una textura intermedia (más chica con el pixelado)
+// y un triángulo de pantalla completa la pasa a la ventana con el filtro elegido
+// =============================================================================
+
+struct ParametrosPosproceso {
+    // 1 = reducir a PALETA_RETRO con tramado ordenado
+    paleta: u32,
+    // 1 si el destino es sRGB: los colores llegan en lineal y la paleta está en sRGB
+    srgb: u32,
+}
+
+@group(1) @binding(0)
+var imagen_escena: texture_2d<f32>;
+@group(1) @binding(1)
+var muestreador_escena: sampler;
+@group(1) @binding(2)
+var<uniform> posproceso: ParametrosPosproceso;
+
+struct SalidaPosproceso {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vertex_posproceso(@builtin(vertex_index) indice: u32) -> SalidaPosproceso {
+    var salida: SalidaPosproceso;
+    let uv = vec2<f32>(f32((indice << 1u) & 2u), f32(indice & 2u));
+    salida.posicion_clip = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    salida.uv = uv;
+    return salida;
+}
+
+// 16 colores de una consola de fantasía, en sRGB
+const PALETA_RETRO = array<vec3<f32>, 16>(
+    vec3<f32>(0.000, 0.000, 0.000), vec3<f32>(0.114, 0.169, 0.325),
+    vec3<f32>(0.494, 0.145, 0.325), vec3<f32>(0.000, 0.529, 0.318),
+    vec3<f32>(0.671, 0.322, 0.212), vec3<f32>(0.373, 0.341, 0.310),
+    vec3<f32>(0.761, 0.765, 0.780), vec3<f32>(1.000, 0.945, 0.910),
+    vec3<f32>(1.000, 0.000, 0.302), vec3<f32>(1.000, 0.639, 0.000),
+    vec3<f32>(1.000, 0.925, 0.153), vec3<f32>(0.000, 0.894, 0.212),
+    vec3<f32>(0.161, 0.678, 1.000), vec3<f32>(0.514, 0.463, 0.612),
+    vec3<f32>(1.000, 0.467, 0.659), vec3<f32>(1.000, 0.800, 0.667),
+);
+
+// Umbrales de Bayer 4×4 (0 a 15) del tramado ordenado
+const BAYER_4X4 = array<f32, 16>(0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0);
+const AMPLITUD_TRAMADO: f32 = 0.25;
+
+/// Con la paleta activa, el color de la paleta más cercano al del píxel de la escena
+/// `pixel`, desplazado por el umbral de Bayer de ese píxel para que los degradados se tramen
+fn paletizar(color: vec3<f32>, pixel: vec2<u32>) -> vec3<f32> {
+    if (posproceso.paleta == 0u) {
+        return color;
+    }
+    let en_srgb = select(color, pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / 2.2)), posproceso.srgb != 0u);
+    // Las constantes no se pueden indexar con un valor dinámico: se copian a variables
+    var bayer = BAYER_4X4;
+    var paleta = PALETA_RETRO;
+    let umbral = bayer[(pixel.y % 4u) * 4u + pixel.x % 4u] / 16.0 - 0.5;
+    let buscado = en_srgb + vec3<f32>(umbral * AMPLITUD_TRAMADO);
+    var elegido = paleta[0];
+    var distancia = 1e9;
+    for (var i = 0; i < 16; i++) {
+        let diferencia = paleta[i] - buscado;
+        let d = dot(diferencia, diferencia);
+        if (d < distancia) {
+            distancia = d;
+            elegido = paleta[i];
+        }
+    }
+    return select(elegido, pow(elegido, vec3<f32>(2.2)), posproceso.srgb != 0u);
+}
+
+/// Píxel de la textura intermedia bajo `uv`
+fn pixel_escena(uv: vec2<f32>) -> vec2<u32> {
+    let tamano = textureDimensions(imagen_escena);
+    return min(vec2<u32>(uv * vec2<f32>(tamano)), tamano - 1u);
+}
+
+@fragment
+fn fragment_copia(entrada: SalidaPosproceso) -> @location(0) vec4<f32> {
+    let color = textureSample(imagen_escena, muestreador_escena, entrada.uv).rgb;
+    return vec4<f32>(paletizar(color, pixel_escena(entrada.uv)), 1.0);
+}
+
+// Curvatura del tubo, oscurecimiento de las líneas de barrido y separación de los
+// canales en el borde (en píxeles)
+const CURVATURA_CRT: f32 = 0.12;
+const LINEAS_CRT: f32 = 0.35;
+const ABERRACION_CRT: f32 = 2.5;
+
+@fragment
+fn fragment_crt(entrada: SalidaPosproceso) -> @location(0) vec4<f32> {
+    // Distorsión de barril: los puntos se alejan del centro con el cuadrado de su distancia
+    let centrado = entrada.uv * 2.0 - 1.0;
+    let curvado = centrado * (1.0 + CURVATURA_CRT * dot(centrado, centrado));
+    let uv = curvado * 0.5 + 0.5;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    // Aberración cromática: rojo y azul se corren en sentidos opuestos hacia el borde
+    let corrimiento = curvado * ABERRACION_CRT / uniformes.resolucion;
+    let rojo = textureSample(imagen_escena, muestreador_escena, uv + corrimiento).r;
+    let verde = textureSample(imagen_escena, muestreador_escena, uv).g;
+    let azul = textureSample(imagen_escena, muestreador_escena, uv - corrimiento).b;
+    var color = paletizar(vec3<f32>(rojo, verde, azul), pixel_escena(uv));
+
+    // Una línea de barrido cada dos píxeles, con un leve parpadeo que baja por la pantalla
+    let fila = uv.y * uniformes.resolucion.y;
+    let barrido = 0.5 + 0.5 * cos(fila * PI);
+    color *= 1.0 - LINEAS_CRT * barrido;
+    color *= 0.97 + 0.03 * sin(uv.y * 8.0 - uniformes.tiempo * 6.0);
+
+    // Viñeteado y bordes redondeados del tubo
+    let borde = uv * (1.0 - uv);
+    let vineteado = pow(borde.x * borde.y * 16.0, 0.25);
+    let esquinas = smoothstep(0.0, 0.01, min(borde.x, borde.y));
+    return vec4<f32>(color * vineteado * esquinas * 1.15, 1.0);
+}
+
+// =============================================================================
+// MIPMAPS - Cada nivel se filtra del anterior con un triángulo de pantalla completa
+// =============================================================================
+
+@group(0) @binding(0)
+var mip_anterior: texture_2d<f32>;
+@group(0) @binding(1)
+var muestreador_mip: sampler;
+
+struct SalidaMipmap {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vertex_mipmap(@builtin(vertex_index) indice: u32) -> SalidaMipmap {
+    var salida: SalidaMipmap;
+    let uv = vec2<f32>(f32((indice << 1u) & 2u), f32(indice & 2u));
+    salida.posicion_clip = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    salida.uv = uv;
+    return salida;
+}
+
+@fragment
+fn fragment_mipmap(entrada: SalidaMipmap) -> @location(0) vec4<f32> {
+    // Con filtrado lineal, el centro de cada píxel promedia su bloque de 2x2 del nivel anterior;
+    // en formatos sRGB el promedio ocurre en lineal
+    return textureSample(mip_anterior, muestreador_mip, entrada.uv);
+}
+
+// =============================================================================
+// CINTURÓN DE ASTEROIDES - Cada asteroide sale de su índice (hash), así que la CPU
+// nunca los recorre: un compute recorta por frustum y por los cuerpos que los tapan
+// y deja los índices visibles y los argumentos del dibujo indirecto
+// =============================================================================
+
+struct Cinturon {
+    radio_interno: f32,
+    radio_externo: f32,
+    espesor: f32,
+    // Segundos por vuelta en el borde interno
+    periodo: f32,
+    tamano: f32,
+    semilla: u32,
+    cantidad: u32,
+    _relleno: u32,
+    // Origen de la escena en el marco de referencia actual
+    centro: vec2<f32>,
+    _relleno_centro: vec2<f32>,
+}
+
+struct ParametrosCulling {
+    vista_proyeccion: mat4x4<f32>,
+    ojo: vec3<f32>,
+    tiempo: f32,
+    cinturon: Cinturon,
+    // Radio en el espacio de la vista -> clip (la mayor escala de la proyección)
+    margen: f32,
+    cantidad_oclusores: u32,
+    // xyz = centro, w = radio de los cuerpos dibujados, que tapan al cinturón
+    oclusores: array<vec4<f32>, 8>,
+}
+
+struct ArgumentosDibujo {
+    cantidad_vertices: u32,
+    cantidad_instancias: atomic<u32>,
+    primer_vertice: u32,
+    primera_instancia: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> culling: ParametrosCulling;
+@group(0) @binding(1)
+var<storage, read_write> visibles: array<u32>;
+@group(0) @binding(2)
+var<storage, read_write> argumentos_dibujo: ArgumentosDibujo;
+@group(1) @binding(0)
+var<uniform> cinturon: Cinturon;
+
+struct Asteroide {
+    posicion: vec3<f32>,
+    tamano: f32,
+    tono: f32,
+}
+
+// Hash PCG de 32 bits
+fn mezclar_bits(x: u32) -> u32 {
+    let estado = x * 747796405u + 2891336453u;
+    let palabra = ((estado >> ((estado >> 28u) + 4u)) ^ estado) * 277803737u;
+    return (palabra >> 22u) ^ palabra;
+}
+
+fn azar_asteroide(c: Cinturon, indice: u32, canal: u32) -> f32 {
+    return f32(mezclar_bits(indice * 8u + canal + c.semilla * 2654435769u)) / 4294967296.0;
+}
+
+fn asteroide(c: Cinturon, indice: u32, tiempo: f32) -> Asteroide {
+    // Densidad uniforme por área y periodo de Kepler (T ∝ r^1.5) desde el borde interno
+    let r2 = mix(c.radio_interno * c.radio_interno, c.radio_externo * c.radio_externo, azar_asteroide(c, indice, 0u));
+    let radio = sqrt(r2);
+    let periodo = c.periodo * pow(radio / c.radio_interno, 1.5);
+    let angulo = (azar_asteroide(c, indice, 1u) + tiempo / periodo) * 6.2831853;
+    var a: Asteroide;
+    let altura = (azar_asteroide(c, indice, 2u) - 0.5) * c.espesor;
+    a.posicion = vec3<f32>(c.centro + radio * vec2<f32>(cos(angulo), sin(angulo)), altura);
+    let variacion = azar_asteroide(c, indice, 3u);
+    a.tamano = c.tamano * mix(0.35, 1.0, variacion * variacion);
+    a.tono = azar_asteroide(c, indice, 4u);
+    return a;
+}
+
+@compute @workgroup_size(64)
+fn recortar_asteroides(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= culling.cinturon.cantidad) {
+        return;
+    }
+    let a = asteroide(culling.cinturon, id.x, culling.tiempo);
+
+    // Frustum: el centro en clip, con margen de su tamaño proyectado
+    let clip = culling.vista_proyeccion * vec4<f32>(a.posicion, 1.0);
+    let margen = a.tamano * culling.margen;
+    if (clip.w <= 0.0 || abs(clip.x) > clip.w + margen || abs(clip.y) > clip.w + margen
+        || clip.z < -margen || clip.z > clip.w) {
+        return;
+    }
+
+    // Oclusión: los cuerpos se dibujan después (orden del pintor), así que lo que cae
+    // por completo dentro de su silueta no se ve, esté delante o detrás
+    let direccion = normalize(a.posicion - culling.ojo);
+    for (var i = 0u; i < culling.cantidad_oclusores; i++) {
+        let oclusor = culling.oclusores[i];
+        let hacia_centro = oclusor.xyz - culling.ojo;
+        let a_lo_largo = dot(hacia_centro, direccion);
+        let apartado = length(hacia_centro - direccion * a_lo_largo);
+        if (a_lo_largo > 0.0 && apartado + a.tamano < oclusor.w) {
+            return;
+        }
+    }
+
+    let ranura = atomicAdd(&argumentos_dibujo.cantidad_instancias, 1u);
+    visibles[ranura] = id.x;
+}
+
+struct SalidaAsteroide {
+    @builtin(position) posicion_clip: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) luz: vec3<f32>,
+    @location(2) color: vec3<f32>,
+}
+
+@vertex
+fn vertex_asteroide(@builtin(vertex_index) indice_vertice: u32, @location(0) indice: u32) -> SalidaAsteroide {
+    var salida: SalidaAsteroide;
+    let a = asteroide(cinturon, indice, uniformes.tiempo);
+    let esquina = esquina_quad(indice_vertice);
+    var en_vista = uniformes.matriz_vista * vec4<f32>(a.posicion, 1.0);
+    // Iluminado desde la estrella, en el espacio de la vista
+    let estrella = (uniformes.matriz_vista * vec4<f32>(uniformes.luz, 1.0)).xyz;
+    salida.luz = normalize(estrella - en_vista.xyz);
+    en_vista = vec4<f32>(en_vista.xy + esquina * a.tamano, en_vista.zw);
+    salida.posicion_clip = uniformes.matriz_proyeccion * en_vista;
+    salida.local = esquina;
+    salida.color = mix(vec3<f32>(0.42, 0.38, 0.34), vec3<f32>(0.64, 0.56, 0.46), a.tono);
+    return salida;
+}
+
+// Impostor esférico: normal reconstruida sobre el disco del billboard
+@fragment
+fn fragment_asteroide(entrada: SalidaAsteroide) -> @location(0) vec4<f32> {
+    let r2 = dot(entrada.local, entrada.local);
+    if (r2 > 1.0) {
+        discard;
+    }
+    let normal = vec3<f32>(entrada.local, sqrt(1.0 - r2));
+    let difusa = max(dot(normal, normalize(entrada.luz)), 0.0);
+    return vec4<f32>(entrada.color * (0.12 + 0.88 * difusa), 1.0);
 }